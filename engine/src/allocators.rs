@@ -2,6 +2,8 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+#[cfg(feature = "allocator-api2")]
+mod allocator_api;
 mod linear_allocator;
 
 pub use linear_allocator::{static_allocator, LinearAllocator};
@@ -0,0 +1,66 @@
+// SPDX-FileCopyrightText: 2025 Jens Pitkänen <jens.pitkanen@helsinki.fi>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! An implementation of the [`allocator_api2`] crate's [`Allocator`] trait
+//! for [`LinearAllocator`], which allows using the arenas for e.g. the
+//! collections of [`allocator_api2`] in tools and benchmarks. The trait is the
+//! same as the unstable `core::alloc::Allocator`, and becomes it if the
+//! `nightly` feature of [`allocator_api2`] is enabled.
+//!
+//! Only compiled with the engine's `allocator-api2` feature.
+
+use core::{alloc::Layout, ptr::NonNull};
+
+use allocator_api2::alloc::{AllocError, Allocator};
+
+use super::LinearAllocator;
+
+// Safety:
+// - The blocks are allocated with `try_alloc_uninit_slice`, so they're valid,
+//   distinct regions of memory for as long as the allocator is borrowed, i.e.
+//   as long as this reference and its copies are alive. The allocator can't be
+//   reset while it's borrowed, since that needs a mutable borrow.
+// - The blocks are aligned to `layout.align()` and `layout.size()` bytes long,
+//   as requested from `try_alloc_uninit_slice`.
+// - The blocks are never freed, so any of them can be passed to the other
+//   methods (which all use the default implementations based on `allocate`)
+//   until the allocator is reset.
+unsafe impl Allocator for &LinearAllocator<'_> {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        let block = (self.try_alloc_uninit_slice::<u8>(layout.size(), Some(layout.align())))
+            .ok_or(AllocError)?;
+        let ptr = NonNull::from(block).cast::<u8>();
+        Ok(NonNull::slice_from_raw_parts(ptr, layout.size()))
+    }
+
+    unsafe fn deallocate(&self, _ptr: NonNull<u8>, _layout: Layout) {
+        // Individual allocations can't be freed from a linear allocator, the
+        // memory is reclaimed when the whole allocator is reset.
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use allocator_api2::vec::Vec;
+
+    use crate::allocators::{static_allocator, LinearAllocator};
+
+    #[test]
+    fn vec_can_be_allocated_in_the_arena() {
+        static ARENA: &LinearAllocator = static_allocator!(4096);
+        // The old buffers aren't freed when the vec grows, so this takes a
+        // bit more memory than the final 400 bytes.
+        let mut numbers = Vec::new_in(ARENA);
+        for i in 0..100u32 {
+            numbers.push(i);
+        }
+        assert_eq!(4950, numbers.iter().sum::<u32>());
+        assert!(ARENA.allocated() >= 100 * size_of::<u32>());
+        assert_eq!(0, numbers.as_ptr().align_offset(align_of::<u32>()));
+
+        // Running out of memory is an error, not a panic.
+        let mut bytes = Vec::<u8, _>::new_in(ARENA);
+        assert!(bytes.try_reserve(4096).is_err());
+    }
+}
@@ -198,7 +198,9 @@ impl LinearAllocator<'_> {
     /// Note regardless of if the allocation is successful, `len` bytes are
     /// "allocated" from the allocation offset. This means that once this
     /// returns `None`, subsequent allocations will always fail until
-    /// [`LinearAllocator::reset`].
+    /// [`LinearAllocator::reset`]. The exception is allocations which would
+    /// not fit even in an empty allocator (including ones whose size overflows
+    /// `usize`), which fail without allocating anything.
     ///
     /// If `alignment` is Some, it will be used for alignment instead of `T`'s
     /// alignment. If the resulting alignment would result in `T` being
@@ -219,18 +221,30 @@ impl LinearAllocator<'_> {
             align_of::<T>()
         };
 
-        let reserved_bytes = len * size_of::<T>() + alignment - 1;
+        // The size is checked before touching `self.allocated`, so that
+        // `reserved_bytes` is at most `self.backing_mem_size`, which the
+        // bounds checks below rely on to not overflow.
+        let reserved_bytes = (len.checked_mul(size_of::<T>()))
+            .and_then(|size| size.checked_add(alignment - 1))
+            .filter(|&reserved_bytes| reserved_bytes <= self.backing_mem_size)?;
         // This is a relaxed fetch_add since we don't really care about the
         // order of allocations, we don't have any other atomic operations to
         // order, all we care about is that we get distinct allocation offsets
         // between different calls to try_alloc_uninit_slice. `self.allocated`
-        // may overflow, but that's simply taken as a signal that the allocator
-        // is full.
+        // may go past `self.backing_mem_size`, but that's simply taken as a
+        // signal that the allocator is full.
         let allocation_unaligned_offset =
             self.allocated.fetch_add(reserved_bytes, Ordering::Relaxed);
 
         // Make sure the entire allocation fits in the backing memory.
-        if allocation_unaligned_offset + reserved_bytes > self.backing_mem_size {
+        if allocation_unaligned_offset > self.backing_mem_size - reserved_bytes {
+            // Repeated failing allocations could eventually wrap
+            // `self.allocated` around to a small value, which would hand out
+            // memory that's already allocated, so it's clamped to just past
+            // the end of the backing memory, which still signals that the
+            // allocator is full.
+            self.allocated
+                .fetch_min(self.backing_mem_size + 1, Ordering::Relaxed);
             return None;
         }
 
@@ -307,3 +321,32 @@ impl LinearAllocator<'_> {
         self.allocated.store(0, Ordering::Release);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::LinearAllocator;
+
+    #[test]
+    fn allocations_with_overflowing_sizes_fail() {
+        static ARENA: &LinearAllocator = static_allocator!(1024);
+        // The size in bytes overflows
+        assert!(ARENA
+            .try_alloc_uninit_slice::<u64>(usize::MAX / 4, None)
+            .is_none());
+        // The size in bytes fits, but the padding for the alignment overflows
+        assert!(ARENA
+            .try_alloc_uninit_slice::<u8>(usize::MAX, Some(2))
+            .is_none());
+        // Allocations larger than the whole allocator don't fill it up
+        assert_eq!(0, ARENA.allocated());
+        assert!(ARENA.try_alloc_uninit_slice::<u8>(1000, None).is_some());
+
+        // Once full, failing allocations keep failing without the allocated
+        // byte count wrapping around.
+        for _ in 0..10 {
+            assert!(ARENA.try_alloc_uninit_slice::<u8>(1000, None).is_none());
+        }
+        assert_eq!(1024, ARENA.allocated());
+        assert!(ARENA.try_alloc_uninit_slice::<u8>(1, None).is_none());
+    }
+}
@@ -2,13 +2,15 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+mod blocking_queue;
 mod channel;
 mod queue;
 mod ring_buffer;
 mod sparse_array;
 mod vec;
 
-pub use channel::channel;
+pub use blocking_queue::BlockingQueue;
+pub use channel::{channel, mpsc_channel};
 pub use queue::Queue;
 pub use ring_buffer::{RingAllocationMetadata, RingBox, RingBuffer, RingSlice};
 pub use sparse_array::SparseArray;
@@ -0,0 +1,186 @@
+// SPDX-FileCopyrightText: 2025 Jens Pitkänen <jens.pitkanen@helsinki.fi>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use core::{
+    cell::UnsafeCell,
+    hint::spin_loop,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use platform::{Platform, Semaphore};
+
+use crate::allocators::LinearAllocator;
+
+use super::Queue;
+
+/// Bounded FIFO queue of `T` which can be shared between threads, waiting for
+/// room when pushing into a full queue, and for values when popping from an
+/// empty one.
+///
+/// The waiting is done with [`Semaphore`]s, so with single-threaded
+/// semaphores (e.g. on single-threaded platforms), nothing waits, and
+/// [`BlockingQueue::push_back`] and [`BlockingQueue::pop_front`] fail like
+/// the ones of a plain [`Queue`] instead.
+pub struct BlockingQueue<'a, T> {
+    /// Only accessed while `locked` is held.
+    queue: UnsafeCell<Queue<'a, T>>,
+    /// Held for the short time it takes to push or pop a value, not while
+    /// waiting on the semaphores.
+    locked: AtomicBool,
+    /// Counts the values in the queue.
+    filled_semaphore: Semaphore,
+    /// Counts the free slots in the queue.
+    room_semaphore: Semaphore,
+}
+
+// Safety: the queue is only accessed while holding the lock, so values are
+// only ever moved in and out of it by one thread at a time.
+unsafe impl<T: Send> Sync for BlockingQueue<'_, T> {}
+
+impl<'a, T> BlockingQueue<'a, T> {
+    /// Allocates room for `capacity` of `T` and creates a [`BlockingQueue`]
+    /// using it, with semaphores from the platform.
+    pub fn new(
+        platform: &dyn Platform,
+        allocator: &'a LinearAllocator,
+        capacity: usize,
+    ) -> Option<BlockingQueue<'a, T>> {
+        let queue = Queue::new(allocator, capacity)?;
+        Some(BlockingQueue::from_queue(
+            queue,
+            platform.create_semaphore(),
+            platform.create_semaphore(),
+        ))
+    }
+
+    /// Creates a [`BlockingQueue`] out of an existing [`Queue`], which may
+    /// already contain values.
+    ///
+    /// The semaphores are used for waiting for values and room in the queue,
+    /// respectively, and shouldn't be used for anything else.
+    pub fn from_queue(
+        queue: Queue<'a, T>,
+        filled_semaphore: Semaphore,
+        room_semaphore: Semaphore,
+    ) -> BlockingQueue<'a, T> {
+        for _ in queue.iter() {
+            filled_semaphore.increment();
+        }
+        for _ in 0..queue.spare_capacity() {
+            room_semaphore.increment();
+        }
+        BlockingQueue {
+            queue: UnsafeCell::new(queue),
+            locked: AtomicBool::new(false),
+            filled_semaphore,
+            room_semaphore,
+        }
+    }
+
+    /// Pushes `value` to the back of the queue, waiting for another thread to
+    /// pop a value if the queue is full.
+    ///
+    /// Returns the value back if there's still no room after waiting, which
+    /// only happens with single-threaded semaphores, or if the semaphore wakes
+    /// up spuriously, in which case this can be retried.
+    pub fn push_back(&self, value: T) -> Result<(), T> {
+        self.room_semaphore.decrement();
+        self.with_queue(|queue| queue.push_back(value))?;
+        self.filled_semaphore.increment();
+        Ok(())
+    }
+
+    /// Pops a value from the front of the queue, waiting for another thread
+    /// to push one if the queue is empty.
+    ///
+    /// Returns `None` if the queue is still empty after waiting, which only
+    /// happens with single-threaded semaphores, or if the semaphore wakes up
+    /// spuriously, in which case this can be retried.
+    pub fn pop_front(&self) -> Option<T> {
+        self.filled_semaphore.decrement();
+        let value = self.with_queue(|queue| queue.pop_front())?;
+        self.room_semaphore.increment();
+        Some(value)
+    }
+
+    fn with_queue<R>(&self, f: impl FnOnce(&mut Queue<'a, T>) -> R) -> R {
+        while (self.locked)
+            .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            spin_loop();
+        }
+        let _lock = LockGuard(&self.locked);
+        // Safety: the lock was just acquired, and it's only released when
+        // `_lock` is dropped, after this borrow has been dropped, so this is
+        // the only borrow of the queue.
+        f(unsafe { &mut *self.queue.get() })
+    }
+}
+
+/// Releases the lock of a [`BlockingQueue`] when dropped, so that the lock is
+/// released even if the code holding it panics.
+struct LockGuard<'a>(&'a AtomicBool);
+
+impl Drop for LockGuard<'_> {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::Release);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use platform::Semaphore;
+
+    use crate::{
+        allocators::{static_allocator, LinearAllocator},
+        collections::Queue,
+    };
+
+    use super::BlockingQueue;
+
+    fn single_threaded_queue(capacity: usize) -> BlockingQueue<'static, u32> {
+        static ARENA: &LinearAllocator = static_allocator!(1024);
+        let queue = Queue::new(ARENA, capacity).unwrap();
+        BlockingQueue::from_queue(
+            queue,
+            Semaphore::single_threaded(),
+            Semaphore::single_threaded(),
+        )
+    }
+
+    #[test]
+    fn full_queue_returns_the_value_without_waiting() {
+        let queue = single_threaded_queue(2);
+        queue.push_back(1).unwrap();
+        queue.push_back(2).unwrap();
+        assert_eq!(Err(3), queue.push_back(3));
+
+        assert_eq!(Some(1), queue.pop_front());
+        queue.push_back(3).unwrap();
+        assert_eq!(Some(2), queue.pop_front());
+        assert_eq!(Some(3), queue.pop_front());
+    }
+
+    #[test]
+    fn empty_queue_returns_none_without_waiting() {
+        let queue = single_threaded_queue(1);
+        assert_eq!(None, queue.pop_front());
+        queue.push_back(1).unwrap();
+        assert_eq!(Some(1), queue.pop_front());
+        assert_eq!(None, queue.pop_front());
+    }
+
+    #[test]
+    fn lock_is_released_after_a_panic() {
+        extern crate std;
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+
+        let queue = single_threaded_queue(1);
+        let result = catch_unwind(AssertUnwindSafe(|| queue.with_queue(|_| panic!())));
+        assert!(result.is_err());
+        queue.push_back(1).unwrap();
+        assert_eq!(Some(1), queue.pop_front());
+    }
+}
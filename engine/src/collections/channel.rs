@@ -4,11 +4,11 @@
 
 use core::{
     mem::{transmute, MaybeUninit},
-    sync::atomic::AtomicUsize,
+    sync::atomic::{AtomicBool, AtomicUsize},
 };
 
 use platform::{
-    channel::{channel_from_parts, CachePadded, Receiver, Sender, SyncUnsafeCell},
+    channel::{channel_from_parts, mpsc, CachePadded, Receiver, Sender, SyncUnsafeCell},
     Platform,
 };
 
@@ -40,13 +40,134 @@ pub fn channel<T: Sync>(
         unsafe { transmute::<&mut [MaybeUninit<AtomicUsize>], &mut [AtomicUsize]>(offsets) };
     let (read, write) = offsets.split_at_mut(1);
 
-    let semaphore = allocator.try_alloc_uninit_slice(1, None)?;
-    let semaphore = semaphore[0].write(platform.create_semaphore());
+    let semaphores = allocator.try_alloc_uninit_slice(2, None)?;
+    let (write_semaphore, read_semaphore) = semaphores.split_at_mut(1);
+    let write_semaphore = write_semaphore[0].write(platform.create_semaphore());
+    let read_semaphore = read_semaphore[0].write(platform.create_semaphore());
+
+    let sender_waiting = allocator.try_alloc_uninit_slice(1, None)?;
+    let sender_waiting = sender_waiting[0].write(AtomicBool::new(false));
 
     Some(channel_from_parts(
         queue,
         &mut read[0],
         &mut write[0],
+        write_semaphore,
+        read_semaphore,
+        sender_waiting,
+    ))
+}
+
+/// Creates a multi-producer single-consumer channel. The sender can be cloned
+/// to send values from multiple threads.
+pub fn mpsc_channel<T: Sync>(
+    platform: &dyn Platform,
+    allocator: &'static LinearAllocator,
+    capacity: usize,
+) -> Option<(mpsc::Sender<T>, mpsc::Receiver<T>)> {
+    let queue = allocator.try_alloc_uninit_slice::<CachePadded<mpsc::Slot<T>>>(capacity, None)?;
+    for slot in &mut *queue {
+        slot.write(CachePadded::new(mpsc::Slot::new()));
+    }
+    // Safety: all the values are initialized above.
+    let queue = unsafe {
+        transmute::<&mut [MaybeUninit<CachePadded<mpsc::Slot<T>>>], &mut [CachePadded<mpsc::Slot<T>>]>(
+            queue,
+        )
+    };
+
+    let positions = allocator.try_alloc_uninit_slice::<CachePadded<AtomicUsize>>(2, None)?;
+    for position in &mut *positions {
+        position.write(CachePadded::new(AtomicUsize::new(0)));
+    }
+    // Safety: all the values are initialized above.
+    let positions = unsafe {
+        transmute::<&mut [MaybeUninit<CachePadded<AtomicUsize>>], &mut [CachePadded<AtomicUsize>]>(
+            positions,
+        )
+    };
+    let (read, write) = positions.split_at_mut(1);
+
+    let semaphore = allocator.try_alloc_uninit_slice(1, None)?;
+    let semaphore = semaphore[0].write(platform.create_semaphore());
+
+    Some(mpsc::channel_from_parts(
+        queue,
+        &mut write[0],
+        &mut read[0],
         semaphore,
     ))
 }
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use core::time::Duration;
+    use std::{thread, vec::Vec};
+
+    use crate::{
+        allocators::{static_allocator, LinearAllocator},
+        test_platform::TestPlatform,
+    };
+
+    use super::{channel, mpsc_channel};
+
+    #[test]
+    #[cfg_attr(target_os = "emscripten", ignore)]
+    fn send_blocking_waits_for_room() {
+        static ARENA: &LinearAllocator = static_allocator!(64 * 1024);
+        let platform = TestPlatform::new(true);
+        let (mut tx, mut rx) = channel::<u32>(&platform, ARENA, 2).unwrap();
+
+        let producer = thread::spawn(move || {
+            for i in 0..100 {
+                while tx.send_blocking(i).is_err() {}
+            }
+        });
+
+        for i in 0..100 {
+            assert_eq!(i, rx.recv());
+            if i % 10 == 0 {
+                // Let the channel fill up, so the producer has to wait.
+                thread::sleep(Duration::from_millis(1));
+            }
+        }
+
+        producer.join().unwrap();
+    }
+
+    #[test]
+    #[cfg_attr(target_os = "emscripten", ignore)]
+    fn mpsc_recv_blocks_until_all_producers_have_sent() {
+        static ARENA: &LinearAllocator = static_allocator!(64 * 1024);
+        let platform = TestPlatform::new(true);
+        let (tx, mut rx) = mpsc_channel::<u32>(&platform, ARENA, 3).unwrap();
+
+        let producers = (0..3)
+            .map(|producer| {
+                let mut tx = tx.clone();
+                thread::spawn(move || {
+                    for i in 0..100 {
+                        while tx.send(producer * 1000 + i).is_err() {
+                            thread::yield_now();
+                        }
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let mut sum = 0;
+        for _ in 0..300 {
+            sum += rx.recv();
+        }
+        let expected: u32 = (0..3)
+            .flat_map(|p| (0..100).map(move |i| p * 1000 + i))
+            .sum();
+        assert_eq!(expected, sum);
+
+        for producer in producers {
+            producer.join().unwrap();
+        }
+    }
+}
@@ -47,6 +47,7 @@ pub struct RingBuffer<'a, T> {
     buffer_len: usize,
     allocated_offset: usize,
     allocated_len: usize,
+    peak_allocated_len: usize,
     buffer_identifier: usize,
 }
 
@@ -77,6 +78,7 @@ impl<T> RingBuffer<'_, T> {
             buffer_len: buffer.len(),
             allocated_offset: 0,
             allocated_len: 0,
+            peak_allocated_len: 0,
             buffer_identifier: make_buffer_id(),
         })
     }
@@ -95,6 +97,7 @@ impl<T> RingBuffer<'_, T> {
             buffer_len: buffer.len(),
             allocated_offset: 0,
             allocated_len: 0,
+            peak_allocated_len: 0,
             buffer_identifier: make_buffer_id(),
         }
     }
@@ -104,23 +107,62 @@ impl<T> RingBuffer<'_, T> {
         self.buffer_len
     }
 
+    /// Returns how many elements are currently allocated, including the
+    /// padding skipped at the end of the buffer when an allocation didn't fit
+    /// there and was wrapped around to the start. In other words, this is how
+    /// much of [`RingBuffer::capacity`] is currently unavailable.
+    pub fn allocated_len(&self) -> usize {
+        self.allocated_len
+    }
+
+    /// Returns the highest [`RingBuffer::allocated_len`] this buffer has had
+    /// since it was created, or since the last call to
+    /// [`RingBuffer::reset_peak_allocated_len`]. Useful for sizing the buffer.
+    pub fn peak_allocated_len(&self) -> usize {
+        self.peak_allocated_len
+    }
+
+    /// Resets [`RingBuffer::peak_allocated_len`] to the current
+    /// [`RingBuffer::allocated_len`].
+    pub fn reset_peak_allocated_len(&mut self) {
+        self.peak_allocated_len = self.allocated_len;
+    }
+
     /// If it fits, allocates `len` contiguous bytes and returns the offset and
     /// padding of the allocation.
     fn allocate_offset(&mut self, len: usize) -> Option<(usize, usize)> {
+        let result = self.allocate_offset_inner(len);
+        self.peak_allocated_len = self.peak_allocated_len.max(self.allocated_len);
+        result
+    }
+
+    fn allocate_offset_inner(&mut self, len: usize) -> Option<(usize, usize)> {
         let allocated_end = self.allocated_offset + self.allocated_len;
-        let padding_to_end = self.buffer_len - (allocated_end % self.buffer_len);
-        if allocated_end + len <= self.buffer_len {
+        if allocated_end > self.buffer_len {
+            // The allocated span already wraps around, so the free space is
+            // the contiguous region between its end and its start
+            let wrapped_end = allocated_end - self.buffer_len;
+            if self.allocated_len + len <= self.buffer_len {
+                self.allocated_len += len;
+                Some((wrapped_end, 0))
+            } else {
+                None
+            }
+        } else if allocated_end + len <= self.buffer_len {
             // The allocation fits between the current allocated slice's end and
             // the end of the buffer
             self.allocated_len += len;
             Some((allocated_end, 0))
-        } else if self.allocated_len + padding_to_end + len <= self.buffer_len {
-            // The slice fits even with padding added to the end so that the
-            // allocated slice starts at the beginning
-            self.allocated_len += padding_to_end + len;
-            Some((0, padding_to_end))
         } else {
-            None
+            // The slice might fit with padding added to the end so that the
+            // allocated slice starts at the beginning
+            let padding_to_end = self.buffer_len - allocated_end;
+            if self.allocated_len + padding_to_end + len <= self.buffer_len {
+                self.allocated_len += padding_to_end + len;
+                Some((0, padding_to_end))
+            } else {
+                None
+            }
         }
     }
 }
@@ -184,7 +226,7 @@ impl<T: Zeroable> RingBuffer<'_, T> {
             Ok(())
         } else if slice.metadata.offset == allocated_offset_with_padding {
             let freed_len = slice.len();
-            self.allocated_offset = (self.allocated_offset + freed_len) % self.buffer_len;
+            self.allocated_offset = (slice.metadata.offset + freed_len) % self.buffer_len;
             self.allocated_len -= freed_len + slice.metadata.padding;
             if self.allocated_len == 0 {
                 self.allocated_offset = 0;
@@ -250,7 +292,7 @@ impl<T> RingBuffer<'_, T> {
             // allocated length being 0.
             Ok(())
         } else if boxed.metadata.offset == allocated_offset_with_padding {
-            self.allocated_offset = (self.allocated_offset + 1) % self.buffer_len;
+            self.allocated_offset = (boxed.metadata.offset + 1) % self.buffer_len;
             self.allocated_len -= 1 + boxed.metadata.padding;
             if self.allocated_len == 0 {
                 self.allocated_offset = 0;
@@ -293,6 +335,55 @@ mod tests {
         assert!(ring.allocate(4).is_none(), "ring should be full");
     }
 
+    #[test]
+    fn cycles_past_the_end_one_at_a_time() {
+        static ALLOC: &LinearAllocator = static_allocator!(3);
+        let mut ring = RingBuffer::<u8>::new(ALLOC, 3).unwrap();
+
+        let mut slices = [
+            ring.allocate(1).unwrap(),
+            ring.allocate(1).unwrap(),
+            ring.allocate(1).unwrap(),
+        ]
+        .map(Some);
+        assert!(ring.allocate(1).is_none(), "ring should be full");
+
+        for i in 0..10 {
+            let oldest = slices[i % 3].take().unwrap();
+            ring.free(oldest).unwrap();
+            slices[i % 3] = Some(ring.allocate(1).unwrap());
+            assert!(ring.allocate(1).is_none(), "ring should be full");
+        }
+    }
+
+    #[test]
+    fn metrics_include_wraparound_padding() {
+        static ALLOC: &LinearAllocator = static_allocator!(10);
+        let mut ring = RingBuffer::<u8>::new(ALLOC, 10).unwrap();
+        assert_eq!(10, ring.capacity());
+
+        let first = ring.allocate(4).unwrap();
+        let second = ring.allocate(4).unwrap();
+        assert_eq!(8, ring.allocated_len());
+        ring.free(first).unwrap();
+        assert_eq!(4, ring.allocated_len());
+        assert_eq!(8, ring.peak_allocated_len());
+
+        // Doesn't fit in the 2 elements at the end, so those are skipped:
+        let third = ring.allocate(4).unwrap();
+        assert_eq!(10, ring.allocated_len());
+        assert_eq!(10, ring.peak_allocated_len());
+
+        ring.free(second).unwrap();
+        assert_eq!(6, ring.allocated_len());
+        ring.free(third).unwrap();
+        assert_eq!(0, ring.allocated_len());
+        assert_eq!(10, ring.peak_allocated_len());
+
+        ring.reset_peak_allocated_len();
+        assert_eq!(0, ring.peak_allocated_len());
+    }
+
     #[test]
     #[should_panic]
     fn panics_on_free_with_wrong_buffer_identity() {
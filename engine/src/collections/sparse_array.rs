@@ -4,7 +4,7 @@
 
 use core::{
     num::NonZeroU32,
-    sync::atomic::{AtomicU32, Ordering},
+    sync::atomic::{AtomicBool, AtomicU32, Ordering},
 };
 
 use bytemuck::Zeroable;
@@ -13,6 +13,8 @@ use crate::{allocators::LinearAllocator, collections::FixedVec};
 
 struct LoadedElementInfo {
     age: AtomicU32,
+    /// Pinned elements are never evicted, see [`SparseArray::pin`].
+    pinned: AtomicBool,
     array_index: u32,
 }
 
@@ -20,6 +22,7 @@ impl LoadedElementInfo {
     fn new(array_index: u32) -> LoadedElementInfo {
         LoadedElementInfo {
             age: AtomicU32::new(0),
+            pinned: AtomicBool::new(false),
             array_index,
         }
     }
@@ -79,6 +82,23 @@ impl<T> SparseArray<'_, T> {
         }
     }
 
+    /// Keeps the value at the index, if it's loaded, from being evicted by
+    /// [`SparseArray::insert`] until [`SparseArray::unpin_all`] is called.
+    pub fn pin(&self, index: u32) {
+        if let Some(loaded_index) = self.index_map[index as usize].get() {
+            (self.loaded_element_infos[loaded_index as usize].pinned)
+                .store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Allows all the elements pinned with [`SparseArray::pin`] to be evicted
+    /// again.
+    pub fn unpin_all(&mut self) {
+        for info in &mut *self.loaded_element_infos {
+            *info.pinned.get_mut() = false;
+        }
+    }
+
     /// Removes the value from the index, freeing space for another
     /// value to be inserted anywhere.
     pub fn unload(&mut self, index: u32) {
@@ -104,16 +124,18 @@ impl<T> SparseArray<'_, T> {
     /// index."
     ///
     /// If the backing memory is full, and every `T` has been used since the
-    /// last call to [`SparseArray::increment_ages`], this returns `None`.
+    /// last call to [`SparseArray::increment_ages`] or is pinned with
+    /// [`SparseArray::pin`], this returns `None`.
     pub fn insert(&mut self, index: u32, init_fn: impl FnOnce() -> Option<T>) -> Option<&mut T> {
         let now_loaded_index = if let Some(unloaded_index) = self.free_indices.pop() {
+            self.loaded_element_infos[unloaded_index as usize] = LoadedElementInfo::new(index);
             unloaded_index
         } else if self.loaded_elements.is_full() {
             let mut least_recent_age = 0;
             let mut least_recent_loaded_index = None;
             for (i, info) in self.loaded_element_infos.iter_mut().enumerate() {
                 let age = *info.age.get_mut();
-                if age > least_recent_age {
+                if age > least_recent_age && !*info.pinned.get_mut() {
                     least_recent_age = age;
                     least_recent_loaded_index = Some(i as u32);
                 }
@@ -148,11 +170,80 @@ impl<T> SparseArray<'_, T> {
         Some(&self.loaded_elements[loaded_index])
     }
 
+    /// Returns true if the value at the index is loaded. Unlike
+    /// [`SparseArray::get`], this does not reset the age of the element.
+    pub fn is_loaded(&self, index: u32) -> bool {
+        self.index_map[index as usize].get().is_some()
+    }
+
     /// Returns the length of the whole array (not the amount of loaded
     /// elements).
     pub fn array_len(&self) -> usize {
         self.index_map.len()
     }
+
+    /// Moves the loaded elements to new indices, and changes the length of
+    /// the array to `array_len`. `new_index` is called with the index of each
+    /// loaded element, and returns the element's new index, or `None` if the
+    /// element should be unloaded. Different indices must not be moved to the
+    /// same new index.
+    ///
+    /// ### Panics
+    ///
+    /// If `array_len` is larger than the array's length, and larger than the
+    /// length reserved with [`SparseArray::reserve_len`].
+    pub fn reindex(&mut self, array_len: u32, mut new_index: impl FnMut(u32) -> Option<u32>) {
+        const UNLOADED: u32 = u32::MAX;
+        let capacity = self.index_map.len() + self.index_map.spare_capacity();
+        assert!(
+            array_len as usize <= capacity,
+            "the array's length has not been reserved",
+        );
+
+        // Find the new indices of the loaded elements before clearing out the
+        // old indices.
+        for (loaded_index, info) in self.loaded_element_infos.iter_mut().enumerate() {
+            let old_index = info.array_index;
+            let is_loaded = (self.index_map.get(old_index as usize))
+                .and_then(|index| index.get())
+                .is_some_and(|index| index == loaded_index as u32);
+            info.array_index = if is_loaded {
+                new_index(old_index).unwrap_or(UNLOADED)
+            } else {
+                UNLOADED
+            };
+        }
+
+        self.index_map.clear();
+        self.index_map.fill_with_zeroes();
+        self.index_map.truncate(array_len as usize);
+        for (loaded_index, info) in self.loaded_element_infos.iter_mut().enumerate() {
+            if info.array_index != UNLOADED {
+                self.index_map[info.array_index as usize].set(loaded_index as u32);
+                continue;
+            }
+            info.array_index = 0;
+            if !self.free_indices.contains(&(loaded_index as u32)) {
+                self.free_indices.push(loaded_index as u32).unwrap();
+            }
+        }
+    }
+}
+
+impl<'a, T> SparseArray<'a, T> {
+    /// Makes sure the array can be made `array_len` long with
+    /// [`SparseArray::reindex`], allocating a larger index map from
+    /// `allocator` if needed. Returns `None` if the allocation fails, in which
+    /// case the array is left as is.
+    pub fn reserve_len(&mut self, allocator: &'a LinearAllocator, array_len: u32) -> Option<()> {
+        if array_len as usize <= self.index_map.len() + self.index_map.spare_capacity() {
+            return Some(());
+        }
+        let mut index_map = FixedVec::new(allocator, array_len as usize)?;
+        assert!(index_map.extend_from_slice(&self.index_map));
+        self.index_map = index_map;
+        Some(())
+    }
 }
 
 /// `Option<u32>` but Zeroable and u32-sized.
@@ -188,3 +279,47 @@ impl OptionalU32 {
         result
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::allocators::{static_allocator, LinearAllocator};
+
+    use super::SparseArray;
+
+    #[test]
+    fn freed_slots_are_reused_for_the_new_index() {
+        static ARENA: &LinearAllocator = static_allocator!(1024);
+        let mut array = SparseArray::<u32>::new(ARENA, 4, 2).unwrap();
+        *array.insert(0, || Some(0)).unwrap() = 0;
+        *array.insert(1, || Some(0)).unwrap() = 1;
+        array.unload(0);
+        *array.insert(2, || Some(0)).unwrap() = 2;
+        assert_eq!(Some(&2), array.get(2));
+
+        // Evicting the reused slot should unload the index it was reused for,
+        // not the one it was freed from.
+        array.increment_ages();
+        assert_eq!(Some(&1), array.get(1));
+        *array.insert(3, || Some(0)).unwrap() = 3;
+        assert!(!array.is_loaded(2));
+        assert_eq!(Some(&1), array.get(1));
+        assert_eq!(Some(&3), array.get(3));
+    }
+
+    #[test]
+    fn pinned_elements_are_not_evicted() {
+        static ARENA: &LinearAllocator = static_allocator!(1024);
+        let mut array = SparseArray::<u32>::new(ARENA, 3, 1).unwrap();
+        *array.insert(0, || Some(0)).unwrap() = 0;
+        array.pin(0);
+        array.increment_ages();
+        array.increment_ages();
+        assert!(array.insert(1, || Some(0)).is_none());
+        assert_eq!(Some(&0), array.get(0));
+
+        array.unpin_all();
+        array.increment_ages();
+        assert!(array.insert(2, || Some(0)).is_some());
+        assert!(!array.is_loaded(0));
+    }
+}
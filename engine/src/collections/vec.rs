@@ -3,6 +3,7 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
 use core::{
+    cmp::Ordering,
     fmt::Debug,
     mem::{needs_drop, transmute, MaybeUninit},
     ops::{Deref, DerefMut},
@@ -93,6 +94,47 @@ impl<T> FixedVec<'_, T> {
         Ok(())
     }
 
+    /// Inserts the value at `index`, shifting all the elements after it to the
+    /// right. If there's no capacity left, returns the given value back
+    /// wrapped in a [`Result::Err`].
+    ///
+    /// ### Panics
+    ///
+    /// If `index` is greater than the length of the array.
+    #[track_caller]
+    pub fn insert(&mut self, index: usize, value: T) -> Result<(), T> {
+        let len = self.initialized_len;
+        assert!(
+            index <= len,
+            "insertion index {index} is out of bounds (len is {len})"
+        );
+        self.push(value)?;
+        self[index..].rotate_right(1);
+        Ok(())
+    }
+
+    /// Inserts the value into an array sorted by `compare`, after any elements
+    /// equal to it, keeping the array sorted. Returns the index the value was
+    /// inserted at, or the value back wrapped in a [`Result::Err`] if there's
+    /// no capacity left, in which case the array is not modified.
+    ///
+    /// Like with [`slice::binary_search_by`], the position is unspecified if
+    /// the array is not sorted.
+    pub fn insert_sorted<F>(&mut self, value: T, mut compare: F) -> Result<usize, T>
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        if self.is_full() {
+            return Err(value);
+        }
+        // Treating equal elements as lesser places the value after them.
+        let index = self
+            .binary_search_by(|element| compare(element, &value).then(Ordering::Less))
+            .unwrap_err();
+        self.insert(index, value)?;
+        Ok(index)
+    }
+
     /// If non-empty, returns the final element and shortens the array by one.
     pub fn pop(&mut self) -> Option<T> {
         if self.initialized_len == 0 {
@@ -145,6 +187,33 @@ impl<T> FixedVec<'_, T> {
     pub fn spare_capacity(&self) -> usize {
         self.uninit_slice.len() - self.initialized_len
     }
+
+    /// Returns the remaining spare capacity of the array as a slice of
+    /// `MaybeUninit<T>`.
+    ///
+    /// The returned slice can be used to fill the array with data (e.g. by
+    /// reading from a file) before marking the data as initialized using
+    /// [`FixedVec::set_len`].
+    pub fn spare_capacity_mut(&mut self) -> &mut [MaybeUninit<T>] {
+        &mut self.uninit_slice[self.initialized_len..]
+    }
+
+    /// Forces the length of the array to `new_len`.
+    ///
+    /// This is a low-level operation that maintains none of the normal
+    /// invariants of the type. Normally changing the length of an array is
+    /// done using one of the safe operations instead, such as
+    /// [`FixedVec::truncate`], [`FixedVec::push`], or [`FixedVec::clear`].
+    ///
+    /// ### Safety
+    ///
+    /// - `new_len` must be less than or equal to the capacity of the array,
+    ///   i.e. the current length plus [`FixedVec::spare_capacity`].
+    /// - The elements at `old_len..new_len` must be initialized.
+    pub unsafe fn set_len(&mut self, new_len: usize) {
+        debug_assert!(new_len <= self.uninit_slice.len());
+        self.initialized_len = new_len;
+    }
 }
 
 impl<T: Copy> FixedVec<'_, T> {
@@ -319,6 +388,42 @@ mod tests {
         assert_eq!(0, ELEMENT_COUNT.load(Ordering::Relaxed));
     }
 
+    #[test]
+    fn spare_capacity_can_be_initialized_in_place() {
+        static ARENA: &LinearAllocator = static_allocator!(size_of::<u32>() * 5);
+        let mut vec: FixedVec<u32> = FixedVec::new(ARENA, 4).unwrap();
+        vec.push(1).unwrap();
+
+        let spare = vec.spare_capacity_mut();
+        assert_eq!(3, spare.len());
+        spare[0].write(2);
+        spare[1].write(3);
+        // Safety: the two elements after the first one were just initialized.
+        unsafe { vec.set_len(3) };
+
+        assert_eq!(&[1, 2, 3], &*vec);
+        assert_eq!(1, vec.spare_capacity());
+    }
+
+    #[test]
+    fn sorted_inserts_keep_the_order() {
+        static ARENA: &LinearAllocator = static_allocator!(size_of::<(u32, u32)>() * 6);
+        let mut vec: FixedVec<(u32, u32)> = FixedVec::new(ARENA, 5).unwrap();
+        let by_key = |a: &(u32, u32), b: &(u32, u32)| a.0.cmp(&b.0);
+
+        assert_eq!(Ok(0), vec.insert_sorted((5, 0), by_key));
+        assert_eq!(Ok(0), vec.insert_sorted((1, 0), by_key));
+        assert_eq!(Ok(1), vec.insert_sorted((3, 0), by_key));
+        assert_eq!(Ok(3), vec.insert_sorted((9, 0), by_key));
+        // Equal values are inserted after the existing ones.
+        assert_eq!(Ok(2), vec.insert_sorted((3, 1), by_key));
+        assert_eq!(&[(1, 0), (3, 0), (3, 1), (5, 0), (9, 0)], &*vec);
+
+        assert_eq!(Err((4, 0)), vec.insert_sorted((4, 0), by_key));
+        assert_eq!(Err((0, 0)), vec.insert(0, (0, 0)));
+        assert_eq!(&[(1, 0), (3, 0), (3, 1), (5, 0), (9, 0)], &*vec);
+    }
+
     #[test]
     fn zst_elements_work() {
         #[derive(Debug, PartialEq)]
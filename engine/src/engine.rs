@@ -2,17 +2,25 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
-use arrayvec::ArrayVec;
+use core::{
+    fmt::{self, Display},
+    mem,
+    ops::ControlFlow,
+    time::Duration,
+};
+
 use platform::{
-    thread_pool::ThreadPool, EngineCallbacks, Event, Instant, Platform, AUDIO_SAMPLE_RATE,
+    thread_pool::ThreadPool, AudioOutput, EngineCallbacks, Event, Instant, Platform,
+    AUDIO_SAMPLE_RATE,
 };
 
 use crate::{
     allocators::LinearAllocator,
-    input::{EventQueue, QueuedEvent},
+    collections::FixedVec,
+    input::{self, EventQueue, EventQueueOverflow, QueuedEvent, EVENT_QUEUE_TIMEOUT},
     mixer::Mixer,
     multithreading::{self, parallelize},
-    resources::{FileReader, ResourceDatabase, ResourceLoader},
+    resources::{FileReader, ReloadError, ResourceDatabase, ResourceDatabaseError, ResourceLoader},
 };
 
 /// Parameters affecting the memory usage of the engine, used in
@@ -30,6 +38,24 @@ pub struct EngineLimits {
     ///
     /// Defaults to 8 MiB (`8 * 1024 * 1024`).
     pub frame_arena_size: usize,
+    /// Whether the engine should alternate between two frame arenas, so that
+    /// the previous frame's allocations are only reset at the start of the
+    /// frame after the current one. See [`Engine::previous_frame_arena`]. Each
+    /// of the arenas is [`EngineLimits::frame_arena_size`] bytes.
+    ///
+    /// Defaults to `false`.
+    pub double_buffered_frame_arena: bool,
+    /// The paths of the resource database files to load, in the order they're
+    /// mounted. If multiple databases contain an asset with the same name, the
+    /// one mounted last wins, i.e. the database later in this list overrides
+    /// the earlier ones. This can be used for e.g. patches and mods, by
+    /// mounting their databases after the base game's database.
+    ///
+    /// Files which can't be opened are skipped, and if none of them can be
+    /// opened, the engine runs without any assets.
+    ///
+    /// Defaults to `&["resources.db"]`.
+    pub resource_database_paths: &'static [&'static str],
     /// The maximum amount of concurrently loaded resource chunks. This count,
     /// multiplied by [`CHUNK_SIZE`](crate::resources::CHUNK_SIZE), is the
     /// amount of bytes allocated for non-VRAM based asset memory, like audio
@@ -67,6 +93,13 @@ pub struct EngineLimits {
     ///
     /// Defaults to 8 MiB (`8 * 1024 * 1024`).
     pub resource_database_buffer_size: usize,
+    /// Whether the resource database's chunk data should be checked against
+    /// its checksum at startup. This catches corrupted or truncated databases
+    /// early, but requires reading through the whole database file, which can
+    /// take a while for large databases. The asset metadata is always checked.
+    ///
+    /// Defaults to `true` in debug builds and `false` in release builds.
+    pub resource_database_verify_chunk_data: bool,
     /// The amount of channels the engine's [`Mixer`] has. Each channel can be
     /// individually controlled volume-wise, and all played sounds play on a
     /// specific channel.
@@ -99,19 +132,65 @@ pub struct EngineLimits {
     /// pessimistic 30 FPS, this would be 3200. The default length is half a
     /// second, i.e. `AUDIO_SAMPLE_RATE / 2`.
     pub audio_window_length: usize,
+    /// Overrides the speaker layout the audio is mixed for, which is
+    /// [`Platform::audio_output`] by default. Can be used to e.g. provide an
+    /// option for mono audio for accessibility. See [`Mixer::output`].
+    ///
+    /// Defaults to `None`.
+    pub audio_output: Option<AudioOutput>,
+    /// The minimum alignment of the [`Mixer`]'s playback buffer, in bytes,
+    /// e.g. 16 or 32 for processing the samples with SIMD instructions. Must
+    /// be a power of two. See [`Mixer::with_alignment`].
+    ///
+    /// Defaults to 1, i.e. the samples' own alignment.
+    pub audio_buffer_alignment: usize,
+    /// What happens to new events when [`Engine::event_queue`] is full, e.g.
+    /// due to a flood of input events, or events not being consumed by the
+    /// game.
+    ///
+    /// Defaults to [`EventQueueOverflow::DropOldest`].
+    pub event_queue_overflow: EventQueueOverflow,
+    /// How long events are held in [`Engine::event_queue`] without being
+    /// handled before they're dropped. Shorter timeouts make input more
+    /// strict, e.g. for timing-sensitive games, while longer ones buffer
+    /// inputs more leniently.
+    ///
+    /// Defaults to [`EVENT_QUEUE_TIMEOUT`].
+    pub event_queue_timeout: Duration,
+    /// The length of the simulation steps counted by
+    /// [`Engine::fixed_timestep`].
+    ///
+    /// Defaults to 10 milliseconds.
+    pub fixed_timestep_length: Duration,
+    /// The maximum amount of simulation steps [`Engine::fixed_timestep`] counts
+    /// for one frame. If a frame takes longer than this many steps, the rest
+    /// of the time is skipped, to avoid the simulation falling further and
+    /// further behind when the steps take longer to simulate than they cover.
+    ///
+    /// Defaults to 8.
+    pub fixed_timestep_max_steps: u32,
 }
 
 impl EngineLimits {
     /// The default configuration for the engine used in its unit tests.
     pub const DEFAULT: EngineLimits = EngineLimits {
         frame_arena_size: 8 * 1024 * 1024,
+        double_buffered_frame_arena: false,
+        resource_database_paths: &["resources.db"],
         resource_database_loaded_chunks_count: 128,
         resource_database_loaded_sprite_chunks_count: 512,
         resource_database_read_queue_capacity: 128,
         resource_database_buffer_size: 8 * 1024 * 1024,
+        resource_database_verify_chunk_data: cfg!(debug_assertions),
         audio_channel_count: 1,
         audio_concurrent_sounds_count: 64,
         audio_window_length: (AUDIO_SAMPLE_RATE / 2) as usize,
+        audio_output: None,
+        audio_buffer_alignment: 1,
+        event_queue_overflow: EventQueueOverflow::DropOldest,
+        event_queue_timeout: EVENT_QUEUE_TIMEOUT,
+        fixed_timestep_length: Duration::from_millis(10),
+        fixed_timestep_max_steps: 8,
     };
 }
 
@@ -121,6 +200,173 @@ impl Default for EngineLimits {
     }
 }
 
+/// Splits the time between frames into fixed-length simulation steps, for
+/// running game logic like physics deterministically regardless of the frame
+/// rate.
+///
+/// Updated by the engine at the start of each frame, after which
+/// [`FixedTimestep::steps`] tells how many steps the game should simulate
+/// during the frame. This can be zero on fast frames, and multiple on slow
+/// ones. Time not covered by the steps is carried over to the next frame, and
+/// can be used to interpolate rendering between the last two steps with
+/// [`FixedTimestep::interpolation`].
+#[derive(Debug, Clone, Copy)]
+pub struct FixedTimestep {
+    step_length: Duration,
+    max_steps: u32,
+    previous_timestamp: Option<Instant>,
+    accumulated: Duration,
+    steps: u32,
+    skipped_steps: u32,
+    last_step_end: Instant,
+}
+
+impl FixedTimestep {
+    /// Creates a new [`FixedTimestep`] with steps of `step_length`, and at most
+    /// `max_steps` steps per frame.
+    ///
+    /// ### Panics
+    ///
+    /// If `step_length` is zero.
+    pub fn new(step_length: Duration, max_steps: u32) -> FixedTimestep {
+        assert!(
+            !step_length.is_zero(),
+            "fixed timestep length can't be zero"
+        );
+        FixedTimestep {
+            step_length,
+            max_steps,
+            previous_timestamp: None,
+            accumulated: Duration::ZERO,
+            steps: 0,
+            skipped_steps: 0,
+            last_step_end: Instant::reference(),
+        }
+    }
+
+    /// Accumulates the time since the previous update, and counts how many
+    /// steps fit in the accumulated time. The first update counts no steps.
+    pub fn update(&mut self, timestamp: Instant) {
+        let frame_time = (self.previous_timestamp)
+            .and_then(|previous| timestamp.duration_since(previous))
+            .unwrap_or(Duration::ZERO);
+        self.previous_timestamp = Some(timestamp);
+
+        self.accumulated += frame_time;
+        let steps = self.accumulated.as_nanos() / self.step_length.as_nanos();
+        // The remainder is less than one step, so it fits in a Duration.
+        self.accumulated = Duration::from_nanos(
+            (self.accumulated.as_nanos() % self.step_length.as_nanos()) as u64,
+        );
+        self.steps = steps.min(self.max_steps as u128) as u32;
+        self.skipped_steps = (steps - self.steps as u128).min(u32::MAX as u128) as u32;
+        self.last_step_end = timestamp - self.accumulated;
+    }
+
+    /// The amount of steps to simulate this frame.
+    pub fn steps(&self) -> u32 {
+        self.steps
+    }
+
+    /// The amount of steps skipped this frame because there would've been more
+    /// than the maximum amount of steps.
+    pub fn skipped_steps(&self) -> u32 {
+        self.skipped_steps
+    }
+
+    /// The length of each step.
+    pub fn step_length(&self) -> Duration {
+        self.step_length
+    }
+
+    /// Returns the point in time at the end of the given step of this frame,
+    /// counting from 0, for comparing against timestamps of e.g. events
+    /// converted with [`Engine::to_game_time`].
+    ///
+    /// ### Panics
+    ///
+    /// If `step` is not less than [`FixedTimestep::steps`].
+    pub fn step_timestamp(&self, step: u32) -> Instant {
+        assert!(step < self.steps, "step {step} is not simulated this frame");
+        self.last_step_end - self.step_length * (self.steps - 1 - step)
+    }
+
+    /// How far along the way to the next step the current frame is, from 0 to
+    /// 1, for interpolating between the state of the previous step and the
+    /// current one when rendering.
+    pub fn interpolation(&self) -> f32 {
+        self.accumulated.as_secs_f32() / self.step_length.as_secs_f32()
+    }
+}
+
+/// Reasons for [`Engine::try_new`] to fail.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EngineError {
+    /// The arena did not have enough memory for the named part of the engine.
+    OutOfMemory(&'static str),
+    /// One of the [`EngineLimits::resource_database_paths`] was opened, but
+    /// could not be loaded.
+    ResourceDatabase {
+        /// The path of the database which could not be loaded.
+        path: &'static str,
+        /// The reason the database could not be loaded.
+        error: ResourceDatabaseError,
+    },
+}
+
+impl Display for EngineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EngineError::OutOfMemory(part) => {
+                write!(f, "engine arena does not have enough memory for the {part}")
+            }
+            EngineError::ResourceDatabase { path, error } => {
+                write!(f, "{path} could not be loaded: {error}")
+            }
+        }
+    }
+}
+
+/// A breakdown of the memory allocated from the persistent arena by
+/// [`Engine::new`], returned by [`Engine::memory_report`].
+///
+/// All the sizes are in bytes, and include any padding needed for alignment,
+/// so [`MemoryReport::total`] is exactly the amount of memory the engine
+/// allocated from the arena. Useful for sizing the arena and the
+/// [`EngineLimits`] to fit the game.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MemoryReport {
+    /// The task and result channels of [`Engine::thread_pool`].
+    pub thread_pool: usize,
+    /// The frame arenas, i.e. [`EngineLimits::frame_arena_size`], twice if
+    /// [`EngineLimits::double_buffered_frame_arena`] is enabled.
+    pub frame_arenas: usize,
+    /// The staging buffer and read queues of [`Engine::resource_loader`].
+    pub resource_loader: usize,
+    /// The asset metadata and chunk descriptors of [`Engine::resource_db`].
+    pub resource_metadata: usize,
+    /// The in-memory chunk caches of [`Engine::resource_db`], based on
+    /// [`EngineLimits::resource_database_loaded_chunks_count`] and
+    /// [`EngineLimits::resource_database_loaded_sprite_chunks_count`]. The
+    /// sprite chunks' pixels are not included, as they're stored by the
+    /// platform.
+    pub loaded_chunks: usize,
+    /// The playing clips and playback buffer of [`Engine::audio_mixer`].
+    pub audio_mixer: usize,
+}
+
+impl MemoryReport {
+    /// Returns the sum of all the parts of the report.
+    pub fn total(&self) -> usize {
+        self.thread_pool
+            + self.frame_arenas
+            + self.resource_loader
+            + self.resource_metadata
+            + self.loaded_chunks
+            + self.audio_mixer
+    }
+}
+
 /// The top-level structure of the game engine which owns all the runtime state
 /// of the game engine and has methods for running the engine.
 pub struct Engine<'a> {
@@ -132,6 +378,13 @@ pub struct Engine<'a> {
     /// Linear allocator for any frame-internal dynamic allocation needs. Reset
     /// at the start of each frame.
     pub frame_arena: LinearAllocator<'a>,
+    /// The frame arena used during the previous frame, if
+    /// [`EngineLimits::double_buffered_frame_arena`] is enabled. The arenas are
+    /// swapped at the start of each frame, and only then is the new
+    /// [`Engine::frame_arena`] reset, so the previous frame's allocations stay
+    /// intact until the start of the next frame. This allows e.g. work started
+    /// during the previous frame to keep using its data during this frame.
+    pub previous_frame_arena: Option<LinearAllocator<'a>>,
     /// Thread pool for splitting compute-heavy workloads to multiple threads.
     pub thread_pool: ThreadPool,
     /// Mixer for playing back audio.
@@ -141,6 +394,20 @@ pub struct Engine<'a> {
     /// [`InputDeviceState`](crate::input::InputDeviceState), or after
     /// a timeout if not.
     pub event_queue: EventQueue,
+    /// The fixed-length simulation steps for the current frame, updated at the
+    /// start of each frame with the same timestamp as the one passed to the
+    /// game.
+    pub fixed_timestep: FixedTimestep,
+    event_queue_overflow: EventQueueOverflow,
+    event_queue_timeout: Duration,
+    paused: bool,
+    /// The platform's time when the current pause started, if the engine is
+    /// paused.
+    paused_at: Option<Instant>,
+    /// The total duration of the pauses so far, subtracted from the platform's
+    /// time to get the timestamps passed to the game.
+    paused_duration: Duration,
+    memory_report: MemoryReport,
 }
 
 impl Engine<'_> {
@@ -153,14 +420,53 @@ impl Engine<'_> {
     /// - `limits`: defines the limits for the various subsystems of the engine,
     ///   for dialing in the appropriate tradeoffs between memory usage and game
     ///   requirements.
+    ///
+    /// If the platform can't open any of the
+    /// [`EngineLimits::resource_database_paths`], the engine runs without any
+    /// assets. See [`Engine::try_new`] for the details.
+    ///
+    /// ### Panics
+    ///
+    /// If [`Engine::try_new`] would return an error.
+    #[track_caller]
     pub fn new(
         platform: &dyn Platform,
         arena: &'static LinearAllocator,
         limits: EngineLimits,
     ) -> Self {
+        match Engine::try_new(platform, arena, limits) {
+            Ok(engine) => engine,
+            Err(err) => panic!("engine could not be created: {err}"),
+        }
+    }
+
+    /// Creates a new instance of the engine like [`Engine::new`], returning
+    /// an error instead of panicking if the arena doesn't have enough memory
+    /// for the engine, or if the resource database could not be loaded.
+    ///
+    /// Resource database files which the platform can't open at all are
+    /// skipped. If none of the [`EngineLimits::resource_database_paths`] can
+    /// be opened, the engine runs with an empty resource database, so that
+    /// e.g. [`ResourceDatabase::find_sprite`] returns `None` for every name.
+    /// This allows running tools and tests without any assets.
+    pub fn try_new(
+        platform: &dyn Platform,
+        arena: &'static LinearAllocator,
+        limits: EngineLimits,
+    ) -> Result<Self, EngineError> {
         profiling::function_scope!();
-        let mut thread_pool = multithreading::create_thread_pool(arena, platform, 1)
-            .expect("engine arena should have enough memory for the thread pool");
+        use EngineError::OutOfMemory as Oom;
+        let mut memory_report = MemoryReport::default();
+        let mut allocated = arena.allocated();
+        let mut measure_allocated = |size: &mut usize| {
+            let now_allocated = arena.allocated();
+            *size += now_allocated - allocated;
+            allocated = now_allocated;
+        };
+
+        let mut thread_pool =
+            multithreading::create_thread_pool(arena, platform, 1).ok_or(Oom("thread pool"))?;
+        measure_allocated(&mut memory_report.thread_pool);
 
         // Name all the threads
         let dummy_slice = &mut [(); 1024][..thread_pool.thread_count()];
@@ -169,52 +475,223 @@ impl Engine<'_> {
         });
         profiling::register_thread!("engine main");
 
-        let frame_arena = LinearAllocator::new(arena, limits.frame_arena_size)
-            .expect("should have enough memory for the frame arena");
+        let mut frame_arena =
+            LinearAllocator::new(arena, limits.frame_arena_size).ok_or(Oom("frame arena"))?;
+        let previous_frame_arena = if limits.double_buffered_frame_arena {
+            let previous_frame_arena = LinearAllocator::new(arena, limits.frame_arena_size)
+                .ok_or(Oom("second frame arena"))?;
+            Some(previous_frame_arena)
+        } else {
+            None
+        };
+        measure_allocated(&mut memory_report.frame_arenas);
 
-        let db_file = platform
-            .open_file("resources.db")
-            .expect("resources.db should exist and be readable");
+        let mut res_reader;
+        let resource_db = {
+            // The list of opened files is only needed during initialization,
+            // so it's allocated from the frame arena, which is reset below.
+            let paths = limits.resource_database_paths;
+            let mut db_files =
+                FixedVec::new(&frame_arena, paths.len()).ok_or(Oom("frame arena"))?;
+            let mut db_paths =
+                FixedVec::new(&frame_arena, paths.len()).ok_or(Oom("frame arena"))?;
+            for &path in paths {
+                if let Some(file) = platform.open_file(path) {
+                    let (Ok(_), Ok(_)) = (db_files.push(file), db_paths.push(path)) else {
+                        unreachable!()
+                    };
+                } else {
+                    platform.println(format_args!(
+                        "{path} could not be opened, running without its assets"
+                    ));
+                }
+            }
 
-        let mut res_reader = FileReader::new(
-            arena,
-            db_file,
-            limits.resource_database_buffer_size,
-            limits.resource_database_read_queue_capacity,
-        )
-        .expect("engine arena should have enough memory for the resource db file reader");
+            // Without any files to read, the staging buffer would go unused.
+            let buffer_size = if db_files.is_empty() {
+                0
+            } else {
+                limits.resource_database_buffer_size
+            };
+            res_reader = FileReader::new(
+                arena,
+                buffer_size,
+                limits.resource_database_read_queue_capacity,
+            )
+            .ok_or(Oom("resource database file reader"))?;
+            measure_allocated(&mut memory_report.resource_loader);
 
-        let resource_db = ResourceDatabase::new(
-            platform,
-            arena,
-            &mut res_reader,
-            limits.resource_database_loaded_chunks_count,
-            limits.resource_database_loaded_sprite_chunks_count,
-        )
-        .expect("engine arena should have enough memory for the resource database");
+            match ResourceDatabase::new(
+                platform,
+                arena,
+                &mut res_reader,
+                &db_files,
+                limits.resource_database_loaded_chunks_count,
+                limits.resource_database_loaded_sprite_chunks_count,
+                limits.resource_database_verify_chunk_data,
+            ) {
+                Ok(resource_db) => resource_db,
+                Err((_, ResourceDatabaseError::OutOfMemory)) => {
+                    return Err(Oom("resource database"))
+                }
+                Err((i, error)) => {
+                    let path = db_paths[i];
+                    return Err(EngineError::ResourceDatabase { path, error });
+                }
+            }
+        };
+        frame_arena.reset();
+        measure_allocated(&mut memory_report.resource_metadata);
+        memory_report.resource_metadata -= resource_db.loaded_chunks_memory;
+        memory_report.loaded_chunks = resource_db.loaded_chunks_memory;
 
-        let resource_loader = ResourceLoader::new(arena, res_reader, &resource_db)
-            .expect("engine arena should have enough memory for the resource loader");
+        let resource_loader =
+            ResourceLoader::new(arena, res_reader, &resource_db).ok_or(Oom("resource loader"))?;
+        measure_allocated(&mut memory_report.resource_loader);
 
-        let audio_mixer = Mixer::new(
+        let mut audio_mixer = Mixer::with_alignment(
             arena,
             limits.audio_channel_count,
             limits.audio_concurrent_sounds_count,
             limits.audio_window_length,
+            limits.audio_buffer_alignment,
         )
-        .expect("engine arena should have enough memory for the audio mixer");
+        .ok_or(Oom("audio mixer"))?;
+        audio_mixer.output = limits.audio_output.unwrap_or(platform.audio_output());
+        measure_allocated(&mut memory_report.audio_mixer);
 
-        Engine {
+        Ok(Engine {
             resource_db,
             resource_loader,
             frame_arena,
+            previous_frame_arena,
             audio_mixer,
             thread_pool,
-            event_queue: ArrayVec::new(),
+            event_queue: EventQueue::new(),
+            fixed_timestep: FixedTimestep::new(
+                limits.fixed_timestep_length,
+                limits.fixed_timestep_max_steps,
+            ),
+            event_queue_overflow: limits.event_queue_overflow,
+            event_queue_timeout: limits.event_queue_timeout,
+            paused: false,
+            paused_at: None,
+            paused_duration: Duration::ZERO,
+            memory_report,
+        })
+    }
+
+    /// Returns a breakdown of the memory allocated by [`Engine::new`] from the
+    /// persistent arena, for e.g. sizing the arena and the [`EngineLimits`].
+    /// See [`MemoryReport`].
+    pub fn memory_report(&self) -> MemoryReport {
+        self.memory_report
+    }
+
+    /// Reloads the resource database files, e.g. after reimporting assets
+    /// while the game is running. See [`ResourceDatabase::reload`]. The
+    /// metadata is read into [`Engine::frame_arena`], so it needs to have room
+    /// for the metadata of all the database files.
+    pub fn reload_resources(&mut self, platform: &dyn Platform) -> Result<(), ReloadError> {
+        let scratch_arena = &self.frame_arena;
+        (self.resource_db).reload(platform, &mut self.resource_loader, scratch_arena)
+    }
+
+    /// Pauses the engine's clock and the audio playback, e.g. when the game's
+    /// menu is opened or the window loses focus.
+    ///
+    /// The timestamps passed to the game each frame stop advancing from the
+    /// next frame onwards, until the engine is resumed with [`Engine::resume`],
+    /// after which they continue from where they stopped. So deltas between
+    /// those timestamps don't include the time spent paused, unlike ones
+    /// between [`Platform::now`]s. Frames are still run while paused, e.g. for
+    /// rendering a pause menu. See [`Mixer::pause`] for how audio is paused.
+    pub fn pause(&mut self) {
+        self.paused = true;
+        self.audio_mixer.pause();
+    }
+
+    /// Resumes the engine's clock and the audio playback after
+    /// [`Engine::pause`].
+    pub fn resume(&mut self) {
+        self.paused = false;
+        self.audio_mixer.resume();
+    }
+
+    /// Returns true if the engine has been paused with [`Engine::pause`].
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Converts a timestamp from the platform, e.g. from an event in
+    /// [`Engine::event_queue`], into the timeline of the timestamps passed to
+    /// the game, which don't advance while the engine is paused.
+    pub fn to_game_time(&self, platform_timestamp: Instant) -> Instant {
+        let timestamp = match self.paused_at {
+            Some(paused_at) => platform_timestamp.min(paused_at),
+            None => platform_timestamp,
+        };
+        timestamp - self.paused_duration
+    }
+
+    /// Returns the timestamp to pass to the game for a frame starting at the
+    /// platform's time `now`, accounting for the time spent paused.
+    fn game_timestamp(&mut self, now: Instant) -> Instant {
+        match (self.paused, self.paused_at) {
+            (true, None) => self.paused_at = Some(now),
+            (false, Some(paused_at)) => {
+                self.paused_duration += paused_at.elapsed(now);
+                self.paused_at = None;
+            }
+            _ => {}
         }
+        self.paused_at.unwrap_or(now) - self.paused_duration
     }
 }
 
+/// A game running on the engine, with memory allocated from an arena which
+/// lives as long as the game does.
+///
+/// Platforms' game loops run a frame of the game with a closure returning
+/// [`ControlFlow`], and return the value the closure breaks with. The
+/// [`ControlFlow::Break`] returned by [`Game::run_frame`] can be used to
+/// reinitialize the game by resetting the arena and calling [`Game::init`]
+/// again:
+///
+/// ```ignore
+/// let mut params = InitParams::default();
+/// loop {
+///     // The previous game has been dropped, so its memory can be reused.
+///     game_arena.reset();
+///     let mut game = MyGame::init(&game_arena, &engine, params);
+///     match platform.run_game_loop(&mut engine, |timestamp, platform, engine| {
+///         game.run_frame(timestamp, platform, engine)
+///     }) {
+///         Some(Some(next_params)) => params = next_params,
+///         _ => break,
+///     }
+/// }
+/// ```
+pub trait Game<'a>: Sized {
+    /// The parameters the game is initialized with, e.g. the level to start
+    /// from.
+    type InitParams;
+
+    /// Creates the game, allocating any memory it needs for its whole lifetime
+    /// from `arena`.
+    fn init(arena: &'a LinearAllocator, engine: &Engine, params: Self::InitParams) -> Self;
+
+    /// Runs one frame of the game. Returning [`ControlFlow::Break`] ends the
+    /// game loop, with `Some` parameters if the game should be reinitialized
+    /// with them, and `None` if the game should exit.
+    fn run_frame(
+        &mut self,
+        timestamp: Instant,
+        platform: &dyn Platform,
+        engine: &mut Engine,
+    ) -> ControlFlow<Option<Self::InitParams>>;
+}
+
 impl EngineCallbacks for Engine<'_> {
     fn run_frame(
         &mut self,
@@ -224,51 +701,63 @@ impl EngineCallbacks for Engine<'_> {
         profiling::function_scope!();
 
         let timestamp = platform.now();
+        if let Some(previous_frame_arena) = &mut self.previous_frame_arena {
+            mem::swap(&mut self.frame_arena, previous_frame_arena);
+        }
         self.frame_arena.reset();
         self.resource_loader
             .finish_reads(&mut self.resource_db, platform, 128);
-        self.resource_db.chunks.increment_ages();
-        self.resource_db.sprite_chunks.increment_ages();
+        self.resource_db.increment_ages();
         self.audio_mixer.update_audio_sync(timestamp, platform);
 
-        run_game_frame(timestamp, platform, self);
+        let game_timestamp = self.game_timestamp(timestamp);
+        self.fixed_timestep.update(game_timestamp);
+        run_game_frame(game_timestamp, platform, self);
 
         self.audio_mixer.render_audio(
             &mut self.thread_pool,
             platform,
-            &self.resource_db,
+            &mut self.resource_db,
             &mut self.resource_loader,
         );
         self.resource_loader.dispatch_reads(platform);
+        let timeout = self.event_queue_timeout;
         self.event_queue
-            .retain(|queued| !queued.timed_out(timestamp));
+            .retain(|queued| !queued.timed_out(timestamp, timeout));
 
         profiling::finish_frame!();
     }
 
     fn event(&mut self, event: Event, timestamp: Instant) {
         profiling::function_scope!();
-        self.event_queue.push(QueuedEvent { event, timestamp });
+        let event = QueuedEvent { event, timestamp };
+        input::push_event(&mut self.event_queue, event, self.event_queue_overflow);
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use core::{ops::ControlFlow, time::Duration};
+
     use platform::{
         ActionCategory, Button, EngineCallbacks, Event, InputDevice, Instant, Platform,
     };
 
     use crate::{
         allocators::LinearAllocator,
+        collections::FixedVec,
         geom::Rect,
         input::{ActionKind, ActionState, InputDeviceState},
         renderer::DrawQueue,
-        resources::{audio_clip::AudioClipHandle, sprite::SpriteHandle, ResourceDatabase},
+        resources::{
+            audio_clip::AudioClipHandle, sprite::SpriteHandle, ResourceDatabase,
+            ResourceDatabaseError,
+        },
         static_allocator,
         test_platform::TestPlatform,
     };
 
-    use super::{Engine, EngineLimits};
+    use super::{Engine, EngineError, EngineLimits, Game};
 
     #[repr(usize)]
     enum TestInput {
@@ -294,10 +783,13 @@ mod tests {
                     ActionState {
                         kind: ActionKind::Instant,
                         mapping: Some(button),
+                        secondary_mapping: None,
                         disabled: false,
                         pressed: false,
+                        released: false,
                     },
                 ],
+                analog_actions: [],
             };
             Self {
                 test_input,
@@ -318,11 +810,11 @@ mod tests {
             if action_test {
                 engine
                     .audio_mixer
-                    .play_clip(0, self.test_audio, true, &engine.resource_db);
+                    .play_clip(0, self.test_audio, 0, &engine.resource_db);
                 self.test_counter += 1;
             }
 
-            let test_sprite = engine.resource_db.get_sprite(self.test_sprite);
+            let test_sprite = engine.resource_db.get_sprite(self.test_sprite).unwrap();
             let mut offset = 0.0;
             for mip in 0..9 {
                 if self.test_counter % 9 > mip {
@@ -390,6 +882,310 @@ mod tests {
         }
     }
 
+    #[test]
+    fn memory_report_adds_up_to_the_arena_usage() {
+        static PERSISTENT_ARENA: &LinearAllocator = static_allocator!(8 * 1024 * 1024);
+        let platform = TestPlatform::new(true);
+        let limits = EngineLimits {
+            frame_arena_size: 1024 * 1024,
+            double_buffered_frame_arena: true,
+            resource_database_loaded_chunks_count: 16,
+            resource_database_buffer_size: 1024 * 1024,
+            ..EngineLimits::DEFAULT
+        };
+        let engine = Engine::new(&platform, PERSISTENT_ARENA, limits);
+
+        let report = engine.memory_report();
+        assert_eq!(PERSISTENT_ARENA.allocated(), report.total());
+        assert!(report.total() <= PERSISTENT_ARENA.total());
+        assert!(report.frame_arenas >= 2 * limits.frame_arena_size);
+        assert!(report.resource_loader >= limits.resource_database_buffer_size);
+        assert!(report.thread_pool > 0);
+        assert!(report.resource_metadata > 0);
+        assert!(report.loaded_chunks > 0);
+        assert!(report.audio_mixer > 0);
+    }
+
+    #[test]
+    fn engine_runs_without_a_resource_database() {
+        static PERSISTENT_ARENA: &LinearAllocator = static_allocator!(4 * 1024 * 1024);
+        let platform = TestPlatform::new(false);
+        platform.remove_resources_db();
+        let limits = EngineLimits {
+            frame_arena_size: 1024 * 1024,
+            ..EngineLimits::DEFAULT
+        };
+        let mut engine = Engine::try_new(&platform, PERSISTENT_ARENA, limits).unwrap();
+
+        assert!(engine.resource_db.find_sprite("player").is_none());
+        assert!(engine.resource_db.find_audio_clip("whack").is_none());
+        engine.run_frame(&platform, &mut |_, _, _| {});
+    }
+
+    #[test]
+    fn truncated_resource_database_is_an_error() {
+        static PERSISTENT_ARENA: &LinearAllocator = static_allocator!(4 * 1024 * 1024);
+        let platform = TestPlatform::new(false);
+        platform.resources_db_mut().truncate(8);
+        let limits = EngineLimits {
+            frame_arena_size: 1024 * 1024,
+            resource_database_buffer_size: 1024 * 1024,
+            ..EngineLimits::DEFAULT
+        };
+        let result = Engine::try_new(&platform, PERSISTENT_ARENA, limits);
+        assert_eq!(
+            Some(EngineError::ResourceDatabase {
+                path: "resources.db",
+                error: ResourceDatabaseError::ReadFailed,
+            }),
+            result.err(),
+        );
+    }
+
+    #[test]
+    fn event_queue_flood_keeps_the_newest_events() {
+        static PERSISTENT_ARENA: &LinearAllocator = static_allocator!(64 * 1024 * 1024);
+        let platform = TestPlatform::new(false);
+        let mut engine = Engine::new(&platform, PERSISTENT_ARENA, EngineLimits::DEFAULT);
+
+        let device = platform.input_devices()[0];
+        let capacity = engine.event_queue.capacity() as u64;
+        for i in 0..capacity + 10 {
+            let event = Event::DigitalInputPressed(device, Button::new(i));
+            engine.event(event, platform.now());
+        }
+
+        assert!(engine.event_queue.is_full());
+        let first = &engine.event_queue.iter().next().unwrap().event;
+        let last = &engine.event_queue.iter().next_back().unwrap().event;
+        assert!(matches!(first, Event::DigitalInputPressed(_, b) if *b == Button::new(10)));
+        assert!(
+            matches!(last, Event::DigitalInputPressed(_, b) if *b == Button::new(capacity + 9))
+        );
+    }
+
+    #[test]
+    fn engine_keeps_running_after_a_failed_read() {
+        static PERSISTENT_ARENA: &LinearAllocator = static_allocator!(64 * 1024 * 1024);
+        let platform = TestPlatform::new(false);
+        let device = platform.input_devices()[0];
+        let button = platform
+            .default_button_for_action(ActionCategory::ActPrimary, device)
+            .unwrap();
+        let mut engine = Engine::new(&platform, PERSISTENT_ARENA, EngineLimits::DEFAULT);
+        let mut game = SmokeTestGame::new(device, button, &engine.resource_db);
+        let mut run_frame = |timestamp: Instant, platform: &dyn Platform, engine: &mut Engine| {
+            game.run_frame(timestamp, platform, engine);
+        };
+
+        // The first chunk read fails, and all of them take a few frames.
+        platform.fail_nth_file_read(0);
+        platform.set_file_read_latency(2);
+        for frame in 0..10 {
+            platform.set_elapsed_millis(frame * 100);
+            platform.take_draw_calls();
+            engine.run_frame(&platform, &mut run_frame);
+        }
+
+        // The failed chunk should've been requested and loaded again.
+        let draw_calls = platform.take_draw_calls();
+        assert!(draw_calls.iter().any(|call| call.settings.sprite.is_some()));
+    }
+
+    #[test]
+    fn pausing_freezes_the_game_clock_and_audio() {
+        static PERSISTENT_ARENA: &LinearAllocator = static_allocator!(64 * 1024 * 1024);
+        let platform = TestPlatform::new(false);
+        let mut engine = Engine::new(
+            &platform,
+            PERSISTENT_ARENA,
+            EngineLimits {
+                audio_window_length: 4800,
+                ..EngineLimits::DEFAULT
+            },
+        );
+        let run_frame_at = |engine: &mut Engine, millis: u64| -> Instant {
+            platform.set_elapsed_millis(millis);
+            let mut game_timestamp = None;
+            engine.run_frame(&platform, &mut |timestamp, _, _| {
+                game_timestamp = Some(timestamp);
+            });
+            game_timestamp.unwrap()
+        };
+
+        // A clip about a quarter of a second long.
+        let clip = engine.resource_db.find_audio_clip("whack").unwrap();
+        run_frame_at(&mut engine, 0);
+        assert!((engine.audio_mixer).play_clip(0, clip, 0, &engine.resource_db));
+        let before_pause = run_frame_at(&mut engine, 50);
+
+        engine.pause();
+        let paused = run_frame_at(&mut engine, 100);
+        for millis in (150..5000).step_by(50) {
+            assert_eq!(paused, run_frame_at(&mut engine, millis));
+        }
+        engine.resume();
+        let after_resume = run_frame_at(&mut engine, 5000);
+
+        let delta_over_pause = after_resume.duration_since(paused).unwrap();
+        assert!(delta_over_pause < Duration::from_millis(1));
+        let delta_before_pause = paused.duration_since(before_pause).unwrap();
+        assert_eq!(Duration::from_millis(50), delta_before_pause);
+
+        // The clip should continue after the pause instead of having ended.
+        assert!(engine.audio_mixer.is_channel_active(0));
+        run_frame_at(&mut engine, 5500);
+        assert!(!engine.audio_mixer.is_channel_active(0));
+    }
+
+    #[test]
+    fn fixed_timestep_counts_whole_steps_over_irregular_frames() {
+        static PERSISTENT_ARENA: &LinearAllocator = static_allocator!(64 * 1024 * 1024);
+        let platform = TestPlatform::new(false);
+        let mut engine = Engine::new(
+            &platform,
+            PERSISTENT_ARENA,
+            EngineLimits {
+                fixed_timestep_length: Duration::from_millis(10),
+                fixed_timestep_max_steps: 4,
+                ..EngineLimits::DEFAULT
+            },
+        );
+
+        // (frame time in ms, expected steps, expected skipped steps)
+        let frames = [
+            (0, 0, 0),
+            (3, 0, 0),
+            (17, 2, 0),
+            (1, 0, 0),
+            (29, 3, 0),
+            (0, 0, 0),
+            (16, 1, 0),
+            (4, 1, 0),
+            // The spiral of death guard skips most of a long frame
+            (1005, 4, 96),
+            (10, 1, 0),
+        ];
+        let mut millis = 0;
+        for (frame_time, steps, skipped_steps) in frames {
+            millis += frame_time;
+            platform.set_elapsed_millis(millis);
+            engine.run_frame(&platform, &mut |timestamp, _, engine| {
+                let timestep = engine.fixed_timestep;
+                assert_eq!(steps, timestep.steps(), "at {millis} ms");
+                assert_eq!(skipped_steps, timestep.skipped_steps(), "at {millis} ms");
+                assert!((0.0..1.0).contains(&timestep.interpolation()));
+                if steps > 0 {
+                    let last_step = timestep.step_timestamp(steps - 1);
+                    let until_now = timestamp.duration_since(last_step).unwrap();
+                    assert!(until_now < timestep.step_length());
+                }
+            });
+        }
+    }
+
+    #[test]
+    fn double_buffered_frame_arena_keeps_the_previous_frame_intact() {
+        static PERSISTENT_ARENA: &LinearAllocator = static_allocator!(64 * 1024 * 1024);
+        let platform = TestPlatform::new(false);
+        let mut engine = Engine::new(
+            &platform,
+            PERSISTENT_ARENA,
+            EngineLimits {
+                frame_arena_size: 1024,
+                double_buffered_frame_arena: true,
+                ..EngineLimits::DEFAULT
+            },
+        );
+
+        let allocate = |engine: &mut Engine, value: u32| -> *const u32 {
+            let mut data = FixedVec::new(&engine.frame_arena, 16).unwrap();
+            while data.push(value).is_ok() {}
+            data.as_ptr()
+        };
+
+        engine.run_frame(&platform, &mut |_, _, _| {});
+        let first_frame_data = allocate(&mut engine, 1);
+
+        engine.run_frame(&platform, &mut |_, _, _| {});
+        let previous_arena = engine.previous_frame_arena.as_ref().unwrap();
+        assert_eq!(1024, previous_arena.total());
+        assert!(previous_arena.allocated() > 0);
+        let second_frame_data = allocate(&mut engine, 2);
+        assert_ne!(first_frame_data, second_frame_data);
+        // Safety: the previous frame arena has not been reset yet, so the
+        // allocation is still intact and not aliased.
+        let first_frame_slice = unsafe { core::slice::from_raw_parts(first_frame_data, 16) };
+        assert!(first_frame_slice.iter().all(|value| *value == 1));
+
+        // The first frame's arena is reset by now, and reused.
+        engine.run_frame(&platform, &mut |_, _, _| {});
+        assert_eq!(first_frame_data, allocate(&mut engine, 3));
+    }
+
+    /// Allocates a kilobyte from its arena, and runs for a few frames before
+    /// reinitializing itself with the next generation, up to generation 3.
+    struct ReinitTestGame<'a> {
+        generation: u32,
+        frames: u32,
+        _memory: FixedVec<'a, u8>,
+    }
+
+    impl<'a> Game<'a> for ReinitTestGame<'a> {
+        type InitParams = u32;
+
+        fn init(arena: &'a LinearAllocator, _: &Engine, generation: u32) -> Self {
+            ReinitTestGame {
+                generation,
+                frames: 0,
+                _memory: FixedVec::new(arena, 1024).unwrap(),
+            }
+        }
+
+        fn run_frame(
+            &mut self,
+            _: Instant,
+            _: &dyn Platform,
+            _: &mut Engine,
+        ) -> ControlFlow<Option<u32>> {
+            self.frames += 1;
+            if self.frames < 3 {
+                ControlFlow::Continue(())
+            } else if self.generation < 3 {
+                ControlFlow::Break(Some(self.generation + 1))
+            } else {
+                ControlFlow::Break(None)
+            }
+        }
+    }
+
+    #[test]
+    fn reinitializing_the_game_reuses_its_arena() {
+        static PERSISTENT_ARENA: &LinearAllocator = static_allocator!(64 * 1024 * 1024);
+        let platform = TestPlatform::new(false);
+        let mut engine = Engine::new(&platform, PERSISTENT_ARENA, EngineLimits::DEFAULT);
+
+        // Only fits one generation of the game at a time.
+        let mut game_arena = LinearAllocator::new(PERSISTENT_ARENA, 1536).unwrap();
+        let mut generation = 0;
+        let mut frames = 0;
+        loop {
+            game_arena.reset();
+            let mut game = ReinitTestGame::init(&game_arena, &engine, generation);
+            let result = platform.run_game_loop(&mut engine, |timestamp, platform, engine| {
+                frames += 1;
+                game.run_frame(timestamp, platform, engine)
+            });
+            match result {
+                Some(Some(next_generation)) => generation = next_generation,
+                _ => break,
+            }
+        }
+
+        assert_eq!(3, generation);
+        assert_eq!(4 * 3, frames);
+    }
+
     #[test]
     #[cfg(not(target_os = "emscripten"))]
     fn smoke_test_multithreaded() {
@@ -14,25 +14,30 @@ mod scene_builder;
 use core::{
     any::{Any, TypeId},
     cmp::{Ordering, Reverse},
+    hash::Hash,
 };
 
 use arrayvec::ArrayVec;
 use bytemuck::Pod;
 
-use crate::collections::FixedVec;
+use crate::{collections::FixedVec, resources::Crc32};
 
 pub use game_object::{impl_game_object, ComponentInfo, GameObject};
 pub use scene_builder::SceneBuilder;
 
-/// The maximum amount of components in a [`GameObject`] type.
+/// The default maximum amount of components in a [`GameObject`] type.
+///
+/// Scenes with game objects with more components can be created with
+/// [`Scene::builder_with_max_components`], see the [`Scene`] documentation.
 pub const MAX_COMPONENTS: usize = 32;
 
-/// An [`ArrayVec`] with capacity for [`MAX_COMPONENTS`] elements.
+/// An [`ArrayVec`] with capacity for `N` elements, [`MAX_COMPONENTS`] by
+/// default.
 ///
 /// This exists since these are used throughout the game_objects module, and
 /// this allows dependents to e.g. implement the [`GameObject`] trait without
 /// depending on [`arrayvec`].
-pub type ComponentVec<T> = ArrayVec<T, MAX_COMPONENTS>;
+pub type ComponentVec<T, const N: usize = MAX_COMPONENTS> = ArrayVec<T, N>;
 
 /// Generic storage for the components inside [`Scene`].
 ///
@@ -49,6 +54,21 @@ impl ComponentColumn<'_> {
         self.component_info.type_id
     }
 
+    /// If the [`TypeId`] of `C` is the same as
+    /// [`ComponentColumn::component_type`], returns a shared borrow of the
+    /// components in this column.
+    ///
+    /// Like [`ComponentColumn::get_mut`], this generally doesn't need to be
+    /// interfaced with directly, as [`define_system`] calls this for the
+    /// parameters which are shared slices.
+    pub fn get<C: Any + Pod>(&self) -> Option<&[C]> {
+        if self.component_info.type_id == TypeId::of::<C>() {
+            Some(bytemuck::cast_slice::<u8, C>(&self.data))
+        } else {
+            None
+        }
+    }
+
     /// If the [`TypeId`] of `C` is the same as
     /// [`ComponentColumn::component_type`], returns a mutable borrow of the
     /// components in this column.
@@ -65,12 +85,12 @@ impl ComponentColumn<'_> {
     }
 }
 
-struct GameObjectTable<'a> {
+struct GameObjectTable<'a, const N: usize> {
     game_object_type: TypeId,
-    columns: ComponentVec<ComponentColumn<'a>>,
+    columns: ComponentVec<ComponentColumn<'a>, N>,
 }
 
-impl GameObjectTable<'_> {
+impl<const N: usize> GameObjectTable<'_, N> {
     /// Swaps the components in all component between the first and second
     /// index.
     ///
@@ -111,6 +131,20 @@ impl GameObjectTable<'_> {
             col.data.len() / col.component_info.size
         }
     }
+
+    fn capacity(&self) -> usize {
+        if self.columns.is_empty() {
+            0
+        } else {
+            let col = &self.columns[0];
+            (col.data.len() + col.data.spare_capacity()) / col.component_info.size
+        }
+    }
+
+    /// The combined size of one game object's components in this table.
+    fn game_object_size(&self) -> usize {
+        self.columns.iter().map(|col| col.component_info.size).sum()
+    }
 }
 
 /// Error type returned by [`Scene::spawn`].
@@ -129,10 +163,54 @@ pub enum SpawnError {
     /// to make room. Game objects can be removed with the [`Scene::delete`]
     /// function.
     NoSpace,
+    /// The component at index `column` returned from
+    /// [`GameObject::components`] is not of the same type as the one at the
+    /// same index in [`GameObject::component_infos`]. If the amount of
+    /// components doesn't match the amount of component types, `column` is
+    /// the amount of component types instead. This hints at a bug in a
+    /// manually written [`GameObject`] implementation.
+    ComponentTypeMismatch {
+        /// The index of the mismatched component.
+        column: usize,
+    },
+    /// The component at index `column` returned from
+    /// [`GameObject::components`] is not the size of the component type, so it
+    /// could not be written into the scene. Like
+    /// [`SpawnError::ComponentTypeMismatch`], this hints at a bug in a manually
+    /// written [`GameObject`] implementation.
+    ComponentWriteFailed {
+        /// The index of the component which could not be written.
+        column: usize,
+    },
+}
+
+/// Error type returned by [`Scene::migrate`].
+#[derive(Debug, PartialEq)]
+pub enum MigrateError {
+    /// Attempted to migrate a game object to a type that wasn't registered for
+    /// the [`Scene`] with [`SceneBuilder::with_game_object_type`].
+    UnregisteredGameObjectType,
+    /// The handle is invalid, e.g. it has been invalidated by a previous call
+    /// to [`Scene::delete`] or [`Scene::migrate`].
+    InvalidHandle,
+    /// The [`Scene`]'s storage limit for the target [`GameObject`] type has
+    /// been reached. See [`SpawnError::NoSpace`].
+    NoSpace,
+}
+
+/// Error type returned by [`Scene::restore_state`].
+#[derive(Debug, PartialEq)]
+pub enum RestoreStateError {
+    /// The state was serialized from a [`Scene`] with different game object
+    /// types or components.
+    LayoutMismatch,
+    /// The state is truncated, has extra bytes at the end, or contains more
+    /// game objects than the [`Scene`] has space for.
+    InvalidLength,
 }
 
 /// Temporary handle for operating on specific game objects. Invalidated by
-/// [`Scene::delete`].
+/// [`Scene::delete`] and [`Scene::migrate`].
 ///
 /// After invalidation, these handles don't refer to anything.
 #[derive(Clone, Copy, Debug)]
@@ -281,7 +359,32 @@ impl Iterator for GameObjectHandleIterator {
 /// // handles_to_delete would need to be re-acquired from a run_system call.
 /// scene.delete(&mut handles_to_delete).unwrap();
 /// ```
-pub struct Scene<'a> {
+///
+/// ### Game objects with many components
+///
+/// By default, game objects can have up to [`MAX_COMPONENTS`] components. The
+/// `N` parameter raises this limit, for scenes created with
+/// [`Scene::builder_with_max_components`]. The game objects in such a scene
+/// need to implement [`GameObject<N>`](GameObject) with the same `N`:
+///
+/// ```
+/// # static ARENA: &engine::allocators::LinearAllocator = engine::static_allocator!(100_000);
+/// # use engine::{game_objects::Scene, impl_game_object};
+/// # #[derive(Debug)]
+/// # struct Foo { pub a: u32 }
+/// impl_game_object! {
+///     impl GameObject<64> for Foo using components {
+///         a: u32,
+///     }
+/// }
+///
+/// let mut scene = Scene::<64>::builder_with_max_components()
+///     .with_game_object_type::<Foo>(5)
+///     .build(ARENA, ARENA)
+///     .unwrap();
+/// scene.spawn(Foo { a: 1 }).unwrap();
+/// ```
+pub struct Scene<'a, const N: usize = MAX_COMPONENTS> {
     /// A unique identifier for distinguishing between [`GameObjectHandle`]s
     /// acquired from different scenes.
     id: u32,
@@ -289,14 +392,14 @@ pub struct Scene<'a> {
     /// Incremented whenever indexes to game_object_tables or the tables' inner
     /// vecs are invalidated.
     generation: u64,
-    game_object_tables: FixedVec<'a, GameObjectTable<'a>>,
+    game_object_tables: FixedVec<'a, GameObjectTable<'a, N>>,
 }
 
-impl Scene<'_> {
+impl<'a, const N: usize> Scene<'a, N> {
     /// Spawns the game object into this scene if there's space for it.
     ///
     /// See the [`Scene`] documentation for example usage.
-    pub fn spawn<G: GameObject>(&mut self, object: G) -> Result<(), SpawnError> {
+    pub fn spawn<G: GameObject<N>>(&mut self, object: G) -> Result<(), SpawnError> {
         self.spawn_inner(object.type_id(), &object.components())
     }
 
@@ -315,10 +418,31 @@ impl Scene<'_> {
             return Err(SpawnError::NoSpace);
         }
 
-        for (col, (c_type, c_data)) in table.columns.iter_mut().zip(components) {
-            assert_eq!(col.component_info.type_id, *c_type);
+        // Validate everything before writing, to avoid leaving the columns
+        // with different lengths.
+        if components.len() != table.columns.len() {
+            return Err(SpawnError::ComponentTypeMismatch {
+                column: table.columns.len(),
+            });
+        }
+        for (column, col) in table.columns.iter().enumerate() {
+            let Some((c_type, c_data)) = components.get(column) else {
+                return Err(SpawnError::ComponentTypeMismatch { column });
+            };
+            if col.component_info.type_id != *c_type {
+                return Err(SpawnError::ComponentTypeMismatch { column });
+            }
+            if col.component_info.size != c_data.len() {
+                return Err(SpawnError::ComponentWriteFailed { column });
+            }
+        }
+
+        for (col, (_, c_data)) in table.columns.iter_mut().zip(components) {
+            // does not fail: the first column has room for one more game
+            // object, as checked above, every column has the same capacity in
+            // game objects, and the component sizes match the columns'
             let write_succeeded = col.data.extend_from_slice(c_data);
-            assert!(write_succeeded, "component should fit");
+            debug_assert!(write_succeeded);
         }
 
         Ok(())
@@ -346,29 +470,66 @@ impl Scene<'_> {
     /// documentation for example usage.
     pub fn run_system<F>(&mut self, mut system_func: F) -> bool
     where
-        F: FnMut(GameObjectHandleIterator, ComponentVec<&mut ComponentColumn>) -> bool,
+        F: FnMut(GameObjectHandleIterator, ComponentVec<&mut ComponentColumn, N>) -> bool,
     {
         profiling::function_scope!();
         let mut matched_any_components = false;
-        for (table_index, table) in self.game_object_tables.iter_mut().enumerate() {
-            let handle_iter = GameObjectHandleIterator {
-                scene_id: self.id,
-                scene_generation: self.generation,
-                game_object_table_index: table_index as u32,
-                next_game_object_index: 0,
-                total_game_objects: table.len(),
-            };
-
-            let mut columns = ArrayVec::new();
-            for col in &mut *table.columns {
-                columns.push(col);
-            }
-
+        for table_index in 0..self.game_object_tables.len() {
+            let (handle_iter, columns) = self.table_columns(table_index);
             matched_any_components |= system_func(handle_iter, columns);
         }
         matched_any_components
     }
 
+    /// Runs `system_func` once, passing in the components of the game objects
+    /// of type `G`, if `G` has been registered for this [`Scene`].
+    ///
+    /// Works like [`Scene::run_system`], but only for a single game object
+    /// type, so any closures generated by [`define_system`] can be used with
+    /// this as well.
+    ///
+    /// Returns `false` if `G` has not been registered, or if `system_func`
+    /// returns `false`, like [`Scene::run_system`].
+    pub fn for_each<G, F>(&mut self, system_func: F) -> bool
+    where
+        G: GameObject<N>,
+        F: FnOnce(GameObjectHandleIterator, ComponentVec<&mut ComponentColumn, N>) -> bool,
+    {
+        profiling::function_scope!();
+        if let Some(table_index) = (self.game_object_tables.iter())
+            .position(|table| table.game_object_type == TypeId::of::<G>())
+        {
+            let (handle_iter, columns) = self.table_columns(table_index);
+            system_func(handle_iter, columns)
+        } else {
+            false
+        }
+    }
+
+    fn table_columns(
+        &mut self,
+        table_index: usize,
+    ) -> (
+        GameObjectHandleIterator,
+        ComponentVec<&mut ComponentColumn<'a>, N>,
+    ) {
+        let table = &mut self.game_object_tables[table_index];
+        let handle_iter = GameObjectHandleIterator {
+            scene_id: self.id,
+            scene_generation: self.generation,
+            game_object_table_index: table_index as u32,
+            next_game_object_index: 0,
+            total_game_objects: table.len(),
+        };
+
+        let mut columns = ArrayVec::new();
+        for col in &mut *table.columns {
+            columns.push(col);
+        }
+
+        (handle_iter, columns)
+    }
+
     /// Deletes the game objects referred to by the given handles.
     ///
     /// If any handles are invalid (e.g. have been invalidated by a previous
@@ -414,6 +575,188 @@ impl Scene<'_> {
         }
     }
 
+    /// Changes the type of the game object referred to by the handle to `G`,
+    /// e.g. to add a marker component to a game object by migrating it to a
+    /// type with the same components and the marker.
+    ///
+    /// The components are copied over from the game object's current type for
+    /// as long as the component types of the current type and `G` match, in
+    /// order, i.e. for the component prefix shared by the types. Any further
+    /// components of `G` are zeroed, and any further components of the current
+    /// type are dropped.
+    ///
+    /// If the migration succeeds, all handles are invalidated, like with
+    /// [`Scene::delete`]. Migrating a game object to its current type does
+    /// nothing.
+    pub fn migrate<G: GameObject<N>>(
+        &mut self,
+        handle: GameObjectHandle,
+    ) -> Result<(), MigrateError> {
+        profiling::function_scope!();
+        if handle.scene_id != self.id || handle.scene_generation != self.generation {
+            return Err(MigrateError::InvalidHandle);
+        }
+        let source_index = handle.game_object_table_index as usize;
+        let object_index = handle.game_object_index;
+        if source_index >= self.game_object_tables.len()
+            || object_index >= self.game_object_tables[source_index].len()
+        {
+            return Err(MigrateError::InvalidHandle);
+        }
+
+        let Some(target_index) = (self.game_object_tables.iter())
+            .position(|table| table.game_object_type == TypeId::of::<G>())
+        else {
+            return Err(MigrateError::UnregisteredGameObjectType);
+        };
+        if target_index == source_index {
+            return Ok(());
+        }
+
+        let (source, target) = if source_index < target_index {
+            let (head, tail) = self.game_object_tables.split_at_mut(target_index);
+            (&mut head[source_index], &mut tail[0])
+        } else {
+            let (head, tail) = self.game_object_tables.split_at_mut(source_index);
+            (&mut tail[0], &mut head[target_index])
+        };
+
+        if target.columns.is_empty() || target.columns[0].data.is_full() {
+            return Err(MigrateError::NoSpace);
+        }
+
+        let shared_prefix_len = (source.columns.iter())
+            .zip(&target.columns)
+            .take_while(|(a, b)| a.component_type() == b.component_type())
+            .count();
+        for (i, target_col) in target.columns.iter_mut().enumerate() {
+            let size = target_col.component_info.size;
+            if i < shared_prefix_len {
+                let bytes = &source.columns[i].data[object_index * size..(object_index + 1) * size];
+                let write_succeeded = target_col.data.extend_from_slice(bytes);
+                assert!(write_succeeded, "component should fit");
+            } else {
+                for _ in 0..size {
+                    // does not panic: there's space for one more component, as
+                    // checked above for the first column, and every column has
+                    // the same capacity in components
+                    target_col.data.push(0).unwrap();
+                }
+            }
+        }
+
+        let source_last_index = source.len() - 1;
+        source.swap(object_index, source_last_index);
+        source.truncate(source_last_index);
+
+        self.generation += 1;
+
+        Ok(())
+    }
+
+    /// Returns the amount of bytes [`Scene::serialize_state`] would currently
+    /// write.
+    pub fn serialized_state_size(&self) -> usize {
+        let tables_size: usize = (self.game_object_tables.iter())
+            .map(|table| size_of::<u32>() + table.len() * table.game_object_size())
+            .sum();
+        size_of::<u32>() + tables_size
+    }
+
+    /// Writes the components of all the game objects in this scene into `out`,
+    /// to be restored later with [`Scene::restore_state`], e.g. for rolling
+    /// back the game state.
+    ///
+    /// Returns false without writing anything if `out` does not have space for
+    /// [`Scene::serialized_state_size`] bytes.
+    ///
+    /// The state contains the raw bytes of the components, and a checksum of
+    /// the layout of the scene, so it's only intended to be restored in the
+    /// same process, into the same scene or one built the same way.
+    pub fn serialize_state(&self, out: &mut FixedVec<u8>) -> bool {
+        profiling::function_scope!();
+        if out.spare_capacity() < self.serialized_state_size() {
+            return false;
+        }
+
+        let mut write = |bytes: &[u8]| {
+            let write_succeeded = out.extend_from_slice(bytes);
+            assert!(write_succeeded, "state should fit");
+        };
+        write(&self.layout_checksum().to_le_bytes());
+        for table in &*self.game_object_tables {
+            write(&(table.len() as u32).to_le_bytes());
+            for col in &table.columns {
+                write(&col.data);
+            }
+        }
+
+        true
+    }
+
+    /// Replaces all the game objects in this scene with the ones in the state
+    /// written by [`Scene::serialize_state`].
+    ///
+    /// The state is validated before restoring anything, so if an error is
+    /// returned, the scene is left as is. If the state is restored, all handles
+    /// are invalidated, like with [`Scene::delete`].
+    pub fn restore_state(&mut self, data: &[u8]) -> Result<(), RestoreStateError> {
+        profiling::function_scope!();
+        fn split_u32(data: &[u8]) -> Result<(u32, &[u8]), RestoreStateError> {
+            let (value, rest) =
+                (data.split_first_chunk::<4>()).ok_or(RestoreStateError::InvalidLength)?;
+            Ok((u32::from_le_bytes(*value), rest))
+        }
+
+        let (layout_checksum, tables_data) = split_u32(data)?;
+        if layout_checksum != self.layout_checksum() {
+            return Err(RestoreStateError::LayoutMismatch);
+        }
+
+        let mut remaining = tables_data;
+        for table in &*self.game_object_tables {
+            let (len, rest) = split_u32(remaining)?;
+            let len = len as usize;
+            let size = len * table.game_object_size();
+            if len > table.capacity() || rest.len() < size {
+                return Err(RestoreStateError::InvalidLength);
+            }
+            remaining = &rest[size..];
+        }
+        if !remaining.is_empty() {
+            return Err(RestoreStateError::InvalidLength);
+        }
+
+        let mut remaining = tables_data;
+        for table in &mut *self.game_object_tables {
+            // does not panic: the lengths were validated above
+            let (len, rest) = split_u32(remaining).unwrap();
+            remaining = rest;
+            table.truncate(0);
+            for col in &mut table.columns {
+                let size = len as usize * col.component_info.size;
+                let write_succeeded = col.data.extend_from_slice(&remaining[..size]);
+                assert!(write_succeeded, "components should fit");
+                remaining = &remaining[size..];
+            }
+        }
+
+        self.generation += 1;
+
+        Ok(())
+    }
+
+    fn layout_checksum(&self) -> u32 {
+        let mut checksum = Crc32::new();
+        for table in &*self.game_object_tables {
+            table.game_object_type.hash(&mut checksum);
+            for col in &table.columns {
+                col.component_type().hash(&mut checksum);
+            }
+        }
+        checksum.finish()
+    }
+
     /// Deletes all game objects in this scene.
     pub fn reset(&mut self) {
         for table in self.game_object_tables.iter_mut() {
@@ -424,8 +767,8 @@ impl Scene<'_> {
 
 /// Searches the columns for one containing components of type `C`, and returns
 /// it as a properly typed slice.
-pub fn extract_component_column<'a, C: Pod + Any>(
-    columns: &mut ComponentVec<&'a mut ComponentColumn>,
+pub fn extract_component_column<'a, C: Pod + Any, const N: usize>(
+    columns: &mut ComponentVec<&'a mut ComponentColumn, N>,
 ) -> Option<&'a mut [C]> {
     let index = columns
         .iter()
@@ -434,6 +777,19 @@ pub fn extract_component_column<'a, C: Pod + Any>(
     Some(col.get_mut().unwrap())
 }
 
+/// Searches the columns for one containing components of type `C`, and returns
+/// it as a properly typed shared slice. Like [`extract_component_column`], the
+/// column is removed from `columns`, but only borrowed immutably after that.
+pub fn extract_component_column_ref<'a, C: Pod + Any, const N: usize>(
+    columns: &mut ComponentVec<&'a mut ComponentColumn, N>,
+) -> Option<&'a [C]> {
+    let index = columns
+        .iter()
+        .position(|col| col.component_type() == TypeId::of::<C>())?;
+    let col: &'a ComponentColumn = columns.swap_remove(index);
+    Some(col.get().unwrap())
+}
+
 /// Gutputs a closure that can be passed into [`Scene::run_system`], handling
 /// extracting properly typed component columns based on the parameter list.
 ///
@@ -444,9 +800,12 @@ pub fn extract_component_column<'a, C: Pod + Any>(
 /// anonymous [`ComponentColumn`]s, and makes them available to the closure body
 /// as variables, using the names from the parameter list.
 ///
-/// For simplicity, the parameters after the first one can only be mutable
-/// slices, but note that [`Scene::run_system`] takes a [`FnMut`], so the
-/// closure can borrow and even mutate their captured environment.
+/// For simplicity, the parameters after the first one can only be slices, and
+/// their types need to be written out as `&[C]` or `&mut [C]`. Shared slices
+/// only borrow their column immutably, so systems which don't modify a
+/// component should take it as a shared slice. Note that [`Scene::run_system`]
+/// takes a [`FnMut`], so the closure can borrow and even mutate their captured
+/// environment.
 ///
 /// ### Example
 /// ```
@@ -477,26 +836,32 @@ pub fn extract_component_column<'a, C: Pod + Any>(
 /// ```
 #[macro_export]
 macro_rules! define_system {
-    (/param_defs/ $table:ident / $func_body:block / |$param_name:ident: $param_type:ty|) => {{
-        let col: Option<&mut [_]> = $crate::game_objects::extract_component_column(&mut $table);
-        let Some(col) = col else {
+    (/param_defs/ $table:ident / $func_body:block / |$param_name:ident: &mut [$param_type:ty]|) => {{
+        let col: Option<&mut [$param_type]> = $crate::game_objects::extract_component_column(&mut $table);
+        let Some($param_name) = col else {
+            return false;
+        };
+        $func_body
+    }};
+    (/param_defs/ $table:ident / $func_body:block / |$param_name:ident: &[$param_type:ty]|) => {{
+        let col: Option<&[$param_type]> = $crate::game_objects::extract_component_column_ref(&mut $table);
+        let Some($param_name) = col else {
             return false;
         };
-        let $param_name: $param_type = col;
         $func_body
     }};
-    (/param_defs/ $table:ident / $func_body:block / |$param_name:ident: $param_type:ty, $($rest_names:ident: $rest_types:ty),+|) => {
+    (/param_defs/ $table:ident / $func_body:block / |$param_name:ident: &$($mut_:ident)? [$param_type:ty], $($rest_names:ident: &$($rest_muts:ident)? [$rest_types:ty]),+|) => {
         define_system!(/param_defs/ $table / {
-            define_system!(/param_defs/ $table / $func_body / |$param_name: $param_type|)
-        } / |$($rest_names: $rest_types),+|)
+            define_system!(/param_defs/ $table / $func_body / |$param_name: &$($mut_)? [$param_type]|)
+        } / |$($rest_names: &$($rest_muts)? [$rest_types]),+|)
     };
 
-    (|$handle_name:pat_param, $($param_name:ident: $param_type:ty),+| $func_body:block) => {
+    (|$handle_name:pat_param, $($param_name:ident: &$($param_mut:ident)? [$param_type:ty]),+| $func_body:block) => {
         |#[allow(unused_variables)] handle_iter: $crate::game_objects::GameObjectHandleIterator,
-         mut table: $crate::game_objects::ComponentVec<&mut $crate::game_objects::ComponentColumn>| {
+         mut table: $crate::game_objects::ComponentVec<&mut $crate::game_objects::ComponentColumn, _>| {
             $crate::profiling::scope!("system_func", concat!(file!(), ":", line!()));
             let $handle_name = handle_iter;
-            define_system!(/param_defs/ table / $func_body / |$($param_name: $param_type),+|);
+            define_system!(/param_defs/ table / $func_body / |$($param_name: &$($param_mut)? [$param_type]),+|);
             true
         }
     };
@@ -510,11 +875,11 @@ mod tests {
     use bytemuck::{Pod, Zeroable};
 
     use crate::{
-        allocators::LinearAllocator, game_objects::GameObjectHandle, impl_game_object,
-        static_allocator,
+        allocators::LinearAllocator, collections::FixedVec, game_objects::GameObjectHandle,
+        impl_game_object, static_allocator,
     };
 
-    use super::{Scene, SpawnError};
+    use super::{MigrateError, RestoreStateError, Scene, SpawnError};
 
     #[test]
     fn run_scene() {
@@ -637,4 +1002,553 @@ mod tests {
         }));
         assert!(processed_count > 0);
     }
+
+    #[test]
+    fn migrate_keeps_shared_components_and_zeroes_the_rest() {
+        #[derive(Clone, Copy, Debug, PartialEq)]
+        struct Position {
+            x: i32,
+        }
+        unsafe impl Zeroable for Position {}
+        unsafe impl Pod for Position {}
+
+        #[derive(Clone, Copy, Debug, PartialEq)]
+        struct Stunned {
+            frames_left: u32,
+        }
+        unsafe impl Zeroable for Stunned {}
+        unsafe impl Pod for Stunned {}
+
+        #[derive(Debug)]
+        struct Walker {
+            pos: Position,
+        }
+        impl_game_object! {
+            impl GameObject for Walker using components {
+                pos: Position,
+            }
+        }
+
+        #[derive(Debug)]
+        struct StunnedWalker {
+            pos: Position,
+            stunned: Stunned,
+        }
+        impl_game_object! {
+            impl GameObject for StunnedWalker using components {
+                pos: Position,
+                stunned: Stunned,
+            }
+        }
+
+        #[derive(Debug)]
+        struct Unregistered {
+            pos: Position,
+        }
+        impl_game_object! {
+            impl GameObject for Unregistered using components {
+                pos: Position,
+            }
+        }
+
+        static ARENA: &LinearAllocator = static_allocator!(10_000);
+        let temp_arena = LinearAllocator::new(ARENA, 1000).unwrap();
+        let mut scene = Scene::builder()
+            .with_game_object_type::<Walker>(2)
+            .with_game_object_type::<StunnedWalker>(1)
+            .build(ARENA, &temp_arena)
+            .unwrap();
+
+        let stunned = Stunned { frames_left: 5 };
+        scene
+            .spawn(Walker {
+                pos: Position { x: 1 },
+            })
+            .unwrap();
+        scene
+            .spawn(StunnedWalker {
+                pos: Position { x: 2 },
+                stunned,
+            })
+            .unwrap();
+
+        let mut stunned_handle = None;
+        scene.run_system(define_system!(|handles, _stunned: &[Stunned]| {
+            stunned_handle = handles.last();
+        }));
+        let stunned_handle = stunned_handle.unwrap();
+        let mut walker_handle = None;
+        scene.run_system(define_system!(|handles, pos: &[Position]| {
+            for (handle, pos) in handles.zip(pos) {
+                if pos.x == 1 {
+                    walker_handle = Some(handle);
+                }
+            }
+        }));
+        let walker_handle = walker_handle.unwrap();
+
+        assert_eq!(
+            Err(MigrateError::UnregisteredGameObjectType),
+            scene.migrate::<Unregistered>(walker_handle),
+        );
+        assert_eq!(
+            Err(MigrateError::NoSpace),
+            scene.migrate::<StunnedWalker>(walker_handle),
+            "the only StunnedWalker slot should already be in use",
+        );
+
+        // Unstun the stunned walker, dropping the Stunned component, and then
+        // stun the other walker, which should get a zeroed Stunned component.
+        scene.migrate::<Walker>(stunned_handle).unwrap();
+        assert_eq!(
+            Err(MigrateError::InvalidHandle),
+            scene.migrate::<StunnedWalker>(walker_handle),
+            "the previous migration should've invalidated the handle",
+        );
+        let mut walker_handle = None;
+        scene.run_system(define_system!(|handles, pos: &[Position]| {
+            for (handle, pos) in handles.zip(pos) {
+                if pos.x == 1 {
+                    walker_handle = Some(handle);
+                }
+            }
+        }));
+        scene
+            .migrate::<StunnedWalker>(walker_handle.unwrap())
+            .unwrap();
+
+        let mut walkers = ArrayVec::<Position, 2>::new();
+        scene.run_system(define_system!(|_, pos: &[Position]| {
+            walkers.extend(pos.iter().copied());
+        }));
+        walkers.sort_unstable_by_key(|pos| pos.x);
+        assert_eq!([Position { x: 1 }, Position { x: 2 }], *walkers);
+
+        let mut stunned_walkers = 0;
+        scene.run_system(define_system!(
+            |_, pos: &[Position], stunned: &[Stunned]| {
+                for (pos, stunned) in pos.iter().zip(stunned) {
+                    assert_eq!(Position { x: 1 }, *pos);
+                    assert_eq!(Stunned { frames_left: 0 }, *stunned);
+                    stunned_walkers += 1;
+                }
+            }
+        ));
+        assert_eq!(1, stunned_walkers);
+    }
+
+    #[test]
+    fn for_each_only_runs_for_the_requested_type() {
+        #[derive(Clone, Copy, Debug)]
+        struct Health {
+            value: u32,
+        }
+        unsafe impl Zeroable for Health {}
+        unsafe impl Pod for Health {}
+
+        #[derive(Debug)]
+        struct Player {
+            health: Health,
+        }
+        impl_game_object! {
+            impl GameObject for Player using components {
+                health: Health,
+            }
+        }
+
+        #[derive(Debug)]
+        struct Enemy {
+            health: Health,
+        }
+        impl_game_object! {
+            impl GameObject for Enemy using components {
+                health: Health,
+            }
+        }
+
+        #[derive(Debug)]
+        struct Boss {
+            health: Health,
+        }
+        impl_game_object! {
+            impl GameObject for Boss using components {
+                health: Health,
+            }
+        }
+
+        static ARENA: &LinearAllocator = static_allocator!(10_000);
+        let temp_arena = LinearAllocator::new(ARENA, 1000).unwrap();
+        let mut scene = Scene::builder()
+            .with_game_object_type::<Player>(1)
+            .with_game_object_type::<Enemy>(3)
+            .build(ARENA, &temp_arena)
+            .unwrap();
+
+        scene
+            .spawn(Player {
+                health: Health { value: 10 },
+            })
+            .unwrap();
+        for _ in 0..3 {
+            scene
+                .spawn(Enemy {
+                    health: Health { value: 10 },
+                })
+                .unwrap();
+        }
+
+        let mut damaged = 0;
+        assert!(
+            scene.for_each::<Enemy, _>(define_system!(|_, health: &mut [Health]| {
+                for health in health {
+                    health.value -= 5;
+                    damaged += 1;
+                }
+            }))
+        );
+        assert_eq!(3, damaged);
+
+        let mut healths = ArrayVec::<u32, 4>::new();
+        assert!(
+            scene.for_each::<Player, _>(define_system!(|_, health: &[Health]| {
+                healths.extend(health.iter().map(|health| health.value));
+            }))
+        );
+        assert_eq!([10], *healths);
+        assert!(
+            scene.for_each::<Enemy, _>(define_system!(|_, health: &[Health]| {
+                healths.extend(health.iter().map(|health| health.value));
+            }))
+        );
+        assert_eq!([10, 5, 5, 5], *healths);
+
+        // The boss isn't in the scene, so there's nothing to run the system on.
+        assert!(
+            !scene.for_each::<Boss, _>(define_system!(|_, health: &[Health]| {
+                healths.extend(health.iter().map(|health| health.value));
+            }))
+        );
+        assert_eq!([10, 5, 5, 5], *healths);
+    }
+
+    #[test]
+    fn restoring_state_undoes_changes() {
+        #[derive(Clone, Copy, Debug, PartialEq)]
+        struct Position {
+            x: i32,
+            y: i32,
+        }
+        unsafe impl Zeroable for Position {}
+        unsafe impl Pod for Position {}
+
+        #[derive(Debug)]
+        struct Ball {
+            pos: Position,
+        }
+        impl_game_object! {
+            impl GameObject for Ball using components {
+                pos: Position,
+            }
+        }
+
+        #[derive(Debug)]
+        struct Wall {
+            pos: Position,
+            height: u8,
+        }
+        impl_game_object! {
+            impl GameObject for Wall using components {
+                pos: Position,
+                height: u8,
+            }
+        }
+
+        static ARENA: &LinearAllocator = static_allocator!(100_000);
+        let temp_arena = LinearAllocator::new(ARENA, 1000).unwrap();
+        let build_scene = || {
+            Scene::builder()
+                .with_game_object_type::<Ball>(4)
+                .with_game_object_type::<Wall>(2)
+                .build(ARENA, &temp_arena)
+                .unwrap()
+        };
+        let mut scene = build_scene();
+
+        for x in 0..3 {
+            scene
+                .spawn(Ball {
+                    pos: Position { x, y: 0 },
+                })
+                .unwrap();
+        }
+        let pos = Position { x: 5, y: 5 };
+        scene.spawn(Wall { pos, height: 3 }).unwrap();
+
+        let mut snapshot = FixedVec::new(ARENA, scene.serialized_state_size()).unwrap();
+        assert!(scene.serialize_state(&mut snapshot));
+        assert!(snapshot.is_full());
+
+        // Move, delete, and spawn some game objects
+        scene.run_system(define_system!(|_, pos: &mut [Position]| {
+            for pos in pos {
+                pos.y += 10;
+            }
+        }));
+        let mut last_ball = None;
+        scene.for_each::<Ball, _>(define_system!(|handles, _pos: &[Position]| {
+            last_ball = handles.last();
+        }));
+        scene.delete(&mut [last_ball.unwrap()]).unwrap();
+        scene.spawn(Wall { pos, height: 1 }).unwrap();
+
+        scene.restore_state(&snapshot).unwrap();
+        assert_eq!(
+            Err(MigrateError::InvalidHandle),
+            scene.migrate::<Wall>(last_ball.unwrap()),
+            "restoring the state should've invalidated the handle",
+        );
+
+        let mut balls = ArrayVec::<Position, 4>::new();
+        scene.for_each::<Ball, _>(define_system!(|_, pos: &[Position]| {
+            balls.extend(pos.iter().copied());
+        }));
+        let expected_balls = [0, 1, 2].map(|x| Position { x, y: 0 });
+        assert_eq!(expected_balls, *balls);
+        let mut walls = ArrayVec::<(Position, u8), 2>::new();
+        scene.for_each::<Wall, _>(define_system!(|_, pos: &[Position], height: &[u8]| {
+            walls.extend(pos.iter().copied().zip(height.iter().copied()));
+        }));
+        assert_eq!([(pos, 3)], *walls);
+
+        // Invalid states should be rejected without changing anything
+        assert_eq!(
+            Err(RestoreStateError::InvalidLength),
+            scene.restore_state(&snapshot[..snapshot.len() - 1]),
+        );
+        let other_scene = Scene::builder()
+            .with_game_object_type::<Ball>(4)
+            .build(ARENA, &temp_arena)
+            .unwrap();
+        let mut other_snapshot = FixedVec::new(ARENA, 64).unwrap();
+        assert!(other_scene.serialize_state(&mut other_snapshot));
+        assert_eq!(
+            Err(RestoreStateError::LayoutMismatch),
+            scene.restore_state(&other_snapshot),
+        );
+        assert!(build_scene().restore_state(&snapshot).is_ok());
+    }
+
+    #[test]
+    fn systems_with_only_shared_params_read_the_components() {
+        #[derive(Debug)]
+        struct Pair {
+            a: u32,
+            b: u64,
+        }
+        impl_game_object! {
+            impl GameObject for Pair using components {
+                a: u32,
+                b: u64,
+            }
+        }
+
+        static ARENA: &LinearAllocator = static_allocator!(10_000);
+        let temp_arena = LinearAllocator::new(ARENA, 1000).unwrap();
+        let mut scene = Scene::builder()
+            .with_game_object_type::<Pair>(3)
+            .build(ARENA, &temp_arena)
+            .unwrap();
+        for i in 1..=3 {
+            scene
+                .spawn(Pair {
+                    a: i,
+                    b: i as u64 * 10,
+                })
+                .unwrap();
+        }
+
+        let mut sum = 0;
+        let matched = scene.run_system(define_system!(|_, b: &[u64], a: &[u32]| {
+            for (a, b) in a.iter().zip(b) {
+                sum += *a as u64 + *b;
+            }
+        }));
+        assert!(matched);
+        assert_eq!(66, sum);
+
+        // Shared and mutable params can still be mixed.
+        scene.run_system(define_system!(|_, a: &[u32], b: &mut [u64]| {
+            for (a, b) in a.iter().zip(b) {
+                *b += *a as u64;
+            }
+        }));
+        let mut bs = ArrayVec::<u64, 3>::new();
+        assert!(
+            !scene.run_system(define_system!(|_, b: &[u64], _missing: &[u8]| {
+                bs.extend(b.iter().copied());
+            }))
+        );
+        scene.run_system(define_system!(|_, b: &[u64]| {
+            bs.extend(b.iter().copied());
+        }));
+        assert_eq!([11, 22, 33], *bs);
+    }
+
+    #[test]
+    fn misordered_manual_game_object_impl_is_an_error() {
+        use core::any::TypeId;
+
+        use super::{ComponentInfo, ComponentVec, GameObject};
+
+        #[derive(Debug)]
+        struct Misordered {
+            a: u32,
+            b: u64,
+        }
+        impl GameObject for Misordered {
+            fn component_infos() -> ComponentVec<ComponentInfo> {
+                let mut infos = ComponentVec::new();
+                infos.push(ComponentInfo {
+                    type_id: TypeId::of::<u32>(),
+                    size: size_of::<u32>(),
+                    alignment: align_of::<u32>(),
+                });
+                infos.push(ComponentInfo {
+                    type_id: TypeId::of::<u64>(),
+                    size: size_of::<u64>(),
+                    alignment: align_of::<u64>(),
+                });
+                infos
+            }
+
+            fn components(&self) -> ComponentVec<(TypeId, &[u8])> {
+                let mut components = ComponentVec::new();
+                components.push((TypeId::of::<u64>(), bytemuck::bytes_of(&self.b)));
+                components.push((TypeId::of::<u32>(), bytemuck::bytes_of(&self.a)));
+                components
+            }
+        }
+
+        static ARENA: &LinearAllocator = static_allocator!(10_000);
+        let temp_arena = LinearAllocator::new(ARENA, 1000).unwrap();
+        let mut scene = Scene::builder()
+            .with_game_object_type::<Misordered>(1)
+            .build(ARENA, &temp_arena)
+            .unwrap();
+        assert_eq!(
+            Err(SpawnError::ComponentTypeMismatch { column: 0 }),
+            scene.spawn(Misordered { a: 1, b: 2 }),
+        );
+
+        // Nothing should've been written, so the scene is still empty.
+        let mut count = 0;
+        scene.run_system(define_system!(|_, a: &[u32]| {
+            count += a.len();
+        }));
+        assert_eq!(0, count);
+    }
+
+    #[test]
+    fn extra_components_in_a_manual_game_object_impl_are_an_error() {
+        use core::any::TypeId;
+
+        use super::{ComponentInfo, ComponentVec, GameObject};
+
+        #[derive(Debug)]
+        struct Extra {
+            a: u32,
+            b: u64,
+        }
+        impl GameObject for Extra {
+            fn component_infos() -> ComponentVec<ComponentInfo> {
+                let mut infos = ComponentVec::new();
+                infos.push(ComponentInfo {
+                    type_id: TypeId::of::<u32>(),
+                    size: size_of::<u32>(),
+                    alignment: align_of::<u32>(),
+                });
+                infos
+            }
+
+            fn components(&self) -> ComponentVec<(TypeId, &[u8])> {
+                let mut components = ComponentVec::new();
+                components.push((TypeId::of::<u32>(), bytemuck::bytes_of(&self.a)));
+                components.push((TypeId::of::<u64>(), bytemuck::bytes_of(&self.b)));
+                components
+            }
+        }
+
+        static ARENA: &LinearAllocator = static_allocator!(10_000);
+        let temp_arena = LinearAllocator::new(ARENA, 1000).unwrap();
+        let mut scene = Scene::builder()
+            .with_game_object_type::<Extra>(1)
+            .build(ARENA, &temp_arena)
+            .unwrap();
+        assert_eq!(
+            Err(SpawnError::ComponentTypeMismatch { column: 1 }),
+            scene.spawn(Extra { a: 1, b: 2 }),
+        );
+
+        let mut count = 0;
+        scene.run_system(define_system!(|_, a: &[u32]| {
+            count += a.len();
+        }));
+        assert_eq!(0, count);
+    }
+
+    #[test]
+    fn game_objects_can_have_more_components_under_a_raised_cap() {
+        #[derive(Clone, Copy, Debug, Default)]
+        struct Counter<const I: usize>(u32);
+        unsafe impl<const I: usize> Zeroable for Counter<I> {}
+        unsafe impl<const I: usize> Pod for Counter<I> {}
+
+        macro_rules! define_big_game_object {
+            ($($field:ident: $index:literal),+) => {
+                #[derive(Debug, Default)]
+                struct Big {
+                    $($field: Counter<$index>),+
+                }
+                impl_game_object! {
+                    impl GameObject<64> for Big using components {
+                        $($field: Counter<$index>),+
+                    }
+                }
+            };
+        }
+        define_big_game_object!(
+            c0: 0, c1: 1, c2: 2, c3: 3, c4: 4, c5: 5, c6: 6, c7: 7, c8: 8, c9: 9,
+            c10: 10, c11: 11, c12: 12, c13: 13, c14: 14, c15: 15, c16: 16, c17: 17, c18: 18,
+            c19: 19, c20: 20, c21: 21, c22: 22, c23: 23, c24: 24, c25: 25, c26: 26, c27: 27,
+            c28: 28, c29: 29, c30: 30, c31: 31, c32: 32, c33: 33, c34: 34, c35: 35, c36: 36,
+            c37: 37, c38: 38, c39: 39
+        );
+
+        static ARENA: &LinearAllocator = static_allocator!(10_000);
+        let temp_arena = LinearAllocator::new(ARENA, 5000).unwrap();
+        let mut scene = Scene::<64>::builder_with_max_components()
+            .with_game_object_type::<Big>(2)
+            .build(ARENA, &temp_arena)
+            .unwrap();
+        scene
+            .spawn(Big {
+                c39: Counter(39),
+                ..Default::default()
+            })
+            .unwrap();
+
+        let mut matched = 0;
+        scene.run_system(define_system!(
+            |_, first: &mut [Counter<0>], last: &[Counter<39>]| {
+                for (first, last) in first.iter_mut().zip(last) {
+                    first.0 = last.0 + 1;
+                    matched += 1;
+                }
+            }
+        ));
+        assert_eq!(1, matched);
+        scene.for_each::<Big, _>(define_system!(|_, first: &[Counter<0>]| {
+            assert_eq!(40, first[0].0);
+        }));
+    }
 }
@@ -7,7 +7,7 @@ use core::{
     fmt::Debug,
 };
 
-use super::ComponentVec;
+use super::{ComponentVec, MAX_COMPONENTS};
 
 /// Type description for allocation and type comparison of components. Generated
 /// by [`impl_game_object`](super::impl_game_object).
@@ -26,18 +26,21 @@ pub struct ComponentInfo {
 /// Trait that game object types implement to be able to be added to a
 /// [`Scene`](super::Scene). Impl generated with
 /// [`impl_game_object`](super::impl_game_object).
-pub trait GameObject: Any + Debug {
+///
+/// `N` is the maximum amount of components of the game object, which must match
+/// the `N` of the [`Scene`](super::Scene) the game object is added to.
+pub trait GameObject<const N: usize = MAX_COMPONENTS>: Any + Debug {
     /// Returns the allocation and type comparison details for the components of
     /// this game object type.
     ///
     /// The order of the infos is the same as [`GameObject::components`].
-    fn component_infos() -> ComponentVec<ComponentInfo>;
+    fn component_infos() -> ComponentVec<ComponentInfo, N>;
     /// Returns a single game object's components as anonymous byte slices, with
     /// the type id for component type detection.
     ///
     /// The order of the components is the same as
     /// [`GameObject::component_infos`].
-    fn components(&self) -> ComponentVec<(TypeId, &[u8])>;
+    fn components(&self) -> ComponentVec<(TypeId, &[u8]), N>;
 }
 
 /// Generates a [`GameObject`] impl block for a type.
@@ -86,6 +89,10 @@ pub trait GameObject: Any + Debug {
 /// }
 /// ```
 ///
+/// Game objects with more than [`MAX_COMPONENTS`] components can be defined
+/// by specifying a higher limit, as in `impl GameObject<64> for Foo using
+/// components { ... }`, for use in scenes with the same limit.
+///
 /// For a more fully featured example for using these game objects, see the
 /// documentation for [`Scene`](super::Scene).
 #[macro_export]
@@ -116,12 +123,12 @@ macro_rules! impl_game_object {
     };
 
     // The main impl-block generator
-    (impl GameObject for $struct_name:ident using components {
+    (impl GameObject$(<$max_components:tt>)? for $struct_name:ident using components {
         $($field_names:ident: $field_types:ty),+$(,)?
     }) => {
-        impl $crate::game_objects::GameObject for $struct_name {
+        impl $crate::game_objects::GameObject$(<$max_components>)? for $struct_name {
             fn component_infos(
-            ) -> $crate::game_objects::ComponentVec<$crate::game_objects::ComponentInfo>
+            ) -> $crate::game_objects::ComponentVec<$crate::game_objects::ComponentInfo $(, $max_components)?>
             {
                 let mut infos = $crate::game_objects::ComponentVec::new();
                 $crate::impl_game_object!(/push_info infos/ $($field_types),+);
@@ -130,10 +137,11 @@ macro_rules! impl_game_object {
 
             fn components(
                 &self,
-            ) -> $crate::game_objects::ComponentVec<(core::any::TypeId, &[u8])>
+            ) -> $crate::game_objects::ComponentVec<(core::any::TypeId, &[u8]) $(, $max_components)?>
             {
                 let mut components: $crate::game_objects::ComponentVec::<
-                    (core::any::TypeId, &[u8]),
+                    (core::any::TypeId, &[u8])
+                    $(, $max_components)?
                 > = $crate::game_objects::ComponentVec::new();
                 $crate::impl_game_object!(/push_component components, self/ $($field_names: $field_types),+);
                 components
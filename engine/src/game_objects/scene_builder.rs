@@ -11,38 +11,41 @@ use arrayvec::ArrayVec;
 
 use crate::{allocators::LinearAllocator, collections::FixedVec};
 
-use super::{ComponentColumn, ComponentInfo, ComponentVec, GameObject, GameObjectTable, Scene};
+use super::{
+    ComponentColumn, ComponentInfo, ComponentVec, GameObject, GameObjectTable, Scene,
+    MAX_COMPONENTS,
+};
 
-struct GameObjectInfo {
-    component_infos: ComponentVec<ComponentInfo>,
+struct GameObjectInfo<const N: usize> {
+    component_infos: ComponentVec<ComponentInfo, N>,
     game_object_type: TypeId,
     game_object_count: usize,
 }
 
 #[allow(clippy::large_enum_variant)]
-enum GameObjectInfoLinkedList<'a> {
+enum GameObjectInfoLinkedList<'a, const N: usize> {
     End,
     Element {
-        next: &'a GameObjectInfoLinkedList<'a>,
-        info: GameObjectInfo,
+        next: &'a GameObjectInfoLinkedList<'a, N>,
+        info: GameObjectInfo<N>,
     },
 }
 
-impl<'a> IntoIterator for &'a GameObjectInfoLinkedList<'a> {
-    type Item = &'a GameObjectInfo;
-    type IntoIter = GameObjectInfoLinkedListIterator<'a>;
+impl<'a, const N: usize> IntoIterator for &'a GameObjectInfoLinkedList<'a, N> {
+    type Item = &'a GameObjectInfo<N>;
+    type IntoIter = GameObjectInfoLinkedListIterator<'a, N>;
 
     fn into_iter(self) -> Self::IntoIter {
         GameObjectInfoLinkedListIterator { next: self }
     }
 }
 
-struct GameObjectInfoLinkedListIterator<'a> {
-    next: &'a GameObjectInfoLinkedList<'a>,
+struct GameObjectInfoLinkedListIterator<'a, const N: usize> {
+    next: &'a GameObjectInfoLinkedList<'a, N>,
 }
 
-impl<'a> Iterator for GameObjectInfoLinkedListIterator<'a> {
-    type Item = &'a GameObjectInfo;
+impl<'a, const N: usize> Iterator for GameObjectInfoLinkedListIterator<'a, N> {
+    type Item = &'a GameObjectInfo<N>;
 
     fn next(&mut self) -> Option<Self::Item> {
         match self.next {
@@ -56,14 +59,17 @@ impl<'a> Iterator for GameObjectInfoLinkedListIterator<'a> {
 }
 
 /// Builder for [`Scene`].
-pub struct SceneBuilder<'a> {
-    game_object_infos: GameObjectInfoLinkedList<'a>,
+pub struct SceneBuilder<'a, const N: usize = MAX_COMPONENTS> {
+    game_object_infos: GameObjectInfoLinkedList<'a, N>,
 }
 
-impl<'a> SceneBuilder<'a> {
+impl<'a, const N: usize> SceneBuilder<'a, N> {
     /// Adds `G` as a game object type and reserves space for a maximum of
     /// `count` game objects at a time.
-    pub fn with_game_object_type<G: GameObject>(&'a mut self, count: usize) -> SceneBuilder<'a> {
+    pub fn with_game_object_type<G: GameObject<N>>(
+        &'a mut self,
+        count: usize,
+    ) -> SceneBuilder<'a, N> {
         SceneBuilder {
             game_object_infos: GameObjectInfoLinkedList::Element {
                 next: &self.game_object_infos,
@@ -80,13 +86,22 @@ impl<'a> SceneBuilder<'a> {
 impl Scene<'_> {
     /// Creates a [`SceneBuilder`] which is used to create a [`Scene`].
     pub fn builder<'a>() -> SceneBuilder<'a> {
+        Scene::builder_with_max_components()
+    }
+}
+
+impl<const N: usize> Scene<'_, N> {
+    /// Creates a [`SceneBuilder`] which is used to create a [`Scene`] for game
+    /// objects with up to `N` components, instead of the default
+    /// [`MAX_COMPONENTS`].
+    pub fn builder_with_max_components<'a>() -> SceneBuilder<'a, N> {
         SceneBuilder {
             game_object_infos: GameObjectInfoLinkedList::End,
         }
     }
 }
 
-impl SceneBuilder<'_> {
+impl<const N: usize> SceneBuilder<'_, N> {
     /// Allocates memory for and creates a [`Scene`], if `arena` has enough
     /// memory for it.
     ///
@@ -103,7 +118,7 @@ impl SceneBuilder<'_> {
         self,
         arena: &'a LinearAllocator,
         temp_arena: &LinearAllocator,
-    ) -> Option<Scene<'a>> {
+    ) -> Option<Scene<'a, N>> {
         profiling::function_scope!();
 
         // Count how many component types there are across every game object type
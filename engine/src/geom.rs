@@ -2,6 +2,12 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+mod fixed;
+mod spatial_grid;
+
+pub use fixed::{Fixed, FixedRect};
+pub use spatial_grid::SpatialGrid;
+
 /// A floating-point axis-aligned 2D rectangle.
 #[derive(Debug, Clone, Copy)]
 pub struct Rect {
@@ -30,4 +36,109 @@ impl Rect {
             h,
         }
     }
+
+    /// Returns true if this rectangle and `other` overlap.
+    ///
+    /// Rectangles which only touch at their edges, i.e. whose overlap has zero
+    /// area, do not count as intersecting.
+    pub fn intersects(&self, other: &Rect) -> bool {
+        self.x < other.x + other.w
+            && other.x < self.x + self.w
+            && self.y < other.y + other.h
+            && other.y < self.y + self.h
+    }
+
+    /// Returns the area covered by both this rectangle and `other`, if they
+    /// [intersect](Rect::intersects).
+    pub fn intersection(&self, other: &Rect) -> Option<Rect> {
+        if !self.intersects(other) {
+            return None;
+        }
+        let x0 = self.x.max(other.x);
+        let y0 = self.y.max(other.y);
+        let x1 = (self.x + self.w).min(other.x + other.w);
+        let y1 = (self.y + self.h).min(other.y + other.h);
+        Some(Rect::xywh(x0, y0, x1 - x0, y1 - y0))
+    }
+
+    /// Returns true if the point is inside this rectangle. Points on the left
+    /// and top edges are inside, points on the right and bottom edges are not,
+    /// so that a point is only inside one of two rectangles sharing an edge.
+    pub fn contains_point(&self, x: f32, y: f32) -> bool {
+        self.x <= x && x < self.x + self.w && self.y <= y && y < self.y + self.h
+    }
+}
+
+/// Approximates the square root of `x`, with a relative error in the same
+/// ballpark as the precision of `f32`. Returns 0 for non-positive inputs.
+///
+/// Needed because `f32::sqrt` is not available in `core`.
+pub(crate) fn sqrt(x: f32) -> f32 {
+    if x.is_nan() || x <= 0.0 {
+        return 0.0;
+    }
+    // Halving the exponent gets the initial guess within a few percent of the
+    // result, after which Newton's method converges quickly.
+    let mut guess = f32::from_bits((x.to_bits() >> 1) + 0x1FC0_0000);
+    for _ in 0..3 {
+        guess = 0.5 * (guess + x / guess);
+    }
+    guess
+}
+
+/// Rounds `x` to the nearest integer, with halfway cases rounded away from
+/// zero. Values outside of the range of `i32` are clamped to it.
+///
+/// Needed because `f32::round` is not available in `core`.
+pub(crate) fn round(x: f32) -> f32 {
+    let half = if x < 0.0 { -0.5 } else { 0.5 };
+    (x + half) as i32 as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Rect;
+
+    #[test]
+    fn separated_rects_do_not_intersect() {
+        let a = Rect::xywh(0.0, 0.0, 10.0, 10.0);
+        let b = Rect::xywh(20.0, 5.0, 10.0, 10.0);
+        assert!(!a.intersects(&b) && !b.intersects(&a));
+        assert!(a.intersection(&b).is_none());
+    }
+
+    #[test]
+    fn touching_rects_do_not_intersect() {
+        let a = Rect::xywh(0.0, 0.0, 10.0, 10.0);
+        let right = Rect::xywh(10.0, 0.0, 10.0, 10.0);
+        let below = Rect::xywh(5.0, 10.0, 10.0, 10.0);
+        assert!(!a.intersects(&right) && !right.intersects(&a));
+        assert!(!a.intersects(&below) && !below.intersects(&a));
+        assert!(a.intersection(&right).is_none());
+
+        assert!(a.contains_point(0.0, 0.0));
+        assert!(!a.contains_point(10.0, 5.0));
+        assert!(right.contains_point(10.0, 5.0));
+    }
+
+    #[test]
+    fn overlapping_rects_intersect() {
+        let a = Rect::xywh(0.0, 0.0, 10.0, 10.0);
+        let b = Rect::around(10.0, 10.0, 10.0, 6.0);
+        assert!(a.intersects(&b) && b.intersects(&a));
+        let overlap = a.intersection(&b).unwrap();
+        assert_eq!(
+            (5.0, 7.0, 5.0, 3.0),
+            (overlap.x, overlap.y, overlap.w, overlap.h)
+        );
+
+        let inner = Rect::xywh(2.0, 3.0, 4.0, 5.0);
+        let overlap = a.intersection(&inner).unwrap();
+        assert_eq!(
+            (2.0, 3.0, 4.0, 5.0),
+            (overlap.x, overlap.y, overlap.w, overlap.h)
+        );
+        assert!(a.contains_point(9.5, 9.5));
+        assert!(!a.contains_point(-0.5, 5.0));
+    }
 }
@@ -0,0 +1,268 @@
+// SPDX-FileCopyrightText: 2025 Jens Pitkänen <jens.pitkanen@helsinki.fi>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use core::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+
+use super::Rect;
+
+/// A signed fixed-point number with 16 integer bits and 16 fractional bits.
+///
+/// Unlike floating-point math, the results of the operations on these are
+/// exactly the same on every platform and optimization level, which makes this
+/// a good fit for gameplay state that needs to be deterministic, e.g. for
+/// lockstep multiplayer or replays. The values can be converted to `f32` for
+/// rendering with [`Fixed::to_f32`], or by drawing a [`FixedRect`].
+///
+/// Overflowing the range of roughly ±32768 panics in debug builds and wraps
+/// around in release builds, like the primitive integer types do.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Fixed(pub i32);
+
+impl Fixed {
+    /// The amount of fractional bits.
+    pub const FRACTIONAL_BITS: u32 = 16;
+    /// Zero.
+    pub const ZERO: Fixed = Fixed(0);
+    /// One.
+    pub const ONE: Fixed = Fixed(1 << Self::FRACTIONAL_BITS);
+    /// The smallest positive value, 1/65536.
+    pub const EPSILON: Fixed = Fixed(1);
+
+    /// Creates a [`Fixed`] with the given integer value.
+    pub const fn from_int(value: i16) -> Fixed {
+        Fixed((value as i32) << Self::FRACTIONAL_BITS)
+    }
+
+    /// Creates a [`Fixed`] with the value `numerator / denominator`, rounded
+    /// towards zero.
+    ///
+    /// ### Panics
+    ///
+    /// Panics if the denominator is zero.
+    pub const fn from_ratio(numerator: i32, denominator: i32) -> Fixed {
+        Fixed((((numerator as i64) << Self::FRACTIONAL_BITS) / denominator as i64) as i32)
+    }
+
+    /// Returns the integer part of the value, rounded towards negative
+    /// infinity.
+    pub const fn floor(self) -> i16 {
+        (self.0 >> Self::FRACTIONAL_BITS) as i16
+    }
+
+    /// Converts the value into the closest `f32`.
+    ///
+    /// This is exact for values within ±256, and values with at most 8
+    /// fractional bits (i.e. multiples of 1/256) within ±32768, which covers
+    /// pixel and subpixel positions on any reasonably sized screen.
+    pub const fn to_f32(self) -> f32 {
+        self.0 as f32 / (1 << Self::FRACTIONAL_BITS) as f32
+    }
+
+    /// Returns the absolute value.
+    pub const fn abs(self) -> Fixed {
+        Fixed(self.0.abs())
+    }
+}
+
+impl From<i16> for Fixed {
+    fn from(value: i16) -> Self {
+        Fixed::from_int(value)
+    }
+}
+
+impl From<Fixed> for f32 {
+    fn from(value: Fixed) -> Self {
+        value.to_f32()
+    }
+}
+
+impl Add for Fixed {
+    type Output = Fixed;
+    fn add(self, rhs: Fixed) -> Fixed {
+        Fixed(self.0 + rhs.0)
+    }
+}
+
+impl Sub for Fixed {
+    type Output = Fixed;
+    fn sub(self, rhs: Fixed) -> Fixed {
+        Fixed(self.0 - rhs.0)
+    }
+}
+
+impl Mul for Fixed {
+    type Output = Fixed;
+    /// Multiplies the values, rounding towards negative infinity.
+    fn mul(self, rhs: Fixed) -> Fixed {
+        let product = (self.0 as i64 * rhs.0 as i64) >> Fixed::FRACTIONAL_BITS;
+        debug_assert!(
+            i32::try_from(product).is_ok(),
+            "fixed-point multiplication overflowed",
+        );
+        Fixed(product as i32)
+    }
+}
+
+impl Div for Fixed {
+    type Output = Fixed;
+    /// Divides the values, rounding towards zero.
+    ///
+    /// ### Panics
+    ///
+    /// Panics if `rhs` is zero.
+    fn div(self, rhs: Fixed) -> Fixed {
+        let quotient = ((self.0 as i64) << Fixed::FRACTIONAL_BITS) / rhs.0 as i64;
+        debug_assert!(
+            i32::try_from(quotient).is_ok(),
+            "fixed-point division overflowed",
+        );
+        Fixed(quotient as i32)
+    }
+}
+
+impl Neg for Fixed {
+    type Output = Fixed;
+    fn neg(self) -> Fixed {
+        Fixed(-self.0)
+    }
+}
+
+impl AddAssign for Fixed {
+    fn add_assign(&mut self, rhs: Fixed) {
+        *self = *self + rhs;
+    }
+}
+
+impl SubAssign for Fixed {
+    fn sub_assign(&mut self, rhs: Fixed) {
+        *self = *self - rhs;
+    }
+}
+
+impl MulAssign for Fixed {
+    fn mul_assign(&mut self, rhs: Fixed) {
+        *self = *self * rhs;
+    }
+}
+
+impl DivAssign for Fixed {
+    fn div_assign(&mut self, rhs: Fixed) {
+        *self = *self / rhs;
+    }
+}
+
+/// A fixed-point axis-aligned 2D rectangle, the deterministic counterpart of
+/// [`Rect`], which it can be converted into for rendering.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct FixedRect {
+    /// The horizontal coordinate of the top-left corner of the rectangle.
+    pub x: Fixed,
+    /// The vertical coordinate of the top-left corner of the rectangle.
+    pub y: Fixed,
+    /// The width of the rectangle.
+    pub w: Fixed,
+    /// The height of the rectangle.
+    pub h: Fixed,
+}
+
+impl FixedRect {
+    /// Creates a new [`FixedRect`] from a given top-left corner and
+    /// dimensions.
+    pub const fn xywh(x: Fixed, y: Fixed, w: Fixed, h: Fixed) -> FixedRect {
+        FixedRect { x, y, w, h }
+    }
+
+    /// Creates a new [`FixedRect`] from a given center coordinate and
+    /// dimensions.
+    pub const fn around(x: Fixed, y: Fixed, w: Fixed, h: Fixed) -> FixedRect {
+        FixedRect {
+            x: Fixed(x.0 - w.0 / 2),
+            y: Fixed(y.0 - h.0 / 2),
+            w,
+            h,
+        }
+    }
+
+    /// Returns true if this rectangle and `other` overlap. Like with
+    /// [`Rect::intersects`], rectangles which only touch at their edges do not
+    /// count as intersecting.
+    pub fn intersects(&self, other: &FixedRect) -> bool {
+        self.x < other.x + other.w
+            && other.x < self.x + self.w
+            && self.y < other.y + other.h
+            && other.y < self.y + self.h
+    }
+
+    /// Converts the rectangle into a floating-point [`Rect`], e.g. for passing
+    /// into [`SpriteAsset::draw`](crate::resources::sprite::SpriteAsset::draw).
+    /// See [`Fixed::to_f32`] for notes on precision.
+    pub const fn to_rect(self) -> Rect {
+        Rect::xywh(
+            self.x.to_f32(),
+            self.y.to_f32(),
+            self.w.to_f32(),
+            self.h.to_f32(),
+        )
+    }
+}
+
+impl From<FixedRect> for Rect {
+    fn from(rect: FixedRect) -> Self {
+        rect.to_rect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Fixed, FixedRect};
+
+    #[test]
+    fn arithmetic_matches_expected_fixed_point_results() {
+        let half = Fixed::from_ratio(1, 2);
+        let three = Fixed::from_int(3);
+        assert_eq!(Fixed(0x8000), half);
+        assert_eq!(Fixed(0x3_8000), three + half);
+        assert_eq!(Fixed(0x2_8000), three - half);
+        assert_eq!(Fixed(-0x2_8000), half - three);
+        assert_eq!(Fixed(0x1_8000), three * half);
+        assert_eq!(Fixed(0x6_0000), three / half);
+        assert_eq!(Fixed(-0x6_0000), -three / half);
+
+        // 1/3 can't be represented exactly, so check the rounding.
+        let third = Fixed::ONE / three;
+        assert_eq!(Fixed(0x5555), third);
+        assert_eq!(Fixed(-0x5555), -Fixed::ONE / three);
+        assert_eq!(Fixed(0xFFFF), third * three);
+        // Multiplication rounds towards negative infinity.
+        assert_eq!(Fixed(-0x1), Fixed(-0x5555) * Fixed::EPSILON);
+
+        let mut value = Fixed::from_int(-7);
+        value += half;
+        value *= Fixed::from_int(2);
+        value /= Fixed::from_int(-13);
+        value -= Fixed::ONE;
+        assert_eq!(Fixed::ZERO, value);
+        assert_eq!(-1, Fixed(-1).floor());
+        assert_eq!(3, (three + half).floor());
+    }
+
+    #[test]
+    fn render_coordinates_are_exact() {
+        assert_eq!(
+            1920.5,
+            (Fixed::from_int(1920) + Fixed::from_ratio(1, 2)).to_f32()
+        );
+        assert_eq!(-0.25, Fixed::from_ratio(-1, 4).to_f32());
+        assert_eq!(255.99998, (Fixed::from_int(256) - Fixed::EPSILON).to_f32());
+
+        let rect = FixedRect::around(
+            Fixed::from_int(100),
+            Fixed::from_int(50),
+            Fixed::from_int(31),
+            Fixed::from_int(10),
+        )
+        .to_rect();
+        assert_eq!((84.5, 45.0, 31.0, 10.0), (rect.x, rect.y, rect.w, rect.h));
+    }
+}
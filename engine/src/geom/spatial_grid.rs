@@ -0,0 +1,210 @@
+// SPDX-FileCopyrightText: 2025 Jens Pitkänen <jens.pitkanen@helsinki.fi>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::{allocators::LinearAllocator, collections::FixedVec};
+
+use super::Rect;
+
+/// A uniform grid of rectangles and their associated handles, for quickly
+/// finding the rectangles overlapping some area, e.g. for the broad-phase of
+/// collision detection.
+///
+/// The grid covers a fixed area split into equally sized cells, and each
+/// inserted rectangle is listed in every cell it overlaps. Rectangles which are
+/// (partially) outside of the grid's area are treated as if they were clamped
+/// to the closest cells, so they're still found by queries, just less
+/// efficiently. If a rectangle does not fit in all of its cells due to their
+/// limited capacity, it's put in an overflow list which is checked by every
+/// query, so a crowded grid gets slower instead of losing rectangles.
+pub struct SpatialGrid<'a, H> {
+    bounds: Rect,
+    cell_width: f32,
+    cell_height: f32,
+    columns: usize,
+    rows: usize,
+    entries: FixedVec<'a, (Rect, H)>,
+    /// Indices to `entries` for each cell, in rows from the top-left.
+    cells: FixedVec<'a, FixedVec<'a, u32>>,
+    /// Indices to `entries` which did not fit in their cells.
+    overflow: FixedVec<'a, u32>,
+}
+
+impl<'a, H: Copy> SpatialGrid<'a, H> {
+    /// Creates a new [`SpatialGrid`] covering `bounds` with `columns` times
+    /// `rows` cells, with room for `max_entries` rectangles in total, and
+    /// `cell_capacity` rectangles in each cell. Returns None if the allocator
+    /// does not have enough free space.
+    ///
+    /// ### Panics
+    ///
+    /// Panics if `columns` or `rows` is zero, or `max_entries` does not fit in
+    /// a `u32`.
+    pub fn new(
+        allocator: &'a LinearAllocator,
+        bounds: Rect,
+        columns: usize,
+        rows: usize,
+        max_entries: usize,
+        cell_capacity: usize,
+    ) -> Option<SpatialGrid<'a, H>> {
+        assert!(columns > 0 && rows > 0, "the grid must have cells");
+        assert!(max_entries <= u32::MAX as usize, "too many entries");
+        let mut cells = FixedVec::new(allocator, columns * rows)?;
+        for _ in 0..columns * rows {
+            // does not panic: the capacity is the amount of cells
+            cells
+                .push(FixedVec::new(allocator, cell_capacity)?)
+                .ok()
+                .unwrap();
+        }
+        Some(SpatialGrid {
+            bounds,
+            cell_width: bounds.w / columns as f32,
+            cell_height: bounds.h / rows as f32,
+            columns,
+            rows,
+            entries: FixedVec::new(allocator, max_entries)?,
+            cells,
+            overflow: FixedVec::new(allocator, max_entries)?,
+        })
+    }
+
+    /// Removes all the rectangles from the grid.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        for cell in &mut *self.cells {
+            cell.clear();
+        }
+        self.overflow.clear();
+    }
+
+    /// Adds the rectangle to the grid, to be returned from queries overlapping
+    /// it. Returns false if the grid already has `max_entries` rectangles.
+    pub fn insert(&mut self, rect: Rect, handle: H) -> bool {
+        let index = self.entries.len() as u32;
+        if self.entries.push((rect, handle)).is_err() {
+            return false;
+        }
+
+        let (x0, y0, x1, y1) = self.cell_range(&rect);
+        let fits_in_cells =
+            (y0..=y1).all(|y| (x0..=x1).all(|x| !self.cells[x + y * self.columns].is_full()));
+        if fits_in_cells {
+            for y in y0..=y1 {
+                for x in x0..=x1 {
+                    // does not panic: all the cells were checked to have room
+                    self.cells[x + y * self.columns].push(index).unwrap();
+                }
+            }
+        } else {
+            // does not panic: the overflow list has room for all entries
+            self.overflow.push(index).unwrap();
+        }
+
+        true
+    }
+
+    /// Returns the handles of the rectangles which
+    /// [intersect](Rect::intersects) with `rect`, each handle once.
+    pub fn query(&self, rect: Rect) -> impl Iterator<Item = H> + use<'_, 'a, H> {
+        profiling::function_scope!();
+        let (x0, y0, x1, y1) = self.cell_range(&rect);
+        let cells = (y0..=y1).flat_map(move |y| (x0..=x1).map(move |x| (x, y)));
+        let from_cells = cells.flat_map(move |(x, y)| {
+            let cell = &self.cells[x + y * self.columns];
+            cell.iter().filter_map(move |&index| {
+                let (entry_rect, handle) = self.entries[index as usize];
+                // Entries spanning multiple cells are only returned from the
+                // first cell that both the entry and the query cover.
+                let (entry_x0, entry_y0, _, _) = self.cell_range(&entry_rect);
+                let first_cell = (entry_x0.max(x0), entry_y0.max(y0));
+                (first_cell == (x, y) && entry_rect.intersects(&rect)).then_some(handle)
+            })
+        });
+        let from_overflow = self.overflow.iter().filter_map(move |&index| {
+            let (entry_rect, handle) = self.entries[index as usize];
+            entry_rect.intersects(&rect).then_some(handle)
+        });
+        from_cells.chain(from_overflow)
+    }
+
+    /// Returns the column and row of the top-left and bottom-right cells
+    /// covered by the rectangle, clamped to the grid.
+    fn cell_range(&self, rect: &Rect) -> (usize, usize, usize, usize) {
+        let column = |x: f32| {
+            let column = (x - self.bounds.x) / self.cell_width;
+            // Float to int casts saturate, and NaNs turn into 0.
+            (column.max(0.0) as usize).min(self.columns - 1)
+        };
+        let row = |y: f32| {
+            let row = (y - self.bounds.y) / self.cell_height;
+            (row.max(0.0) as usize).min(self.rows - 1)
+        };
+        (
+            column(rect.x),
+            row(rect.y),
+            column(rect.x + rect.w),
+            row(rect.y + rect.h),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrayvec::ArrayVec;
+
+    use crate::{
+        allocators::{static_allocator, LinearAllocator},
+        geom::Rect,
+    };
+
+    use super::SpatialGrid;
+
+    #[test]
+    fn query_matches_brute_force() {
+        static ARENA: &LinearAllocator = static_allocator!(64 * 1024);
+        let bounds = Rect::xywh(0.0, 0.0, 100.0, 100.0);
+        // Small cells to make the rectangles overflow them.
+        let mut grid = SpatialGrid::<usize>::new(ARENA, bounds, 8, 8, 200, 4).unwrap();
+
+        // Simple LCG to generate a reproducible mess of rectangles, some
+        // spanning many cells, some partially outside the grid.
+        let mut seed = 12345u32;
+        let mut random = |max: f32| {
+            seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            (seed >> 8) as f32 / (1 << 24) as f32 * max
+        };
+        let mut rects = ArrayVec::<Rect, 200>::new();
+        while !rects.is_full() {
+            let rect = Rect::xywh(
+                random(120.0) - 10.0,
+                random(120.0) - 10.0,
+                random(30.0),
+                random(30.0),
+            );
+            assert!(grid.insert(rect, rects.len()));
+            rects.push(rect);
+        }
+        assert!(!grid.insert(bounds, 0), "grid should be full");
+        assert!(!grid.overflow.is_empty(), "some cells should overflow");
+
+        for _ in 0..100 {
+            let query = Rect::xywh(
+                random(120.0) - 10.0,
+                random(120.0) - 10.0,
+                random(40.0),
+                random(40.0),
+            );
+            let mut found = grid.query(query).collect::<ArrayVec<usize, 200>>();
+            found.sort_unstable();
+            let expected = (rects.iter().enumerate())
+                .filter(|(_, rect)| rect.intersects(&query))
+                .map(|(i, _)| i);
+            assert!(found.iter().copied().eq(expected));
+        }
+
+        grid.clear();
+        assert_eq!(0, grid.query(bounds).count());
+    }
+}
@@ -2,17 +2,111 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
-use core::time::Duration;
+use core::{iter::Chain, mem, slice, time::Duration};
 
 use arrayvec::ArrayVec;
-use platform::{Button, Event, InputDevice, Instant};
+use platform::{Axis, Button, Event, InputDevice, Instant};
 
-/// The amount of time [`QueuedEvent`]s are held in the [`EventQueue`] without
-/// being handled.
+use crate::resources::{deserialize, serialize, Deserialize, Serialize};
+
+/// The default amount of time [`QueuedEvent`]s are held in the [`EventQueue`]
+/// without being handled, see
+/// [`EngineLimits::event_queue_timeout`](crate::EngineLimits::event_queue_timeout).
 pub const EVENT_QUEUE_TIMEOUT: Duration = Duration::from_millis(200);
 
-/// A queue of input events to be processed by [`InputDeviceState::update`].
-pub type EventQueue = ArrayVec<QueuedEvent, 1000>;
+/// A queue of input events to be processed by [`InputDeviceState::update`],
+/// oldest first.
+#[derive(Default)]
+pub struct EventQueue {
+    events: ArrayVec<QueuedEvent, 1000>,
+    /// The index of the oldest event in `events`. Only nonzero while the queue
+    /// is full, when new events overwrite the oldest ones like in a ring
+    /// buffer, to avoid moving all the other events to make room.
+    start: usize,
+}
+
+impl EventQueue {
+    /// Creates an empty [`EventQueue`].
+    pub const fn new() -> EventQueue {
+        EventQueue {
+            events: ArrayVec::new_const(),
+            start: 0,
+        }
+    }
+
+    /// Returns the amount of events in the queue.
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    /// Returns true if there are no events in the queue.
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Returns true if the queue can't fit any more events.
+    pub fn is_full(&self) -> bool {
+        self.events.is_full()
+    }
+
+    /// Returns the maximum amount of events the queue can hold.
+    pub fn capacity(&self) -> usize {
+        self.events.capacity()
+    }
+
+    /// Returns an iterator over the events, from the oldest to the newest.
+    pub fn iter(&self) -> Chain<slice::Iter<'_, QueuedEvent>, slice::Iter<'_, QueuedEvent>> {
+        let (newer, older) = self.events.split_at(self.start);
+        older.iter().chain(newer)
+    }
+
+    /// Keeps only the events for which `f` returns true, in the same order.
+    pub fn retain(&mut self, f: impl FnMut(&mut QueuedEvent) -> bool) {
+        self.events.rotate_left(self.start);
+        self.start = 0;
+        self.events.retain(f);
+    }
+}
+
+impl<'a> IntoIterator for &'a EventQueue {
+    type Item = &'a QueuedEvent;
+    type IntoIter = Chain<slice::Iter<'a, QueuedEvent>, slice::Iter<'a, QueuedEvent>>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// What happens to new events when the [`EventQueue`] is full, see
+/// [`push_event`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventQueueOverflow {
+    /// The oldest event in the queue is dropped to make room for the new one,
+    /// so the most recent input is never lost.
+    DropOldest,
+    /// The new event is dropped, leaving the queue as it is.
+    DropNewest,
+}
+
+/// Pushes the event into the queue, dropping an event as specified by
+/// `overflow` if the queue is full. Returns the dropped event, if any.
+pub fn push_event(
+    event_queue: &mut EventQueue,
+    event: QueuedEvent,
+    overflow: EventQueueOverflow,
+) -> Option<QueuedEvent> {
+    match event_queue.events.try_push(event) {
+        Ok(()) => None,
+        Err(err) => match overflow {
+            EventQueueOverflow::DropNewest => Some(err.element()),
+            EventQueueOverflow::DropOldest => {
+                let start = event_queue.start;
+                let oldest = mem::replace(&mut event_queue.events[start], err.element());
+                event_queue.start = (start + 1) % event_queue.events.len();
+                Some(oldest)
+            }
+        },
+    }
+}
 
 /// Input event that happened at some point in the past, waiting to be used as a
 /// trigger for an [`ActionState`], or to be timed out.
@@ -25,10 +119,10 @@ pub struct QueuedEvent {
 
 impl QueuedEvent {
     /// Returns true if the time between this event and the given timestamp is
-    /// greater than [`EVENT_QUEUE_TIMEOUT`].
-    pub fn timed_out(&self, timestamp: Instant) -> bool {
+    /// at least `timeout`, e.g. [`EVENT_QUEUE_TIMEOUT`].
+    pub fn timed_out(&self, timestamp: Instant, timeout: Duration) -> bool {
         if let Some(time_since_event) = timestamp.duration_since(self.timestamp) {
-            time_since_event >= EVENT_QUEUE_TIMEOUT
+            time_since_event >= timeout
         } else {
             false
         }
@@ -43,6 +137,10 @@ impl QueuedEvent {
 /// [`InputDeviceState::actions`] should be used to trigger any relevant events
 /// in the game based on the actions' index.
 ///
+/// `A` is the amount of analog actions (e.g. "move horizontally" mapped to a
+/// thumbstick axis), which work the same way, except that their current
+/// position is in [`AnalogActionState::value`].
+///
 /// ### Example
 /// ```
 /// # let mut event_queue = engine::input::EventQueue::new();
@@ -62,6 +160,7 @@ impl QueuedEvent {
 /// let mut input_device_state = InputDeviceState {
 ///     device: a_device_from_platform,
 ///     actions: [ActionState::default(); PlayerAction::_Count as usize],
+///     analog_actions: [],
 /// };
 ///
 /// // Maybe bind the actions:
@@ -79,36 +178,56 @@ impl QueuedEvent {
 ///     // Jump!
 /// }
 /// ```
-pub struct InputDeviceState<const N: usize> {
+pub struct InputDeviceState<const N: usize, const A: usize = 0> {
     /// The device this [`InputDeviceState`] tracks.
     pub device: InputDevice,
     /// Each action's current state, updated based on events in
     /// [`InputDeviceState::update`].
     pub actions: [ActionState; N],
+    /// Each analog action's current state, updated based on events in
+    /// [`InputDeviceState::update`].
+    pub analog_actions: [AnalogActionState; A],
 }
 
-impl<const N: usize> InputDeviceState<N> {
+impl<const N: usize, const A: usize> InputDeviceState<N, A> {
     /// Checks the event queue for any events that could be consumed by this
     /// [`InputDeviceState`], and consumes any such events to trigger actions.
     ///
     /// Also resets the [`ActionState::pressed`] status of
-    /// [`ActionKind::Instant`] actions.
+    /// [`ActionKind::Instant`] actions, and the [`ActionState::released`]
+    /// status of all actions. Analog actions keep their value until the next
+    /// event for their axis.
     pub fn update(&mut self, event_queue: &mut EventQueue) {
-        // Reset any instant actions to "not pressed"
+        // Reset any instant actions to "not pressed", and all actions to "not released"
         for action in &mut self.actions {
             if matches!(action.kind, ActionKind::Instant) {
                 action.pressed = false;
             }
+            action.released = false;
         }
 
+        // Once an action has an event left in the queue, the rest of its events
+        // are left there as well, to keep presses and releases in order.
+        let mut deferred = [false; N];
+
         // Handle events, removing events from the queue if they triggered an action
         event_queue.retain(|event| {
             match event.event {
-                Event::DigitalInputPressed(device, button) if device == self.device => {
-                    for action in &mut self.actions {
-                        if action.mapping == Some(button) && !action.disabled {
+                Event::DigitalInputPressed(device, button)
+                | Event::PointerButton {
+                    device,
+                    button,
+                    pressed: true,
+                } if device == self.device => {
+                    for (action, deferred) in self.actions.iter_mut().zip(&mut deferred) {
+                        if action.is_mapped_to(button) && !action.disabled {
                             match action.kind {
-                                ActionKind::Instant if action.pressed => return true, // handle this event on the next frame, there's many presses queued up
+                                _ if *deferred => return true,
+                                ActionKind::Instant if action.pressed => {
+                                    // handle this event on the next frame, there's many presses queued up
+                                    *deferred = true;
+                                    return true;
+                                }
                                 ActionKind::Instant => action.pressed = true,
                                 ActionKind::Held => action.pressed = true,
                                 ActionKind::Toggle => action.pressed = !action.pressed,
@@ -118,12 +237,36 @@ impl<const N: usize> InputDeviceState<N> {
                     }
                 }
 
-                Event::DigitalInputReleased(device, button) if device == self.device => {
-                    for action in &mut self.actions {
-                        if action.mapping == Some(button) && !action.disabled {
+                Event::DigitalInputReleased(device, button)
+                | Event::PointerButton {
+                    device,
+                    button,
+                    pressed: false,
+                } if device == self.device => {
+                    for (action, deferred) in self.actions.iter_mut().zip(&mut deferred) {
+                        if action.is_mapped_to(button) && !action.disabled {
+                            if *deferred || action.released {
+                                // handle this event on the next frame, so that each release is visible for a frame
+                                *deferred = true;
+                                return true;
+                            }
                             if matches!(action.kind, ActionKind::Held) {
                                 action.pressed = false;
                             }
+                            action.released = true;
+                            return false;
+                        }
+                    }
+                }
+
+                Event::AnalogInputChanged(device, axis, value) if device == self.device => {
+                    for action in &mut self.analog_actions {
+                        if action.mapping == Some(axis) {
+                            action.value = if value.abs() < action.dead_zone {
+                                0.0
+                            } else {
+                                value.clamp(-1.0, 1.0)
+                            };
                             return false;
                         }
                     }
@@ -134,6 +277,103 @@ impl<const N: usize> InputDeviceState<N> {
             true
         });
     }
+
+    /// The length of the buffer passed into
+    /// [`InputDeviceState::serialize_bindings`] and
+    /// [`InputDeviceState::deserialize_bindings`].
+    pub const BINDINGS_SERIALIZED_SIZE: usize = <u32 as Serialize>::SERIALIZED_SIZE * 2
+        + <Option<Button> as Serialize>::SERIALIZED_SIZE * 2 * N
+        + <Option<Axis> as Serialize>::SERIALIZED_SIZE * A;
+
+    /// Serializes the button and axis mappings of all the actions into the byte
+    /// buffer, e.g. for saving the player's control settings. The length of
+    /// `dst` must match [`InputDeviceState::BINDINGS_SERIALIZED_SIZE`].
+    pub fn serialize_bindings(&self, dst: &mut [u8]) {
+        assert_eq!(Self::BINDINGS_SERIALIZED_SIZE, dst.len());
+        let mut cursor = 0;
+        serialize::<u32>(&(N as u32), dst, &mut cursor);
+        serialize::<u32>(&(A as u32), dst, &mut cursor);
+        for action in &self.actions {
+            serialize::<Option<Button>>(&action.mapping, dst, &mut cursor);
+            serialize::<Option<Button>>(&action.secondary_mapping, dst, &mut cursor);
+        }
+        for action in &self.analog_actions {
+            serialize::<Option<Axis>>(&action.mapping, dst, &mut cursor);
+        }
+    }
+
+    /// Replaces the button and axis mappings of all the actions with ones
+    /// serialized with [`InputDeviceState::serialize_bindings`], matching the
+    /// actions by index.
+    ///
+    /// If the bindings were serialized from an [`InputDeviceState`] with a
+    /// different amount of actions, or the buffer is otherwise the wrong size,
+    /// no mappings are changed and this function returns `false`.
+    pub fn deserialize_bindings(&mut self, src: &[u8]) -> bool {
+        if src.len() != Self::BINDINGS_SERIALIZED_SIZE {
+            return false;
+        }
+        let mut cursor = 0;
+        let action_count = deserialize::<u32>(src, &mut cursor);
+        let analog_action_count = deserialize::<u32>(src, &mut cursor);
+        if action_count as usize != N || analog_action_count as usize != A {
+            return false;
+        }
+        for action in &mut self.actions {
+            action.mapping = deserialize::<Option<Button>>(src, &mut cursor);
+            action.secondary_mapping = deserialize::<Option<Button>>(src, &mut cursor);
+        }
+        for action in &mut self.analog_actions {
+            action.mapping = deserialize::<Option<Axis>>(src, &mut cursor);
+        }
+        true
+    }
+}
+
+impl Serialize for Option<Button> {
+    const SERIALIZED_SIZE: usize =
+        <bool as Serialize>::SERIALIZED_SIZE + <u64 as Serialize>::SERIALIZED_SIZE;
+    fn serialize(&self, dst: &mut [u8]) {
+        assert_eq!(<Self as Serialize>::SERIALIZED_SIZE, dst.len());
+        let mut cursor = 0;
+        serialize::<bool>(&self.is_some(), dst, &mut cursor);
+        serialize::<u64>(&self.map(Button::inner).unwrap_or(0), dst, &mut cursor);
+    }
+}
+
+impl Deserialize for Option<Button> {
+    const SERIALIZED_SIZE: usize =
+        <bool as Deserialize>::SERIALIZED_SIZE + <u64 as Deserialize>::SERIALIZED_SIZE;
+    fn deserialize(src: &[u8]) -> Self {
+        assert_eq!(<Self as Deserialize>::SERIALIZED_SIZE, src.len());
+        let mut cursor = 0;
+        let is_some = deserialize::<bool>(src, &mut cursor);
+        let id = deserialize::<u64>(src, &mut cursor);
+        is_some.then(|| Button::new(id))
+    }
+}
+
+impl Serialize for Option<Axis> {
+    const SERIALIZED_SIZE: usize =
+        <bool as Serialize>::SERIALIZED_SIZE + <u64 as Serialize>::SERIALIZED_SIZE;
+    fn serialize(&self, dst: &mut [u8]) {
+        assert_eq!(<Self as Serialize>::SERIALIZED_SIZE, dst.len());
+        let mut cursor = 0;
+        serialize::<bool>(&self.is_some(), dst, &mut cursor);
+        serialize::<u64>(&self.map(Axis::inner).unwrap_or(0), dst, &mut cursor);
+    }
+}
+
+impl Deserialize for Option<Axis> {
+    const SERIALIZED_SIZE: usize =
+        <bool as Deserialize>::SERIALIZED_SIZE + <u64 as Deserialize>::SERIALIZED_SIZE;
+    fn deserialize(src: &[u8]) -> Self {
+        assert_eq!(<Self as Deserialize>::SERIALIZED_SIZE, src.len());
+        let mut cursor = 0;
+        let is_some = deserialize::<bool>(src, &mut cursor);
+        let id = deserialize::<u64>(src, &mut cursor);
+        is_some.then(|| Axis::new(id))
+    }
 }
 
 /// A rebindable action and its current state.
@@ -143,6 +383,12 @@ pub struct ActionState {
     pub kind: ActionKind,
     /// Button which triggers this action.
     pub mapping: Option<Button>,
+    /// Another button which triggers this action, e.g. to allow both WASD and
+    /// the arrow keys for movement.
+    ///
+    /// Note that for [`ActionKind::Held`] actions, releasing either button
+    /// releases the action, even if the other one is still held down.
+    pub secondary_mapping: Option<Button>,
     /// If true, events are ignored, but unless the events time out, they will
     /// trigger the action once this is set to false again.
     ///
@@ -152,6 +398,49 @@ pub struct ActionState {
     /// True if the action should be triggered based on input events, parsed
     /// according to the action's [`ActionKind`].
     pub pressed: bool,
+    /// True only for the [`InputDeviceState::update`] during which the mapped
+    /// button was released, regardless of the action's [`ActionKind`]. Useful
+    /// for e.g. charge-and-release mechanics.
+    ///
+    /// If the button is pressed and released between two updates, both
+    /// [`ActionState::pressed`] (for [`ActionKind::Instant`]) and this are
+    /// true after the update.
+    pub released: bool,
+}
+
+impl ActionState {
+    /// Creates a new enabled and unpressed [`ActionState`] triggered by the
+    /// given button, with no secondary mapping.
+    pub const fn new(kind: ActionKind, mapping: Option<Button>) -> ActionState {
+        ActionState {
+            kind,
+            mapping,
+            secondary_mapping: None,
+            disabled: false,
+            pressed: false,
+            released: false,
+        }
+    }
+
+    /// Returns true if the given button is either the
+    /// [`ActionState::mapping`] or the [`ActionState::secondary_mapping`] of
+    /// this action.
+    pub fn is_mapped_to(&self, button: Button) -> bool {
+        self.mapping == Some(button) || self.secondary_mapping == Some(button)
+    }
+}
+
+/// A rebindable analog action and its current state.
+#[derive(Clone, Copy, Default)]
+pub struct AnalogActionState {
+    /// Axis which controls this action.
+    pub mapping: Option<Axis>,
+    /// Values closer to zero than this are considered to be zero. Useful for
+    /// ignoring the small drift of a thumbstick at rest.
+    pub dead_zone: f32,
+    /// The current position of the axis, between -1 and 1, or exactly 0 if
+    /// the axis is within [`AnalogActionState::dead_zone`].
+    pub value: f32,
 }
 
 /// The button press pattern to be used to trigger a specific action.
@@ -169,3 +458,284 @@ pub enum ActionKind {
     /// button is pressed one time, and stop happening when it's pressed again.
     Toggle,
 }
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use platform::{Axis, Button, Event, InputDevice, Instant};
+
+    use super::{
+        ActionKind, ActionState, AnalogActionState, EventQueue, EventQueueOverflow,
+        InputDeviceState, QueuedEvent,
+    };
+
+    #[test]
+    fn events_time_out_after_the_given_timeout() {
+        let queued = QueuedEvent {
+            event: Event::DigitalInputPressed(InputDevice::new(0), Button::new(0)),
+            timestamp: Instant::reference(),
+        };
+        let short = Duration::from_millis(16);
+        let long = Duration::from_secs(1);
+
+        let now = Instant::reference() + Duration::from_millis(10);
+        assert!(!queued.timed_out(now, short));
+        assert!(!queued.timed_out(now, long));
+
+        let now = Instant::reference() + Duration::from_millis(100);
+        assert!(queued.timed_out(now, short));
+        assert!(!queued.timed_out(now, long));
+
+        let now = Instant::reference() + long;
+        assert!(queued.timed_out(now, short));
+        assert!(queued.timed_out(now, long));
+    }
+
+    #[test]
+    fn dropping_the_oldest_events_keeps_the_rest_in_order() {
+        let mut event_queue = EventQueue::new();
+        let event = |i: u64| QueuedEvent {
+            event: Event::DigitalInputPressed(InputDevice::new(0), Button::new(i)),
+            timestamp: Instant::reference(),
+        };
+        let button = |queued: &QueuedEvent| match queued.event {
+            Event::DigitalInputPressed(_, button) => button,
+            _ => unreachable!(),
+        };
+        let capacity = event_queue.capacity() as u64;
+        for i in 0..capacity + 10 {
+            let dropped =
+                super::push_event(&mut event_queue, event(i), EventQueueOverflow::DropOldest);
+            assert_eq!(
+                i.checked_sub(capacity).map(Button::new),
+                dropped.as_ref().map(button)
+            );
+        }
+        assert!((event_queue.iter().map(button)).eq((10..capacity + 10).map(Button::new)));
+
+        event_queue.retain(|queued| button(queued) != Button::new(11));
+        let expected = (10..capacity + 10).filter(|&i| i != 11).map(Button::new);
+        assert!((event_queue.iter().map(button)).eq(expected));
+    }
+
+    fn push_event(queue: &mut EventQueue, event: Event) {
+        let event = QueuedEvent {
+            event,
+            timestamp: Instant::reference(),
+        };
+        assert!(super::push_event(queue, event, EventQueueOverflow::DropNewest).is_none());
+    }
+
+    #[test]
+    fn releases_are_detected_for_one_update() {
+        let device = InputDevice::new(0);
+        let button = Button::new(0);
+        let mut input = InputDeviceState {
+            device,
+            actions: [ActionState::new(ActionKind::Instant, Some(button))],
+            analog_actions: [],
+        };
+        let mut event_queue = EventQueue::new();
+
+        // Press and release on separate updates
+        input.actions[0].kind = ActionKind::Held;
+        push_event(&mut event_queue, Event::DigitalInputPressed(device, button));
+        input.update(&mut event_queue);
+        assert!(input.actions[0].pressed);
+        assert!(!input.actions[0].released);
+
+        push_event(
+            &mut event_queue,
+            Event::DigitalInputReleased(device, button),
+        );
+        input.update(&mut event_queue);
+        assert!(!input.actions[0].pressed);
+        assert!(input.actions[0].released);
+
+        // Press and release within one batch of events
+        input.actions[0].kind = ActionKind::Instant;
+        push_event(&mut event_queue, Event::DigitalInputPressed(device, button));
+        push_event(
+            &mut event_queue,
+            Event::DigitalInputReleased(device, button),
+        );
+        input.update(&mut event_queue);
+        assert!(input.actions[0].pressed);
+        assert!(input.actions[0].released);
+
+        input.update(&mut event_queue);
+        assert!(!input.actions[0].pressed);
+        assert!(!input.actions[0].released);
+
+        // Two presses and releases within one batch are spread over two updates
+        for _ in 0..2 {
+            push_event(&mut event_queue, Event::DigitalInputPressed(device, button));
+            push_event(
+                &mut event_queue,
+                Event::DigitalInputReleased(device, button),
+            );
+        }
+        input.update(&mut event_queue);
+        assert!(input.actions[0].pressed);
+        assert!(input.actions[0].released);
+        assert_eq!(2, event_queue.len());
+
+        input.update(&mut event_queue);
+        assert!(input.actions[0].pressed);
+        assert!(input.actions[0].released);
+        assert!(event_queue.is_empty());
+    }
+
+    #[test]
+    fn bindings_round_trip() {
+        let device = InputDevice::new(0);
+        let mut input = InputDeviceState {
+            device,
+            actions: [
+                ActionState {
+                    secondary_mapping: Some(Button::new(2)),
+                    ..ActionState::new(ActionKind::Held, Some(Button::new(1)))
+                },
+                ActionState::new(ActionKind::Instant, None),
+            ],
+            analog_actions: [AnalogActionState {
+                mapping: Some(Axis::new(3)),
+                ..Default::default()
+            }],
+        };
+
+        let mut bindings = [0; InputDeviceState::<2, 1>::BINDINGS_SERIALIZED_SIZE];
+        input.serialize_bindings(&mut bindings);
+
+        input.actions = [ActionState::default(); 2];
+        input.analog_actions = [AnalogActionState::default()];
+        assert!(input.deserialize_bindings(&bindings));
+        assert_eq!(Some(Button::new(1)), input.actions[0].mapping);
+        assert_eq!(Some(Button::new(2)), input.actions[0].secondary_mapping);
+        assert_eq!(None, input.actions[1].mapping);
+        assert_eq!(None, input.actions[1].secondary_mapping);
+        assert_eq!(Some(Axis::new(3)), input.analog_actions[0].mapping);
+    }
+
+    #[test]
+    fn bindings_with_different_action_count_are_rejected() {
+        let device = InputDevice::new(0);
+        let original = InputDeviceState {
+            device,
+            actions: [ActionState::new(ActionKind::Held, Some(Button::new(1))); 3],
+            analog_actions: [],
+        };
+        let mut bindings = [0; InputDeviceState::<3>::BINDINGS_SERIALIZED_SIZE];
+        original.serialize_bindings(&mut bindings);
+
+        let mut smaller = InputDeviceState {
+            device,
+            actions: [ActionState::default(); 2],
+            analog_actions: [],
+        };
+        assert!(!smaller.deserialize_bindings(&bindings));
+        assert!(!smaller.deserialize_bindings(&bindings[..bindings.len() - 18]));
+        assert!(!smaller.deserialize_bindings(&[]));
+        assert_eq!(None, smaller.actions[0].mapping);
+
+        // Same total size, but split differently between digital and analog actions
+        let mut mixed = InputDeviceState {
+            device,
+            actions: [ActionState::default(); 1],
+            analog_actions: [AnalogActionState::default(); 4],
+        };
+        assert_eq!(
+            bindings.len(),
+            InputDeviceState::<1, 4>::BINDINGS_SERIALIZED_SIZE
+        );
+        assert!(!mixed.deserialize_bindings(&bindings));
+    }
+
+    #[test]
+    fn either_mapped_button_triggers_action() {
+        let device = InputDevice::new(0);
+        let (primary, secondary, unmapped) = (Button::new(0), Button::new(1), Button::new(2));
+        let mut input = InputDeviceState {
+            device,
+            actions: [ActionState {
+                secondary_mapping: Some(secondary),
+                ..ActionState::new(ActionKind::Held, Some(primary))
+            }],
+            analog_actions: [],
+        };
+        let mut event_queue = EventQueue::new();
+
+        for button in [primary, secondary] {
+            push_event(&mut event_queue, Event::DigitalInputPressed(device, button));
+            input.update(&mut event_queue);
+            assert!(input.actions[0].pressed);
+
+            push_event(
+                &mut event_queue,
+                Event::DigitalInputReleased(device, button),
+            );
+            input.update(&mut event_queue);
+            assert!(!input.actions[0].pressed);
+        }
+
+        push_event(
+            &mut event_queue,
+            Event::DigitalInputPressed(device, unmapped),
+        );
+        input.update(&mut event_queue);
+        assert!(!input.actions[0].pressed);
+        assert_eq!(1, event_queue.len());
+    }
+
+    #[test]
+    fn analog_events_update_analog_actions() {
+        let device = InputDevice::new(0);
+        let other_device = InputDevice::new(1);
+        let axis = Axis::new(0);
+        let mut input = InputDeviceState {
+            device,
+            actions: [],
+            analog_actions: [AnalogActionState {
+                mapping: Some(axis),
+                dead_zone: 0.2,
+                value: 0.0,
+            }],
+        };
+
+        let mut event_queue = EventQueue::new();
+
+        push_event(
+            &mut event_queue,
+            Event::AnalogInputChanged(device, axis, 0.5),
+        );
+        input.update(&mut event_queue);
+        assert_eq!(0.5, input.analog_actions[0].value);
+        assert!(event_queue.is_empty());
+
+        // Within the dead zone, snaps to zero
+        push_event(
+            &mut event_queue,
+            Event::AnalogInputChanged(device, axis, -0.1),
+        );
+        input.update(&mut event_queue);
+        assert_eq!(0.0, input.analog_actions[0].value);
+
+        // Latest event wins, other devices' events are left in the queue
+        push_event(
+            &mut event_queue,
+            Event::AnalogInputChanged(device, axis, 0.3),
+        );
+        push_event(
+            &mut event_queue,
+            Event::AnalogInputChanged(other_device, axis, 0.9),
+        );
+        push_event(
+            &mut event_queue,
+            Event::AnalogInputChanged(device, axis, -1.0),
+        );
+        input.update(&mut event_queue);
+        assert_eq!(-1.0, input.analog_actions[0].value);
+        assert_eq!(1, event_queue.len());
+    }
+}
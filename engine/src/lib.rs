@@ -23,6 +23,7 @@
 //! - [`geom`]: Geometry types and related math operations.
 //! - [`multithreading`]: Utilities for spreading work between multiple CPU
 //!   cores.
+//! - [`random`]: Deterministic pseudorandom number generation.
 //!
 //! Specific game engine systems can be found in:
 //! - [`resources`]: Resource/game asset types and their loading systems.
@@ -61,6 +62,9 @@ pub mod input;
 pub mod mixer;
 /// Utilities for splitting work to be processed in parallel.
 pub mod multithreading;
+/// Seedable pseudorandom number generation, with the same results on every
+/// platform.
+pub mod random;
 /// Low-level graphics-related data structures and functionality.
 pub mod renderer;
 /// The resource database and everything related to querying, loading, and using
@@ -69,4 +73,4 @@ pub mod resources;
 
 mod engine;
 
-pub use engine::{Engine, EngineLimits};
+pub use engine::{Engine, EngineError, EngineLimits, FixedTimestep, Game, MemoryReport};
@@ -2,29 +2,248 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
-use core::cmp::Reverse;
+use core::{
+    cmp::{Ordering, Reverse},
+    ops::Range,
+    time::Duration,
+};
 
-use platform::{thread_pool::ThreadPool, Instant, Platform, AUDIO_CHANNELS, AUDIO_SAMPLE_RATE};
+use platform::{
+    thread_pool::ThreadPool, AudioOutput, Instant, Platform, AUDIO_CHANNELS, AUDIO_SAMPLE_RATE,
+};
 
 use crate::{
     allocators::LinearAllocator,
     collections::FixedVec,
     multithreading::parallelize,
     resources::{
-        audio_clip::AudioClipHandle, ResourceDatabase, ResourceLoader, AUDIO_SAMPLES_PER_CHUNK,
+        audio_clip::{AudioClipAsset, AudioClipHandle},
+        ResourceDatabase, ResourceLoader, AUDIO_SAMPLES_PER_CHUNK,
     },
 };
 
+/// The lowest playback rate allowed in [`PlaybackSettings::rate`], three
+/// octaves below the original pitch.
+pub const MIN_PLAYBACK_RATE: f32 = 0.125;
+/// The highest playback rate allowed in [`PlaybackSettings::rate`], three
+/// octaves above the original pitch.
+pub const MAX_PLAYBACK_RATE: f32 = 8.0;
+
+/// The amount of fractional bits in the fixed point playback rates and source
+/// positions used in rendering.
+const RATE_FRACTION_BITS: u32 = 16;
+/// A playback rate of 1 in the fixed point representation.
+const RATE_ONE: u64 = 1 << RATE_FRACTION_BITS;
+
+/// Parameters for playing back an individual audio clip, used with
+/// [`Mixer::play_clip_with_settings`].
+#[derive(Debug, Clone, Copy)]
+pub struct PlaybackSettings {
+    /// If true, the clip loops back to the start whenever it ends, and keeps
+    /// playing until it's stopped. See [`Mixer::play_clip_looping`].
+    ///
+    /// Defaults to false.
+    pub looping: bool,
+    /// The speed the clip is played back at, which also affects the pitch: 2
+    /// plays the clip twice as fast and an octave higher, 0.5 half as fast and
+    /// an octave lower. Clamped between [`MIN_PLAYBACK_RATE`] and
+    /// [`MAX_PLAYBACK_RATE`].
+    ///
+    /// Defaults to 1.
+    pub rate: f32,
+    /// The stereo position of the clip, from -1 (only the left speaker) to 1
+    /// (only the right speaker), with 0 playing the clip as is. The speaker on
+    /// the side of the clip always plays it at full volume, and the other one
+    /// is turned down following the constant power panning curve. For stereo
+    /// clips, the left and right channels are scaled separately.
+    ///
+    /// Defaults to 0.
+    pub pan: f32,
+    /// If true, the clip's chunks are released as soon as they've been
+    /// played, so that only the chunks around the playback position are
+    /// loaded at a time. Useful for long music tracks, which would otherwise
+    /// take up a lot of the loaded chunk budget. The chunks needed for the
+    /// near future are always requested from the [`ResourceLoader`] in
+    /// [`Mixer::render_audio`], streaming or not.
+    ///
+    /// Defaults to false.
+    pub streaming: bool,
+}
+
+impl PlaybackSettings {
+    /// The settings used by [`Mixer::play_clip`].
+    pub const DEFAULT: PlaybackSettings = PlaybackSettings {
+        looping: false,
+        rate: 1.0,
+        pan: 0.0,
+        streaming: false,
+    };
+}
+
+impl Default for PlaybackSettings {
+    fn default() -> Self {
+        PlaybackSettings::DEFAULT
+    }
+}
+
 #[derive(Debug)]
 struct PlayingClip {
     channel: usize,
     clip: AudioClipHandle,
+    priority: u8,
     start_position: u64,
+    /// The playback position after the last sample of the clip, or
+    /// [`u64::MAX`] for looping clips. Does not account for fade-outs.
+    clip_end_position: u64,
+    fade_out: Option<FadeOut>,
+    looping: bool,
+    streaming: bool,
+    /// The playback rate as a fixed point number with
+    /// [`RATE_FRACTION_BITS`] fractional bits.
+    rate: u64,
+    /// The volume multipliers of the left and right channels based on the
+    /// panning of the clip.
+    pan_gains: [f32; AUDIO_CHANNELS],
 }
 
 impl PlayingClip {
-    fn get_end(&self, resources: &ResourceDatabase) -> u64 {
-        self.start_position + resources.get_audio_clip(self.clip).samples as u64
+    fn get_end(&self) -> u64 {
+        if let Some(fade_out) = &self.fade_out {
+            self.clip_end_position.min(fade_out.end_position)
+        } else {
+            self.clip_end_position
+        }
+    }
+
+    /// Returns the position in the clip's samples that plays at the given
+    /// playback position, as a fixed point number with [`RATE_FRACTION_BITS`]
+    /// fractional bits. Does not wrap around for looping clips.
+    fn source_position(&self, position: u64) -> u64 {
+        position.saturating_sub(self.start_position) * self.rate
+    }
+
+    /// Moves the clip later in the playback timeline by `samples`, as if it
+    /// had been started that much later.
+    fn delay(&mut self, samples: u64) {
+        self.start_position += samples;
+        if self.clip_end_position != u64::MAX {
+            self.clip_end_position += samples;
+        }
+        if let Some(fade_out) = &mut self.fade_out {
+            fade_out.start_position += samples;
+            fade_out.end_position += samples;
+        }
+    }
+
+    /// Returns the indices of the chunks that are played back within the
+    /// window of playback positions, and at least the chunk after the current
+    /// one, so that it's loaded by the time it's needed.
+    fn chunks_in_window(
+        &self,
+        asset: &AudioClipAsset,
+        window: Range<u64>,
+    ) -> impl Iterator<Item = u32> + use<> {
+        let chunk_count = asset.chunks.len() as u64;
+        let first_sample = self.source_position(window.start) >> RATE_FRACTION_BITS;
+        let last_sample = self.source_position(window.end) >> RATE_FRACTION_BITS;
+        let first_chunk = first_sample / AUDIO_SAMPLES_PER_CHUNK as u64;
+        let last_chunk = (last_sample / AUDIO_SAMPLES_PER_CHUNK as u64).max(first_chunk + 1);
+        let last_chunk = last_chunk.min(first_chunk + chunk_count.max(1) - 1);
+
+        let (first_index, looping) = (asset.chunks.start, self.looping);
+        (first_chunk..=last_chunk)
+            .filter(move |_| chunk_count > 0)
+            .map_while(move |chunk_offset| {
+                if looping {
+                    Some(first_index + (chunk_offset % chunk_count) as u32)
+                } else if chunk_offset < chunk_count {
+                    Some(first_index + chunk_offset as u32)
+                } else {
+                    None
+                }
+            })
+    }
+
+    /// Returns the volume multipliers for each audio channel at the given
+    /// playback position, given the channel's volume.
+    fn gains_at(&self, volume: f32, position: u64) -> [f32; AUDIO_CHANNELS] {
+        let mut gain = volume;
+        if let Some(fade_out) = &self.fade_out {
+            gain *= fade_out.gain_at(position);
+        }
+        self.pan_gains.map(|pan_gain| pan_gain * gain)
+    }
+
+    /// Returns the order in which the clip should be replaced by new sounds
+    /// compared to `other`, [`Ordering::Less`] meaning this clip is replaced
+    /// first: lower priority clips first, then the quieter ones (clips which
+    /// have already ended being silent), then the ones closer to their end,
+    /// and finally the older ones.
+    fn cmp_replacement_order(
+        &self,
+        other: &PlayingClip,
+        channels: &[ChannelSettings],
+        position: u64,
+    ) -> Ordering {
+        let loudness = |clip: &PlayingClip| {
+            if clip.get_end() <= position {
+                return 0.0;
+            }
+            let volume = channels[clip.channel].multiplier();
+            let gains = clip.gains_at(volume, position);
+            gains.into_iter().fold(0.0, f32::max)
+        };
+        (self.priority.cmp(&other.priority))
+            .then_with(|| loudness(self).total_cmp(&loudness(other)))
+            .then_with(|| self.get_end().cmp(&other.get_end()))
+            .then_with(|| self.start_position.cmp(&other.start_position))
+    }
+}
+
+/// Returns the left and right channel volume multipliers for a pan position
+/// between -1 and 1, following the constant power panning law, normalized so
+/// that the center position has both multipliers at 1. The multipliers are
+/// capped at 1, so panning only turns down the channel further from the clip.
+fn pan_gains(pan: f32) -> [f32; AUDIO_CHANNELS] {
+    if pan.is_nan() || pan == 0.0 {
+        return [1.0; AUDIO_CHANNELS];
+    }
+    // The pan as an angle from 0 (left) to pi/2 (right).
+    let angle = (pan.clamp(-1.0, 1.0) + 1.0) * core::f32::consts::FRAC_PI_4;
+    let (sin, cos) = sin_cos_quarter_turn(angle);
+    let scale = core::f32::consts::SQRT_2;
+    [(cos * scale).min(1.0), (sin * scale).min(1.0)]
+}
+
+/// Approximates the sine and cosine of an angle between 0 and pi/2 with Taylor
+/// series, since the float trigonometric functions are not available in
+/// `core`. Accurate to about 5 decimals in the given range.
+fn sin_cos_quarter_turn(x: f32) -> (f32, f32) {
+    let x2 = x * x;
+    let sin = x * (1.0 - x2 / 6.0 * (1.0 - x2 / 20.0 * (1.0 - x2 / 42.0 * (1.0 - x2 / 72.0))));
+    let cos = 1.0 - x2 / 2.0 * (1.0 - x2 / 12.0 * (1.0 - x2 / 30.0 * (1.0 - x2 / 56.0)));
+    (sin, cos)
+}
+
+/// A linear volume ramp from full volume at `start_position` to silence at
+/// `end_position`.
+#[derive(Debug, Clone, Copy)]
+struct FadeOut {
+    start_position: u64,
+    end_position: u64,
+}
+
+impl FadeOut {
+    /// Returns the volume multiplier at the given playback position.
+    fn gain_at(&self, position: u64) -> f32 {
+        let length = self.end_position - self.start_position;
+        if position >= self.end_position {
+            0.0
+        } else if position <= self.start_position {
+            1.0
+        } else {
+            (self.end_position - position) as f32 / length as f32
+        }
     }
 }
 
@@ -34,6 +253,17 @@ impl PlayingClip {
 pub struct ChannelSettings {
     /// The volume of the audio, from 0 (muted) to 255 (played raw).
     pub volume: u8,
+    /// A volume multiplier applied on top of `volume`. Values above 1 amplify
+    /// the audio, and the final mix is clamped to avoid overflows. See
+    /// [`Mixer::set_channel_volume`].
+    pub gain: f32,
+}
+
+impl ChannelSettings {
+    /// Returns the combined volume multiplier of `volume` and `gain`.
+    fn multiplier(&self) -> f32 {
+        self.volume as f32 / u8::MAX as f32 * self.gain
+    }
 }
 
 /// Holds currently playing audio tracks and their playback parameters.
@@ -41,10 +271,24 @@ pub struct Mixer {
     playing_clips: FixedVec<'static, PlayingClip>,
     /// Configurable settings for the channels where audio clips are played.
     pub channels: FixedVec<'static, ChannelSettings>,
+    /// The speaker layout the audio is mixed for. With [`AudioOutput::Mono`],
+    /// the stereo mix is downmixed so that both channels contain the average
+    /// of the channels, to avoid e.g. panned sounds being quiet or inaudible
+    /// from a single speaker.
+    pub output: AudioOutput,
     playback_buffer: FixedVec<'static, [i16; AUDIO_CHANNELS]>,
+    /// The chunks needed by the playing clips during the current playback
+    /// window, sorted, for checking which chunks streaming clips can release.
+    /// Cleared and refilled in [`Mixer::render_audio`].
+    needed_chunks: FixedVec<'static, u32>,
     /// The audio position where new sounds should start playing, updated at the
     /// start of each frame with [`Mixer::update_audio_sync`].
     playback_position: u64,
+    paused: bool,
+    /// Whether the previous [`Mixer::render_audio`] rendered silence because
+    /// the mixer was paused, in which case the playing clips need to be delayed
+    /// by the time passed since then.
+    rendered_paused: bool,
 }
 
 impl Mixer {
@@ -67,21 +311,66 @@ impl Mixer {
         max_playing_clips: usize,
         playback_buffer_length: usize,
     ) -> Option<Mixer> {
-        let mut playback_buffer = FixedVec::new(arena, playback_buffer_length)?;
+        Mixer::with_alignment(
+            arena,
+            channel_count,
+            max_playing_clips,
+            playback_buffer_length,
+            1,
+        )
+    }
+
+    /// Like [`Mixer::new`], but with the playback buffer aligned to at least
+    /// `buffer_alignment` bytes, e.g. 16 or 32 for processing the samples with
+    /// SIMD instructions.
+    ///
+    /// ### Panics
+    ///
+    /// If `buffer_alignment` is not a power of two.
+    #[track_caller]
+    pub fn with_alignment(
+        arena: &'static LinearAllocator,
+        channel_count: usize,
+        max_playing_clips: usize,
+        playback_buffer_length: usize,
+        buffer_alignment: usize,
+    ) -> Option<Mixer> {
+        assert!(
+            buffer_alignment.is_power_of_two(),
+            "buffer alignment must be a power of two",
+        );
+        let alignment = buffer_alignment.max(align_of::<[i16; AUDIO_CHANNELS]>());
+        let mut playback_buffer =
+            FixedVec::with_alignment(arena, playback_buffer_length, alignment)?;
         playback_buffer.fill_with_zeroes();
 
         let playing_clips = FixedVec::new(arena, max_playing_clips)?;
 
+        // The most chunks a clip can play through during one playback window,
+        // at the maximum playback rate, plus the next chunk and partial chunks
+        // at both ends.
+        let max_chunks_per_clip =
+            playback_buffer_length * MAX_PLAYBACK_RATE as usize / AUDIO_SAMPLES_PER_CHUNK + 3;
+        let needed_chunks = FixedVec::new(arena, max_playing_clips * max_chunks_per_clip)?;
+
         let mut channels = FixedVec::new(arena, channel_count)?;
         for _ in 0..channel_count {
-            channels.push(ChannelSettings { volume: 0xFF }).unwrap();
+            let settings = ChannelSettings {
+                volume: u8::MAX,
+                gain: 1.0,
+            };
+            channels.push(settings).unwrap();
         }
 
         Some(Mixer {
             playing_clips,
             channels,
+            output: AudioOutput::Stereo,
             playback_buffer,
+            needed_chunks,
             playback_position: 0,
+            paused: false,
+            rendered_paused: false,
         })
     }
 
@@ -89,53 +378,194 @@ impl Mixer {
     /// can't be played.
     ///
     /// If the mixer is already playing the maximum amount of concurrent clips,
-    /// and `important` is `true`, the clip with the least playback time left
-    /// will be replaced with this sound. Note that this may cause popping audio
-    /// artifacts, though on the other hand, with many other sounds playing, it
-    /// may not be as noticeable. If `important` is `false`, this sound will not
-    /// be played.
+    /// one of the playing clips with the same or a lower `priority` is replaced
+    /// with this sound. The replaced clip is picked from the lowest priority
+    /// clips, preferring the quietest one, then the one with the least playback
+    /// time left, and then the oldest one, so that sounds of the same priority
+    /// played in quick succession replace the old sounds instead of each
+    /// other. Note that replacing a sound may cause popping audio artifacts,
+    /// though on the other hand, with many other sounds playing, it may not be
+    /// as noticeable. If all the playing clips have a higher priority, this
+    /// sound will not be played.
     ///
-    /// If the channel index is out of bounds, the sound will not be played.
+    /// If the channel index is out of bounds, or the clip has been removed by
+    /// [`ResourceDatabase::reload`], the sound will not be played.
     pub fn play_clip(
         &mut self,
         channel: usize,
         clip: AudioClipHandle,
-        important: bool,
+        priority: u8,
+        resources: &ResourceDatabase,
+    ) -> bool {
+        let settings = PlaybackSettings::DEFAULT;
+        self.play_clip_with_settings(channel, clip, priority, settings, resources)
+    }
+
+    /// Plays the audio clip starting this frame, looping back to the start of
+    /// the clip whenever it ends, returning false if the sound can't be
+    /// played.
+    ///
+    /// The clip keeps playing until it's stopped with
+    /// [`Mixer::stop_channel`] or [`Mixer::fade_out_channel`]. Since looping
+    /// clips never end on their own, they're only replaced by new clips of the
+    /// same priority when all the other playing clips of that priority are
+    /// looping as well, and equally loud. Otherwise, this works like
+    /// [`Mixer::play_clip`].
+    pub fn play_clip_looping(
+        &mut self,
+        channel: usize,
+        clip: AudioClipHandle,
+        priority: u8,
+        resources: &ResourceDatabase,
+    ) -> bool {
+        let settings = PlaybackSettings {
+            looping: true,
+            ..PlaybackSettings::DEFAULT
+        };
+        self.play_clip_with_settings(channel, clip, priority, settings, resources)
+    }
+
+    /// Plays the audio clip starting this frame with the given playback
+    /// settings, returning false if the sound can't be played.
+    ///
+    /// Otherwise works like [`Mixer::play_clip`], see it for details.
+    pub fn play_clip_with_settings(
+        &mut self,
+        channel: usize,
+        clip: AudioClipHandle,
+        priority: u8,
+        settings: PlaybackSettings,
         resources: &ResourceDatabase,
     ) -> bool {
         if channel >= self.channels.len() {
             return false;
         }
+        let Some(asset) = resources.get_audio_clip(clip) else {
+            return false;
+        };
 
+        let rate = if settings.rate.is_nan() {
+            1.0
+        } else {
+            settings.rate.clamp(MIN_PLAYBACK_RATE, MAX_PLAYBACK_RATE)
+        };
+        let rate = (rate * RATE_ONE as f32) as u64;
+        let clip_end_position = if settings.looping {
+            u64::MAX
+        } else {
+            let samples = asset.samples as u64;
+            self.playback_position + (samples << RATE_FRACTION_BITS).div_ceil(rate)
+        };
         let playing_clip = PlayingClip {
             channel,
             clip,
+            priority,
             start_position: self.playback_position,
+            clip_end_position,
+            fade_out: None,
+            looping: settings.looping,
+            streaming: settings.streaming,
+            rate,
+            pan_gains: pan_gains(settings.pan),
         };
 
         if !self.playing_clips.is_full() {
             self.playing_clips.push(playing_clip).unwrap();
-        } else if important {
-            if self.playing_clips.is_empty() {
-                return false; // both full and empty, can't play anything
-            }
-
-            let mut lowest_end_time = self.playing_clips[0].get_end(resources);
-            let mut candidate_index = 0;
-            for (i, clip) in self.playing_clips.iter().enumerate().skip(1) {
-                let end_time = clip.get_end(resources);
-                if end_time < lowest_end_time {
-                    lowest_end_time = end_time;
-                    candidate_index = i;
-                }
-            }
+            return true;
+        }
 
-            self.playing_clips[candidate_index] = playing_clip;
+        let (channels, position) = (&*self.channels, self.playback_position);
+        let replaced_clip = (self.playing_clips.iter_mut())
+            .filter(|clip| clip.priority <= priority)
+            .min_by(|a, b| a.cmp_replacement_order(b, channels, position));
+        if let Some(replaced_clip) = replaced_clip {
+            *replaced_clip = playing_clip;
+            true
         } else {
-            return false;
+            false // full of higher priority clips, or no room at all
         }
+    }
+
+    /// Sets the volume multiplier of the channel, 0 being muted and 1 being
+    /// played raw, by setting [`ChannelSettings::volume`] to 255 and
+    /// [`ChannelSettings::gain`] to `volume`. Negative volumes are treated as
+    /// 0.
+    ///
+    /// ### Panics
+    ///
+    /// If the channel index is out of bounds.
+    pub fn set_channel_volume(&mut self, channel: usize, volume: f32) {
+        self.channels[channel] = ChannelSettings {
+            volume: u8::MAX,
+            gain: volume.max(0.0),
+        };
+    }
+
+    /// Returns the volume multiplier of the channel, combining
+    /// [`ChannelSettings::volume`] and [`ChannelSettings::gain`].
+    ///
+    /// ### Panics
+    ///
+    /// If the channel index is out of bounds.
+    pub fn channel_volume(&self, channel: usize) -> f32 {
+        self.channels[channel].multiplier()
+    }
 
-        true
+    /// Returns true if any sound playing on the channel has samples left to
+    /// play, i.e. it hasn't played to the end, been stopped, or faded out.
+    pub fn is_channel_active(&self, channel: usize) -> bool {
+        (self.playing_clips.iter())
+            .any(|clip| clip.channel == channel && clip.get_end() > self.playback_position)
+    }
+
+    /// Stops all sounds playing on the channel.
+    ///
+    /// The sounds are cut off immediately, which may cause a popping sound. To
+    /// avoid that, use [`Mixer::fade_out_channel`] with a short fade.
+    pub fn stop_channel(&mut self, channel: usize) {
+        self.fade_out_channel(channel, Duration::ZERO);
+    }
+
+    /// Fades out all sounds playing on the channel, linearly lowering their
+    /// volume to zero over `duration`, after which the sounds are stopped.
+    ///
+    /// Sounds played on the channel after calling this are not affected. If a
+    /// sound is already fading out, and would fade out faster than `duration`,
+    /// it keeps fading out at its current pace.
+    pub fn fade_out_channel(&mut self, channel: usize, duration: Duration) {
+        let fade_length = duration.as_micros() * AUDIO_SAMPLE_RATE as u128 / 1_000_000;
+        let fade_out = FadeOut {
+            start_position: self.playback_position,
+            end_position: self.playback_position + fade_length as u64,
+        };
+        for clip in &mut *self.playing_clips {
+            if clip.channel != channel {
+                continue;
+            }
+            match clip.fade_out {
+                Some(existing) if existing.end_position <= fade_out.end_position => {}
+                _ => clip.fade_out = Some(fade_out),
+            }
+        }
+    }
+
+    /// Pauses all audio playback until [`Mixer::resume`] is called.
+    ///
+    /// While paused, the mixer renders silence, and the playing clips
+    /// (including ones played while paused) stay where they were, continuing
+    /// from the same point when resumed.
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    /// Resumes audio playback paused with [`Mixer::pause`].
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    /// Returns true if the mixer has been paused with [`Mixer::pause`].
+    pub fn is_paused(&self) -> bool {
+        self.paused
     }
 
     /// Synchronizes the mixer's internal clock with the platform's audio
@@ -143,6 +573,7 @@ impl Mixer {
     ///
     /// Should be called at the start of the frame by the engine.
     pub fn update_audio_sync(&mut self, frame_timestamp: Instant, platform: &dyn Platform) {
+        let previous_position = self.playback_position;
         let (playback_position, playback_timestamp) = platform.audio_playback_position();
         if let Some(time_since_playback_pos) = frame_timestamp.duration_since(playback_timestamp) {
             let frame_offset_from_playback_pos =
@@ -151,31 +582,60 @@ impl Mixer {
         } else {
             self.playback_position = playback_position;
         }
+
+        // Only silence was played since the last sync, so the clips should
+        // continue from where they were.
+        if self.rendered_paused {
+            let paused_samples = self.playback_position.saturating_sub(previous_position);
+            for clip in &mut *self.playing_clips {
+                clip.delay(paused_samples);
+            }
+        }
     }
 
     /// Mixes the currently playing tracks together and updates the platform's
     /// audio buffer with the result.
     ///
+    /// Also queues up the chunks needed for the next frame's audio, and
+    /// unloads the chunks already played by
+    /// [streaming](PlaybackSettings::streaming) clips. If a chunk has not been
+    /// loaded in time, the clip is silent for the duration of that chunk.
+    ///
+    /// The playback window is split into one segment per thread of
+    /// `thread_pool`, and each segment is mixed on its own thread. Within a
+    /// segment, the clips are summed one after another in the same order as
+    /// when mixing on a single thread, and each output sample only depends on
+    /// its position, so the result is identical regardless of the amount of
+    /// threads.
+    ///
     /// Should be called at the end of the frame by the engine.
     pub fn render_audio(
         &mut self,
         thread_pool: &mut ThreadPool,
         platform: &dyn Platform,
-        resources: &ResourceDatabase,
+        resources: &mut ResourceDatabase,
         resource_loader: &mut ResourceLoader,
     ) {
         profiling::function_scope!();
+        self.rendered_paused = self.paused;
+        if self.paused {
+            self.playback_buffer.fill([0; AUDIO_CHANNELS]);
+            platform.update_audio_buffer(self.playback_position, &self.playback_buffer);
+            return;
+        }
+
         // Remove clips that have played to the end
         self.playing_clips
-            .sort_unstable_by_key(|clip| Reverse(clip.get_end(resources)));
+            .sort_unstable_by_key(|clip| Reverse(clip.get_end()));
         if let Some(finished_clips_start_index) = (self.playing_clips)
             .iter()
-            .position(|clip| clip.get_end(resources) < self.playback_position)
+            .position(|clip| clip.get_end() < self.playback_position)
         {
             self.playing_clips.truncate(finished_clips_start_index);
         }
 
         // Render
+        let resources_ = &*resources;
         parallelize(
             thread_pool,
             &mut self.playback_buffer,
@@ -184,45 +644,24 @@ impl Mixer {
                 playback_buffer.fill([0; AUDIO_CHANNELS]);
                 let playback_start = self.playback_position + offset as u64;
                 for clip in &*self.playing_clips {
-                    let volume = self.channels[clip.channel].volume;
-                    let asset = resources.get_audio_clip(clip.clip);
-
-                    let already_played = playback_start.saturating_sub(clip.start_position) as u32;
-                    let first_chunk =
-                        asset.chunks.start + already_played / AUDIO_SAMPLES_PER_CHUNK as u32;
-                    let last_chunk =
-                        asset.chunks.start + asset.samples / AUDIO_SAMPLES_PER_CHUNK as u32;
-
-                    let mut playback_offset =
-                        clip.start_position.saturating_sub(playback_start) as usize;
-                    for chunk_index in first_chunk..=last_chunk {
-                        if playback_buffer.len() <= playback_offset {
-                            break;
-                        }
-
-                        let chunk_start =
-                            (chunk_index - asset.chunks.start) * AUDIO_SAMPLES_PER_CHUNK as u32;
-                        let chunk_end =
-                            (chunk_index - asset.chunks.start + 1) * AUDIO_SAMPLES_PER_CHUNK as u32;
-
-                        if let Some(chunk) = &resources.chunks.get(chunk_index) {
-                            let chunk_samples =
-                                bytemuck::cast_slice::<u8, [i16; AUDIO_CHANNELS]>(&chunk.0);
-                            let first_sample_idx =
-                                (already_played.max(chunk_start) - chunk_start) as usize;
-                            let last_sample_idx =
-                                (asset.samples.min(chunk_end).saturating_sub(chunk_start)) as usize;
-                            if first_sample_idx < last_sample_idx {
-                                render_audio_chunk(
-                                    &chunk_samples[first_sample_idx..last_sample_idx],
-                                    &mut playback_buffer[playback_offset..],
-                                    volume,
-                                );
-                                playback_offset += last_sample_idx - first_sample_idx;
-                            }
-                        } else {
-                            break;
-                        }
+                    let volume = self.channels[clip.channel].multiplier();
+                    if clip.rate == RATE_ONE {
+                        render_clip(clip, volume, resources_, playback_buffer, playback_start);
+                    } else {
+                        render_resampled_clip(
+                            clip,
+                            volume,
+                            resources_,
+                            playback_buffer,
+                            playback_start,
+                        );
+                    }
+                }
+                if self.output == AudioOutput::Mono {
+                    profiling::scope!("downmix to mono");
+                    for frame in playback_buffer {
+                        let sum = frame.iter().map(|sample| *sample as i32).sum::<i32>();
+                        *frame = [(sum / AUDIO_CHANNELS as i32) as i16; AUDIO_CHANNELS];
                     }
                 }
             },
@@ -231,32 +670,719 @@ impl Mixer {
         // Send the rendered audio to be played back
         platform.update_audio_buffer(self.playback_position, &self.playback_buffer);
 
-        // Queue up any missing audio chunks in preparation for the next frame
+        // Queue up any missing audio chunks in preparation for the next frame:
+        // the chunks that will be played during the current playback window,
+        // and at least the chunk after the current one.
+        let window =
+            self.playback_position..self.playback_position + self.playback_buffer.len() as u64;
+        self.needed_chunks.clear();
+        let mut all_needed_chunks_listed = true;
         for clip in &*self.playing_clips {
-            let asset = resources.get_audio_clip(clip.clip);
-            let current_pos = self.playback_position.saturating_sub(clip.start_position);
-            let current_chunk_index = (current_pos / AUDIO_SAMPLES_PER_CHUNK as u64) as u32;
-            let next_chunk_index = current_chunk_index + 1;
-
-            resource_loader.queue_chunk(asset.chunks.start + current_chunk_index, resources);
-            if asset.chunks.start + next_chunk_index < asset.chunks.end {
-                resource_loader.queue_chunk(asset.chunks.start + next_chunk_index, resources);
+            profiling::scope!("queue clip chunks");
+            let Some(asset) = resources.get_audio_clip(clip.clip) else {
+                continue;
+            };
+            for chunk in clip.chunks_in_window(asset, window.clone()) {
+                resource_loader.queue_chunk(chunk, resources);
+                all_needed_chunks_listed &= self.needed_chunks.push(chunk).is_ok();
             }
         }
+        if !all_needed_chunks_listed {
+            // Shouldn't happen, but releasing chunks without knowing all the
+            // needed ones could release chunks that are still being played.
+            return;
+        }
+        self.needed_chunks.sort_unstable();
+
+        // Release the chunks of streaming clips which are not in the window
+        // anymore (or yet), unless another clip still needs them.
+        for clip in self.playing_clips.iter().filter(|clip| clip.streaming) {
+            profiling::scope!("release streamed clip chunks");
+            let Some(asset) = resources.get_audio_clip(clip.clip) else {
+                continue;
+            };
+            for chunk in asset.chunks.clone() {
+                let needed = self.needed_chunks.binary_search(&chunk).is_ok();
+                if !needed && resources.chunks.is_loaded(chunk) {
+                    resources.chunks.unload(chunk);
+                }
+            }
+        }
+    }
+}
+
+/// Mixes the clip's samples into `dst` at the original playback rate.
+/// `dst_position` is the playback position of the first sample in `dst`.
+fn render_clip(
+    clip: &PlayingClip,
+    volume: f32,
+    resources: &ResourceDatabase,
+    dst: &mut [[i16; AUDIO_CHANNELS]],
+    dst_position: u64,
+) {
+    profiling::function_scope!();
+    let Some(asset) = resources.get_audio_clip(clip.clip) else {
+        return;
+    };
+    if asset.samples == 0 {
+        return;
+    }
+
+    let mut dst_offset = clip.start_position.saturating_sub(dst_position) as usize;
+    while dst_offset < dst.len() {
+        // The position of the next sample to render, relative to the start of
+        // the clip.
+        let position = dst_position + dst_offset as u64;
+        let mut clip_position = position - clip.start_position;
+        if clip.looping {
+            clip_position %= asset.samples as u64;
+        } else if clip_position >= asset.samples as u64 {
+            break;
+        }
+        let clip_position = clip_position as u32;
+
+        let chunk_offset = clip_position / AUDIO_SAMPLES_PER_CHUNK as u32;
+        let chunk_start = chunk_offset * AUDIO_SAMPLES_PER_CHUNK as u32;
+        let chunk_end = (chunk_start + AUDIO_SAMPLES_PER_CHUNK as u32).min(asset.samples);
+
+        let Some(chunk) = &resources.chunks.get(asset.chunks.start + chunk_offset) else {
+            // Skip over the missing chunk instead of stopping, so that the
+            // rest of the clip plays the same regardless of where the
+            // playback window was split for mixing.
+            dst_offset += (chunk_end - clip_position) as usize;
+            continue;
+        };
+        let chunk_samples = bytemuck::cast_slice::<u8, [i16; AUDIO_CHANNELS]>(&chunk.0);
+        let first_sample_idx = (clip_position - chunk_start) as usize;
+        let last_sample_idx = (chunk_end - chunk_start) as usize;
+        dst_offset += render_audio_chunk(
+            &chunk_samples[first_sample_idx..last_sample_idx],
+            &mut dst[dst_offset..],
+            clip,
+            volume,
+            position,
+        );
+    }
+}
+
+/// Mixes the clip's samples into `dst`, resampling them with linear
+/// interpolation to match the clip's playback rate. `dst_position` is the
+/// playback position of the first sample in `dst`.
+fn render_resampled_clip(
+    clip: &PlayingClip,
+    volume: f32,
+    resources: &ResourceDatabase,
+    dst: &mut [[i16; AUDIO_CHANNELS]],
+    dst_position: u64,
+) {
+    profiling::function_scope!();
+    let Some(asset) = resources.get_audio_clip(clip.clip) else {
+        return;
+    };
+    let samples = asset.samples as u64;
+    if samples == 0 {
+        return;
+    }
+
+    let get_sample = |index: u64| -> Option<[i16; AUDIO_CHANNELS]> {
+        let chunk_offset = (index / AUDIO_SAMPLES_PER_CHUNK as u64) as u32;
+        let chunk = resources.chunks.get(asset.chunks.start + chunk_offset)?;
+        let chunk_samples = bytemuck::cast_slice::<u8, [i16; AUDIO_CHANNELS]>(&chunk.0);
+        Some(chunk_samples[index as usize % AUDIO_SAMPLES_PER_CHUNK])
+    };
+
+    let first_dst_offset = clip.start_position.saturating_sub(dst_position) as usize;
+    for (i, dst) in dst.iter_mut().enumerate().skip(first_dst_offset) {
+        let position = dst_position + i as u64;
+        let source_position = clip.source_position(position);
+        let mut index = source_position >> RATE_FRACTION_BITS;
+        let fraction = (source_position & (RATE_ONE - 1)) as i64;
+
+        let next_index;
+        if clip.looping {
+            index %= samples;
+            next_index = (index + 1) % samples;
+        } else if index < samples {
+            next_index = (index + 1).min(samples - 1);
+        } else {
+            break;
+        }
+
+        let (Some(current), Some(next)) = (get_sample(index), get_sample(next_index)) else {
+            continue;
+        };
+
+        let gains = clip.gains_at(volume, position);
+        for channel in 0..AUDIO_CHANNELS {
+            let (a, b) = (current[channel] as i64, next[channel] as i64);
+            let interpolated = a + (((b - a) * fraction) >> RATE_FRACTION_BITS);
+            // Float to int `as` casts saturate, so this can't overflow.
+            let attenuated = (interpolated as f32 * gains[channel]) as i16;
+            dst[channel] = dst[channel].saturating_add(attenuated);
+        }
     }
 }
 
+/// Mixes the samples of the clip into `dst`, clamping the results to the range
+/// of `i16`. `dst_position` is the playback position of the first sample in
+/// `dst`, used to calculate the gain of a fade-out for each sample.
+///
+/// Returns the amount of samples mixed, i.e. the length of the shorter slice.
 fn render_audio_chunk(
     chunk_samples: &[[i16; AUDIO_CHANNELS]],
     dst: &mut [[i16; AUDIO_CHANNELS]],
-    volume: u8,
-) {
+    clip: &PlayingClip,
+    volume: f32,
+    dst_position: u64,
+) -> usize {
     profiling::function_scope!();
-    for (dst, sample) in dst.iter_mut().zip(chunk_samples) {
+    for (i, (dst, sample)) in dst.iter_mut().zip(chunk_samples).enumerate() {
+        let gains = clip.gains_at(volume, dst_position + i as u64);
         for channel in 0..AUDIO_CHANNELS {
-            let sample = sample[channel];
-            let attenuated = ((sample as i32 * volume as i32) / u8::MAX as i32) as i16;
-            dst[channel] += attenuated;
+            // Float to int `as` casts saturate, so this can't overflow.
+            let attenuated = (sample[channel] as f32 * gains[channel]) as i16;
+            dst[channel] = dst[channel].saturating_add(attenuated);
+        }
+    }
+    dst.len().min(chunk_samples.len())
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use core::time::Duration;
+    use std::vec::Vec;
+
+    use platform::{AudioOutput, EngineCallbacks, Platform, AUDIO_CHANNELS, AUDIO_SAMPLE_RATE};
+
+    use crate::{
+        allocators::{static_allocator, LinearAllocator},
+        multithreading::create_thread_pool,
+        resources::AUDIO_SAMPLES_PER_CHUNK,
+        test_platform::{audio_clip_database, TestPlatform},
+        Engine, EngineLimits,
+    };
+
+    use super::{Mixer, PlaybackSettings};
+
+    const WINDOW_LENGTH: usize = 4800;
+
+    /// Creates an engine, plays the "whack" clip on the given channel, and
+    /// makes sure the clip's first chunks are loaded.
+    fn engine_playing_clip(
+        platform: &TestPlatform,
+        arena: &'static LinearAllocator,
+        channel: usize,
+        window_length: usize,
+        settings: PlaybackSettings,
+    ) -> Engine<'static> {
+        let mut engine = Engine::new(
+            platform,
+            arena,
+            EngineLimits {
+                audio_channel_count: 2,
+                audio_window_length: window_length,
+                ..EngineLimits::DEFAULT
+            },
+        );
+        let clip = engine.resource_db.find_audio_clip("whack").unwrap();
+        engine
+            .audio_mixer
+            .update_audio_sync(platform.now(), platform);
+        let resources = &engine.resource_db;
+        let mixer = &mut engine.audio_mixer;
+        assert!(mixer.play_clip_with_settings(channel, clip, 0, settings, resources));
+        render(&mut engine, platform);
+        engine.resource_loader.dispatch_reads(platform);
+        engine
+            .resource_loader
+            .finish_reads(&mut engine.resource_db, platform, 128);
+        engine
+    }
+
+    fn render(engine: &mut Engine, platform: &TestPlatform) -> Vec<[i16; AUDIO_CHANNELS]> {
+        engine.audio_mixer.render_audio(
+            &mut engine.thread_pool,
+            platform,
+            &mut engine.resource_db,
+            &mut engine.resource_loader,
+        );
+        engine.audio_mixer.playback_buffer.to_vec()
+    }
+
+    #[test]
+    fn playback_buffer_is_aligned_as_requested() {
+        static ARENA: &LinearAllocator = static_allocator!(64 * 1024);
+        // Misalign the rest of the arena.
+        ARENA.try_alloc_uninit_slice::<u8>(1, None).unwrap();
+        let mixer = Mixer::with_alignment(ARENA, 1, 4, 100, 32).unwrap();
+        assert_eq!(0, mixer.playback_buffer.as_ptr() as usize % 32);
+        assert_eq!(100, mixer.playback_buffer.len());
+    }
+
+    #[test]
+    fn fade_out_tapers_to_silence() {
+        static ARENA: &LinearAllocator = static_allocator!(64 * 1024 * 1024);
+        let platform = TestPlatform::new(false);
+        let mut engine = engine_playing_clip(
+            &platform,
+            ARENA,
+            0,
+            WINDOW_LENGTH,
+            PlaybackSettings::DEFAULT,
+        );
+
+        let full_volume = render(&mut engine, &platform);
+        let fade_length = WINDOW_LENGTH / 2;
+        assert!(
+            full_volume[fade_length..].iter().any(|s| *s != [0; 2]),
+            "the test clip should not be silent after the fade-out",
+        );
+
+        let fade_duration =
+            Duration::from_micros(fade_length as u64 * 1_000_000 / AUDIO_SAMPLE_RATE as u64);
+        engine.audio_mixer.fade_out_channel(1, fade_duration);
+        assert_eq!(full_volume, render(&mut engine, &platform));
+
+        engine.audio_mixer.fade_out_channel(0, fade_duration);
+        let faded = render(&mut engine, &platform);
+        for (i, (full, faded)) in full_volume.iter().zip(&faded).enumerate() {
+            for (full, faded) in full.iter().zip(faded) {
+                let gain = fade_length.saturating_sub(i) as f32 / fade_length as f32;
+                let expected = (*full as f32 * gain) as i16;
+                assert_eq!(expected, *faded, "sample {i} is not faded out linearly");
+            }
+        }
+    }
+
+    #[test]
+    fn mono_output_averages_the_channels() {
+        static ARENA: &LinearAllocator = static_allocator!(64 * 1024 * 1024);
+        let platform = TestPlatform::new(false);
+        let settings = PlaybackSettings {
+            pan: 1.0,
+            ..PlaybackSettings::DEFAULT
+        };
+        let mut engine = engine_playing_clip(&platform, ARENA, 0, WINDOW_LENGTH, settings);
+
+        let stereo = render(&mut engine, &platform);
+        assert!(
+            stereo.iter().any(|[left, right]| left != right),
+            "the test clip should be panned to one side",
+        );
+
+        engine.audio_mixer.output = AudioOutput::Mono;
+        let mono = render(&mut engine, &platform);
+        for (i, ([left, right], mono)) in stereo.iter().zip(&mono).enumerate() {
+            let expected = ((*left as i32 + *right as i32) / 2) as i16;
+            assert_eq!([expected; 2], *mono, "sample {i} is not downmixed");
+        }
+    }
+
+    #[test]
+    fn stopping_channels() {
+        static ARENA: &LinearAllocator = static_allocator!(64 * 1024 * 1024);
+        let platform = TestPlatform::new(false);
+        let mut engine = engine_playing_clip(
+            &platform,
+            ARENA,
+            0,
+            WINDOW_LENGTH,
+            PlaybackSettings::DEFAULT,
+        );
+
+        engine.audio_mixer.stop_channel(1);
+        assert!(render(&mut engine, &platform).iter().any(|s| *s != [0; 2]));
+
+        engine.audio_mixer.stop_channel(0);
+        assert!(render(&mut engine, &platform).iter().all(|s| *s == [0; 2]));
+    }
+
+    #[test]
+    fn channel_volume_scales_samples() {
+        static ARENA: &LinearAllocator = static_allocator!(64 * 1024 * 1024);
+        let platform = TestPlatform::new(false);
+        let mut engine = engine_playing_clip(
+            &platform,
+            ARENA,
+            1,
+            WINDOW_LENGTH,
+            PlaybackSettings::DEFAULT,
+        );
+
+        let full_volume = render(&mut engine, &platform);
+        assert!(full_volume.iter().any(|s| *s != [0; 2]));
+
+        engine.audio_mixer.set_channel_volume(0, 0.0);
+        assert_eq!(full_volume, render(&mut engine, &platform));
+
+        engine.audio_mixer.set_channel_volume(1, 0.5);
+        assert_eq!(0.5, engine.audio_mixer.channel_volume(1));
+        let half_volume = render(&mut engine, &platform);
+        for (full, half) in full_volume.iter().zip(&half_volume) {
+            for (full, half) in full.iter().zip(half) {
+                assert_eq!(*full / 2, *half);
+            }
+        }
+
+        engine.audio_mixer.set_channel_volume(1, 1000.0);
+        let amplified = render(&mut engine, &platform);
+        for (full, amplified) in full_volume.iter().zip(&amplified) {
+            for (full, amplified) in full.iter().zip(amplified) {
+                assert_eq!(full.signum(), amplified.signum());
+            }
         }
+
+        // The u8 volume is applied on top of the multiplier.
+        engine.audio_mixer.channels[1].volume = 0;
+        assert_eq!(0.0, engine.audio_mixer.channel_volume(1));
+        assert!(render(&mut engine, &platform).iter().all(|s| *s == [0; 2]));
+    }
+
+    #[test]
+    fn looping_clips_repeat_seamlessly() {
+        static ARENA: &LinearAllocator = static_allocator!(64 * 1024 * 1024);
+        let platform = TestPlatform::new(false);
+        let settings = PlaybackSettings {
+            looping: true,
+            ..PlaybackSettings::DEFAULT
+        };
+        let mut engine = engine_playing_clip(&platform, ARENA, 0, 40_000, settings);
+
+        let clip = engine.resource_db.find_audio_clip("whack").unwrap();
+        let clip_length = engine.resource_db.get_audio_clip(clip).unwrap().samples as usize;
+        assert!(
+            clip_length * 2 < 40_000,
+            "the test clip should loop in the window"
+        );
+
+        let samples = render(&mut engine, &platform);
+        assert!(samples[..clip_length].iter().any(|s| *s != [0; 2]));
+        for i in clip_length..samples.len() {
+            assert_eq!(samples[i - clip_length], samples[i], "sample {i} differs");
+        }
+
+        engine.audio_mixer.stop_channel(0);
+        assert!(render(&mut engine, &platform).iter().all(|s| *s == [0; 2]));
+    }
+
+    #[test]
+    fn playback_rate_resamples_clip() {
+        static ARENA: &LinearAllocator = static_allocator!(64 * 1024 * 1024);
+        let platform = TestPlatform::new(false);
+        let settings = PlaybackSettings::DEFAULT;
+        let mut engine = engine_playing_clip(&platform, ARENA, 0, 20_000, settings);
+        let clip = engine.resource_db.find_audio_clip("whack").unwrap();
+        let clip_length = engine.resource_db.get_audio_clip(clip).unwrap().samples as usize;
+        assert!(
+            clip_length < 20_000,
+            "the test clip should end in the window"
+        );
+
+        let original = render(&mut engine, &platform);
+        assert!(original[..clip_length].iter().any(|s| *s != [0; 2]));
+
+        engine.audio_mixer.stop_channel(0);
+        let settings = PlaybackSettings {
+            rate: 2.0,
+            ..PlaybackSettings::DEFAULT
+        };
+        let resources = &engine.resource_db;
+        let mixer = &mut engine.audio_mixer;
+        assert!(mixer.play_clip_with_settings(0, clip, 0, settings, resources));
+        let doubled = render(&mut engine, &platform);
+
+        // Every other sample of the original, at half the length.
+        let doubled_length = clip_length.div_ceil(2);
+        for i in 0..doubled_length {
+            assert_eq!(original[i * 2], doubled[i], "sample {i} differs");
+        }
+        assert!(doubled[doubled_length..].iter().all(|s| *s == [0; 2]));
+    }
+
+    #[test]
+    fn panning_left_silences_right_channel() {
+        static ARENA: &LinearAllocator = static_allocator!(64 * 1024 * 1024);
+        let platform = TestPlatform::new(false);
+        let settings = PlaybackSettings::DEFAULT;
+        let mut engine = engine_playing_clip(&platform, ARENA, 0, WINDOW_LENGTH, settings);
+        let clip = engine.resource_db.find_audio_clip("whack").unwrap();
+
+        let centered = render(&mut engine, &platform);
+        assert!(centered.iter().any(|[_, right]| *right != 0));
+
+        engine.audio_mixer.stop_channel(0);
+        let settings = PlaybackSettings {
+            pan: -1.0,
+            ..PlaybackSettings::DEFAULT
+        };
+        let resources = &engine.resource_db;
+        let mixer = &mut engine.audio_mixer;
+        assert!(mixer.play_clip_with_settings(0, clip, 0, settings, resources));
+        let panned = render(&mut engine, &platform);
+
+        for ([center_left, _], [left, right]) in centered.iter().zip(&panned) {
+            assert_eq!(0, *right);
+            assert!(left.unsigned_abs() >= center_left.unsigned_abs());
+        }
+    }
+
+    #[test]
+    fn centered_clips_are_played_as_is() {
+        static ARENA: &LinearAllocator = static_allocator!(64 * 1024 * 1024);
+        let platform = TestPlatform::new(false);
+        let settings = PlaybackSettings::DEFAULT;
+        let mut engine = engine_playing_clip(&platform, ARENA, 0, WINDOW_LENGTH, settings);
+        let clip = engine.resource_db.find_audio_clip("whack").unwrap();
+
+        let centered = render(&mut engine, &platform);
+        assert!(centered.iter().any(|s| *s != [0; 2]));
+
+        // A clip panned fully to one side plays unscaled on that side, which
+        // is what both channels should play without panning.
+        let mut render_panned = |pan: f32| {
+            engine.audio_mixer.stop_channel(0);
+            let settings = PlaybackSettings {
+                pan,
+                ..PlaybackSettings::DEFAULT
+            };
+            let resources = &engine.resource_db;
+            let mixer = &mut engine.audio_mixer;
+            assert!(mixer.play_clip_with_settings(0, clip, 0, settings, resources));
+            render(&mut engine, &platform)
+        };
+        let left = render_panned(-1.0);
+        let right = render_panned(1.0);
+
+        for (i, center) in centered.iter().enumerate() {
+            assert_eq!([left[i][0], right[i][1]], *center, "sample {i} differs");
+        }
+    }
+
+    #[test]
+    fn pan_gains_have_constant_power() {
+        assert_eq!([1.0, 1.0], super::pan_gains(0.0));
+        assert_eq!([1.0, 1.0], super::pan_gains(f32::NAN));
+        let [left, right] = super::pan_gains(-1.0);
+        assert!(left == 1.0 && right.abs() < 0.001);
+        let mut previous_far = 1.0;
+        for i in 0..=10 {
+            // The closer channel stays at full volume, and the further one is
+            // turned down the further the clip is panned.
+            let [left, right] = super::pan_gains(i as f32 / 10.0);
+            let [mirrored_left, mirrored_right] = super::pan_gains(-i as f32 / 10.0);
+            assert!((mirrored_left - right).abs() < 0.001);
+            assert!((mirrored_right - left).abs() < 0.001);
+            assert_eq!(1.0, right);
+            assert!(left <= previous_far);
+            previous_far = left;
+        }
+        // Halfway to the side, the unscaled gains add up to constant power.
+        let [_, far] = super::pan_gains(-0.5);
+        let far = far / core::f32::consts::SQRT_2;
+        let near = 0.923_879_5; // cos(pi / 8)
+        assert!((near * near + far * far - 1.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn channel_becomes_inactive_after_clip_ends() {
+        static ARENA: &LinearAllocator = static_allocator!(64 * 1024 * 1024);
+        let platform = TestPlatform::new(false);
+        let settings = PlaybackSettings::DEFAULT;
+        let mut engine = engine_playing_clip(&platform, ARENA, 0, WINDOW_LENGTH, settings);
+        assert!(engine.audio_mixer.is_channel_active(0));
+        assert!(!engine.audio_mixer.is_channel_active(1));
+
+        // The test clip is about 270 milliseconds long.
+        platform.set_elapsed_millis(500);
+        engine
+            .audio_mixer
+            .update_audio_sync(platform.now(), &platform);
+        render(&mut engine, &platform);
+        assert!(!engine.audio_mixer.is_channel_active(0));
+    }
+
+    #[test]
+    fn streamed_clips_only_keep_the_upcoming_chunks_loaded() {
+        static ARENA: &LinearAllocator = static_allocator!(64 * 1024 * 1024);
+        let platform = TestPlatform::new(false);
+        let chunks = 8;
+        *platform.resources_db_mut() = audio_clip_database("long", chunks);
+        let mut engine = Engine::new(
+            &platform,
+            ARENA,
+            EngineLimits {
+                resource_database_loaded_chunks_count: 3,
+                audio_window_length: WINDOW_LENGTH,
+                ..EngineLimits::DEFAULT
+            },
+        );
+
+        let clip = engine.resource_db.find_audio_clip("long").unwrap();
+        let settings = PlaybackSettings {
+            looping: true,
+            streaming: true,
+            ..PlaybackSettings::DEFAULT
+        };
+        let resources = &engine.resource_db;
+        assert!((engine.audio_mixer).play_clip_with_settings(0, clip, 0, settings, resources));
+
+        // Play through the clip one and a half times, 100 ms at a time.
+        let clip_length = chunks as u64 * AUDIO_SAMPLES_PER_CHUNK as u64;
+        let frame_length = AUDIO_SAMPLE_RATE as u64 / 10;
+        for frame in 0..clip_length * 3 / 2 / frame_length {
+            platform.set_elapsed_millis(frame * 100);
+            engine.run_frame(&platform, &mut |_, _, _| {});
+
+            let first_sample = engine.audio_mixer.playback_buffer[0];
+            if frame == 0 {
+                // Nothing is loaded yet, which should just be silent.
+                assert_eq!([0; AUDIO_CHANNELS], first_sample);
+            } else {
+                let position = frame * frame_length % clip_length;
+                let chunk = (position / AUDIO_SAMPLES_PER_CHUNK as u64) as i16;
+                assert_eq!([chunk + 1; AUDIO_CHANNELS], first_sample, "frame {frame}");
+            }
+
+            let loaded_chunks =
+                (0..chunks).filter(|&chunk| engine.resource_db.chunks.is_loaded(chunk));
+            assert!(loaded_chunks.count() <= 2, "frame {frame}");
+        }
+    }
+
+    #[test]
+    fn high_priority_sounds_replace_low_priority_ones() {
+        static ARENA: &LinearAllocator = static_allocator!(64 * 1024 * 1024);
+        let platform = TestPlatform::new(false);
+        let mut engine = Engine::new(
+            &platform,
+            ARENA,
+            EngineLimits {
+                audio_channel_count: 2,
+                audio_concurrent_sounds_count: 3,
+                ..EngineLimits::DEFAULT
+            },
+        );
+        let clip = engine.resource_db.find_audio_clip("whack").unwrap();
+        let resources = &engine.resource_db;
+        let mixer = &mut engine.audio_mixer;
+        let play_at = |mixer: &mut Mixer, millis: u64, channel: usize, priority: u8| {
+            platform.set_elapsed_millis(millis);
+            mixer.update_audio_sync(platform.now(), &platform);
+            mixer.play_clip(channel, clip, priority, resources)
+        };
+        let start_positions = |mixer: &Mixer| {
+            let mut starts = (mixer.playing_clips.iter())
+                .map(|clip| (clip.start_position, clip.priority))
+                .collect::<Vec<_>>();
+            starts.sort();
+            starts
+        };
+        let ms = |millis: u64| millis * AUDIO_SAMPLE_RATE as u64 / 1000;
+
+        assert!(play_at(mixer, 0, 0, 1));
+        assert!(play_at(mixer, 10, 0, 1));
+        assert!(play_at(mixer, 20, 1, 1));
+        // All voices are taken by higher priority sounds.
+        assert!(!play_at(mixer, 30, 0, 0));
+
+        // Sounds of the same priority replace the oldest one, not each other.
+        assert!(play_at(mixer, 30, 0, 1));
+        assert!(play_at(mixer, 40, 0, 1));
+        let expected = [(ms(20), 1), (ms(30), 1), (ms(40), 1)];
+        assert_eq!(&expected[..], start_positions(mixer));
+
+        // Higher priority sounds replace the quietest lower priority sound.
+        mixer.set_channel_volume(1, 0.5);
+        assert!(play_at(mixer, 50, 0, 2));
+        let expected = [(ms(30), 1), (ms(40), 1), (ms(50), 2)];
+        assert_eq!(&expected[..], start_positions(mixer));
+
+        // Nothing can replace a higher priority sound.
+        assert!(play_at(mixer, 60, 0, 1));
+        assert!(play_at(mixer, 70, 0, 1));
+        assert!(!play_at(mixer, 80, 0, 0));
+        assert!(start_positions(mixer).contains(&(ms(50), 2)));
+    }
+
+    #[test]
+    fn parallel_mix_matches_serial_mix() {
+        static ARENA: &LinearAllocator = static_allocator!(64 * 1024 * 1024);
+        let platform = TestPlatform::new(true);
+        let chunks = 4;
+        *platform.resources_db_mut() = audio_clip_database("long", chunks);
+        let window_length = chunks as usize * AUDIO_SAMPLES_PER_CHUNK;
+        let mut engine = Engine::new(
+            &platform,
+            ARENA,
+            EngineLimits {
+                audio_channel_count: 2,
+                audio_window_length: window_length,
+                ..EngineLimits::DEFAULT
+            },
+        );
+        assert!(engine.thread_pool.thread_count() > 1);
+
+        // Load every chunk except the second one, which is split between the
+        // first two segments of the playback window.
+        let resources = &engine.resource_db;
+        for chunk in 0..chunks {
+            engine.resource_loader.queue_chunk(chunk, resources);
+        }
+        engine.resource_loader.dispatch_reads(&platform);
+        (engine.resource_loader).finish_reads(&mut engine.resource_db, &platform, 128);
+        engine.resource_db.chunks.unload(1);
+
+        let clip = engine.resource_db.find_audio_clip("long").unwrap();
+        let clips = [
+            (0, PlaybackSettings::DEFAULT),
+            (
+                1,
+                PlaybackSettings {
+                    rate: 0.75,
+                    pan: -0.5,
+                    ..PlaybackSettings::DEFAULT
+                },
+            ),
+            (
+                1,
+                PlaybackSettings {
+                    looping: true,
+                    rate: 1.5,
+                    pan: 0.25,
+                    ..PlaybackSettings::DEFAULT
+                },
+            ),
+        ];
+        for (i, (channel, settings)) in clips.into_iter().enumerate() {
+            platform.set_elapsed_millis(i as u64 * 10);
+            let mixer = &mut engine.audio_mixer;
+            mixer.update_audio_sync(platform.now(), &platform);
+            let resources = &engine.resource_db;
+            assert!(mixer.play_clip_with_settings(channel, clip, 0, settings, resources));
+        }
+
+        let parallel = render(&mut engine, &platform);
+        let mut serial_thread_pool =
+            create_thread_pool(ARENA, &TestPlatform::new(false), 1).unwrap();
+        assert_eq!(1, serial_thread_pool.thread_count());
+        engine.audio_mixer.render_audio(
+            &mut serial_thread_pool,
+            &platform,
+            &mut engine.resource_db,
+            &mut engine.resource_loader,
+        );
+        let serial = engine.audio_mixer.playback_buffer.to_vec();
+
+        assert!(
+            parallel == serial,
+            "parallel mix differs from the serial mix"
+        );
+        let last_chunk = &serial[(chunks as usize - 1) * AUDIO_SAMPLES_PER_CHUNK..];
+        assert!(
+            last_chunk.iter().any(|s| *s != [0; 2]),
+            "the clips should play after the missing chunk",
+        );
     }
 }
@@ -2,7 +2,7 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
-use core::{mem::MaybeUninit, slice};
+use core::{any::type_name, mem::MaybeUninit, slice};
 
 use arrayvec::ArrayVec;
 use platform::{
@@ -75,6 +75,39 @@ where
     F: Sync + Fn(&mut [T], usize),
 {
     profiling::function_scope!();
+    let max_tasks = thread_pool.thread_count().min(MAX_THREADS);
+    let chunk_size = data.len().div_ceil(max_tasks);
+    parallelize_with_chunk_size(thread_pool, data, chunk_size.max(1), func);
+    chunk_size
+}
+
+/// Runs the function on multiple threads, splitting the data into parts of
+/// `chunk_size` elements (except for the last part, which may be shorter).
+///
+/// Unlike [`parallelize`], this can split the data into more parts than there
+/// are threads. The parts are assigned to the threads in a round-robin
+/// fashion, so using smaller parts can balance out the work between the
+/// threads when the cost of processing the elements varies between different
+/// regions of the slice. Smaller parts do add some overhead per part though.
+///
+/// The function also gets the offset of the specific subslice it got, relative
+/// to the start of `data`.
+///
+/// ### Panics
+///
+/// If `chunk_size` is 0, or if the thread pool already has pending tasks, like
+/// [`parallelize`].
+#[track_caller]
+pub fn parallelize_with_chunk_size<T, F>(
+    thread_pool: &mut ThreadPool,
+    data: &mut [T],
+    chunk_size: usize,
+    func: F,
+) where
+    T: Sync,
+    F: Sync + Fn(&mut [T], usize),
+{
+    profiling::function_scope!();
 
     struct Task {
         data_ptr: *mut (),
@@ -92,15 +125,22 @@ where
         panic!("thread pool has pending tasks but was used in a parallellize() call");
     }
 
+    assert!(chunk_size > 0, "chunk size must be non-zero");
+
     if data.is_empty() {
-        return 0;
+        return;
     }
 
-    let max_tasks = thread_pool.thread_count().min(MAX_THREADS);
+    // The amount of tasks that can be spawned before some need to be joined:
+    // the queue of each thread has room for `queue_len` tasks, and the tasks
+    // are spawned on the threads in a round-robin fashion and joined in FIFO
+    // order, so the thread of each spawned task has room for it.
+    let max_tasks_in_flight =
+        (thread_pool.thread_count() * thread_pool.queue_len()).clamp(1, MAX_THREADS);
 
     let mut backing_task_buffer = ArrayVec::<MaybeUninit<Task>, MAX_THREADS>::new();
     let mut backing_task_proxies = ArrayVec::<MaybeUninit<TaskProxy>, MAX_THREADS>::new();
-    for _ in 0..max_tasks {
+    for _ in 0..max_tasks_in_flight {
         backing_task_buffer.push(MaybeUninit::uninit());
         backing_task_proxies.push(MaybeUninit::uninit());
     }
@@ -111,18 +151,41 @@ where
     let mut task_buffer = unsafe { RingBuffer::from_mut(&mut backing_task_buffer) };
     let mut task_proxies = Queue::from_mut(&mut backing_task_proxies).unwrap();
 
+    // Joins the oldest task and frees its buffers (doesn't free up space for
+    // anything, but makes sure we're not leaking anything, which would violate
+    // the safety requirements of the non-static RingBuffer).
+    fn join_oldest_task(
+        thread_pool: &mut ThreadPool,
+        task_proxies: &mut Queue<TaskProxy>,
+        task_buffer: &mut RingBuffer<Task>,
+    ) -> bool {
+        profiling::scope!("receive result");
+        let Some(proxy) = task_proxies.pop_front() else {
+            return false;
+        };
+        let task = thread_pool.join_task(proxy.handle).ok().unwrap(); // does not panic: we're joining tasks in FIFO order
+
+        // Safety: the `Task` was allocated in the spawning loop, with the
+        // actual boxed task being sent onto a thread, and the metadata stored
+        // in the proxy, alongside the handle for said task. Since `task` here
+        // is the result of that task, it must be the same boxed task allocated
+        // alongside this metadata.
+        let boxed = unsafe { RingBox::from_parts(task, proxy.metadata) };
+        task_buffer.free_box(boxed).ok().unwrap();
+        true
+    }
+
     thread_pool.reset_thread_counter();
 
     // Shadow `func` to ensure that the value doesn't get dropped until the end
     // of this function, since this borrow is shared with the threads.
     let func: *const F = &func;
 
-    let chunk_size = data.len().div_ceil(max_tasks);
     for (i, data_part) in data.chunks_mut(chunk_size).enumerate() {
         profiling::scope!("send task");
-        // Shouldn't ever trip, but if it does, we'd much rather crash here than
-        // having half-spawned a task, which could be unsound.
-        assert!(i < max_tasks);
+        if task_proxies.is_full() {
+            join_oldest_task(thread_pool, &mut task_proxies, &mut task_buffer);
+        }
 
         // Allocate the thread pool task.
         let data_ptr: *mut T = data_part.as_mut_ptr();
@@ -135,69 +198,116 @@ where
                 data_offset: i * chunk_size,
             })
             .ok()
-            .unwrap() // does not panic: task_buffer is guaranteed to have capacity via the assert at the start of this loop body
+            .unwrap() // does not panic: task_buffer has room for as many tasks as task_proxies, which was just checked to not be full
             .into_parts();
 
         // Send off the task, using the proxy function from it to call the
-        // user-provided one.
+        // user-provided one. The type name of `func` points to where the
+        // closure was written, for the panic message if a task panics.
+        let label = Some(type_name::<F>());
         let handle = thread_pool
-            .spawn_task(task, |task| {
-                let data_ptr = task.data_ptr as *mut T;
-                let data_len = task.data_len;
-                // Safety:
-                // - Type, pointer and length validity-wise, this slice is ok to
-                //   create as it was created from a slice of T in the first
-                //   place.
-                // - Lifetime-wise, creating this slice is valid because the
-                //   slice's lifetime spans this function, and this function is
-                //   run within the lifetime of the `parallelize` function call
-                //   due to all tasks being joined before the end, and the
-                //   original slice is valid for the entirety of `parallellize`.
-                // - Exclusive-access-wise, it's valid since the backing slice
-                //   is only used to split it with chunks_mut, and those chunks
-                //   are simply sent off to worker threads. Since this all
-                //   happens during parallelize() (see lifetime point), there's
-                //   definitely no others creating any kind of borrow of this
-                //   particular chunk.
-                let data: &mut [T] = unsafe { slice::from_raw_parts_mut(data_ptr, data_len) };
-                let func = task.func as *const F;
-                // Safety: same logic as for the data, except that this
-                // reference is shared, which is valid because it's a
-                // const-pointer and we borrow it immutably.
-                unsafe { (*func)(data, task.data_offset) };
-            })
+            .spawn_labeled_task(
+                task,
+                |task| {
+                    let data_ptr = task.data_ptr as *mut T;
+                    let data_len = task.data_len;
+                    // Safety:
+                    // - Type, pointer and length validity-wise, this slice is
+                    //   ok to create as it was created from a slice of T in the
+                    //   first place.
+                    // - Lifetime-wise, creating this slice is valid because the
+                    //   slice's lifetime spans this function, and this function
+                    //   is run within the lifetime of the `parallelize`
+                    //   function call due to all tasks being joined before the
+                    //   end, and the original slice is valid for the entirety
+                    //   of `parallellize`.
+                    // - Exclusive-access-wise, it's valid since the backing
+                    //   slice is only used to split it with chunks_mut, and
+                    //   those chunks are simply sent off to worker threads.
+                    //   Since this all happens during parallelize() (see
+                    //   lifetime point), there's definitely no others creating
+                    //   any kind of borrow of this particular chunk.
+                    let data = unsafe { slice::from_raw_parts_mut(data_ptr, data_len) };
+                    let func = task.func as *const F;
+                    // Safety: same logic as for the data, except that this
+                    // reference is shared, which is valid because it's a
+                    // const-pointer and we borrow it immutably.
+                    unsafe { (*func)(data, task.data_offset) };
+                },
+                label,
+            )
             .ok()
-            .unwrap(); // does not panic: thread_pool is guaranteed to have capacity, it's empty and we're only spawning thread_count tasks
+            .unwrap(); // does not panic: thread_pool is guaranteed to have capacity, see max_tasks_in_flight
 
         // Add the task handle to the queue to be joined before returning.
         task_proxies
             .push_back(TaskProxy { handle, metadata })
             .ok()
-            .unwrap(); // does not panic: task_proxies is guaranteed to have capacity via the assert at the start of this loop body
+            .unwrap(); // does not panic: task_proxies was just checked to not be full
     }
 
-    // Join tasks and free the buffers (doesn't free up space for anything, but
-    // makes sure we're not leaking anything, which would violate the safety
-    // requirements of the non-static RingBuffer).
-    while let Some(proxy) = task_proxies.pop_front() {
-        profiling::scope!("receive result");
-        let task = thread_pool.join_task(proxy.handle).ok().unwrap(); // does not panic: we're joining tasks in FIFO order
+    while join_oldest_task(thread_pool, &mut task_proxies, &mut task_buffer) {}
+}
 
-        // Safety: the `Task` was allocated in the previous loop, with the
-        // actual boxed task being sent onto a thread, and the metadata stored
-        // in the proxy, alongside the handle for said task. Since `task` here
-        // is the result of that task, it must be the same boxed task allocated
-        // alongside this metadata.
-        let boxed = unsafe { RingBox::from_parts(task, proxy.metadata) };
-        task_buffer.free_box(boxed).ok().unwrap();
+/// Runs `map` on multiple threads, splitting the data into one part for each
+/// thread, and combines the results of each part with `reduce`.
+///
+/// Like with [`parallelize`], `map` also gets the offset of the specific
+/// subslice it got, relative to the start of `data`.
+///
+/// The results are combined in the order of the parts, starting from
+/// `identity`, i.e. `reduce(reduce(identity, first), second)` and so on, so
+/// `reduce` only needs to be associative, not commutative. If `data` is empty,
+/// `identity` is returned as-is.
+///
+/// ### Panics
+///
+/// If the thread pool already has pending tasks, like [`parallelize`].
+#[track_caller]
+pub fn parallelize_reduce<T, R, F, G>(
+    thread_pool: &mut ThreadPool,
+    data: &[T],
+    identity: R,
+    map: F,
+    reduce: G,
+) -> R
+where
+    T: Sync,
+    R: Send + Sync,
+    F: Sync + Fn(&[T], usize) -> R,
+    G: FnMut(R, R) -> R,
+{
+    profiling::function_scope!();
+
+    if data.is_empty() {
+        return identity;
     }
 
-    chunk_size
+    // One result per part, each filled by the parallelize() call below, which
+    // splits `results` into one-element parts as there's at most as many
+    // results as there are threads.
+    let max_tasks = thread_pool.thread_count().min(MAX_THREADS);
+    let chunk_size = data.len().div_ceil(max_tasks);
+    let mut results = ArrayVec::<Option<R>, MAX_THREADS>::new();
+    for _ in 0..data.len().div_ceil(chunk_size) {
+        results.push(None);
+    }
+
+    parallelize(thread_pool, &mut results, |results, i| {
+        let offset = i * chunk_size;
+        let data_part = &data[offset..(offset + chunk_size).min(data.len())];
+        results[0] = Some(map(data_part, offset));
+    });
+
+    results
+        .into_iter()
+        .map(|result| result.unwrap()) // does not panic: parallelize runs the function for every part
+        .fold(identity, reduce)
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{create_thread_pool, parallelize};
+    use super::{create_thread_pool, parallelize, parallelize_reduce, parallelize_with_chunk_size};
     use crate::{
         allocators::{static_allocator, LinearAllocator},
         test_platform::TestPlatform,
@@ -218,6 +328,22 @@ mod tests {
         assert_eq!([1, 4, 9, 16], data);
     }
 
+    #[test]
+    fn parallelize_reduce_works_singlethreaded() {
+        static ARENA: &LinearAllocator = static_allocator!(10_000);
+        let platform = TestPlatform::new(false);
+        let mut thread_pool = create_thread_pool(ARENA, &platform, 1).unwrap();
+
+        let data: [u32; 1000] = core::array::from_fn(|i| i as u32);
+        let sum = |data: &[u32], _| data.iter().sum::<u32>();
+        let add = |a, b| a + b;
+        assert_eq!(
+            499_500,
+            parallelize_reduce(&mut thread_pool, &data, 0, sum, add)
+        );
+        assert_eq!(7, parallelize_reduce(&mut thread_pool, &[], 7, sum, add));
+    }
+
     #[test]
     #[cfg(not(target_os = "emscripten"))]
     fn parallelize_works_multithreaded() {
@@ -238,4 +364,80 @@ mod tests {
     #[ignore = "the emscripten target doesn't support multithreading"]
     #[cfg(target_os = "emscripten")]
     fn parallelize_works_multithreaded() {}
+
+    #[test]
+    #[cfg(not(target_os = "emscripten"))]
+    fn parallelize_with_small_chunks_works_multithreaded() {
+        static ARENA: &LinearAllocator = static_allocator!(10_000);
+        let platform = TestPlatform::new(true);
+        let mut thread_pool = create_thread_pool(ARENA, &platform, 1).unwrap();
+
+        // More chunks than threads (and task queue slots), and an uneven last chunk
+        let mut data = [0; 23];
+        let chunk_size = 2;
+        assert!(chunk_size < data.len() / thread_pool.thread_count());
+        parallelize_with_chunk_size(&mut thread_pool, &mut data, chunk_size, |data, offset| {
+            for (i, n) in data.iter_mut().enumerate() {
+                *n = offset + i;
+            }
+        });
+        assert_eq!(core::array::from_fn::<usize, 23, _>(|i| i), data);
+    }
+
+    #[test]
+    #[ignore = "the emscripten target doesn't support multithreading"]
+    #[cfg(target_os = "emscripten")]
+    fn parallelize_with_small_chunks_works_multithreaded() {}
+
+    #[test]
+    #[cfg(not(target_os = "emscripten"))]
+    fn full_result_queues_do_not_drop_tasks() {
+        extern crate std;
+        use std::collections::VecDeque;
+
+        static ARENA: &LinearAllocator = static_allocator!(100_000);
+        let platform = TestPlatform::new(true);
+        let mut thread_pool = create_thread_pool(ARENA, &platform, 1).unwrap();
+
+        // The task queues are drained by the threads, so tasks can be spawned
+        // faster than the results are joined, which fills up the result
+        // queues and makes the threads wait for room to send their results.
+        const TASKS: u32 = 10_000;
+        let mut boxes = (0..thread_pool.thread_count() * 4)
+            .map(|_| ARENA.try_alloc_box(0u32).unwrap())
+            .collect::<VecDeque<_>>();
+        let mut handles = VecDeque::new();
+        let mut joined = 0;
+        for _ in 0..TASKS {
+            let mut task = boxes.pop_front().unwrap_or_else(|| {
+                joined += 1;
+                thread_pool.join_task(handles.pop_front().unwrap()).unwrap()
+            });
+            loop {
+                match thread_pool.spawn_task(task, |runs| *runs += 1) {
+                    Ok(handle) => break handles.push_back(handle),
+                    Err(task_) => {
+                        boxes.push_back(
+                            thread_pool.join_task(handles.pop_front().unwrap()).unwrap(),
+                        );
+                        joined += 1;
+                        task = task_;
+                    }
+                }
+            }
+        }
+        while let Some(handle) = handles.pop_front() {
+            boxes.push_back(thread_pool.join_task(handle).unwrap());
+            joined += 1;
+        }
+
+        assert_eq!(TASKS, joined);
+        assert_eq!(TASKS, boxes.iter().map(|runs| **runs).sum::<u32>());
+        assert!(!thread_pool.has_pending());
+    }
+
+    #[test]
+    #[ignore = "the emscripten target doesn't support multithreading"]
+    #[cfg(target_os = "emscripten")]
+    fn full_result_queues_do_not_drop_tasks() {}
 }
@@ -0,0 +1,139 @@
+// SPDX-FileCopyrightText: 2025 Jens Pitkänen <jens.pitkanen@helsinki.fi>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+/// The multiplier of the underlying linear congruential generator, from the
+/// reference PCG implementation.
+const MULTIPLIER: u64 = 6_364_136_223_846_793_005;
+/// The increment of the underlying linear congruential generator, the default
+/// stream of the reference PCG implementation.
+const INCREMENT: u64 = 1_442_695_040_888_963_407;
+
+/// A small, fast, seedable pseudorandom number generator.
+///
+/// Implements the PCG-XSH-RR variant with 64 bits of state and 32-bit outputs
+/// (`pcg32_oneseq` in the [reference implementation](https://www.pcg-random.org/)),
+/// using only integer arithmetic, so the sequence of numbers generated from a
+/// specific seed is the same on every platform, and stays the same between
+/// versions of the engine. Not suitable for anything security related.
+///
+/// The generator is just a `u64`, so it's cheap to create one per system that
+/// needs randomness, e.g. seeded from the level or frame number, to keep the
+/// systems from affecting each other's sequences.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    /// Creates a new generator, which will always generate the same sequence
+    /// of numbers for the same `seed`.
+    pub const fn new(seed: u64) -> Rng {
+        let mut rng = Rng { state: 0 };
+        rng.step();
+        rng.state = rng.state.wrapping_add(seed);
+        rng.step();
+        rng
+    }
+
+    const fn step(&mut self) {
+        self.state = self.state.wrapping_mul(MULTIPLIER).wrapping_add(INCREMENT);
+    }
+
+    /// Returns the next number in the sequence, uniformly distributed over
+    /// the whole range of `u32`.
+    pub fn next_u32(&mut self) -> u32 {
+        let state = self.state;
+        self.step();
+        let xorshifted = (((state >> 18) ^ state) >> 27) as u32;
+        let rotation = (state >> 59) as u32;
+        xorshifted.rotate_right(rotation)
+    }
+
+    /// Returns a number between 0 (inclusive) and 1 (exclusive), with all the
+    /// 2<sup>24</sup> evenly spaced values representable in that range being
+    /// equally likely.
+    pub fn next_f32(&mut self) -> f32 {
+        const SCALE: f32 = 1.0 / (1u32 << 24) as f32;
+        (self.next_u32() >> 8) as f32 * SCALE
+    }
+
+    /// Returns a number between `min` (inclusive) and `max` (exclusive), with
+    /// each number in the range being equally likely.
+    ///
+    /// Uses Lemire's multiply-and-shift method, which only needs a division
+    /// for the rare samples that need to be rejected to avoid bias.
+    ///
+    /// ### Panics
+    ///
+    /// If `min` is not less than `max`.
+    #[track_caller]
+    pub fn range(&mut self, min: i32, max: i32) -> i32 {
+        assert!(min < max, "the range {min}..{max} is empty");
+        let span = max.wrapping_sub(min) as u32;
+        let mut product = self.next_u32() as u64 * span as u64;
+        if (product as u32) < span {
+            let threshold = span.wrapping_neg() % span;
+            while (product as u32) < threshold {
+                product = self.next_u32() as u64 * span as u64;
+            }
+        }
+        min.wrapping_add((product >> 32) as i32)
+    }
+
+    /// Returns a number between `min` (inclusive) and `max` (exclusive), based
+    /// on [`Rng::next_f32`]. Useful for e.g. randomizing the pitch of sounds
+    /// or the velocities of particles.
+    pub fn range_f32(&mut self, min: f32, max: f32) -> f32 {
+        min + (max - min) * self.next_f32()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Rng;
+
+    #[test]
+    fn sequence_is_pinned_for_a_seed() {
+        let mut rng = Rng::new(42);
+        let expected = [
+            3_270_867_926,
+            1_795_671_209,
+            1_924_641_435,
+            1_143_034_755,
+            4_121_910_957,
+            1_757_328_946,
+        ];
+        for expected in expected {
+            assert_eq!(expected, rng.next_u32());
+        }
+
+        let mut rng = Rng::new(42);
+        assert_eq!(
+            (3_270_867_926u32 >> 8) as f32 / 16_777_216.0,
+            rng.next_f32()
+        );
+        let mut other_seed = Rng::new(43);
+        assert_ne!(3_270_867_926, other_seed.next_u32());
+    }
+
+    #[test]
+    fn ranges_are_covered_and_respected() {
+        let mut rng = Rng::new(1234);
+        let mut seen = [false; 7];
+        for _ in 0..1000 {
+            let value = rng.range(-3, 4);
+            assert!((-3..4).contains(&value));
+            seen[(value + 3) as usize] = true;
+
+            let float = rng.range_f32(0.5, 2.0);
+            assert!((0.5..2.0).contains(&float));
+            assert!((0.0..1.0).contains(&rng.next_f32()));
+        }
+        assert!(seen.iter().all(|seen| *seen));
+
+        // The whole range of i32 does not overflow.
+        rng.range(i32::MIN, i32::MAX);
+        assert_eq!(5, rng.range(5, 6));
+    }
+}
@@ -2,11 +2,20 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+pub mod animation;
+pub mod camera;
+pub mod font;
 pub mod sprite;
+pub mod tilemap;
 
+use camera::Camera2D;
 use platform::{BlendMode, DrawSettings2D, Platform, SpriteRef, TextureFilter, Vertex2D};
 
-use crate::{allocators::LinearAllocator, collections::FixedVec};
+use crate::{
+    allocators::LinearAllocator,
+    collections::FixedVec,
+    geom::{self, Rect},
+};
 
 /// Parameters for rendering a sprite.
 ///
@@ -14,11 +23,13 @@ use crate::{allocators::LinearAllocator, collections::FixedVec};
 /// [`SpriteAsset::draw`](crate::resources::sprite::SpriteAsset::draw).
 #[derive(Debug)]
 pub struct SpriteQuad {
-    /// The top-left coordinate of the quad in the same coordinate system as
-    /// what [`Platform::draw_area`] returns.
+    /// The top-left coordinate of the quad in world space, which
+    /// [`DrawQueue::camera`] maps to the coordinate system of
+    /// [`Platform::draw_area`].
     pub position_top_left: (f32, f32),
-    /// The bottom-right coordinate of the quad in the same coordinate system as
-    /// what [`Platform::draw_area`] returns.
+    /// The bottom-right coordinate of the quad in world space, which
+    /// [`DrawQueue::camera`] maps to the coordinate system of
+    /// [`Platform::draw_area`].
     pub position_bottom_right: (f32, f32),
     /// The top-left texture coordinate of the quad, each axis between 0..1,
     /// with (0, 0) describing the top-left corner of the texture.
@@ -26,20 +37,77 @@ pub struct SpriteQuad {
     /// The bottom-right texture coordinate of the quad, each axis between 0..1,
     /// with (0, 0) describing the top-left corner of the texture.
     pub texcoord_bottom_right: (f32, f32),
+    /// The color multiplied with the sprite's colors, in order: `[red, green,
+    /// blue, alpha]`. White for drawing the sprite as-is.
+    pub color: [u8; 4],
     /// The drawing order of this particular sprite. Sprites with a lower draw
     /// order are rendered below others with a higher one.
     pub draw_order: u8,
     /// The blending mode (if any) to use to draw this sprite above the other
     /// sprites drawn below this one.
     pub blend_mode: BlendMode,
+    /// The filtering used to sample the sprite.
+    pub texture_filter: TextureFilter,
     /// The sprite used to draw this quad with. The region of the sprite used is
     /// controlled with the `texcoord_*` fields.
     pub sprite: SpriteRef,
 }
 
 impl SpriteQuad {
-    fn draw_call_identifier(&self) -> (SpriteRef, BlendMode, u8) {
-        (self.sprite, self.blend_mode, self.draw_order)
+    fn draw_call_identifier(&self) -> (SpriteRef, BlendMode, TextureFilter, u8) {
+        (
+            self.sprite,
+            self.blend_mode,
+            self.texture_filter,
+            self.draw_order,
+        )
+    }
+
+    fn draw_call_sort_key(&self) -> (u8, SpriteRef, BlendMode, TextureFilter) {
+        (
+            self.draw_order,
+            self.sprite,
+            self.blend_mode,
+            self.texture_filter,
+        )
+    }
+}
+
+/// Parameters for rendering an untextured, single-colored quad.
+///
+/// Generally created by the primitive drawing functions of [`DrawQueue`], e.g.
+/// [`DrawQueue::draw_line`] and [`DrawQueue::fill_rect`].
+#[derive(Debug)]
+pub struct ShapeQuad {
+    /// The corners of the quad in world space (see [`DrawQueue::camera`]), in
+    /// clockwise or counter-clockwise order. The quad is rendered as two
+    /// triangles, the first made from the first three corners, and the second
+    /// from the first, third and fourth corner.
+    pub corners: [(f32, f32); 4],
+    /// The color of the quad, in order: `[red, green, blue, alpha]`.
+    pub color: [u8; 4],
+}
+
+/// Statistics about the draws made by [`DrawQueue::dispatch_draw`], for
+/// profiling rendering.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DrawStats {
+    /// The amount of [`Platform::draw_2d`] calls made.
+    pub draw_calls: usize,
+    /// The total amount of vertices passed to the platform.
+    pub vertices: usize,
+    /// The total amount of indices passed to the platform.
+    pub indices: usize,
+    /// The amount of sprite quads which were batched into the same draw call
+    /// as an earlier quad, instead of needing a draw call of their own.
+    pub batches_merged: usize,
+}
+
+impl DrawStats {
+    fn record_draw_call(&mut self, vertices: usize, indices: usize) {
+        self.draw_calls += 1;
+        self.vertices += vertices;
+        self.indices += indices;
     }
 }
 
@@ -51,38 +119,221 @@ impl SpriteQuad {
 pub struct DrawQueue<'frm> {
     /// Sprites to draw.
     pub sprites: FixedVec<'frm, SpriteQuad>,
+    /// Untextured shapes to draw. These are drawn on top of all the sprites,
+    /// in the order they were queued in, which makes them mostly useful for
+    /// debug visualizations.
+    pub shapes: FixedVec<'frm, ShapeQuad>,
     /// [`Platform::draw_scale_factor`], stored here because all sprite
     /// rendering needs it, and also has access to the draw queue.
     pub scale_factor: f32,
+    /// The view transform applied to all the queued sprites and shapes when
+    /// they're drawn. Things which should stay put on the screen regardless of
+    /// the camera, like UI, can be drawn with another draw queue using
+    /// [`Camera2D::DEFAULT`].
+    pub camera: Camera2D,
+    /// If set, only the pixels within this rectangle are drawn. Unlike the
+    /// queued sprites and shapes, this is in screen space, i.e. in the
+    /// coordinate system of [`Platform::draw_area`], and not affected by the
+    /// camera.
+    pub clip_area: Option<Rect>,
+    /// The minimum alignment of the queue's buffers, see
+    /// [`DrawQueue::with_alignment`].
+    buffer_alignment: usize,
 }
 
 impl<'frm> DrawQueue<'frm> {
-    /// Creates a new queue of draws.
+    /// Creates a new queue of draws, with space for `max_quads` sprite quads
+    /// and no shapes. See [`DrawQueue::with_shapes`] for drawing lines and
+    /// rectangles as well.
     pub fn new(
         allocator: &'frm LinearAllocator,
         max_quads: usize,
         scale_factor: f32,
     ) -> Option<DrawQueue<'frm>> {
+        DrawQueue::with_shapes(allocator, max_quads, 0, scale_factor)
+    }
+
+    /// Creates a new queue of draws, with space for `max_quads` sprite quads
+    /// and `max_shapes` shape quads.
+    pub fn with_shapes(
+        allocator: &'frm LinearAllocator,
+        max_quads: usize,
+        max_shapes: usize,
+        scale_factor: f32,
+    ) -> Option<DrawQueue<'frm>> {
+        DrawQueue::with_alignment(allocator, max_quads, max_shapes, scale_factor, 1)
+    }
+
+    /// Like [`DrawQueue::with_shapes`], but with the sprite and shape buffers, and the
+    /// vertex and index buffers allocated when the queue is dispatched,
+    /// aligned to at least `buffer_alignment` bytes, e.g. 16 or 32 for
+    /// processing them with SIMD instructions. Buffers of types with a larger
+    /// alignment are aligned to that instead.
+    ///
+    /// ### Panics
+    ///
+    /// If `buffer_alignment` is not a power of two.
+    #[track_caller]
+    pub fn with_alignment(
+        allocator: &'frm LinearAllocator,
+        max_quads: usize,
+        max_shapes: usize,
+        scale_factor: f32,
+        buffer_alignment: usize,
+    ) -> Option<DrawQueue<'frm>> {
+        assert!(
+            buffer_alignment.is_power_of_two(),
+            "buffer alignment must be a power of two",
+        );
         Some(DrawQueue {
-            sprites: FixedVec::new(allocator, max_quads)?,
+            sprites: aligned_vec(allocator, max_quads, buffer_alignment)?,
+            shapes: aligned_vec(allocator, max_shapes, buffer_alignment)?,
             scale_factor,
+            camera: Camera2D::DEFAULT,
+            clip_area: None,
+            buffer_alignment,
         })
     }
 
+    /// Returns the screen space position of the world space `point`, based on
+    /// the [camera](DrawQueue::camera).
+    pub fn world_to_screen(&self, point: (f32, f32)) -> (f32, f32) {
+        self.camera.world_to_screen(point, self.scale_factor)
+    }
+
+    /// Returns the world space position of the screen space `point`, based on
+    /// the [camera](DrawQueue::camera), e.g. to find out what's under the mouse
+    /// cursor.
+    pub fn screen_to_world(&self, point: (f32, f32)) -> (f32, f32) {
+        self.camera.screen_to_world(point, self.scale_factor)
+    }
+
+    /// Queues up a line from `a` to `b`, which is `thickness` wide, with the
+    /// given color (`[red, green, blue, alpha]`).
+    ///
+    /// Returns false if the line couldn't be drawn due to the draw queue
+    /// filling up.
+    pub fn draw_line(
+        &mut self,
+        a: (f32, f32),
+        b: (f32, f32),
+        thickness: f32,
+        color: [u8; 4],
+    ) -> bool {
+        let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+        let length = geom::sqrt(dx * dx + dy * dy);
+        if length == 0.0 {
+            return true;
+        }
+
+        // The offset from the line to the edges of the quad, perpendicular to
+        // the line, half the thickness long.
+        let (nx, ny) = (
+            -dy / length * thickness / 2.0,
+            dx / length * thickness / 2.0,
+        );
+        self.shapes
+            .push(ShapeQuad {
+                corners: [
+                    (a.0 + nx, a.1 + ny),
+                    (b.0 + nx, b.1 + ny),
+                    (b.0 - nx, b.1 - ny),
+                    (a.0 - nx, a.1 - ny),
+                ],
+                color,
+            })
+            .is_ok()
+    }
+
+    /// Queues up a filled rectangle with the given color (`[red, green, blue,
+    /// alpha]`).
+    ///
+    /// Returns false if the rectangle couldn't be drawn due to the draw queue
+    /// filling up.
+    pub fn fill_rect(&mut self, rect: Rect, color: [u8; 4]) -> bool {
+        let Rect { x, y, w, h } = rect;
+        self.shapes
+            .push(ShapeQuad {
+                corners: [(x, y), (x, y + h), (x + w, y + h), (x + w, y)],
+                color,
+            })
+            .is_ok()
+    }
+
+    /// Sorts the queued sprites by draw order, grouping sprites with the same
+    /// draw order by sprite and blend mode to allow batching them into as few
+    /// draw calls as possible.
+    ///
+    /// The sort is stable, i.e. sprites with the same sort key stay in the
+    /// order they were submitted in. If `allocator` doesn't have space for the
+    /// temporary index buffer, falls back to an unstable sort.
+    fn sort_sprites(&mut self, allocator: &LinearAllocator) {
+        profiling::function_scope!();
+
+        let Some(mut order) = FixedVec::<usize>::new(allocator, self.sprites.len()) else {
+            self.sprites
+                .sort_unstable_by_key(|quad| quad.draw_call_sort_key());
+            return;
+        };
+        for i in 0..self.sprites.len() {
+            order.push(i).unwrap();
+        }
+
+        // Sort the indices instead of the quads to get a stable sort without
+        // the allocations required by the slice's stable sort: ties are broken
+        // by the submission index.
+        let sprites = &self.sprites;
+        order.sort_unstable_by_key(|&i| (sprites[i].draw_call_sort_key(), i));
+
+        // Apply the permutation in-place: `order[i]` is the index of the quad
+        // that should end up at index `i`. Each cycle of the permutation is
+        // walked once, marking the visited indices as already in place.
+        for i in 0..order.len() {
+            let mut j = i;
+            loop {
+                let k = order[j];
+                order[j] = j;
+                if k == i {
+                    break;
+                }
+                self.sprites.swap(j, k);
+                j = k;
+            }
+        }
+    }
+
     /// Calls the platform draw functions to draw everything queued up until
     /// this point.
-    pub fn dispatch_draw(&mut self, allocator: &LinearAllocator, platform: &dyn Platform) {
+    ///
+    /// The sprites are drawn in order of their
+    /// [`draw_order`](SpriteQuad::draw_order), so sprites with a higher draw
+    /// order are drawn on top of ones with a lower draw order, regardless of
+    /// the order they were queued in. Sprites with the same draw order are
+    /// drawn in the order they were queued in, unless they can be batched
+    /// together with an earlier sprite with the same sprite and blend mode.
+    ///
+    /// All the vertices are transformed from world space to screen space by
+    /// the [camera](DrawQueue::camera), and the draws are clipped to the
+    /// [clip area](DrawQueue::clip_area) if it's set.
+    ///
+    /// Returns statistics about the draws, which don't include any draws that
+    /// were skipped due to `allocator` running out of memory.
+    pub fn dispatch_draw(
+        &mut self,
+        allocator: &LinearAllocator,
+        platform: &dyn Platform,
+    ) -> DrawStats {
+        let mut stats = DrawStats::default();
+        let (camera, scale_factor) = (self.camera, self.scale_factor);
+        let to_screen = |point: (f32, f32)| camera.world_to_screen(point, scale_factor);
+        let clip_area = (self.clip_area).map(|Rect { x, y, w, h }| [x, y, w, h]);
+
         'draw_quads: {
             if self.sprites.is_empty() {
                 break 'draw_quads;
             }
 
-            self.sprites.sort_unstable_by(|a, b| {
-                a.draw_order
-                    .cmp(&b.draw_order)
-                    .then_with(|| a.sprite.cmp(&b.sprite))
-                    .then_with(|| a.blend_mode.cmp(&b.blend_mode))
-            });
+            self.sort_sprites(allocator);
 
             let mut max_draw_call_length = 0;
             {
@@ -101,16 +352,20 @@ impl<'frm> DrawQueue<'frm> {
                 max_draw_call_length = max_draw_call_length.max(current_draw_call_length);
             }
 
-            let Some(mut vertices) = FixedVec::new(allocator, max_draw_call_length * 4) else {
+            let alignment = self.buffer_alignment;
+            let Some(mut vertices) = aligned_vec(allocator, max_draw_call_length * 4, alignment)
+            else {
                 break 'draw_quads;
             };
-            let Some(mut indices) = FixedVec::new(allocator, max_draw_call_length * 6) else {
+            let Some(mut indices) = aligned_vec(allocator, max_draw_call_length * 6, alignment)
+            else {
                 break 'draw_quads;
             };
 
             let mut quad_i = 0;
             while quad_i < self.sprites.len() {
                 // Gather vertices for this draw call
+                let first_quad_i = quad_i;
                 let current_draw_call_id = self.sprites[quad_i].draw_call_identifier();
                 while quad_i < self.sprites.len() {
                     let quad = &self.sprites[quad_i];
@@ -118,15 +373,27 @@ impl<'frm> DrawQueue<'frm> {
                         break;
                     }
 
-                    let (x0, y0) = quad.position_top_left;
-                    let (x1, y1) = quad.position_bottom_right;
+                    let (x0, y0) = to_screen(quad.position_top_left);
+                    let (x1, y1) = to_screen(quad.position_bottom_right);
                     let (u0, v0) = quad.texcoord_top_left;
                     let (u1, v1) = quad.texcoord_bottom_right;
+                    let [r, g, b, a] = quad.color;
                     let vert_offset = vertices.len() as u32;
-                    let _ = vertices.push(Vertex2D::new(x0, y0, u0, v0));
-                    let _ = vertices.push(Vertex2D::new(x0, y1, u0, v1));
-                    let _ = vertices.push(Vertex2D::new(x1, y1, u1, v1));
-                    let _ = vertices.push(Vertex2D::new(x1, y0, u1, v0));
+                    let corners = [
+                        (x0, y0, u0, v0),
+                        (x0, y1, u0, v1),
+                        (x1, y1, u1, v1),
+                        (x1, y0, u1, v0),
+                    ];
+                    for (x, y, u, v) in corners {
+                        let _ = vertices.push(Vertex2D {
+                            r,
+                            g,
+                            b,
+                            a,
+                            ..Vertex2D::new(x, y, u, v)
+                        });
+                    }
                     let _ = indices.push(vert_offset);
                     let _ = indices.push(vert_offset + 1);
                     let _ = indices.push(vert_offset + 2);
@@ -138,20 +405,303 @@ impl<'frm> DrawQueue<'frm> {
                 }
 
                 // Draw this one
-                let (sprite, blend_mode, _) = current_draw_call_id;
+                let (sprite, blend_mode, texture_filter, _) = current_draw_call_id;
                 platform.draw_2d(
                     &vertices,
                     &indices,
                     DrawSettings2D {
                         sprite: Some(sprite),
                         blend_mode,
-                        texture_filter: TextureFilter::Linear,
-                        clip_area: None,
+                        texture_filter,
+                        clip_area,
                     },
                 );
+                stats.record_draw_call(vertices.len(), indices.len());
+                stats.batches_merged += quad_i - first_quad_i - 1;
                 vertices.clear();
                 indices.clear();
             }
         }
+
+        'draw_shapes: {
+            if self.shapes.is_empty() {
+                break 'draw_shapes;
+            }
+
+            let alignment = self.buffer_alignment;
+            let Some(mut vertices) = aligned_vec(allocator, self.shapes.len() * 4, alignment)
+            else {
+                break 'draw_shapes;
+            };
+            let Some(mut indices) = aligned_vec(allocator, self.shapes.len() * 6, alignment) else {
+                break 'draw_shapes;
+            };
+
+            for shape in self.shapes.iter() {
+                let [r, g, b, a] = shape.color;
+                let vert_offset = vertices.len() as u32;
+                for (x, y) in shape.corners.map(to_screen) {
+                    let _ = vertices.push(Vertex2D {
+                        r,
+                        g,
+                        b,
+                        a,
+                        ..Vertex2D::xy(x, y)
+                    });
+                }
+                let _ = indices.push(vert_offset);
+                let _ = indices.push(vert_offset + 1);
+                let _ = indices.push(vert_offset + 2);
+                let _ = indices.push(vert_offset);
+                let _ = indices.push(vert_offset + 2);
+                let _ = indices.push(vert_offset + 3);
+            }
+
+            platform.draw_2d(
+                &vertices,
+                &indices,
+                DrawSettings2D {
+                    sprite: None,
+                    blend_mode: BlendMode::Blend,
+                    texture_filter: TextureFilter::Linear,
+                    clip_area,
+                },
+            );
+            stats.record_draw_call(vertices.len(), indices.len());
+        }
+
+        stats
+    }
+}
+
+/// Allocates a [`FixedVec`] aligned to `alignment`, or to `T`'s alignment if
+/// it's larger.
+fn aligned_vec<'a, T>(
+    allocator: &'a LinearAllocator,
+    capacity: usize,
+    alignment: usize,
+) -> Option<FixedVec<'a, T>> {
+    FixedVec::with_alignment(allocator, capacity, alignment.max(align_of::<T>()))
+}
+
+#[cfg(test)]
+mod tests {
+    use platform::{BlendMode, SpriteRef, TextureFilter};
+
+    use crate::{
+        allocators::{static_allocator, LinearAllocator},
+        geom::Rect,
+        test_platform::{TestPlatform, FRAMEBUFFER_WIDTH},
+    };
+
+    use super::{camera::Camera2D, DrawQueue, DrawStats, SpriteQuad};
+
+    fn quad(draw_order: u8, sprite: u64, x: f32) -> SpriteQuad {
+        SpriteQuad {
+            position_top_left: (x, 0.0),
+            position_bottom_right: (x + 1.0, 1.0),
+            texcoord_top_left: (0.0, 0.0),
+            texcoord_bottom_right: (1.0, 1.0),
+            color: [0xFF; 4],
+            draw_order,
+            blend_mode: BlendMode::Blend,
+            texture_filter: TextureFilter::Linear,
+            sprite: SpriteRef::new(sprite),
+        }
+    }
+
+    #[test]
+    fn dispatch_sorts_by_draw_order_stably() {
+        static ARENA: &LinearAllocator = static_allocator!(10_000);
+        let platform = TestPlatform::new(false);
+        let mut draw_queue = DrawQueue::new(ARENA, 6, 1.0).unwrap();
+
+        // The x coordinate is used to identify the quads after sorting.
+        draw_queue.sprites.push(quad(2, 0, 0.0)).unwrap();
+        draw_queue.sprites.push(quad(0, 1, 1.0)).unwrap();
+        draw_queue.sprites.push(quad(1, 0, 2.0)).unwrap();
+        draw_queue.sprites.push(quad(0, 1, 3.0)).unwrap();
+        draw_queue.sprites.push(quad(2, 0, 4.0)).unwrap();
+        draw_queue.sprites.push(quad(0, 0, 5.0)).unwrap();
+
+        let temp_arena = LinearAllocator::new(ARENA, 1000).unwrap();
+        draw_queue.dispatch_draw(&temp_arena, &platform);
+
+        let dispatched_order = draw_queue
+            .sprites
+            .iter()
+            .map(|quad| quad.position_top_left.0 as u8);
+        assert!(dispatched_order.eq([5, 1, 3, 2, 0, 4]));
+    }
+
+    #[test]
+    fn buffers_are_aligned_as_requested() {
+        static ARENA: &LinearAllocator = static_allocator!(10_000);
+        let platform = TestPlatform::new(false);
+        // Misalign the rest of the arena.
+        ARENA.try_alloc_uninit_slice::<u8>(1, None).unwrap();
+
+        let mut draw_queue = DrawQueue::with_alignment(ARENA, 3, 2, 1.0, 32).unwrap();
+        assert_eq!(0, draw_queue.sprites.as_ptr() as usize % 32);
+        assert_eq!(0, draw_queue.shapes.as_ptr() as usize % 32);
+
+        // The vertex and index buffers allocated for drawing are aligned too.
+        let temp_arena = LinearAllocator::new(ARENA, 1000).unwrap();
+        temp_arena.try_alloc_uninit_slice::<u8>(1, None).unwrap();
+        draw_queue.sprites.push(quad(0, 0, 0.0)).unwrap();
+        assert!(draw_queue.fill_rect(Rect::xywh(0.0, 0.0, 1.0, 1.0), [0xFF; 4]));
+        draw_queue.dispatch_draw(&temp_arena, &platform);
+        let draw_calls = platform.take_draw_calls();
+        assert_eq!(2, draw_calls.len());
+        for (vertices, indices) in draw_calls.iter().map(|call| call.buffer_addresses) {
+            assert_eq!(0, vertices % 32);
+            assert_eq!(0, indices % 32);
+        }
+
+        // Alignments smaller than the types' own are ignored.
+        let draw_queue = DrawQueue::with_alignment(ARENA, 1, 1, 1.0, 1).unwrap();
+        let sprites_alignment = align_of::<SpriteQuad>();
+        assert_eq!(0, draw_queue.sprites.as_ptr() as usize % sprites_alignment);
+    }
+
+    #[test]
+    fn draw_stats_match_the_platform_draw_calls() {
+        static ARENA: &LinearAllocator = static_allocator!(10_000);
+        let platform = TestPlatform::new(false);
+        let temp_arena = LinearAllocator::new(ARENA, 1000).unwrap();
+
+        let mut draw_queue = DrawQueue::with_shapes(ARENA, 4, 1, 1.0).unwrap();
+        let stats = draw_queue.dispatch_draw(&temp_arena, &platform);
+        assert_eq!(DrawStats::default(), stats);
+        assert!(platform.take_draw_calls().is_empty());
+
+        // Three quads batched into one draw call, another with a different
+        // sprite, and a shape in a draw call of its own.
+        for x in 0..3 {
+            draw_queue.sprites.push(quad(0, 0, x as f32)).unwrap();
+        }
+        draw_queue.sprites.push(quad(0, 1, 3.0)).unwrap();
+        assert!(draw_queue.fill_rect(Rect::xywh(0.0, 0.0, 1.0, 1.0), [0xFF; 4]));
+        let stats = draw_queue.dispatch_draw(&temp_arena, &platform);
+
+        let expected_stats = DrawStats {
+            draw_calls: 3,
+            vertices: 5 * 4,
+            indices: 5 * 6,
+            batches_merged: 2,
+        };
+        assert_eq!(expected_stats, stats);
+        let draw_calls = platform.take_draw_calls();
+        assert_eq!(stats.draw_calls, draw_calls.len());
+        let vertices = draw_calls.iter().map(|call| call.vertices.len()).sum();
+        let indices = draw_calls.iter().map(|call| call.indices.len()).sum();
+        assert_eq!((stats.vertices, stats.indices), (vertices, indices));
+    }
+
+    #[test]
+    fn filled_rects_are_rasterized_into_the_framebuffer() {
+        static ARENA: &LinearAllocator = static_allocator!(10_000);
+        let platform = TestPlatform::new(false);
+        let mut draw_queue = DrawQueue::with_shapes(ARENA, 0, 2, 1.0).unwrap();
+
+        let red = [0xFF, 0, 0, 0xFF];
+        let translucent_blue = [0, 0, 0xFF, 0x80];
+        assert!(draw_queue.fill_rect(Rect::xywh(10.0, 20.0, 30.0, 40.0), red));
+        assert!(draw_queue.fill_rect(Rect::xywh(100.0, 100.0, 10.0, 10.0), translucent_blue));
+
+        let temp_arena = LinearAllocator::new(ARENA, 1000).unwrap();
+        draw_queue.dispatch_draw(&temp_arena, &platform);
+
+        let framebuffer = platform.framebuffer();
+        let pixel = |x: usize, y: usize| {
+            let i = (x + y * FRAMEBUFFER_WIDTH) * 4;
+            [0, 1, 2, 3].map(|c| framebuffer[i + c])
+        };
+        assert_eq!(red, pixel(25, 40));
+        assert_eq!(red, pixel(10, 20));
+        assert_eq!(red, pixel(39, 59));
+        assert_eq!([0; 4], pixel(40, 40));
+        assert_eq!([0; 4], pixel(25, 60));
+        // Every pixel, including the ones on the diagonal shared by the two
+        // triangles of the quad, should only be blended once.
+        for i in 0..10 {
+            assert_eq!([0, 0, 0x80, 0x80], pixel(100 + i, 100 + i));
+            assert_eq!([0, 0, 0x80, 0x80], pixel(109 - i, 100 + i));
+        }
+    }
+
+    #[test]
+    fn shapes_are_drawn_as_colored_quads() {
+        static ARENA: &LinearAllocator = static_allocator!(10_000);
+        let platform = TestPlatform::new(false);
+        let mut draw_queue = DrawQueue::with_shapes(ARENA, 0, 2, 1.0).unwrap();
+
+        let red = [0xFF, 0, 0, 0xFF];
+        let green = [0, 0xFF, 0, 0x80];
+        assert!(draw_queue.draw_line((0.0, 0.0), (10.0, 0.0), 2.0, red));
+        assert!(draw_queue.fill_rect(Rect::xywh(5.0, 5.0, 10.0, 10.0), green));
+        assert!(!draw_queue.fill_rect(Rect::xywh(0.0, 0.0, 1.0, 1.0), green));
+
+        let temp_arena = LinearAllocator::new(ARENA, 1000).unwrap();
+        draw_queue.dispatch_draw(&temp_arena, &platform);
+
+        let draw_calls = platform.take_draw_calls();
+        assert_eq!(1, draw_calls.len());
+        let draw_call = &draw_calls[0];
+        assert_eq!(None, draw_call.settings.sprite);
+        assert_eq!(8, draw_call.vertices.len());
+        assert_eq!(12, draw_call.indices.len());
+
+        let colors = draw_call.vertices.iter().map(|v| [v.r, v.g, v.b, v.a]);
+        assert!(colors.eq([red, red, red, red, green, green, green, green]));
+
+        // The line is horizontal, so it should extend 1 unit (half the
+        // thickness) up and down from the line.
+        let line_ys = draw_call.vertices[..4].iter().map(|v| v.y);
+        assert!(line_ys.eq([1.0, 1.0, -1.0, -1.0]));
+    }
+
+    #[test]
+    fn camera_maps_world_space_to_screen_space() {
+        static ARENA: &LinearAllocator = static_allocator!(10_000);
+        let platform = TestPlatform::new(false);
+        let mut draw_queue = DrawQueue::with_shapes(ARENA, 1, 1, 1.0).unwrap();
+        draw_queue.camera = Camera2D {
+            position: (100.0, 50.0),
+            zoom: 2.0,
+        };
+        draw_queue.clip_area = Some(Rect::xywh(10.0, 10.0, 20.0, 20.0));
+
+        let mut world_quad = quad(0, 0, 110.0);
+        world_quad.position_top_left.1 = 60.0;
+        world_quad.position_bottom_right = (120.0, 70.0);
+        draw_queue.sprites.push(world_quad).unwrap();
+        assert!(draw_queue.fill_rect(Rect::xywh(100.0, 50.0, 5.0, 5.0), [0xFF; 4]));
+        let temp_arena = LinearAllocator::new(ARENA, 1000).unwrap();
+        draw_queue.dispatch_draw(&temp_arena, &platform);
+
+        let draw_calls = platform.take_draw_calls();
+        let positions = |i: usize| draw_calls[i].vertices.iter().map(|v| (v.x, v.y));
+        assert!(positions(0).eq([(20.0, 20.0), (20.0, 40.0), (40.0, 40.0), (40.0, 20.0)]));
+        assert!(positions(1).eq([(0.0, 0.0), (0.0, 10.0), (10.0, 10.0), (10.0, 0.0)]));
+        // The clip area is in screen space, so the camera doesn't affect it.
+        for draw_call in &draw_calls {
+            assert_eq!(Some([10.0, 10.0, 20.0, 20.0]), draw_call.settings.clip_area);
+        }
+
+        assert_eq!((20.0, 20.0), draw_queue.world_to_screen((110.0, 60.0)));
+        assert_eq!((110.0, 60.0), draw_queue.screen_to_world((20.0, 20.0)));
+    }
+
+    #[test]
+    fn camera_offset_is_snapped_to_physical_pixels() {
+        static ARENA: &LinearAllocator = static_allocator!(10_000);
+        let mut draw_queue = DrawQueue::new(ARENA, 0, 2.0).unwrap();
+        draw_queue.camera.position = (0.3, 0.0);
+
+        // 0.3 screen units is 0.6 physical pixels, which is rounded to one
+        // whole physical pixel, i.e. half a screen unit.
+        assert_eq!((9.5, 0.0), draw_queue.world_to_screen((10.0, 0.0)));
+        assert_eq!((10.0, 0.0), draw_queue.screen_to_world((9.5, 0.0)));
     }
 }
@@ -0,0 +1,275 @@
+// SPDX-FileCopyrightText: 2025 Jens Pitkänen <jens.pitkanen@helsinki.fi>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Animation playback and drawing, built on
+//! [`SpriteAsset::draw_sub`](crate::resources::sprite::SpriteAsset::draw_sub).
+
+use core::time::Duration;
+
+use crate::{
+    geom::Rect,
+    resources::{
+        animation::{AnimationAsset, AnimationMode},
+        sprite::SpriteMipLevel,
+        ResourceDatabase, ResourceLoader,
+    },
+};
+
+use super::{sprite::SpriteDrawOptions, DrawQueue};
+
+impl AnimationAsset {
+    /// Draw the frame at index `frame` into the `dst` rectangle, usually the
+    /// frame returned by [`AnimationPlayer::frame`].
+    ///
+    /// Returns false if the frame couldn't be drawn due to the draw queue
+    /// filling up, like [`SpriteAsset::draw`](crate::resources::sprite::SpriteAsset::draw).
+    /// Frames past the end of the animation are not drawn at all, and
+    /// animations with an empty sprite sheet always return false.
+    #[must_use]
+    pub fn draw_frame(
+        &self,
+        frame: usize,
+        dst: impl Into<Rect>,
+        draw_order: u8,
+        draw_queue: &mut DrawQueue,
+        resources: &ResourceDatabase,
+        resource_loader: &mut ResourceLoader,
+    ) -> bool {
+        self.draw_frame_with_options(
+            frame,
+            dst,
+            SpriteDrawOptions::DEFAULT,
+            draw_order,
+            draw_queue,
+            resources,
+            resource_loader,
+        )
+    }
+
+    /// Draw the frame at index `frame` into the `dst` rectangle like
+    /// [`AnimationAsset::draw_frame`], with any combination of the
+    /// [`SpriteDrawOptions`].
+    #[must_use]
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_frame_with_options(
+        &self,
+        frame: usize,
+        dst: impl Into<Rect>,
+        options: SpriteDrawOptions,
+        draw_order: u8,
+        draw_queue: &mut DrawQueue,
+        resources: &ResourceDatabase,
+        resource_loader: &mut ResourceLoader,
+    ) -> bool {
+        let Some(frame) = self.frames.get(frame) else {
+            return true;
+        };
+        let Some(mip) = self.sheet.mip_chain.first() else {
+            return false;
+        };
+        let (width, height) = match mip {
+            SpriteMipLevel::SingleChunkSprite { size, .. }
+            | SpriteMipLevel::MultiChunkSprite { size, .. } => (size.0 as f32, size.1 as f32),
+        };
+        let src = Rect {
+            x: frame.offset.0 as f32 / width,
+            y: frame.offset.1 as f32 / height,
+            w: frame.size.0 as f32 / width,
+            h: frame.size.1 as f32 / height,
+        };
+        self.sheet.draw_sub_with_options(
+            src,
+            dst,
+            options,
+            draw_order,
+            draw_queue,
+            resources,
+            resource_loader,
+        )
+    }
+}
+
+/// Playback state of an [`AnimationAsset`].
+///
+/// Advanced with [`AnimationPlayer::update`] every frame, and drawn by passing
+/// [`AnimationPlayer::frame`] to [`AnimationAsset::draw_frame`]. The player
+/// does not refer to the animation itself, so the same player can e.g. be
+/// switched to another animation with [`AnimationPlayer::restart`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AnimationPlayer {
+    elapsed: Duration,
+    frame: usize,
+    finished: bool,
+}
+
+impl AnimationPlayer {
+    /// Creates a player at the start of the first frame.
+    pub const fn new() -> AnimationPlayer {
+        AnimationPlayer {
+            elapsed: Duration::ZERO,
+            frame: 0,
+            finished: false,
+        }
+    }
+
+    /// Advances the animation by `delta`, moving on to the next frame(s) if
+    /// the current one has been shown for its whole duration.
+    ///
+    /// [`AnimationMode::Loop`] animations wrap around to the first frame after
+    /// the last one, [`AnimationMode::Once`] animations stay on the last frame
+    /// and [finish](AnimationPlayer::is_finished).
+    pub fn update(&mut self, delta: Duration, animation: &AnimationAsset) {
+        let frame_duration = |ms: u32| Duration::from_millis(ms as u64);
+        let total: Duration = (animation.frames.iter())
+            .map(|frame| frame_duration(frame.duration_ms))
+            .sum();
+
+        self.elapsed = self.elapsed.saturating_add(delta);
+        if self.elapsed >= total {
+            match animation.mode {
+                AnimationMode::Loop if !total.is_zero() => {
+                    let wrapped = self.elapsed.as_nanos() % total.as_nanos();
+                    self.elapsed = Duration::from_nanos(wrapped as u64);
+                }
+                AnimationMode::Loop => self.elapsed = Duration::ZERO,
+                AnimationMode::Once => {
+                    self.elapsed = total;
+                    self.frame = animation.frames.len().saturating_sub(1);
+                    self.finished = true;
+                    return;
+                }
+            }
+        }
+
+        let mut frame_start = Duration::ZERO;
+        self.frame = 0;
+        for (i, frame) in animation.frames.iter().enumerate() {
+            let frame_end = frame_start + frame_duration(frame.duration_ms);
+            if self.elapsed < frame_end {
+                self.frame = i;
+                break;
+            }
+            frame_start = frame_end;
+        }
+    }
+
+    /// Returns the index of the current frame of the animation.
+    pub fn frame(&self) -> usize {
+        self.frame
+    }
+
+    /// Returns true if an [`AnimationMode::Once`] animation has reached the
+    /// end of its last frame. Looping animations never finish.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Starts the animation over from the first frame.
+    pub fn restart(&mut self) {
+        *self = AnimationPlayer::new();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use arrayvec::ArrayVec;
+    use platform::TextureFilter;
+
+    use crate::{
+        allocators::{static_allocator, LinearAllocator},
+        geom::Rect,
+        renderer::DrawQueue,
+        resources::{
+            animation::{AnimationAsset, AnimationFrame, AnimationMode},
+            sprite::SpriteAsset,
+        },
+        test_platform::TestPlatform,
+        Engine, EngineLimits,
+    };
+
+    use super::AnimationPlayer;
+
+    /// Creates an animation with frames of the given durations, without any
+    /// sprite sheet to draw them from.
+    fn animation(mode: AnimationMode, durations_ms: &[u32]) -> AnimationAsset {
+        let mut frames = ArrayVec::new();
+        for &duration_ms in durations_ms {
+            frames.push(AnimationFrame {
+                duration_ms,
+                ..Default::default()
+            });
+        }
+        AnimationAsset {
+            sheet: SpriteAsset {
+                transparent: false,
+                mip_chain: ArrayVec::new(),
+                filter: TextureFilter::Linear,
+            },
+            frames,
+            mode,
+        }
+    }
+
+    #[test]
+    fn looping_animations_wrap_around() {
+        let animation = animation(AnimationMode::Loop, &[100, 50, 100]);
+        let mut player = AnimationPlayer::new();
+        let ms = Duration::from_millis;
+
+        player.update(ms(99), &animation);
+        assert_eq!(0, player.frame());
+        player.update(ms(1), &animation);
+        assert_eq!(1, player.frame());
+        player.update(ms(50), &animation);
+        assert_eq!(2, player.frame());
+        player.update(ms(100), &animation);
+        assert_eq!(0, player.frame());
+
+        // Skips over the frames in between and whole loops in one update
+        player.update(ms(250 * 3 + 120), &animation);
+        assert_eq!(1, player.frame());
+        assert!(!player.is_finished());
+    }
+
+    #[test]
+    fn one_shot_animations_stop_on_the_last_frame() {
+        let animation = animation(AnimationMode::Once, &[100, 100]);
+        let mut player = AnimationPlayer::new();
+        let ms = Duration::from_millis;
+
+        player.update(ms(150), &animation);
+        assert_eq!(1, player.frame());
+        assert!(!player.is_finished());
+        player.update(ms(1000), &animation);
+        assert_eq!(1, player.frame());
+        assert!(player.is_finished());
+
+        player.restart();
+        assert_eq!(0, player.frame());
+        assert!(!player.is_finished());
+    }
+
+    #[test]
+    fn animations_without_a_sprite_sheet_are_not_drawn() {
+        static ARENA: &LinearAllocator = static_allocator!(8 * 1024 * 1024);
+        let platform = TestPlatform::new(false);
+        let limits = EngineLimits {
+            frame_arena_size: 256 * 1024,
+            resource_database_loaded_chunks_count: 4,
+            resource_database_loaded_sprite_chunks_count: 4,
+            resource_database_buffer_size: 512 * 1024,
+            ..EngineLimits::DEFAULT
+        };
+        let mut engine = Engine::new(&platform, ARENA, limits);
+        let animation = animation(AnimationMode::Loop, &[100]);
+
+        let mut draw_queue = DrawQueue::new(&engine.frame_arena, 1, 1.0).unwrap();
+        let (resources, loader) = (&engine.resource_db, &mut engine.resource_loader);
+        let dst = Rect::xywh(0.0, 0.0, 16.0, 16.0);
+        assert!(!animation.draw_frame(0, dst, 0, &mut draw_queue, resources, loader));
+        assert_eq!(0, draw_queue.sprites.len());
+    }
+}
@@ -0,0 +1,91 @@
+// SPDX-FileCopyrightText: 2025 Jens Pitkänen <jens.pitkanen@helsinki.fi>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! The view transform applied to everything drawn with a
+//! [`DrawQueue`](super::DrawQueue).
+
+use crate::geom::{self, Rect};
+
+/// A view into the game world, which determines where things drawn in world
+/// space end up on the screen.
+///
+/// Screen space is the coordinate system of
+/// [`Platform::draw_area`](platform::Platform::draw_area), and world space is
+/// whatever the game uses for its positions. A point at `position` in world
+/// space is drawn at the top-left corner of the screen, and one world unit is
+/// `zoom` screen units long.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Camera2D {
+    /// The point in world space shown at the top-left corner of the screen.
+    pub position: (f32, f32),
+    /// The size of one world unit in screen units. Values above 1 zoom in,
+    /// values below 1 zoom out. Must be positive, as the screen to world
+    /// space conversions divide by it.
+    pub zoom: f32,
+}
+
+impl Camera2D {
+    /// The camera which draws everything as-is, i.e. with world space being
+    /// the same as screen space.
+    pub const DEFAULT: Camera2D = Camera2D {
+        position: (0.0, 0.0),
+        zoom: 1.0,
+    };
+
+    /// Returns the screen space offset added to every point after it's been
+    /// scaled by the zoom.
+    ///
+    /// The offset is rounded to whole physical pixels, based on
+    /// [`Platform::draw_scale_factor`](platform::Platform::draw_scale_factor),
+    /// so that whatever is aligned to the physical pixel grid in world space
+    /// stays aligned to it on the screen, regardless of the camera position.
+    fn offset(&self, scale_factor: f32) -> (f32, f32) {
+        let snap = |x: f32| geom::round(x * scale_factor) / scale_factor;
+        (
+            snap(-self.position.0 * self.zoom),
+            snap(-self.position.1 * self.zoom),
+        )
+    }
+
+    /// Returns the screen space position of the world space `point`.
+    pub fn world_to_screen(&self, point: (f32, f32), scale_factor: f32) -> (f32, f32) {
+        let (offset_x, offset_y) = self.offset(scale_factor);
+        (
+            point.0 * self.zoom + offset_x,
+            point.1 * self.zoom + offset_y,
+        )
+    }
+
+    /// Returns the world space position of the screen space `point`, e.g. to
+    /// find out what's under the mouse cursor. The inverse of
+    /// [`Camera2D::world_to_screen`].
+    pub fn screen_to_world(&self, point: (f32, f32), scale_factor: f32) -> (f32, f32) {
+        debug_assert!(self.zoom > 0.0, "camera zoom must be positive");
+        let (offset_x, offset_y) = self.offset(scale_factor);
+        (
+            (point.0 - offset_x) / self.zoom,
+            (point.1 - offset_y) / self.zoom,
+        )
+    }
+
+    /// Returns the area of the world visible within the screen space
+    /// `screen_rect`, e.g. the whole draw area, for culling things which would
+    /// not be visible anyway, like with
+    /// [`DrawQueue::draw_tilemap`](super::DrawQueue::draw_tilemap).
+    pub fn screen_rect_to_world(&self, screen_rect: Rect, scale_factor: f32) -> Rect {
+        let (x, y) = self.screen_to_world((screen_rect.x, screen_rect.y), scale_factor);
+        Rect {
+            x,
+            y,
+            w: screen_rect.w / self.zoom,
+            h: screen_rect.h / self.zoom,
+        }
+    }
+}
+
+impl Default for Camera2D {
+    fn default() -> Self {
+        Camera2D::DEFAULT
+    }
+}
@@ -0,0 +1,159 @@
+// SPDX-FileCopyrightText: 2025 Jens Pitkänen <jens.pitkanen@helsinki.fi>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Text drawing specifics.
+//!
+//! This is the "runtime-half" of [`FontAsset`], the other half being the
+//! "import-half" implemented in `import_asset::importers::font`, which packs
+//! the glyphs into sprite chunks and measures them.
+
+use platform::{BlendMode, TextureFilter};
+
+use crate::resources::{
+    font::FontAsset, ResourceDatabase, ResourceLoader, SPRITE_CHUNK_DIMENSIONS,
+};
+
+use super::{DrawQueue, SpriteQuad};
+
+const CHUNK_WIDTH: f32 = SPRITE_CHUNK_DIMENSIONS.0 as f32;
+const CHUNK_HEIGHT: f32 = SPRITE_CHUNK_DIMENSIONS.1 as f32;
+
+impl DrawQueue<'_> {
+    /// Queues up `text` to be drawn with `font`, with the top-left corner of
+    /// the first line at `position`, with the given color (`[red, green, blue,
+    /// alpha]`).
+    ///
+    /// The glyphs are drawn at the size the font was imported at, i.e. one
+    /// pixel of the glyph atlas per physical pixel, and drawn on top of any
+    /// sprites. Newlines move the rest of the text to the next line, and
+    /// characters without a glyph in the font are drawn as `?`.
+    ///
+    /// Returns false if the text couldn't be drawn fully due to the draw queue
+    /// filling up.
+    pub fn draw_text(
+        &mut self,
+        font: &FontAsset,
+        text: &str,
+        position: (f32, f32),
+        color: [u8; 4],
+        resources: &ResourceDatabase,
+        resource_loader: &mut ResourceLoader,
+    ) -> bool {
+        profiling::function_scope!();
+        let scale = 1.0 / self.scale_factor;
+        let (mut pen_x, mut pen_y) = position;
+
+        for c in text.chars() {
+            if c == '\n' {
+                pen_x = position.0;
+                pen_y += font.line_height as f32 * scale;
+                continue;
+            }
+
+            let Some(glyph) = font.get_glyph(c).or_else(|| font.get_glyph('?')) else {
+                continue;
+            };
+
+            if glyph.size.0 > 0 && glyph.size.1 > 0 {
+                if let Some(chunk) = resources.sprite_chunks.get(glyph.sprite_chunk) {
+                    let x = pen_x + glyph.bearing.0 as f32 * scale;
+                    let y = pen_y + glyph.bearing.1 as f32 * scale;
+                    let (w, h) = (glyph.size.0 as f32, glyph.size.1 as f32);
+                    let u = glyph.uv_offset.0 as f32 / CHUNK_WIDTH;
+                    let v = glyph.uv_offset.1 as f32 / CHUNK_HEIGHT;
+                    let quad = SpriteQuad {
+                        position_top_left: (x, y),
+                        position_bottom_right: (x + w * scale, y + h * scale),
+                        texcoord_top_left: (u, v),
+                        texcoord_bottom_right: (u + w / CHUNK_WIDTH, v + h / CHUNK_HEIGHT),
+                        color,
+                        draw_order: u8::MAX,
+                        blend_mode: BlendMode::Blend,
+                        texture_filter: TextureFilter::Linear,
+                        sprite: chunk.0,
+                    };
+                    if self.sprites.push(quad).is_err() {
+                        return false;
+                    }
+                } else {
+                    resource_loader.queue_sprite_chunk(glyph.sprite_chunk, resources);
+                }
+            }
+
+            pen_x += glyph.advance as f32 * scale;
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrayvec::ArrayVec;
+
+    use crate::{
+        allocators::{static_allocator, LinearAllocator},
+        renderer::DrawQueue,
+        resources::{
+            font::{FontAsset, FontGlyph, MAX_GLYPHS},
+            Asset,
+        },
+        test_platform::TestPlatform,
+        Engine, EngineLimits,
+    };
+
+    #[test]
+    fn text_is_drawn_glyph_by_glyph() {
+        static ARENA: &LinearAllocator = static_allocator!(64 * 1024 * 1024);
+        let platform = TestPlatform::new(false);
+        let mut engine = Engine::new(&platform, ARENA, EngineLimits::DEFAULT);
+
+        // Borrow the test sprite's first chunk for the glyph atlas.
+        let sprite = engine.resource_db.find_sprite("player").unwrap();
+        let chunk = (engine.resource_db.get_sprite(sprite).unwrap())
+            .get_sprite_chunks()
+            .unwrap()
+            .start;
+        let resources = &engine.resource_db;
+        engine.resource_loader.queue_sprite_chunk(chunk, resources);
+        engine.resource_loader.dispatch_reads(&platform);
+        (engine.resource_loader).finish_reads(&mut engine.resource_db, &platform, 128);
+
+        let glyph = FontGlyph {
+            sprite_chunk: chunk,
+            uv_offset: (0, 0),
+            size: (8, 10),
+            bearing: (1, 2),
+            advance: 10,
+        };
+        let space = FontGlyph {
+            size: (0, 0),
+            ..glyph
+        };
+        let mut glyphs = ArrayVec::<FontGlyph, MAX_GLYPHS>::new();
+        glyphs.push(space);
+        while !glyphs.is_full() {
+            glyphs.push(glyph);
+        }
+        let font = FontAsset {
+            line_height: 12,
+            sprite_chunks: chunk..chunk + 1,
+            glyphs,
+        };
+
+        let mut draw_queue = DrawQueue::new(&engine.frame_arena, 3, 1.0).unwrap();
+        let red = [0xFF, 0, 0, 0xFF];
+        let (resources, loader) = (&engine.resource_db, &mut engine.resource_loader);
+        assert!(draw_queue.draw_text(&font, "a b\nc", (5.0, 5.0), red, resources, loader));
+        assert!(!draw_queue.draw_text(&font, "d", (0.0, 0.0), red, resources, loader));
+
+        let positions = draw_queue.sprites.iter().map(|quad| quad.position_top_left);
+        assert!(positions.eq([(6.0, 7.0), (26.0, 7.0), (6.0, 19.0)]));
+        assert!(draw_queue.sprites.iter().all(|quad| quad.color == red));
+        let quad = &draw_queue.sprites[0];
+        assert_eq!((14.0, 17.0), quad.position_bottom_right);
+        assert_eq!((0.0, 0.0), quad.texcoord_top_left);
+        assert_eq!((8.0 / 128.0, 10.0 / 128.0), quad.texcoord_bottom_right);
+    }
+}
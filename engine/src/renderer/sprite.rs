@@ -12,7 +12,7 @@
 
 use core::ops::Range;
 
-use platform::BlendMode;
+use platform::{BlendMode, TextureFilter};
 
 use crate::{
     geom::Rect,
@@ -27,8 +27,73 @@ use super::{DrawQueue, SpriteQuad};
 const CHUNK_WIDTH: u16 = SPRITE_CHUNK_DIMENSIONS.0;
 const CHUNK_HEIGHT: u16 = SPRITE_CHUNK_DIMENSIONS.1;
 
+/// The source rectangle covering the whole sprite, see
+/// [`SpriteAsset::draw_sub`].
+const WHOLE_SPRITE: Rect = Rect::xywh(0.0, 0.0, 1.0, 1.0);
+
+/// Mirroring applied to a sprite when drawing it with
+/// [`SpriteAsset::draw_flipped`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SpriteFlip {
+    /// Mirror the sprite left-to-right, e.g. to make a character face the
+    /// other way.
+    pub horizontal: bool,
+    /// Mirror the sprite upside down.
+    pub vertical: bool,
+}
+
+impl SpriteFlip {
+    /// Draw the sprite as-is.
+    pub const NONE: SpriteFlip = SpriteFlip {
+        horizontal: false,
+        vertical: false,
+    };
+    /// Mirror the sprite left-to-right.
+    pub const HORIZONTAL: SpriteFlip = SpriteFlip {
+        horizontal: true,
+        vertical: false,
+    };
+    /// Mirror the sprite upside down.
+    pub const VERTICAL: SpriteFlip = SpriteFlip {
+        horizontal: false,
+        vertical: true,
+    };
+}
+
+/// Optional parameters for drawing a sprite with
+/// [`SpriteAsset::draw_with_options`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpriteDrawOptions {
+    /// The mirroring applied to the sprite.
+    pub flip: SpriteFlip,
+    /// The color multiplied with the sprite's colors, in order: `[red, green,
+    /// blue, alpha]`. White for drawing the sprite as-is. Alpha below `0xFF`
+    /// makes even opaque sprites blend with the sprites below them, e.g. for
+    /// fading sprites out.
+    pub tint: [u8; 4],
+    /// The filtering used for this draw, or `None` to use the sprite's own
+    /// [`SpriteAsset::filter`].
+    pub filter: Option<TextureFilter>,
+}
+
+impl SpriteDrawOptions {
+    /// Options for drawing the sprite as-is.
+    pub const DEFAULT: SpriteDrawOptions = SpriteDrawOptions {
+        flip: SpriteFlip::NONE,
+        tint: [0xFF; 4],
+        filter: None,
+    };
+}
+
+impl Default for SpriteDrawOptions {
+    fn default() -> Self {
+        SpriteDrawOptions::DEFAULT
+    }
+}
+
 impl SpriteAsset {
-    /// Draw this sprite into the `dst` rectangle.
+    /// Draw this sprite into the `dst` rectangle, which can also be a
+    /// [`FixedRect`](crate::geom::FixedRect).
     ///
     /// Returns false if the sprite couldn't be drawn due to the draw queue
     /// filling up. Note that one draw may cause multiple draws in the queue,
@@ -37,19 +102,159 @@ impl SpriteAsset {
     #[must_use]
     pub fn draw(
         &self,
-        dst: Rect,
+        dst: impl Into<Rect>,
+        draw_order: u8,
+        draw_queue: &mut DrawQueue,
+        resources: &ResourceDatabase,
+        resource_loader: &mut ResourceLoader,
+    ) -> bool {
+        self.draw_with_options(
+            dst,
+            SpriteDrawOptions::DEFAULT,
+            draw_order,
+            draw_queue,
+            resources,
+            resource_loader,
+        )
+    }
+
+    /// Draw this sprite into the `dst` rectangle like [`SpriteAsset::draw`],
+    /// but mirrored horizontally and/or vertically, as specified by `flip`.
+    /// The sprite still covers the same `dst` rectangle.
+    #[must_use]
+    pub fn draw_flipped(
+        &self,
+        dst: impl Into<Rect>,
+        flip: SpriteFlip,
+        draw_order: u8,
+        draw_queue: &mut DrawQueue,
+        resources: &ResourceDatabase,
+        resource_loader: &mut ResourceLoader,
+    ) -> bool {
+        let options = SpriteDrawOptions {
+            flip,
+            ..SpriteDrawOptions::DEFAULT
+        };
+        self.draw_with_options(
+            dst,
+            options,
+            draw_order,
+            draw_queue,
+            resources,
+            resource_loader,
+        )
+    }
+
+    /// Draw this sprite into the `dst` rectangle like [`SpriteAsset::draw`],
+    /// but with its colors multiplied by `tint` (`[red, green, blue, alpha]`),
+    /// e.g. to flash it red, or to fade it out.
+    #[must_use]
+    pub fn draw_tinted(
+        &self,
+        dst: impl Into<Rect>,
+        tint: [u8; 4],
+        draw_order: u8,
+        draw_queue: &mut DrawQueue,
+        resources: &ResourceDatabase,
+        resource_loader: &mut ResourceLoader,
+    ) -> bool {
+        let options = SpriteDrawOptions {
+            tint,
+            ..SpriteDrawOptions::DEFAULT
+        };
+        self.draw_with_options(
+            dst,
+            options,
+            draw_order,
+            draw_queue,
+            resources,
+            resource_loader,
+        )
+    }
+
+    /// Draw this sprite into the `dst` rectangle like [`SpriteAsset::draw`],
+    /// with any combination of the [`SpriteDrawOptions`].
+    #[must_use]
+    pub fn draw_with_options(
+        &self,
+        dst: impl Into<Rect>,
+        options: SpriteDrawOptions,
+        draw_order: u8,
+        draw_queue: &mut DrawQueue,
+        resources: &ResourceDatabase,
+        resource_loader: &mut ResourceLoader,
+    ) -> bool {
+        self.draw_sub_with_options(
+            WHOLE_SPRITE,
+            dst,
+            options,
+            draw_order,
+            draw_queue,
+            resources,
+            resource_loader,
+        )
+    }
+
+    /// Draw the `src` region of this sprite into the `dst` rectangle, e.g. one
+    /// frame of a sprite sheet.
+    ///
+    /// The `src` rectangle is normalized, i.e. (0, 0) is the top-left corner
+    /// of the sprite and (1, 1) the bottom-right one, regardless of the
+    /// sprite's resolution. Parts of `src` outside of the sprite are cut off,
+    /// and the rest of `src` still covers the whole `dst` rectangle.
+    ///
+    /// Like with [`SpriteAsset::draw`], multi-chunk sprites may cause multiple
+    /// draws in the queue, but only for the chunks `src` overlaps.
+    #[must_use]
+    pub fn draw_sub(
+        &self,
+        src: Rect,
+        dst: impl Into<Rect>,
+        draw_order: u8,
+        draw_queue: &mut DrawQueue,
+        resources: &ResourceDatabase,
+        resource_loader: &mut ResourceLoader,
+    ) -> bool {
+        self.draw_sub_with_options(
+            src,
+            dst,
+            SpriteDrawOptions::DEFAULT,
+            draw_order,
+            draw_queue,
+            resources,
+            resource_loader,
+        )
+    }
+
+    /// Draw the `src` region of this sprite into the `dst` rectangle like
+    /// [`SpriteAsset::draw_sub`], with any combination of the
+    /// [`SpriteDrawOptions`]. Flipping mirrors the region within `dst`.
+    #[must_use]
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_sub_with_options(
+        &self,
+        src: Rect,
+        dst: impl Into<Rect>,
+        options: SpriteDrawOptions,
         draw_order: u8,
         draw_queue: &mut DrawQueue,
         resources: &ResourceDatabase,
         resource_loader: &mut ResourceLoader,
     ) -> bool {
+        let Some(source) = src.intersection(&WHOLE_SPRITE) else {
+            // Nothing to draw, which is not due to the draw queue being full.
+            return true;
+        };
         draw(
             RenderableSprite {
                 mip_chain: &self.mip_chain,
                 transparent: self.transparent,
+                filter: options.filter.unwrap_or(self.filter),
+                source,
                 draw_order,
+                options,
             },
-            dst,
+            dst.into(),
             draw_queue,
             resources,
             resource_loader,
@@ -66,9 +271,15 @@ struct RenderableSprite<'a> {
     /// Should be set to true if the sprite has any non-opaque pixels to avoid
     /// rendering artifacts.
     pub transparent: bool,
+    /// The filtering used when drawing this sprite.
+    pub filter: TextureFilter,
+    /// The normalized region of the sprite to draw, within (0, 0) to (1, 1).
+    pub source: Rect,
     /// The draw order used when drawing this sprite. See
     /// [`TexQuad::draw_order`].
     pub draw_order: u8,
+    /// The flipping and tinting applied to the whole sprite.
+    pub options: SpriteDrawOptions,
 }
 
 /// The main sprite rendering function.
@@ -93,17 +304,29 @@ fn draw(
     let mut draw_chunk = |chunk_index: u32, dst: Rect, tex: Rect| {
         profiling::scope!("draw_chunk");
         if let Some(chunk) = resources.sprite_chunks.get(chunk_index) {
+            // Flipping is done by swapping the texture coordinates, so the
+            // quad's corners get the texels from the opposite corners.
+            let (mut u0, mut u1) = (tex.x, tex.x + tex.w);
+            let (mut v0, mut v1) = (tex.y, tex.y + tex.h);
+            if src.options.flip.horizontal {
+                (u0, u1) = (u1, u0);
+            }
+            if src.options.flip.vertical {
+                (v0, v1) = (v1, v0);
+            }
             let quad = SpriteQuad {
                 position_top_left: (dst.x, dst.y),
                 position_bottom_right: (dst.x + dst.w, dst.y + dst.h),
-                texcoord_top_left: (tex.x, tex.y),
-                texcoord_bottom_right: (tex.x + tex.w, tex.y + tex.h),
+                texcoord_top_left: (u0, v0),
+                texcoord_bottom_right: (u1, v1),
+                color: src.options.tint,
                 draw_order: src.draw_order,
-                blend_mode: if src.transparent {
+                blend_mode: if src.transparent || src.options.tint[3] < 0xFF {
                     BlendMode::Blend
                 } else {
                     BlendMode::None
                 },
+                texture_filter: src.filter,
                 sprite: chunk.0,
             };
 
@@ -113,12 +336,27 @@ fn draw(
         }
     };
 
-    // Get the sprite's size divided by the resolution it's being rendered at.
+    // Scales the normalized source rectangle to the pixels of a mip level.
+    let source_pixels = |(width, height): (u16, u16)| Rect {
+        x: src.source.x * width as f32,
+        y: src.source.y * height as f32,
+        w: src.source.w * width as f32,
+        h: src.source.h * height as f32,
+    };
+
+    // Get the size of the drawn region of the sprite divided by the resolution
+    // it's being rendered at.
     let rendering_scale_ratio = match &src.mip_chain[0] {
         SpriteMipLevel::SingleChunkSprite { size, .. }
         | SpriteMipLevel::MultiChunkSprite { size, .. } => {
-            let width_scale = size.0 / (dst.w * draw_queue.scale_factor) as u16;
-            let height_scale = size.1 / (dst.h * draw_queue.scale_factor) as u16;
+            let source = source_pixels(*size);
+            // The sprite is drawn in world space, so the physical pixels
+            // covered also depend on the camera's zoom.
+            // Draws smaller than a pixel are clamped to one pixel, which
+            // picks the smallest mip instead of dividing by zero.
+            let scale = draw_queue.scale_factor * draw_queue.camera.zoom;
+            let width_scale = source.w as u16 / ((dst.w * scale) as u16).max(1);
+            let height_scale = source.h as u16 / ((dst.h * scale) as u16).max(1);
             width_scale.min(height_scale)
         }
     };
@@ -143,11 +381,12 @@ fn draw(
                 return false;
             }
 
+            let source = source_pixels(*size);
             let tex_src = Rect {
-                x: offset.0 as f32 / CHUNK_WIDTH as f32,
-                y: offset.1 as f32 / CHUNK_HEIGHT as f32,
-                w: size.0 as f32 / CHUNK_WIDTH as f32,
-                h: size.1 as f32 / CHUNK_HEIGHT as f32,
+                x: (offset.0 as f32 + source.x) / CHUNK_WIDTH as f32,
+                y: (offset.1 as f32 + source.y) / CHUNK_HEIGHT as f32,
+                w: source.w / CHUNK_WIDTH as f32,
+                h: source.h / CHUNK_HEIGHT as f32,
             };
             draw_chunk(*sprite_chunk, dst, tex_src);
 
@@ -166,15 +405,20 @@ fn draw(
                 "resource database has a corrupt chunk? the amount of chunks does not match the sprite size",
             );
 
-            if draws_left < (chunks_x * chunks_y) as usize {
+            let source = source_pixels(*size);
+            let columns = chunk_span(source.x, source.w, CHUNK_WIDTH - 2);
+            let rows = chunk_span(source.y, source.h, CHUNK_HEIGHT - 2);
+            if draws_left < columns.len() * rows.len() {
                 return false;
             }
 
             draw_multi_chunk_sprite(
                 dst,
-                *size,
+                source,
                 sprite_chunks.clone(),
-                (chunks_x, chunks_y),
+                chunks_x,
+                (columns, rows),
+                src.options.flip,
                 draw_chunk,
             );
 
@@ -183,43 +427,301 @@ fn draw(
     }
 }
 
+/// Returns the indices of the chunks (along one axis) covering the pixels from
+/// `start` to `start + len`, each chunk covering `chunk_len` pixels of the
+/// sprite.
+pub(super) fn chunk_span(start: f32, len: f32, chunk_len: u16) -> Range<u32> {
+    let chunk_len = chunk_len as f32;
+    let first = (start / chunk_len) as u32;
+    // Rounded up, as `f32::ceil` is not available in `core`.
+    let end = (start + len) / chunk_len;
+    let end = if end > end as u32 as f32 {
+        end as u32 + 1
+    } else {
+        end as u32
+    };
+    first..end.max(first)
+}
+
+/// Draws the `source` region (in the mip's pixels) of a multi-chunk sprite
+/// into `dst`, one quad per chunk in the `columns` and `rows` of chunks the
+/// region overlaps.
 fn draw_multi_chunk_sprite(
     Rect { x, y, w, h }: Rect,
-    (tex_width, tex_height): (u16, u16),
+    source: Rect,
     chunks: Range<u32>,
-    (chunks_x, chunks_y): (u32, u32),
+    chunks_x: u32,
+    (columns, rows): (Range<u32>, Range<u32>),
+    flip: SpriteFlip,
     mut draw: impl FnMut(u32, Rect, Rect),
 ) {
-    let scale_x = w / tex_width as f32;
-    let scale_y = h / tex_height as f32;
+    let scale_x = w / source.w;
+    let scale_y = h / source.h;
+    let (source_x1, source_y1) = (source.x + source.w, source.y + source.h);
 
-    let mut tex_x_pos = 0;
-    let mut tex_y_pos = 0;
-    for cy in 0..chunks_y {
-        let curr_chunk_h = (tex_height - tex_y_pos).min(CHUNK_HEIGHT - 2);
-        for cx in 0..chunks_x {
-            let curr_chunk_index = chunks.start + cx + cy * chunks_x;
-            let curr_chunk_w = (tex_width - tex_x_pos).min(CHUNK_WIDTH - 2);
+    // Each chunk has a 1px border, the rest of the chunk is the sprite.
+    let chunk_inner_w = (CHUNK_WIDTH - 2) as f32;
+    let chunk_inner_h = (CHUNK_HEIGHT - 2) as f32;
 
+    for cy in rows {
+        let chunk_y = cy as f32 * chunk_inner_h;
+        // The part of the source region within this row of chunks, in the
+        // sprite's pixels.
+        let y0 = source.y.max(chunk_y);
+        let y1 = source_y1.min(chunk_y + chunk_inner_h);
+        for cx in columns.clone() {
+            let chunk_x = cx as f32 * chunk_inner_w;
+            let x0 = source.x.max(chunk_x);
+            let x1 = source_x1.min(chunk_x + chunk_inner_w);
+            if x1 <= x0 || y1 <= y0 {
+                continue;
+            }
+
+            // The chunks of a flipped sprite are mirrored within the source
+            // region, each chunk's texture coordinates are flipped in `draw`.
+            let dst_x_pos = if flip.horizontal {
+                source_x1 - x1
+            } else {
+                x0 - source.x
+            };
+            let dst_y_pos = if flip.vertical {
+                source_y1 - y1
+            } else {
+                y0 - source.y
+            };
             let dst = Rect {
-                x: x + tex_x_pos as f32 * scale_x,
-                y: y + tex_y_pos as f32 * scale_y,
-                w: curr_chunk_w as f32 * scale_x,
-                h: curr_chunk_h as f32 * scale_y,
+                x: x + dst_x_pos * scale_x,
+                y: y + dst_y_pos * scale_y,
+                w: (x1 - x0) * scale_x,
+                h: (y1 - y0) * scale_y,
             };
 
             let tex_src = Rect {
-                x: 1. / CHUNK_WIDTH as f32,
-                y: 1. / CHUNK_HEIGHT as f32,
-                w: curr_chunk_w as f32 / CHUNK_WIDTH as f32,
-                h: curr_chunk_h as f32 / CHUNK_HEIGHT as f32,
+                x: (1. + x0 - chunk_x) / CHUNK_WIDTH as f32,
+                y: (1. + y0 - chunk_y) / CHUNK_HEIGHT as f32,
+                w: (x1 - x0) / CHUNK_WIDTH as f32,
+                h: (y1 - y0) / CHUNK_HEIGHT as f32,
             };
 
-            draw(curr_chunk_index, dst, tex_src);
+            draw(chunks.start + cx + cy * chunks_x, dst, tex_src);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrayvec::ArrayVec;
+    use platform::{BlendMode, TextureFilter, Vertex2D};
+
+    use crate::{
+        allocators::{static_allocator, LinearAllocator},
+        geom::Rect,
+        renderer::{DrawQueue, SpriteQuad},
+        resources::sprite::{SpriteAsset, SpriteMipLevel},
+        test_platform::{engine_with_loaded_chunks, TestPlatform},
+    };
+
+    use super::{SpriteDrawOptions, SpriteFlip};
+
+    #[test]
+    fn horizontal_flip_mirrors_multi_chunk_sprites() {
+        static ARENA: &LinearAllocator = static_allocator!(64 * 1024 * 1024);
+        let platform = TestPlatform::new(false);
+        let (mut engine, _, chunks) = engine_with_loaded_chunks(&platform, ARENA, 2, 16);
+
+        let mut mip_chain = ArrayVec::new();
+        mip_chain.push(SpriteMipLevel::MultiChunkSprite {
+            size: (200, 100),
+            sprite_chunks: chunks.start..chunks.start + 2,
+        });
+        let sprite = SpriteAsset {
+            transparent: false,
+            mip_chain,
+            filter: TextureFilter::Linear,
+        };
 
-            tex_x_pos += curr_chunk_w;
+        let mut draw_queue = DrawQueue::new(&engine.frame_arena, 4, 1.0).unwrap();
+        let (resources, loader) = (&engine.resource_db, &mut engine.resource_loader);
+        let dst = Rect::xywh(0.0, 0.0, 200.0, 100.0);
+        assert!(sprite.draw(dst, 0, &mut draw_queue, resources, loader));
+        assert!(sprite.draw_flipped(
+            dst,
+            SpriteFlip::HORIZONTAL,
+            0,
+            &mut draw_queue,
+            resources,
+            loader,
+        ));
+
+        // The first chunk is 126px wide (128px minus the borders), and the
+        // second has the remaining 74px, so flipped, they swap sides.
+        let (normal, flipped) = draw_queue.sprites.split_at(2);
+        let normal_positions = [((0.0, 0.0), (126.0, 100.0)), ((126.0, 0.0), (200.0, 100.0))];
+        let flipped_positions = [((74.0, 0.0), (200.0, 100.0)), ((0.0, 0.0), (74.0, 100.0))];
+        let positions = |quad: &SpriteQuad| (quad.position_top_left, quad.position_bottom_right);
+        assert!(normal.iter().map(positions).eq(normal_positions));
+        assert!(flipped.iter().map(positions).eq(flipped_positions));
+
+        for (normal, flipped) in normal.iter().zip(flipped) {
+            assert_eq!(normal.sprite, flipped.sprite);
+            let (u0, v0) = normal.texcoord_top_left;
+            let (u1, v1) = normal.texcoord_bottom_right;
+            assert_eq!((u1, v0), flipped.texcoord_top_left);
+            assert_eq!((u0, v1), flipped.texcoord_bottom_right);
         }
-        tex_y_pos += curr_chunk_h;
-        tex_x_pos = 0;
+    }
+
+    #[test]
+    fn tint_is_applied_to_the_vertices() {
+        static ARENA: &LinearAllocator = static_allocator!(64 * 1024 * 1024);
+        let platform = TestPlatform::new(false);
+        let (mut engine, _, chunks) = engine_with_loaded_chunks(&platform, ARENA, 1, 16);
+
+        let mut mip_chain = ArrayVec::new();
+        mip_chain.push(SpriteMipLevel::SingleChunkSprite {
+            offset: (1, 1),
+            size: (16, 16),
+            sprite_chunk: chunks.start,
+        });
+        let sprite = SpriteAsset {
+            transparent: false,
+            mip_chain,
+            filter: TextureFilter::Linear,
+        };
+
+        let mut draw_queue = DrawQueue::new(&engine.frame_arena, 2, 1.0).unwrap();
+        let (resources, loader) = (&engine.resource_db, &mut engine.resource_loader);
+        let dst = Rect::xywh(0.0, 0.0, 16.0, 16.0);
+        let faded_red = [0xFF, 0, 0, 0x80];
+        assert!(sprite.draw(dst, 0, &mut draw_queue, resources, loader));
+        assert!(sprite.draw_tinted(dst, faded_red, 1, &mut draw_queue, resources, loader));
+        draw_queue.dispatch_draw(&engine.frame_arena, &platform);
+
+        let draw_calls = platform.take_draw_calls();
+        assert_eq!(2, draw_calls.len());
+        let colors = |vertex: &Vertex2D| [vertex.r, vertex.g, vertex.b, vertex.a];
+        let (untinted, tinted) = (&draw_calls[0], &draw_calls[1]);
+        assert!(untinted.vertices.iter().all(|v| colors(v) == [0xFF; 4]));
+        assert_eq!(BlendMode::None, untinted.settings.blend_mode);
+        // The tint has transparency, so it needs blending to fade the sprite.
+        assert!(tinted.vertices.iter().all(|v| colors(v) == faded_red));
+        assert_eq!(BlendMode::Blend, tinted.settings.blend_mode);
+    }
+
+    #[test]
+    fn sprite_filter_is_used_unless_overridden() {
+        static ARENA: &LinearAllocator = static_allocator!(64 * 1024 * 1024);
+        let platform = TestPlatform::new(false);
+        let (mut engine, _, chunks) = engine_with_loaded_chunks(&platform, ARENA, 1, 16);
+
+        let mut mip_chain = ArrayVec::new();
+        mip_chain.push(SpriteMipLevel::SingleChunkSprite {
+            offset: (1, 1),
+            size: (16, 16),
+            sprite_chunk: chunks.start,
+        });
+        let sprite = SpriteAsset {
+            transparent: false,
+            mip_chain,
+            filter: TextureFilter::NearestNeighbor,
+        };
+
+        let mut draw_queue = DrawQueue::new(&engine.frame_arena, 2, 1.0).unwrap();
+        let (resources, loader) = (&engine.resource_db, &mut engine.resource_loader);
+        let dst = Rect::xywh(0.0, 0.0, 16.0, 16.0);
+        let linear = SpriteDrawOptions {
+            filter: Some(TextureFilter::Linear),
+            ..SpriteDrawOptions::DEFAULT
+        };
+        assert!(sprite.draw(dst, 0, &mut draw_queue, resources, loader));
+        assert!(sprite.draw_with_options(dst, linear, 1, &mut draw_queue, resources, loader));
+        draw_queue.dispatch_draw(&engine.frame_arena, &platform);
+
+        let draw_calls = platform.take_draw_calls();
+        assert_eq!(2, draw_calls.len());
+        let filter = TextureFilter::NearestNeighbor;
+        assert_eq!(filter, draw_calls[0].settings.texture_filter);
+        assert_eq!(TextureFilter::Linear, draw_calls[1].settings.texture_filter);
+    }
+
+    #[test]
+    fn sub_rect_uvs_cover_only_the_drawn_region() {
+        static ARENA: &LinearAllocator = static_allocator!(64 * 1024 * 1024);
+        let platform = TestPlatform::new(false);
+        let (mut engine, _, chunks) = engine_with_loaded_chunks(&platform, ARENA, 2, 16);
+
+        let mut mip_chain = ArrayVec::new();
+        mip_chain.push(SpriteMipLevel::MultiChunkSprite {
+            size: (200, 100),
+            sprite_chunks: chunks.start..chunks.start + 2,
+        });
+        let sprite = SpriteAsset {
+            transparent: false,
+            mip_chain,
+            filter: TextureFilter::Linear,
+        };
+
+        let mut draw_queue = DrawQueue::new(&engine.frame_arena, 3, 1.0).unwrap();
+        let (resources, loader) = (&engine.resource_db, &mut engine.resource_loader);
+        let top_left_quarter = Rect::xywh(0.0, 0.0, 0.5, 0.5);
+        let dst = Rect::xywh(0.0, 0.0, 100.0, 50.0);
+        assert!(sprite.draw_sub(top_left_quarter, dst, 0, &mut draw_queue, resources, loader));
+
+        // The top-left 100x50 pixels are all in the first chunk, after its
+        // 1px border.
+        assert_eq!(1, draw_queue.sprites.len());
+        let quad = &draw_queue.sprites[0];
+        assert_eq!((0.0, 0.0), quad.position_top_left);
+        assert_eq!((100.0, 50.0), quad.position_bottom_right);
+        assert_eq!((1.0 / 128.0, 1.0 / 128.0), quad.texcoord_top_left);
+        assert_eq!((101.0 / 128.0, 51.0 / 128.0), quad.texcoord_bottom_right);
+
+        // The right half starts 26px before the end of the first chunk's
+        // 126px wide interior, so it needs a quad from both chunks.
+        let right_half = Rect::xywh(0.5, 0.0, 0.5, 1.0);
+        let dst = Rect::xywh(0.0, 0.0, 100.0, 100.0);
+        assert!(sprite.draw_sub(right_half, dst, 0, &mut draw_queue, resources, loader));
+        assert_eq!(3, draw_queue.sprites.len());
+        let (first, second) = (&draw_queue.sprites[1], &draw_queue.sprites[2]);
+        assert_eq!((0.0, 0.0), first.position_top_left);
+        assert_eq!((26.0, 100.0), first.position_bottom_right);
+        assert_eq!((101.0 / 128.0, 1.0 / 128.0), first.texcoord_top_left);
+        assert_eq!((127.0 / 128.0, 101.0 / 128.0), first.texcoord_bottom_right);
+        assert_eq!((26.0, 0.0), second.position_top_left);
+        assert_eq!((100.0, 100.0), second.position_bottom_right);
+        assert_eq!((1.0 / 128.0, 1.0 / 128.0), second.texcoord_top_left);
+        assert_eq!((75.0 / 128.0, 101.0 / 128.0), second.texcoord_bottom_right);
+    }
+
+    #[test]
+    fn sprites_smaller_than_a_pixel_use_the_smallest_mip() {
+        static ARENA: &LinearAllocator = static_allocator!(8 * 1024 * 1024);
+        let platform = TestPlatform::new(false);
+        let (mut engine, _, chunks) = engine_with_loaded_chunks(&platform, ARENA, 1, 16);
+
+        let mut mip_chain = ArrayVec::new();
+        mip_chain.push(SpriteMipLevel::SingleChunkSprite {
+            offset: (1, 1),
+            size: (16, 16),
+            sprite_chunk: chunks.start,
+        });
+        mip_chain.push(SpriteMipLevel::SingleChunkSprite {
+            offset: (20, 1),
+            size: (8, 8),
+            sprite_chunk: chunks.start,
+        });
+        let sprite = SpriteAsset {
+            transparent: false,
+            mip_chain,
+            filter: TextureFilter::Linear,
+        };
+
+        let mut draw_queue = DrawQueue::new(&engine.frame_arena, 1, 1.0).unwrap();
+        draw_queue.camera.zoom = 0.01;
+        let (resources, loader) = (&engine.resource_db, &mut engine.resource_loader);
+        let dst = Rect::xywh(0.0, 0.0, 16.0, 16.0);
+        assert!(sprite.draw(dst, 0, &mut draw_queue, resources, loader));
+        let quad = &draw_queue.sprites[0];
+        assert_eq!((20.0 / 128.0, 1.0 / 128.0), quad.texcoord_top_left);
     }
 }
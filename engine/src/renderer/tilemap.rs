@@ -0,0 +1,304 @@
+// SPDX-FileCopyrightText: 2025 Jens Pitkänen <jens.pitkanen@helsinki.fi>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Tilemap drawing specifics.
+//!
+//! This is the "runtime-half" of [`TilemapAsset`], the other half being the
+//! "import-half" implemented in `import_asset::importers::tilemap`, which
+//! writes the tile indices into chunks.
+
+use core::ops::Range;
+
+use crate::{
+    geom::Rect,
+    resources::{
+        sprite::{SpriteAsset, SpriteMipLevel},
+        tilemap::TilemapAsset,
+        ResourceDatabase, ResourceLoader, TILES_PER_CHUNK,
+    },
+};
+
+use super::{sprite::chunk_span, DrawQueue};
+
+impl TilemapAsset {
+    /// Returns the columns and rows of the tiles overlapping `visible_rect`,
+    /// when the top-left corner of the map is at `origin`, and each tile is
+    /// [`tile_size`](crate::resources::tilemap::TilesetLayout::tile_size)
+    /// large.
+    pub fn visible_tiles(
+        &self,
+        origin: (f32, f32),
+        visible_rect: Rect,
+    ) -> (Range<u16>, Range<u16>) {
+        let (tile_width, tile_height) = self.tileset.tile_size;
+        if tile_width == 0 || tile_height == 0 {
+            return (0..0, 0..0);
+        }
+        let clamp = |span: Range<u32>, len: u16| {
+            let end = span.end.min(len as u32) as u16;
+            (span.start.min(end as u32) as u16)..end
+        };
+        let Rect { x, y, w, h } = visible_rect;
+        let columns = chunk_span(x - origin.0, w, tile_width);
+        let rows = chunk_span(y - origin.1, h, tile_height);
+        (clamp(columns, self.width), clamp(rows, self.height))
+    }
+
+    /// Returns the region of the tileset sprite the given tile is drawn from,
+    /// in pixels, or `None` for
+    /// [`EMPTY_TILE`](crate::resources::tilemap::EMPTY_TILE).
+    pub fn tile_source(&self, tile: u16) -> Option<Rect> {
+        let layout = &self.tileset;
+        let index = tile.checked_sub(1)?;
+        if layout.columns == 0 {
+            return None;
+        }
+        let (column, row) = (index % layout.columns, index / layout.columns);
+        let (tile_width, tile_height) = layout.tile_size;
+        let x = layout.margin as u32 + column as u32 * (tile_width + layout.spacing) as u32;
+        let y = layout.margin as u32 + row as u32 * (tile_height + layout.spacing) as u32;
+        Some(Rect::xywh(
+            x as f32,
+            y as f32,
+            tile_width as f32,
+            tile_height as f32,
+        ))
+    }
+}
+
+impl DrawQueue<'_> {
+    /// Queues up the tiles of `tilemap` which overlap `visible_rect`, drawn
+    /// from the `tileset` sprite, with the top-left corner of the map at
+    /// `origin`.
+    ///
+    /// Each tile is drawn as large as the tileset's tiles, in world space like
+    /// everything else in the queue, so `visible_rect` is usually the whole
+    /// draw area as seen by the camera, i.e. from
+    /// [`Camera2D::screen_rect_to_world`](super::camera::Camera2D::screen_rect_to_world). Tiles
+    /// outside of `visible_rect` are skipped without reading them, so only the
+    /// visible part of the map costs anything. The tiles are queued like
+    /// sprites, so the ones drawn from the same sprite chunk of the tileset end
+    /// up batched into a single draw call.
+    ///
+    /// Tiles whose chunks aren't loaded yet are skipped, and their chunks are
+    /// queued for loading.
+    ///
+    /// Returns false if the tilemap couldn't be drawn fully due to the draw
+    /// queue filling up.
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_tilemap(
+        &mut self,
+        tilemap: &TilemapAsset,
+        tileset: &SpriteAsset,
+        origin: (f32, f32),
+        visible_rect: Rect,
+        draw_order: u8,
+        resources: &ResourceDatabase,
+        resource_loader: &mut ResourceLoader,
+    ) -> bool {
+        profiling::function_scope!();
+        let (sheet_width, sheet_height) = match &tileset.mip_chain[0] {
+            SpriteMipLevel::SingleChunkSprite { size, .. }
+            | SpriteMipLevel::MultiChunkSprite { size, .. } => (size.0 as f32, size.1 as f32),
+        };
+        let tile_width = tilemap.tileset.tile_size.0 as f32;
+        let tile_height = tilemap.tileset.tile_size.1 as f32;
+
+        let (columns, rows) = tilemap.visible_tiles(origin, visible_rect);
+        for y in rows {
+            for x in columns.clone() {
+                let i = y as usize * tilemap.width as usize + x as usize;
+                let chunk_index = tilemap.chunks.start + (i / TILES_PER_CHUNK) as u32;
+                let Some(chunk) = resources.chunks.get(chunk_index) else {
+                    resource_loader.queue_chunk(chunk_index, resources);
+                    continue;
+                };
+                let offset = (i % TILES_PER_CHUNK) * size_of::<u16>();
+                let tile = u16::from_le_bytes([chunk.0[offset], chunk.0[offset + 1]]);
+
+                let Some(src) = tilemap.tile_source(tile) else {
+                    continue;
+                };
+                let src = Rect {
+                    x: src.x / sheet_width,
+                    y: src.y / sheet_height,
+                    w: src.w / sheet_width,
+                    h: src.h / sheet_height,
+                };
+                let dst = Rect {
+                    x: origin.0 + x as f32 * tile_width,
+                    y: origin.1 + y as f32 * tile_height,
+                    w: tile_width,
+                    h: tile_height,
+                };
+                if !tileset.draw_sub(src, dst, draw_order, self, resources, resource_loader) {
+                    return false;
+                }
+            }
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrayvec::ArrayVec;
+    use platform::TextureFilter;
+
+    use crate::{
+        allocators::{static_allocator, LinearAllocator},
+        geom::Rect,
+        renderer::DrawQueue,
+        resources::{
+            sprite::{SpriteAsset, SpriteMipLevel},
+            tilemap::{TilemapAsset, TilesetLayout},
+            Asset, ChunkData,
+        },
+        test_platform::TestPlatform,
+        Engine, EngineLimits,
+    };
+
+    fn tilemap(width: u16, height: u16, tileset: TilesetLayout) -> TilemapAsset {
+        TilemapAsset {
+            width,
+            height,
+            tileset,
+            chunks: 0..1,
+        }
+    }
+
+    #[test]
+    fn only_tiles_overlapping_the_visible_rect_are_visible() {
+        let layout = TilesetLayout {
+            tile_size: (16, 8),
+            columns: 1,
+            ..Default::default()
+        };
+        let map = tilemap(10, 10, layout);
+
+        // The visible area starts 20px into the map horizontally, and ends
+        // halfway through the fourth row.
+        let visible_rect = Rect::xywh(0.0, 0.0, 64.0, 32.0);
+        assert_eq!((1..6, 0..4), map.visible_tiles((-20.0, 4.0), visible_rect));
+
+        // Tiles past the edges of the map are never visible.
+        let (columns, rows) = map.visible_tiles((-150.0, -70.0), visible_rect);
+        assert_eq!((9..10, 8..10), (columns, rows));
+        let everything = Rect::xywh(-1000.0, -1000.0, 3000.0, 3000.0);
+        assert_eq!((0..10, 0..10), map.visible_tiles((0.0, 0.0), everything));
+        let (columns, rows) = map.visible_tiles((100.0, 0.0), visible_rect);
+        assert!(columns.is_empty());
+        assert_eq!(0..4, rows);
+    }
+
+    #[test]
+    fn tile_sources_skip_tileset_margins_and_spacing() {
+        let layout = TilesetLayout {
+            tile_size: (16, 8),
+            margin: 1,
+            spacing: 2,
+            columns: 3,
+        };
+        let map = tilemap(1, 1, layout);
+        let source = |tile: u16| {
+            let Rect { x, y, w, h } = map.tile_source(tile)?;
+            Some((x, y, w, h))
+        };
+        assert_eq!(None, source(0));
+        assert_eq!(Some((1.0, 1.0, 16.0, 8.0)), source(1));
+        assert_eq!(Some((37.0, 1.0, 16.0, 8.0)), source(3));
+        // The fifth tile is the second tile on the second row.
+        assert_eq!(Some((19.0, 11.0, 16.0, 8.0)), source(5));
+    }
+
+    #[test]
+    fn visible_tiles_are_drawn_in_one_batch() {
+        static ARENA: &LinearAllocator = static_allocator!(64 * 1024 * 1024);
+        let platform = TestPlatform::new(false);
+        let mut engine = Engine::new(&platform, ARENA, EngineLimits::DEFAULT);
+
+        // A 2x2 tileset of 16x8 tiles, in one sprite chunk of the test sprite
+        let player = engine.resource_db.find_sprite("player").unwrap();
+        let sprite_chunk = (engine.resource_db.get_sprite(player).unwrap())
+            .get_sprite_chunks()
+            .unwrap()
+            .start;
+        let resources = &engine.resource_db;
+        engine
+            .resource_loader
+            .queue_sprite_chunk(sprite_chunk, resources);
+        engine.resource_loader.dispatch_reads(&platform);
+        (engine.resource_loader).finish_reads(&mut engine.resource_db, &platform, 128);
+        let mut mip_chain = ArrayVec::new();
+        mip_chain.push(SpriteMipLevel::SingleChunkSprite {
+            offset: (1, 1),
+            size: (32, 16),
+            sprite_chunk,
+        });
+        let tileset = SpriteAsset {
+            transparent: false,
+            mip_chain,
+            filter: TextureFilter::NearestNeighbor,
+        };
+
+        // A 4x2 map with one empty tile, in place of the first chunk
+        let map = tilemap(
+            4,
+            2,
+            TilesetLayout {
+                tile_size: (16, 8),
+                columns: 2,
+                ..Default::default()
+            },
+        );
+        let tiles: [u16; 8] = [1, 0, 2, 3, 4, 4, 4, 4];
+        let chunk = (engine.resource_db.chunks)
+            .insert(0, || Some(ChunkData::empty()))
+            .unwrap();
+        for (i, tile) in tiles.into_iter().enumerate() {
+            chunk.0[i * 2..i * 2 + 2].copy_from_slice(&tile.to_le_bytes());
+        }
+
+        let mut draw_queue = DrawQueue::new(&engine.frame_arena, 8, 1.0).unwrap();
+        let (resources, loader) = (&engine.resource_db, &mut engine.resource_loader);
+
+        // Only the first two tiles are visible, and the second one is empty.
+        let top_left = Rect::xywh(0.0, 0.0, 32.0, 8.0);
+        assert!(draw_queue.draw_tilemap(
+            &map,
+            &tileset,
+            (0.0, 0.0),
+            top_left,
+            0,
+            resources,
+            loader
+        ));
+        assert_eq!(1, draw_queue.sprites.len());
+        let quad = &draw_queue.sprites[0];
+        assert_eq!((0.0, 0.0), quad.position_top_left);
+        assert_eq!((16.0, 8.0), quad.position_bottom_right);
+        assert_eq!((1.0 / 128.0, 1.0 / 128.0), quad.texcoord_top_left);
+        assert_eq!((17.0 / 128.0, 9.0 / 128.0), quad.texcoord_bottom_right);
+
+        draw_queue.sprites.clear();
+        let everything = Rect::xywh(0.0, 0.0, 64.0, 16.0);
+        assert!(draw_queue.draw_tilemap(
+            &map,
+            &tileset,
+            (0.0, 0.0),
+            everything,
+            0,
+            resources,
+            loader
+        ));
+        assert_eq!(7, draw_queue.sprites.len());
+        let last_quad = &draw_queue.sprites[6];
+        assert_eq!((48.0, 8.0), last_quad.position_top_left);
+        assert_eq!((17.0 / 128.0, 9.0 / 128.0), last_quad.texcoord_top_left);
+
+        let stats = draw_queue.dispatch_draw(&engine.frame_arena, &platform);
+        assert_eq!(1, stats.draw_calls);
+    }
+}
@@ -2,21 +2,38 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+mod asset_table;
 mod assets;
+mod checksum;
 mod chunks;
 mod deserialize;
 mod file_reader;
 mod loader;
 mod serialize;
 
-use assets::{audio_clip::AudioClipAsset, sprite::SpriteAsset};
-use platform::{PixelFormat, Platform, AUDIO_CHANNELS};
+use core::{
+    fmt::{self, Display},
+    mem::{transmute, MaybeUninit},
+    ops::Range,
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+use asset_table::AssetTable;
+use assets::{
+    animation::AnimationAsset,
+    audio_clip::AudioClipAsset,
+    font::FontAsset,
+    sprite::{SpriteAsset, SpriteAssetV2, SpriteHandle},
+    tilemap::TilemapAsset,
+};
+use platform::{FileHandle, PixelFormat, Platform, AUDIO_CHANNELS};
 
 pub use assets::*;
+pub use checksum::Crc32;
 pub use chunks::{ChunkData, ChunkDescriptor, SpriteChunkData, SpriteChunkDescriptor};
-pub use deserialize::{deserialize, Deserialize};
+pub use deserialize::{deserialize, try_deserialize, Deserialize};
 pub use file_reader::FileReader;
-pub use loader::ResourceLoader;
+pub use loader::{LoadPriority, ResourceLoader};
 pub use serialize::{serialize, Serialize};
 
 use crate::{
@@ -26,6 +43,18 @@ use crate::{
 
 /// Magic number used when de/serializing [`ResourceDatabaseHeader`].
 pub const RESOURCE_DB_MAGIC_NUMBER: u32 = 0xE97E6D00;
+/// The version of the resource database format, stored in
+/// [`ResourceDatabaseHeader::version`]. Incremented whenever the format changes.
+pub const RESOURCE_DB_VERSION: u32 = 5;
+/// The oldest version of the resource database format which can still be read.
+/// Bumped whenever the format changes in a way that makes older databases
+/// unreadable.
+///
+/// Version 2 databases are read with [`SpriteAssetV2`], as they predate
+/// [`SpriteAsset::filter`]. Headers of older versions are shorter, as they
+/// predate [`ResourceDatabaseHeader::animations`] (version 4) and
+/// [`ResourceDatabaseHeader::tilemaps`] (version 5).
+pub const RESOURCE_DB_MIN_VERSION: u32 = 2;
 /// Amount of bytes in the regular dynamically allocated chunks.
 pub const CHUNK_SIZE: u32 = 64 * 1024;
 /// Width and height of the dynamically allocated sprite chunks.
@@ -36,10 +65,17 @@ pub const SPRITE_CHUNK_FORMAT: PixelFormat = PixelFormat::Rgba;
 /// The amount of audio samples that fit in each chunk.
 pub const AUDIO_SAMPLES_PER_CHUNK: usize = CHUNK_SIZE as usize / size_of::<[i16; AUDIO_CHANNELS]>();
 
+/// The amount of tilemap tiles that fit in each chunk.
+pub const TILES_PER_CHUNK: usize = CHUNK_SIZE as usize / size_of::<u16>();
+
 /// Basic info about a [`ResourceDatabase`] used in its initialization and for
 /// de/serializing the db file.
 #[derive(Clone, Copy)]
 pub struct ResourceDatabaseHeader {
+    /// The version of the format the database was written in. Databases are
+    /// only readable if this is between [`RESOURCE_DB_MIN_VERSION`] and
+    /// [`RESOURCE_DB_VERSION`].
+    pub version: u32,
     /// The amount of regular chunks in the database.
     pub chunks: u32,
     /// The amount of sprite chunks in the database.
@@ -48,6 +84,19 @@ pub struct ResourceDatabaseHeader {
     pub sprites: u32,
     /// The amount of [`AudioClipAsset`]s in the database.
     pub audio_clips: u32,
+    /// The amount of [`FontAsset`]s in the database.
+    pub fonts: u32,
+    /// The amount of [`AnimationAsset`]s in the database.
+    pub animations: u32,
+    /// The amount of [`TilemapAsset`]s in the database.
+    pub tilemaps: u32,
+    /// The [`Crc32`] checksum of the chunk descriptors and asset metadata,
+    /// i.e. everything between the header and the chunk data.
+    pub metadata_checksum: u32,
+    /// The [`Crc32`] checksum of the chunk data, i.e. everything from
+    /// [`ResourceDatabaseHeader::chunk_data_offset`] to the end of the last
+    /// chunk.
+    pub chunk_data_checksum: u32,
 }
 
 impl ResourceDatabaseHeader {
@@ -57,11 +106,171 @@ impl ResourceDatabaseHeader {
     /// This is the size of the header, chunk descriptors, and asset metadata.
     pub const fn chunk_data_offset(&self) -> u64 {
         use serialize::Serialize as Ser;
-        <ResourceDatabaseHeader as Ser>::SERIALIZED_SIZE as u64
+        ResourceDatabaseHeader::size_for_version(self.version) as u64
             + self.chunks as u64 * <ChunkDescriptor as Ser>::SERIALIZED_SIZE as u64
             + self.sprite_chunks as u64 * <SpriteChunkDescriptor as Ser>::SERIALIZED_SIZE as u64
-            + self.sprites as u64 * <NamedAsset<SpriteAsset> as Ser>::SERIALIZED_SIZE as u64
+            + self.sprites as u64 * self.sprite_metadata_size() as u64
             + self.audio_clips as u64 * <NamedAsset<AudioClipAsset> as Ser>::SERIALIZED_SIZE as u64
+            + self.fonts as u64 * <NamedAsset<FontAsset> as Ser>::SERIALIZED_SIZE as u64
+            + self.animations as u64 * <NamedAsset<AnimationAsset> as Ser>::SERIALIZED_SIZE as u64
+            + self.tilemaps as u64 * <NamedAsset<TilemapAsset> as Ser>::SERIALIZED_SIZE as u64
+    }
+
+    /// Returns the size of the header in a database of the given version.
+    ///
+    /// Headers of older versions can be shorter than
+    /// [`Deserialize::SERIALIZED_SIZE`], as they have counts for fewer asset
+    /// types. The asset metadata starts right after the header.
+    pub const fn size_for_version(version: u32) -> usize {
+        use serialize::Serialize as Ser;
+        let count_size = <u32 as Ser>::SERIALIZED_SIZE;
+        let size = <ResourceDatabaseHeader as Ser>::SERIALIZED_SIZE;
+        match version {
+            ..4 => size - 2 * count_size,
+            4 => size - count_size,
+            _ => size,
+        }
+    }
+
+    /// Returns the serialized size of each sprite's metadata, which depends on
+    /// the version of the database.
+    pub const fn sprite_metadata_size(&self) -> usize {
+        use serialize::Serialize as Ser;
+        if self.version < 3 {
+            <NamedAsset<SpriteAssetV2> as Deserialize>::SERIALIZED_SIZE
+        } else {
+            <NamedAsset<SpriteAsset> as Ser>::SERIALIZED_SIZE
+        }
+    }
+}
+
+/// Reasons for [`Deserialize::try_deserialize`] to reject the data it's given.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeserializeError {
+    /// The buffer is shorter than the serialized value.
+    ShortBuffer {
+        /// The length of the buffer.
+        len: usize,
+        /// The [`Deserialize::SERIALIZED_SIZE`] of the value.
+        expected: usize,
+    },
+    /// The data does not start with [`RESOURCE_DB_MAGIC_NUMBER`], so it's
+    /// probably not a resource database at all.
+    InvalidMagicNumber {
+        /// The first four bytes of the data, as a little-endian integer.
+        found: u32,
+    },
+    /// The database was written for a different version of the engine, and
+    /// needs to be reimported.
+    VersionMismatch {
+        /// The version stored in the database.
+        found: u32,
+    },
+    /// The database was written with a different value for one of the
+    /// engine's resource constants, e.g. [`CHUNK_SIZE`], and needs to be
+    /// reimported.
+    ConstantMismatch {
+        /// The name of the mismatched constant.
+        constant: &'static str,
+        /// The value stored in the database.
+        found: u32,
+        /// The value of the constant in this version of the engine.
+        expected: u32,
+    },
+    /// The data contains a value which can't be represented by the
+    /// deserialized type, e.g. a string which is not valid UTF-8.
+    InvalidValue {
+        /// A short description of the problem.
+        reason: &'static str,
+    },
+}
+
+impl Display for DeserializeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeserializeError::ShortBuffer { len, expected } => {
+                write!(f, "data is truncated ({len} bytes long, expected {expected})")
+            }
+            DeserializeError::InvalidMagicNumber { found } => {
+                write!(f, "not a resource database (magic number {found:#010X})")
+            }
+            DeserializeError::VersionMismatch { found } if *found < RESOURCE_DB_MIN_VERSION => {
+                write!(f, "database too old (version {found}), please reimport")
+            }
+            DeserializeError::VersionMismatch { found } => {
+                write!(f, "database too new (version {found}), please reimport")
+            }
+            DeserializeError::ConstantMismatch {
+                constant,
+                found,
+                expected,
+            } => write!(
+                f,
+                "database was created with {constant} = {found} instead of {expected}, please reimport",
+            ),
+            DeserializeError::InvalidValue { reason } => write!(f, "invalid data: {reason}"),
+        }
+    }
+}
+
+/// Reasons for [`ResourceDatabase`] creation to fail.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResourceDatabaseError {
+    /// The arena did not have enough memory for the database.
+    OutOfMemory,
+    /// Reading the header or the asset metadata from the database file
+    /// failed, e.g. due to the file being truncated.
+    ReadFailed,
+    /// The database file's header is not compatible with this version of the
+    /// engine.
+    IncompatibleHeader(DeserializeError),
+    /// The chunk descriptors or asset metadata did not match
+    /// [`ResourceDatabaseHeader::metadata_checksum`].
+    CorruptMetadata,
+    /// The asset metadata matched its checksum, but could not be deserialized,
+    /// which implies a bug in the importer.
+    InvalidMetadata(DeserializeError),
+    /// The chunk data did not match
+    /// [`ResourceDatabaseHeader::chunk_data_checksum`].
+    CorruptChunkData,
+}
+
+impl Display for ResourceDatabaseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResourceDatabaseError::OutOfMemory => write!(f, "not enough memory"),
+            ResourceDatabaseError::ReadFailed => write!(f, "reading the file failed"),
+            ResourceDatabaseError::IncompatibleHeader(err) => err.fmt(f),
+            ResourceDatabaseError::CorruptMetadata => write!(f, "asset metadata is corrupted"),
+            ResourceDatabaseError::InvalidMetadata(err) => {
+                write!(f, "asset metadata is invalid: {err}")
+            }
+            ResourceDatabaseError::CorruptChunkData => write!(f, "chunk data is corrupted"),
+        }
+    }
+}
+
+/// Reasons for [`ResourceDatabase::reload`] to fail.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReloadError {
+    /// The database's arena or the scratch arena did not have enough memory
+    /// for the reloaded metadata.
+    OutOfMemory,
+    /// One of the database files could not be loaded.
+    Database {
+        /// The index of the file, in the order the files were mounted.
+        index: usize,
+        /// The reason the file could not be loaded.
+        error: ResourceDatabaseError,
+    },
+}
+
+impl Display for ReloadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReloadError::OutOfMemory => write!(f, "not enough memory"),
+            ReloadError::Database { index, error } => write!(f, "database #{index}: {error}"),
+        }
     }
 }
 
@@ -69,14 +278,29 @@ impl ResourceDatabaseHeader {
 ///
 /// Game code should mostly use this for the `find_*` and `get_*` functions to
 /// query for assets, which implement the relevant logic for each asset type.
+///
+/// The database can consist of multiple database files, configured with
+/// [`EngineLimits::resource_database_paths`](crate::EngineLimits::resource_database_paths).
+/// If multiple files have an asset with the same name, the `find_*` functions
+/// return the asset from the file mounted last, so e.g. a mod's database
+/// mounted after the base game's database can override the base game's
+/// assets.
+///
+/// The database files can be reloaded while the game is running with
+/// [`ResourceDatabase::reload`].
 pub struct ResourceDatabase {
     // Asset metadata
-    sprites: FixedVec<'static, NamedAsset<SpriteAsset>>,
-    audio_clips: FixedVec<'static, NamedAsset<AudioClipAsset>>,
+    sprites: AssetTable<SpriteAsset>,
+    audio_clips: AssetTable<AudioClipAsset>,
+    fonts: AssetTable<FontAsset>,
+    animations: AssetTable<AnimationAsset>,
+    tilemaps: AssetTable<TilemapAsset>,
     // Chunk loading metadata
-    chunk_data_offset: u64,
+    sources: FixedVec<'static, DatabaseSource>,
     chunk_descriptors: FixedVec<'static, ChunkDescriptor>,
     sprite_chunk_descriptors: FixedVec<'static, SpriteChunkDescriptor>,
+    // Asset use counts, indexed by the sprites' handles
+    sprite_use_counts: FixedVec<'static, &'static AtomicU32>,
     // In-memory chunks
     /// The regular chunks currently loaded in-memory. Loaded via
     /// [`ResourceLoader`], usually by functions making use of an asset.
@@ -84,63 +308,223 @@ pub struct ResourceDatabase {
     /// The sprite chunks currently loaded in-memory. Loaded via
     /// [`ResourceLoader`], usually by functions making use of an asset.
     pub sprite_chunks: SparseArray<'static, SpriteChunkData>,
+    /// The amount of arena memory allocated for `chunks` and `sprite_chunks`,
+    /// in bytes.
+    pub(crate) loaded_chunks_memory: usize,
+    // For reloading
+    arena: &'static LinearAllocator<'static>,
+    verify_chunk_data: bool,
 }
 
 impl ResourceDatabase {
+    /// Loads the resource database from the database files, mounted in the
+    /// order they're in `files`. When multiple files contain an asset with the
+    /// same name, the asset from the file mounted last is used. Each file's
+    /// header and checksums are checked independently.
+    ///
+    /// Errors are returned along with the index of the file in `files` which
+    /// caused them, except for [`ResourceDatabaseError::OutOfMemory`], which
+    /// is not specific to any file, and is always returned with index 0.
     pub(crate) fn new(
         platform: &dyn Platform,
         arena: &'static LinearAllocator,
         file_reader: &mut FileReader,
+        files: &[FileHandle],
         max_loaded_chunks: u32,
         max_loaded_sprite_chunks: u32,
-    ) -> Option<ResourceDatabase> {
+        verify_chunk_data: bool,
+    ) -> Result<ResourceDatabase, (usize, ResourceDatabaseError)> {
         profiling::function_scope!();
-        use Deserialize as De;
-        let header_size = <ResourceDatabaseHeader as De>::SERIALIZED_SIZE;
-
-        assert!(file_reader.push_read(0, header_size));
-        let header = file_reader
-            .pop_read(platform, true, |header_bytes| {
-                deserialize::<ResourceDatabaseHeader>(header_bytes, &mut 0)
-            })
-            .expect("resource database file should be readable");
-
-        let chunk_data_offset = header.chunk_data_offset();
-        let ResourceDatabaseHeader {
-            chunks,
-            sprite_chunks,
+        let oom = (0, ResourceDatabaseError::OutOfMemory);
+        let Metadata {
+            sources,
+            chunk_descriptors,
+            sprite_chunk_descriptors,
             sprites,
             audio_clips,
-        } = header;
+            fonts,
+            animations,
+            tilemaps,
+        } = read_metadata(platform, arena, file_reader, files, verify_chunk_data)?;
 
-        let mut cursor = header_size;
-        let mut queue_read = |size: usize| {
-            assert!(file_reader.push_read(cursor as u64, size));
-            cursor += size;
-        };
+        let sprites = AssetTable::new(arena, sprites).ok_or(oom)?;
+        let audio_clips = AssetTable::new(arena, audio_clips).ok_or(oom)?;
+        let fonts = AssetTable::new(arena, fonts).ok_or(oom)?;
+        let animations = AssetTable::new(arena, animations).ok_or(oom)?;
+        let tilemaps = AssetTable::new(arena, tilemaps).ok_or(oom)?;
+        let sprite_use_counts = alloc_use_counts(arena, &[], sprites.handle_count()).ok_or(oom)?;
 
-        queue_read(chunks as usize * <ChunkDescriptor as De>::SERIALIZED_SIZE);
-        queue_read(sprite_chunks as usize * <SpriteChunkDescriptor as De>::SERIALIZED_SIZE);
-        queue_read(sprites as usize * <NamedAsset<SpriteAsset> as De>::SERIALIZED_SIZE);
-        queue_read(audio_clips as usize * <NamedAsset<AudioClipAsset> as De>::SERIALIZED_SIZE);
+        // There's never a need to have more chunks loaded than there are
+        // chunks, which also keeps an empty database from allocating any.
+        let chunk_count = chunk_descriptors.len() as u32;
+        let sprite_chunk_count = sprite_chunk_descriptors.len() as u32;
+        let max_loaded_chunks = max_loaded_chunks.min(chunk_count);
+        let max_loaded_sprite_chunks = max_loaded_sprite_chunks.min(sprite_chunk_count);
+        let chunk_caches_start = arena.allocated();
+        let chunks = SparseArray::new(arena, chunk_count, max_loaded_chunks).ok_or(oom)?;
+        let sprite_chunks =
+            SparseArray::new(arena, sprite_chunk_count, max_loaded_sprite_chunks).ok_or(oom)?;
+        let loaded_chunks_memory = arena.allocated() - chunk_caches_start;
 
-        // NOTE: These deserialize_vec calls must be in the same order as the queue_reads above.
-        let chunk_descriptors = deserialize_vec(arena, file_reader, platform)?;
-        let sprite_chunk_descriptors = deserialize_vec(arena, file_reader, platform)?;
-        let sprites = sorted(deserialize_vec(arena, file_reader, platform)?);
-        let audio_clips = sorted(deserialize_vec(arena, file_reader, platform)?);
-
-        Some(ResourceDatabase {
+        Ok(ResourceDatabase {
             sprites,
             audio_clips,
-            chunk_data_offset,
+            fonts,
+            animations,
+            tilemaps,
+            sources,
             chunk_descriptors,
             sprite_chunk_descriptors,
-            chunks: SparseArray::new(arena, chunks, max_loaded_chunks)?,
-            sprite_chunks: SparseArray::new(arena, sprite_chunks, max_loaded_sprite_chunks)?,
+            sprite_use_counts,
+            chunks,
+            sprite_chunks,
+            loaded_chunks_memory,
+            arena,
+            verify_chunk_data,
         })
     }
 
+    /// Reloads the headers and asset metadata of the database files, to pick
+    /// up changes made to the files after the database was loaded, e.g. by
+    /// reimporting assets while the game is running.
+    ///
+    /// Handles of assets which still exist with the same name stay valid, and
+    /// refer to the reloaded assets. The `get_*` functions return `None` for
+    /// the handles of removed assets. Loaded chunks are kept for the files
+    /// whose metadata and chunk data did not change, and unloaded for the
+    /// rest, to be loaded again when they're next used.
+    ///
+    /// Before reloading, the reads already dispatched by `resource_loader` are
+    /// finished, and the loads queued up in it but not yet dispatched are
+    /// dropped, as they refer to chunks of the database before the reload.
+    ///
+    /// The metadata is read into `scratch_arena`, which is only used during
+    /// this call, and then copied over the old metadata. The old metadata's
+    /// memory is reused when the new metadata fits in it, so the database's
+    /// arena is only allocated from when the metadata grows, e.g. when assets
+    /// are added. If the reload fails, the database is left as it was.
+    pub fn reload(
+        &mut self,
+        platform: &dyn Platform,
+        resource_loader: &mut ResourceLoader,
+        scratch_arena: &LinearAllocator,
+    ) -> Result<(), ReloadError> {
+        profiling::function_scope!();
+        resource_loader.drain(self, platform);
+
+        let oom = ReloadError::OutOfMemory;
+        let (arena, scratch) = (self.arena, scratch_arena);
+        let mut files = FixedVec::new(scratch, self.sources.len()).ok_or(oom)?;
+        for source in self.sources.iter() {
+            files.push(source.file).ok().unwrap();
+        }
+        let file_reader = resource_loader.file_reader();
+        let verify_chunk_data = self.verify_chunk_data;
+        let mut metadata = read_metadata(platform, scratch, file_reader, &files, verify_chunk_data)
+            .map_err(|(index, error)| match error {
+                ResourceDatabaseError::OutOfMemory => ReloadError::OutOfMemory,
+                error => ReloadError::Database { index, error },
+            })?;
+
+        // Everything which could run out of memory is allocated before
+        // modifying the database, so that it's left as it was on failure.
+        let sprites = (self.sprites)
+            .prepare_reload(arena, scratch, &metadata.sprites)
+            .ok_or(oom)?;
+        let audio_clips = (self.audio_clips)
+            .prepare_reload(arena, scratch, &metadata.audio_clips)
+            .ok_or(oom)?;
+        let fonts = (self.fonts)
+            .prepare_reload(arena, scratch, &metadata.fonts)
+            .ok_or(oom)?;
+        let animations = (self.animations)
+            .prepare_reload(arena, scratch, &metadata.animations)
+            .ok_or(oom)?;
+        let tilemaps = (self.tilemaps)
+            .prepare_reload(arena, scratch, &metadata.tilemaps)
+            .ok_or(oom)?;
+        // The existing use counts are kept, so that existing ResourceGuards
+        // stay in effect. Handles are never removed, so new use counts are
+        // only needed if sprites were added.
+        let handle_count = sprites.handle_count();
+        let sprite_use_counts = if handle_count > self.sprite_use_counts.len() {
+            let existing = &self.sprite_use_counts;
+            Some(alloc_use_counts(arena, existing, handle_count).ok_or(oom)?)
+        } else {
+            None
+        };
+        let sources_len = metadata.sources.len();
+        let sources = Reallocation::new(arena, &self.sources, sources_len).ok_or(oom)?;
+        let chunks_len = metadata.chunk_descriptors.len();
+        let chunk_descriptors =
+            Reallocation::new(arena, &self.chunk_descriptors, chunks_len).ok_or(oom)?;
+        let sprite_chunks_len = metadata.sprite_chunk_descriptors.len();
+        let sprite_chunk_descriptors =
+            Reallocation::new(arena, &self.sprite_chunk_descriptors, sprite_chunks_len)
+                .ok_or(oom)?;
+        let chunk_count = metadata.chunk_descriptors.len() as u32;
+        let sprite_chunk_count = metadata.sprite_chunk_descriptors.len() as u32;
+        self.chunks.reserve_len(arena, chunk_count).ok_or(oom)?;
+        (self.sprite_chunks)
+            .reserve_len(arena, sprite_chunk_count)
+            .ok_or(oom)?;
+
+        // Nothing can fail from here on, so the database can be modified.
+        let (old_sources, new_sources) = (&self.sources, &metadata.sources);
+        let moved_index = |chunks: fn(&DatabaseSource) -> Range<u32>, index: u32| {
+            let (old, new) = (old_sources.iter())
+                .zip(new_sources.iter())
+                .find(|(old, _)| chunks(old).contains(&index))?;
+            let unchanged = old.header.metadata_checksum == new.header.metadata_checksum
+                && old.header.chunk_data_checksum == new.header.chunk_data_checksum;
+            unchanged.then(|| chunks(new).start + (index - chunks(old).start))
+        };
+        (self.chunks).reindex(chunk_count, |index| {
+            moved_index(|source| source.chunks.clone(), index)
+        });
+        (self.sprite_chunks).reindex(sprite_chunk_count, |index| {
+            moved_index(|source| source.sprite_chunks.clone(), index)
+        });
+
+        self.sprites.reload(sprites, &mut metadata.sprites);
+        self.audio_clips
+            .reload(audio_clips, &mut metadata.audio_clips);
+        self.fonts.reload(fonts, &mut metadata.fonts);
+        self.animations.reload(animations, &mut metadata.animations);
+        self.tilemaps.reload(tilemaps, &mut metadata.tilemaps);
+        sources.fill(&mut self.sources, metadata.sources.iter().cloned());
+        chunk_descriptors.fill(
+            &mut self.chunk_descriptors,
+            metadata.chunk_descriptors.iter().cloned(),
+        );
+        sprite_chunk_descriptors.fill(
+            &mut self.sprite_chunk_descriptors,
+            metadata.sprite_chunk_descriptors.iter().cloned(),
+        );
+        if let Some(sprite_use_counts) = sprite_use_counts {
+            self.sprite_use_counts = sprite_use_counts;
+        }
+        Ok(())
+    }
+
+    /// Returns the file the chunk at `chunk_index` is read from, and the range
+    /// of bytes in the file containing the chunk's data.
+    pub(crate) fn chunk_source(&self, chunk_index: u32) -> (FileHandle, Range<u64>) {
+        let source = (self.sources.iter())
+            .find(|source| source.chunks.contains(&chunk_index))
+            .unwrap();
+        source.file_range(&self.chunk_descriptors[chunk_index as usize].source_bytes)
+    }
+
+    /// Returns the file the sprite chunk at `chunk_index` is read from, and
+    /// the range of bytes in the file containing the chunk's data.
+    pub(crate) fn sprite_chunk_source(&self, chunk_index: u32) -> (FileHandle, Range<u64>) {
+        let source = (self.sources.iter())
+            .find(|source| source.sprite_chunks.contains(&chunk_index))
+            .unwrap();
+        source.file_range(&self.sprite_chunk_descriptors[chunk_index as usize].source_bytes)
+    }
+
     /// Returns the longest source bytes length of all the chunks, i.e. the
     /// minimum amount of staging memory required to be able to load any chunk
     /// in this database.
@@ -155,31 +539,432 @@ impl ResourceDatabase {
             .unwrap_or(0);
         largest_chunk_source.max(largest_sprite_chunk_source)
     }
+
+    /// Returns the fraction of the asset's chunks which are currently loaded,
+    /// from 0.0 to 1.0. Assets without any chunks, and assets removed by
+    /// [`ResourceDatabase::reload`], are always fully loaded.
+    ///
+    /// Useful for e.g. progress bars on loading screens. This only checks
+    /// which chunks are loaded, and doesn't count as using them, so it doesn't
+    /// keep the chunks from being evicted either.
+    pub fn residency(&self, handle: impl AssetHandle) -> f32 {
+        let Some(asset) = handle.get_asset(self) else {
+            return 1.0;
+        };
+        let chunks = asset.get_chunks().unwrap_or(0..0);
+        let sprite_chunks = asset.get_sprite_chunks().unwrap_or(0..0);
+        let total = chunks.len() + sprite_chunks.len();
+        if total == 0 {
+            return 1.0;
+        }
+        let loaded = chunks.filter(|&chunk| self.chunks.is_loaded(chunk)).count()
+            + (sprite_chunks.filter(|&chunk| self.sprite_chunks.is_loaded(chunk))).count();
+        loaded as f32 / total as f32
+    }
+
+    /// Marks the sprite as being in use until the returned [`ResourceGuard`]
+    /// is dropped. While any guards for a sprite exist, its loaded sprite
+    /// chunks will not be evicted to make room for other chunks.
+    pub fn acquire_sprite(&self, handle: SpriteHandle) -> ResourceGuard {
+        let use_count = self.sprite_use_counts[handle.0];
+        use_count.fetch_add(1, Ordering::Relaxed);
+        if let Some(sprite) = self.get_sprite(handle) {
+            for chunk_index in sprite.get_sprite_chunks().unwrap_or(0..0) {
+                self.sprite_chunks.pin(chunk_index);
+            }
+        }
+        ResourceGuard { use_count }
+    }
+
+    /// Increments the ages of the loaded chunks in [`ResourceDatabase::chunks`]
+    /// and [`ResourceDatabase::sprite_chunks`], and pins the loaded chunks of
+    /// assets currently acquired with e.g.
+    /// [`ResourceDatabase::acquire_sprite`] to avoid them being evicted.
+    ///
+    /// The chunks of assets whose guards have been dropped are unpinned here,
+    /// so they can be evicted after the next call to this function.
+    pub fn increment_ages(&mut self) {
+        profiling::function_scope!();
+        self.chunks.increment_ages();
+        self.sprite_chunks.increment_ages();
+        self.sprite_chunks.unpin_all();
+
+        for (handle, sprite) in self.sprites.iter() {
+            if self.sprite_use_counts[handle].load(Ordering::Relaxed) == 0 {
+                continue;
+            }
+            for chunk_index in sprite.asset.get_sprite_chunks().unwrap_or(0..0) {
+                self.sprite_chunks.pin(chunk_index);
+            }
+        }
+    }
 }
 
-fn sorted<T: Ord>(mut input: FixedVec<'_, T>) -> FixedVec<'_, T> {
-    input.sort_unstable();
-    input
+/// A resource database file mounted in a [`ResourceDatabase`].
+#[derive(Clone)]
+struct DatabaseSource {
+    file: FileHandle,
+    header: ResourceDatabaseHeader,
+    /// The indices of the [`ResourceDatabase`]'s chunks read from this file.
+    chunks: Range<u32>,
+    /// The indices of the [`ResourceDatabase`]'s sprite chunks read from this
+    /// file.
+    sprite_chunks: Range<u32>,
 }
 
-fn deserialize_vec<'a, D: Deserialize>(
-    alloc: &'a LinearAllocator,
+impl DatabaseSource {
+    /// Returns the file and the range of bytes in it for the chunk data at
+    /// `source_bytes` of one of this file's chunk descriptors.
+    fn file_range(&self, source_bytes: &Range<u64>) -> (FileHandle, Range<u64>) {
+        let offset = self.header.chunk_data_offset();
+        (
+            self.file,
+            offset + source_bytes.start..offset + source_bytes.end,
+        )
+    }
+}
+
+/// The metadata of a [`ResourceDatabase`], read from the database files by
+/// [`read_metadata`]. The assets are sorted by name.
+struct Metadata<'a> {
+    sources: FixedVec<'a, DatabaseSource>,
+    chunk_descriptors: FixedVec<'a, ChunkDescriptor>,
+    sprite_chunk_descriptors: FixedVec<'a, SpriteChunkDescriptor>,
+    sprites: FixedVec<'a, Option<NamedAsset<SpriteAsset>>>,
+    audio_clips: FixedVec<'a, Option<NamedAsset<AudioClipAsset>>>,
+    fonts: FixedVec<'a, Option<NamedAsset<FontAsset>>>,
+    animations: FixedVec<'a, Option<NamedAsset<AnimationAsset>>>,
+    tilemaps: FixedVec<'a, Option<NamedAsset<TilemapAsset>>>,
+}
+
+/// Reads the headers, chunk descriptors, and asset metadata of the database
+/// `files`, mounting them in order, and verifying their checksums.
+///
+/// Errors are returned along with the index of the file in `files` which
+/// caused them, except for [`ResourceDatabaseError::OutOfMemory`], which is
+/// always returned with index 0.
+fn read_metadata<'a>(
+    platform: &dyn Platform,
+    arena: &'a LinearAllocator,
+    file_reader: &mut FileReader,
+    files: &[FileHandle],
+    verify_chunk_data: bool,
+) -> Result<Metadata<'a>, (usize, ResourceDatabaseError)> {
+    profiling::function_scope!();
+    use Deserialize as De;
+    let oom = (0, ResourceDatabaseError::OutOfMemory);
+
+    // The headers are read first, to allocate the metadata of all the files
+    // in one go.
+    let mut sources = FixedVec::new(arena, files.len()).ok_or(oom)?;
+    let (mut chunks_start, mut sprite_chunks_start) = (0, 0);
+    for (i, &file) in files.iter().enumerate() {
+        let header = read_header(file_reader, platform, file).map_err(|err| (i, err))?;
+        let chunks = chunks_start..chunks_start + header.chunks;
+        let sprite_chunks = sprite_chunks_start..sprite_chunks_start + header.sprite_chunks;
+        (chunks_start, sprite_chunks_start) = (chunks.end, sprite_chunks.end);
+        let source = DatabaseSource {
+            file,
+            header,
+            chunks,
+            sprite_chunks,
+        };
+        let Ok(_) = sources.push(source) else {
+            unreachable!()
+        };
+    }
+
+    let total = |count: fn(&ResourceDatabaseHeader) -> u32| {
+        (sources.iter())
+            .map(|source| count(&source.header) as usize)
+            .sum::<usize>()
+    };
+    let mut chunk_descriptors = FixedVec::new(arena, chunks_start as usize).ok_or(oom)?;
+    let mut sprite_chunk_descriptors =
+        FixedVec::new(arena, sprite_chunks_start as usize).ok_or(oom)?;
+    let mut sprites = FixedVec::new(arena, total(|h| h.sprites)).ok_or(oom)?;
+    let mut audio_clips = FixedVec::new(arena, total(|h| h.audio_clips)).ok_or(oom)?;
+    let mut fonts = FixedVec::new(arena, total(|h| h.fonts)).ok_or(oom)?;
+    let mut animations = FixedVec::new(arena, total(|h| h.animations)).ok_or(oom)?;
+    let mut tilemaps = FixedVec::new(arena, total(|h| h.tilemaps)).ok_or(oom)?;
+
+    for (i, source) in sources.iter().enumerate() {
+        profiling::scope!("mount resource database");
+        let at = |err| (i, err);
+        let header = &source.header;
+        let mut cursor = ResourceDatabaseHeader::size_for_version(header.version);
+        let mut queue_read = |size: usize| {
+            assert!(file_reader.push_read(source.file, cursor as u64, size));
+            cursor += size;
+        };
+
+        queue_read(header.chunks as usize * <ChunkDescriptor as De>::SERIALIZED_SIZE);
+        queue_read(header.sprite_chunks as usize * <SpriteChunkDescriptor as De>::SERIALIZED_SIZE);
+        queue_read(header.sprites as usize * header.sprite_metadata_size());
+        queue_read(
+            header.audio_clips as usize * <NamedAsset<AudioClipAsset> as De>::SERIALIZED_SIZE,
+        );
+        queue_read(header.fonts as usize * <NamedAsset<FontAsset> as De>::SERIALIZED_SIZE);
+        queue_read(
+            header.animations as usize * <NamedAsset<AnimationAsset> as De>::SERIALIZED_SIZE,
+        );
+        queue_read(header.tilemaps as usize * <NamedAsset<TilemapAsset> as De>::SERIALIZED_SIZE);
+
+        // NOTE: These calls must be in the same order as the queue_reads above.
+        deserialize_each::<ChunkDescriptor>(file_reader, platform, |chunk| {
+            let Ok(_) = chunk_descriptors.push(chunk) else {
+                unreachable!()
+            };
+        })
+        .map_err(at)?;
+        deserialize_each::<SpriteChunkDescriptor>(file_reader, platform, |chunk| {
+            let Ok(_) = sprite_chunk_descriptors.push(chunk) else {
+                unreachable!()
+            };
+        })
+        .map_err(at)?;
+        if header.version < 3 {
+            mount_assets::<NamedAsset<SpriteAssetV2>, _>(
+                &mut sprites,
+                source,
+                file_reader,
+                platform,
+            )
+            .map_err(at)?;
+        } else {
+            mount_assets::<NamedAsset<SpriteAsset>, _>(&mut sprites, source, file_reader, platform)
+                .map_err(at)?;
+        }
+        mount_assets::<NamedAsset<_>, _>(&mut audio_clips, source, file_reader, platform)
+            .map_err(at)?;
+        mount_assets::<NamedAsset<_>, _>(&mut fonts, source, file_reader, platform).map_err(at)?;
+        mount_assets::<NamedAsset<_>, _>(&mut animations, source, file_reader, platform)
+            .map_err(at)?;
+        mount_assets::<NamedAsset<_>, _>(&mut tilemaps, source, file_reader, platform)
+            .map_err(at)?;
+
+        // The chunk data on the other hand can be large, and reading through
+        // all of it can take a while, so it's up to the caller.
+        if verify_chunk_data {
+            let chunks = &chunk_descriptors[source.chunks.start as usize..];
+            let sprite_chunks = &sprite_chunk_descriptors[source.sprite_chunks.start as usize..];
+            let chunk_data_len = (chunks.iter().map(|chunk| &chunk.source_bytes))
+                .chain(sprite_chunks.iter().map(|chunk| &chunk.source_bytes))
+                .map(|source_bytes| source_bytes.end)
+                .max()
+                .unwrap_or(0);
+            let chunk_data_offset = header.chunk_data_offset();
+            let chunk_data_bytes = chunk_data_offset..chunk_data_offset + chunk_data_len;
+            let checksum = read_checksum(file_reader, platform, source.file, chunk_data_bytes);
+            if checksum != Some(header.chunk_data_checksum) {
+                return Err(at(ResourceDatabaseError::CorruptChunkData));
+            }
+        }
+    }
+
+    Ok(Metadata {
+        sources,
+        chunk_descriptors,
+        sprite_chunk_descriptors,
+        sprites,
+        audio_clips,
+        fonts,
+        animations,
+        tilemaps,
+    })
+}
+
+/// A replacement for one of the [`FixedVec`]s of a [`ResourceDatabase`] in
+/// [`ResourceDatabase::reload`], allocated only if the new contents don't fit
+/// in the existing vec, so that reloading doesn't use up the arena.
+struct Reallocation<T: 'static>(Option<FixedVec<'static, T>>);
+
+impl<T> Reallocation<T> {
+    /// Allocates a new vec with room for `len` elements from `arena`, if
+    /// `existing` doesn't have room for them.
+    fn new(
+        arena: &'static LinearAllocator,
+        existing: &FixedVec<'static, T>,
+        len: usize,
+    ) -> Option<Reallocation<T>> {
+        if len <= existing.len() + existing.spare_capacity() {
+            Some(Reallocation(None))
+        } else {
+            Some(Reallocation(Some(FixedVec::new(arena, len)?)))
+        }
+    }
+
+    /// Replaces the contents of `existing` with `values`, which must fit in
+    /// the length this reallocation was created for.
+    fn fill(self, existing: &mut FixedVec<'static, T>, values: impl IntoIterator<Item = T>) {
+        if let Some(new) = self.0 {
+            *existing = new;
+        }
+        existing.clear();
+        for value in values {
+            let Ok(_) = existing.push(value) else {
+                unreachable!()
+            };
+        }
+    }
+}
+
+/// Allocates use counts for `handle_count` sprites, starting with the
+/// `existing` use counts, so that the [`ResourceGuard`]s referring to them
+/// stay in effect. The rest are new use counts starting at zero.
+fn alloc_use_counts(
+    arena: &'static LinearAllocator,
+    existing: &[&'static AtomicU32],
+    handle_count: usize,
+) -> Option<FixedVec<'static, &'static AtomicU32>> {
+    let new_use_counts =
+        arena.try_alloc_uninit_slice::<AtomicU32>(handle_count - existing.len(), None)?;
+    for use_count in &mut *new_use_counts {
+        use_count.write(AtomicU32::new(0));
+    }
+    // Safety: the whole slice is initialized by the loop above.
+    let new_use_counts = unsafe {
+        transmute::<&'static mut [MaybeUninit<AtomicU32>], &'static [AtomicU32]>(new_use_counts)
+    };
+
+    let mut use_counts = FixedVec::new(arena, handle_count)?;
+    for use_count in existing.iter().copied().chain(new_use_counts) {
+        use_counts.push(use_count).unwrap();
+    }
+    Some(use_counts)
+}
+
+/// Keeps an asset's chunks from being evicted from the [`ResourceDatabase`]
+/// while alive. Created with [`ResourceDatabase::acquire_sprite`].
+///
+/// Note that this doesn't load the chunks, it just makes sure that once loaded,
+/// they stay loaded until this guard is dropped.
+#[must_use]
+pub struct ResourceGuard {
+    use_count: &'static AtomicU32,
+}
+
+impl Drop for ResourceGuard {
+    fn drop(&mut self) {
+        self.use_count.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Reads the header of the database `file`, and checks that the rest of the
+/// metadata matches [`ResourceDatabaseHeader::metadata_checksum`].
+fn read_header(
     file_reader: &mut FileReader,
     platform: &dyn Platform,
-) -> Option<FixedVec<'a, D>> {
+    file: FileHandle,
+) -> Result<ResourceDatabaseHeader, ResourceDatabaseError> {
+    // Older headers are shorter, so this also reads the start of their
+    // metadata, which any database with assets in it has enough of.
+    let header_size = <ResourceDatabaseHeader as Deserialize>::SERIALIZED_SIZE;
+    assert!(file_reader.push_read(file, 0, header_size));
+    let header = file_reader
+        .pop_read(platform, true, |bytes| {
+            ResourceDatabaseHeader::try_deserialize(bytes)
+        })
+        .map_err(|_| ResourceDatabaseError::ReadFailed)?
+        .map_err(ResourceDatabaseError::IncompatibleHeader)?;
+
+    // The metadata is small, so it's always verified before it's trusted.
+    let header_size = ResourceDatabaseHeader::size_for_version(header.version);
+    let metadata_bytes = header_size as u64..header.chunk_data_offset();
+    let checksum = read_checksum(file_reader, platform, file, metadata_bytes);
+    if checksum != Some(header.metadata_checksum) {
+        return Err(ResourceDatabaseError::CorruptMetadata);
+    }
+
+    Ok(header)
+}
+
+/// Deserializes the elements in the read at the front of the file reader's
+/// queue, passing each one to `use_element`.
+fn deserialize_each<D: Deserialize>(
+    file_reader: &mut FileReader,
+    platform: &dyn Platform,
+    mut use_element: impl FnMut(D),
+) -> Result<(), ResourceDatabaseError> {
     file_reader
         .pop_read(platform, true, |src| {
-            let count = src.len() / D::SERIALIZED_SIZE;
-            let mut vec = FixedVec::new(alloc, count)?;
-            assert_eq!(0, vec.len() % D::SERIALIZED_SIZE);
+            assert_eq!(0, src.len() % D::SERIALIZED_SIZE);
             for element_bytes in src.chunks_exact(D::SERIALIZED_SIZE) {
-                let Ok(_) = vec.push(D::deserialize(element_bytes)) else {
+                let element = D::try_deserialize(element_bytes)
+                    .map_err(ResourceDatabaseError::InvalidMetadata)?;
+                use_element(element);
+            }
+            Ok(())
+        })
+        .map_err(|_| ResourceDatabaseError::ReadFailed)?
+}
+
+/// Deserializes the assets in the read at the front of the file reader's
+/// queue into `assets`, converting each one from `D`, which allows reading
+/// metadata stored in the format of an older database version.
+///
+/// The assets' chunk references are offset to match where the `source`'s
+/// chunks are in the [`ResourceDatabase`]. Assets with the same name as an
+/// asset mounted from an earlier source replace the earlier asset. The assets
+/// are left sorted by name, for the binary searches in the `find_*` functions.
+fn mount_assets<D, A>(
+    assets: &mut FixedVec<'_, Option<NamedAsset<A>>>,
+    source: &DatabaseSource,
+    file_reader: &mut FileReader,
+    platform: &dyn Platform,
+) -> Result<(), ResourceDatabaseError>
+where
+    D: Deserialize,
+    NamedAsset<A>: From<D>,
+    A: Asset,
+{
+    let mounted = assets.len();
+    deserialize_each::<D>(file_reader, platform, |asset| {
+        let mut asset = NamedAsset::from(asset);
+        asset.asset.offset_chunks(source.chunks.start as i32);
+        (asset.asset).offset_sprite_chunks(source.sprite_chunks.start as i32);
+        let asset = Some(asset);
+        match assets[..mounted].binary_search(&asset) {
+            Ok(overridden) => assets[overridden] = asset,
+            Err(_) => {
+                let Ok(_) = assets.push(asset) else {
                     unreachable!()
                 };
             }
-            Some(vec)
-        })
-        .expect("resource db file header should be readable")
+        }
+    })?;
+    assets.sort_unstable();
+    Ok(())
+}
+
+/// Reads through the given range of the file, returning its [`Crc32`]
+/// checksum, or `None` if the file could not be read.
+fn read_checksum(
+    file_reader: &mut FileReader,
+    platform: &dyn Platform,
+    file: FileHandle,
+    bytes: Range<u64>,
+) -> Option<u32> {
+    profiling::function_scope!();
+    let max_read_size = file_reader.staging_buffer_size().min(CHUNK_SIZE as usize) as u64;
+    let mut crc = Crc32::new();
+    let mut cursor = bytes.start;
+    let mut reads_in_flight = 0;
+    while cursor < bytes.end || reads_in_flight > 0 {
+        // Keep as many reads queued up as possible to avoid waiting on each one
+        while cursor < bytes.end {
+            let size = (bytes.end - cursor).min(max_read_size);
+            if !file_reader.push_read(file, cursor, size as usize) {
+                break;
+            }
+            cursor += size;
+            reads_in_flight += 1;
+        }
+        file_reader.dispatch_reads(platform);
+        (file_reader.pop_read(platform, true, |bytes| crc.update(bytes))).ok()?;
+        reads_in_flight -= 1;
+    }
+    Some(crc.finish())
 }
 
 pub use named_asset::{NamedAsset, ASSET_NAME_LENGTH};
@@ -226,3 +1011,480 @@ mod named_asset {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use std::vec::Vec;
+
+    use arrayvec::{ArrayString, ArrayVec};
+    use platform::{Platform, TextureFilter};
+
+    use crate::{
+        allocators::{static_allocator, LinearAllocator},
+        test_platform::{audio_clip_database, engine_with_loaded_chunks, TestPlatform},
+        Engine, EngineError, EngineLimits,
+    };
+
+    use super::{
+        Asset, Crc32, Deserialize, DeserializeError, FileReader, NamedAsset, ReloadError,
+        ResourceDatabase, ResourceDatabaseError, ResourceDatabaseHeader, Serialize, SpriteAsset,
+        AUDIO_SAMPLES_PER_CHUNK, RESOURCE_DB_MIN_VERSION, RESOURCE_DB_VERSION,
+    };
+
+    fn serialized_header(
+        version: u32,
+    ) -> [u8; <ResourceDatabaseHeader as Serialize>::SERIALIZED_SIZE] {
+        let header = ResourceDatabaseHeader {
+            version,
+            chunks: 1,
+            sprite_chunks: 2,
+            sprites: 3,
+            audio_clips: 4,
+            fonts: 5,
+            animations: 6,
+            tilemaps: 7,
+            metadata_checksum: 8,
+            chunk_data_checksum: 9,
+        };
+        let mut bytes = [0; <ResourceDatabaseHeader as Serialize>::SERIALIZED_SIZE];
+        header.serialize(&mut bytes);
+        bytes
+    }
+
+    #[test]
+    fn header_with_another_version_is_rejected() {
+        let header =
+            ResourceDatabaseHeader::try_deserialize(&serialized_header(RESOURCE_DB_VERSION))
+                .unwrap();
+        assert_eq!(5, header.fonts);
+
+        let oldest_version = RESOURCE_DB_MIN_VERSION;
+        let header =
+            ResourceDatabaseHeader::try_deserialize(&serialized_header(oldest_version)).unwrap();
+        assert_eq!(oldest_version, header.version);
+
+        let old_version = RESOURCE_DB_MIN_VERSION - 1;
+        let result = ResourceDatabaseHeader::try_deserialize(&serialized_header(old_version));
+        assert_eq!(
+            Err(DeserializeError::VersionMismatch { found: old_version }),
+            result.map(|_| ()),
+        );
+
+        let new_version = RESOURCE_DB_VERSION + 1;
+        let result = ResourceDatabaseHeader::try_deserialize(&serialized_header(new_version));
+        assert_eq!(
+            Err(DeserializeError::VersionMismatch { found: new_version }),
+            result.map(|_| ()),
+        );
+    }
+
+    #[test]
+    fn version_2_sprites_are_filtered_linearly() {
+        static ARENA: &LinearAllocator = static_allocator!(8 * 1024 * 1024);
+        fn checksum(bytes: &[u8]) -> u32 {
+            let mut crc = Crc32::new();
+            crc.update(bytes);
+            crc.finish()
+        }
+
+        // Version 2 sprites are serialized like the current ones, just without
+        // the filter at the end.
+        let sprite = NamedAsset {
+            name: ArrayString::from("player").unwrap(),
+            asset: SpriteAsset {
+                transparent: false,
+                mip_chain: ArrayVec::new(),
+                filter: TextureFilter::NearestNeighbor,
+            },
+        };
+        let mut metadata = std::vec![0; <NamedAsset<SpriteAsset> as Serialize>::SERIALIZED_SIZE];
+        sprite.serialize(&mut metadata);
+        metadata.pop();
+
+        // Version 2 headers are missing the animation and tilemap counts,
+        // which are right before the checksums.
+        let header = ResourceDatabaseHeader {
+            version: 2,
+            chunks: 0,
+            sprite_chunks: 0,
+            sprites: 1,
+            audio_clips: 0,
+            fonts: 0,
+            animations: 0,
+            tilemaps: 0,
+            metadata_checksum: checksum(&metadata),
+            chunk_data_checksum: checksum(&[]),
+        };
+        let mut db = std::vec![0; <ResourceDatabaseHeader as Serialize>::SERIALIZED_SIZE];
+        header.serialize(&mut db);
+        let checksums_start = db.len() - 2 * <u32 as Serialize>::SERIALIZED_SIZE;
+        let counts_removed = db.len() - ResourceDatabaseHeader::size_for_version(2);
+        db.drain(checksums_start - counts_removed..checksums_start);
+        db.extend(metadata);
+
+        let platform = TestPlatform::new(false);
+        platform.add_file("v2.db", db);
+        let limits = EngineLimits {
+            resource_database_paths: &["v2.db"],
+            frame_arena_size: 256 * 1024,
+            resource_database_loaded_chunks_count: 4,
+            resource_database_buffer_size: 512 * 1024,
+            ..EngineLimits::DEFAULT
+        };
+        let engine = Engine::new(&platform, ARENA, limits);
+        let sprite = engine.resource_db.find_sprite("player").unwrap();
+        let sprite = engine.resource_db.get_sprite(sprite).unwrap();
+        assert_eq!(TextureFilter::Linear, sprite.filter);
+    }
+
+    #[test]
+    fn header_with_bad_magic_number_or_constants_is_rejected() {
+        let mut bytes = serialized_header(RESOURCE_DB_VERSION);
+        bytes[0] ^= 0xFF;
+        assert!(matches!(
+            ResourceDatabaseHeader::try_deserialize(&bytes),
+            Err(DeserializeError::InvalidMagicNumber { .. }),
+        ));
+
+        // The chunk size is right after the magic number and the version.
+        let mut bytes = serialized_header(RESOURCE_DB_VERSION);
+        bytes[8] ^= 0xFF;
+        assert!(matches!(
+            ResourceDatabaseHeader::try_deserialize(&bytes),
+            Err(DeserializeError::ConstantMismatch {
+                constant: "CHUNK_SIZE",
+                ..
+            }),
+        ));
+    }
+
+    #[test]
+    fn truncated_header_is_rejected() {
+        let bytes = serialized_header(RESOURCE_DB_VERSION);
+        let result = ResourceDatabaseHeader::try_deserialize(&bytes[..bytes.len() - 1]);
+        assert_eq!(
+            Err(DeserializeError::ShortBuffer {
+                len: bytes.len() - 1,
+                expected: bytes.len(),
+            }),
+            result.map(|_| ()),
+        );
+    }
+
+    #[test]
+    fn corrupted_database_is_rejected() {
+        static ARENA: &LinearAllocator = static_allocator!(16 * 1024 * 1024);
+        let platform = TestPlatform::new(false);
+        let load_db = |verify_chunk_data: bool| {
+            let file = platform.open_file("resources.db").unwrap();
+            let mut file_reader = FileReader::new(ARENA, 1024 * 1024, 16).unwrap();
+            let files = &[file];
+            ResourceDatabase::new(
+                &platform,
+                ARENA,
+                &mut file_reader,
+                files,
+                1,
+                1,
+                verify_chunk_data,
+            )
+            .map(|_| ())
+            .map_err(|(_, err)| err)
+        };
+        assert_eq!(Ok(()), load_db(true));
+
+        let header_size = <ResourceDatabaseHeader as Deserialize>::SERIALIZED_SIZE;
+        platform.resources_db_mut()[header_size] ^= 1;
+        assert_eq!(Err(ResourceDatabaseError::CorruptMetadata), load_db(true));
+        platform.resources_db_mut()[header_size] ^= 1;
+
+        *platform.resources_db_mut().last_mut().unwrap() ^= 1;
+        assert_eq!(Err(ResourceDatabaseError::CorruptChunkData), load_db(true));
+        assert_eq!(Ok(()), load_db(false));
+
+        platform.resources_db_mut().pop();
+        assert_eq!(Err(ResourceDatabaseError::CorruptChunkData), load_db(true));
+    }
+
+    #[test]
+    fn residency_is_the_fraction_of_loaded_chunks() {
+        static ARENA: &LinearAllocator = static_allocator!(8 * 1024 * 1024);
+        let platform = TestPlatform::new(false);
+        let (engine, sprite, chunks) = engine_with_loaded_chunks(&platform, ARENA, 0, 16);
+        assert_eq!(0.0, engine.resource_db.residency(sprite));
+
+        let loaded_count = chunks.len() as u32 / 2;
+        let (engine, _, _) = engine_with_loaded_chunks(&platform, ARENA, loaded_count, 16);
+        let expected = loaded_count as f32 / chunks.len() as f32;
+        assert_eq!(expected, engine.resource_db.residency(sprite));
+    }
+
+    #[test]
+    fn last_mounted_database_overrides_assets_with_the_same_name() {
+        static ARENA: &LinearAllocator = static_allocator!(8 * 1024 * 1024);
+        let platform = TestPlatform::new(false);
+        platform.add_file("override.db", audio_clip_database("whack", 1));
+        let limits = |resource_database_paths| EngineLimits {
+            resource_database_paths,
+            frame_arena_size: 256 * 1024,
+            resource_database_loaded_chunks_count: 4,
+            resource_database_buffer_size: 512 * 1024,
+            ..EngineLimits::DEFAULT
+        };
+
+        let mut engine = Engine::new(&platform, ARENA, limits(&["resources.db", "override.db"]));
+        let resources = &engine.resource_db;
+        let whack = resources.find_audio_clip("whack").unwrap();
+        let whack = resources.get_audio_clip(whack).unwrap();
+        assert_eq!(AUDIO_SAMPLES_PER_CHUNK as u32, whack.samples);
+        let chunk = whack.chunks.start;
+        // The base database's other assets are still there.
+        let player = resources.find_sprite("player").unwrap();
+        assert!(resources
+            .get_sprite(player)
+            .unwrap()
+            .get_sprite_chunks()
+            .is_some());
+
+        // The clip's chunk is read from the override database, where every
+        // sample is 1.
+        engine
+            .resource_loader
+            .queue_chunk(chunk, &engine.resource_db);
+        engine.resource_loader.dispatch_reads(&platform);
+        (engine.resource_loader).finish_reads(&mut engine.resource_db, &platform, 128);
+        let chunk = engine.resource_db.chunks.get(chunk).unwrap();
+        assert_eq!([1, 0, 1, 0], chunk.0[..4]);
+
+        // Mounted the other way around, the base database's clip wins.
+        let engine = Engine::new(&platform, ARENA, limits(&["override.db", "resources.db"]));
+        let resources = &engine.resource_db;
+        let whack = resources.find_audio_clip("whack").unwrap();
+        assert_ne!(
+            AUDIO_SAMPLES_PER_CHUNK as u32,
+            resources.get_audio_clip(whack).unwrap().samples,
+        );
+
+        // Each database is checked on its own, and missing ones are skipped.
+        let mut broken_db = audio_clip_database("whack", 1);
+        broken_db[4] ^= 0xFF; // the version, right after the magic number
+        platform.add_file("broken.db", broken_db);
+        let paths = &["missing.db", "resources.db", "broken.db"];
+        let result = Engine::try_new(&platform, ARENA, limits(paths));
+        assert!(matches!(
+            result.err(),
+            Some(EngineError::ResourceDatabase {
+                path: "broken.db",
+                error: ResourceDatabaseError::IncompatibleHeader(_),
+            }),
+        ));
+    }
+
+    #[test]
+    fn asset_names_are_listed_in_order() {
+        static ARENA: &LinearAllocator = static_allocator!(8 * 1024 * 1024);
+        let platform = TestPlatform::new(false);
+        platform.add_file("grass.db", audio_clip_database("step_grass", 1));
+        platform.add_file("stone.db", audio_clip_database("step_stone", 2));
+        let limits = EngineLimits {
+            resource_database_paths: &["stone.db", "resources.db", "grass.db"],
+            frame_arena_size: 256 * 1024,
+            resource_database_loaded_chunks_count: 4,
+            resource_database_buffer_size: 512 * 1024,
+            ..EngineLimits::DEFAULT
+        };
+        let engine = Engine::new(&platform, ARENA, limits);
+        let resources = &engine.resource_db;
+
+        let names = resources.audio_clip_names().collect::<Vec<_>>();
+        assert_eq!(["step_grass", "step_stone", "whack"], names[..]);
+        assert_eq!(["player"], resources.sprite_names().collect::<Vec<_>>()[..]);
+    }
+
+    #[test]
+    fn prefix_lookups_find_the_range_of_matching_names() {
+        static ARENA: &LinearAllocator = static_allocator!(8 * 1024 * 1024);
+        let platform = TestPlatform::new(false);
+        platform.add_file("grass.db", audio_clip_database("step_grass", 1));
+        platform.add_file("stone.db", audio_clip_database("step_stone", 1));
+        platform.add_file("a.db", audio_clip_database("a", 1));
+        let limits = EngineLimits {
+            resource_database_paths: &["resources.db", "grass.db", "stone.db", "a.db"],
+            frame_arena_size: 256 * 1024,
+            resource_database_loaded_chunks_count: 4,
+            resource_database_buffer_size: 512 * 1024,
+            ..EngineLimits::DEFAULT
+        };
+        let engine = Engine::new(&platform, ARENA, limits);
+        let resources = &engine.resource_db;
+        let names = resources.audio_clip_names().collect::<Vec<_>>();
+        assert_eq!(["a", "step_grass", "step_stone", "whack"], names[..]);
+        let find = |prefix: &str| {
+            (resources.find_audio_clips_with_prefix(prefix))
+                .map(|handle| names[handle.0])
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(["step_grass", "step_stone"], find("step_")[..]);
+        assert_eq!(["step_stone"], find("step_s")[..]);
+        assert!(find("steps").is_empty());
+        assert!(find("Step_").is_empty());
+        // The first and last names, and prefixes sorted before and after all
+        // of the names
+        assert_eq!(["a"], find("a")[..]);
+        assert_eq!(["whack"], find("whack")[..]);
+        assert!(find("whacked").is_empty());
+        assert!(find("0").is_empty());
+        assert!(find("x").is_empty());
+        // An empty prefix matches everything
+        assert_eq!(names, find(""));
+        let sprites = resources.find_sprites_with_prefix("").collect::<Vec<_>>();
+        assert_eq!(1, sprites.len());
+        assert!(resources.find_sprites_with_prefix("play").next().is_some());
+    }
+
+    #[test]
+    fn prefix_lookups_return_the_handles_of_the_matching_assets() {
+        static ARENA: &LinearAllocator = static_allocator!(8 * 1024 * 1024);
+        let platform = TestPlatform::new(false);
+        platform.add_file("grass.db", audio_clip_database("step_grass", 1));
+        platform.add_file("stone.db", audio_clip_database("step_stone", 2));
+        let limits = EngineLimits {
+            resource_database_paths: &["stone.db", "resources.db", "grass.db"],
+            frame_arena_size: 256 * 1024,
+            resource_database_loaded_chunks_count: 4,
+            resource_database_buffer_size: 512 * 1024,
+            ..EngineLimits::DEFAULT
+        };
+        let engine = Engine::new(&platform, ARENA, limits);
+        let resources = &engine.resource_db;
+
+        let steps = (resources.find_audio_clips_with_prefix("step_")).collect::<Vec<_>>();
+        let expected = [("step_grass", 1), ("step_stone", 2)];
+        assert_eq!(expected.len(), steps.len());
+        for (handle, (name, chunks)) in steps.into_iter().zip(expected) {
+            assert_eq!(Some(handle), resources.find_audio_clip(name));
+            let clip = resources.get_audio_clip(handle).unwrap();
+            assert_eq!(chunks, clip.chunks.len());
+        }
+        assert_eq!(1, resources.find_audio_clips_with_prefix("wh").count());
+        assert_eq!(0, resources.find_audio_clips_with_prefix("x").count());
+        assert_eq!(3, resources.find_audio_clips_with_prefix("").count());
+    }
+
+    #[test]
+    fn reloading_keeps_the_handles_of_assets_which_still_exist() {
+        static ARENA: &LinearAllocator = static_allocator!(4 * 1024 * 1024);
+        let platform = TestPlatform::new(false);
+        platform.add_file("steps.db", audio_clip_database("step_stone", 1));
+        let limits = EngineLimits {
+            resource_database_paths: &["resources.db", "steps.db"],
+            frame_arena_size: 256 * 1024,
+            resource_database_loaded_chunks_count: 4,
+            resource_database_buffer_size: 512 * 1024,
+            ..EngineLimits::DEFAULT
+        };
+        let mut engine = Engine::new(&platform, ARENA, limits);
+        let whack = engine.resource_db.find_audio_clip("whack").unwrap();
+        let stone = engine.resource_db.find_audio_clip("step_stone").unwrap();
+        let whack_chunk = engine
+            .resource_db
+            .get_audio_clip(whack)
+            .unwrap()
+            .chunks
+            .start;
+        let stone_chunk = engine
+            .resource_db
+            .get_audio_clip(stone)
+            .unwrap()
+            .chunks
+            .start;
+        assert!((engine.resource_loader).load_blocking(whack, &mut engine.resource_db, &platform));
+        // This read is still in flight when the database is reloaded.
+        (engine.resource_loader).queue_chunk(stone_chunk, &engine.resource_db);
+        engine.resource_loader.dispatch_reads(&platform);
+
+        // The stone steps are reimported as grass steps.
+        platform.add_file("steps.db", audio_clip_database("step_grass", 2));
+        engine.reload_resources(&platform).unwrap();
+        let resources = &engine.resource_db;
+        assert_eq!(Some(whack), resources.find_audio_clip("whack"));
+        assert_eq!(
+            whack_chunk,
+            resources.get_audio_clip(whack).unwrap().chunks.start
+        );
+        assert!(resources.get_audio_clip(stone).is_none());
+        assert!(resources.find_audio_clip("step_stone").is_none());
+        assert_eq!(1.0, resources.residency(stone));
+        let grass = resources.find_audio_clip("step_grass").unwrap();
+        assert_ne!(stone, grass);
+        let names = resources.audio_clip_names().collect::<Vec<_>>();
+        assert_eq!(["step_grass", "whack"], names[..]);
+
+        // The unchanged database's chunks stay loaded, the changed one's don't.
+        assert!(resources.chunks.is_loaded(whack_chunk));
+        let grass_chunks = resources.get_audio_clip(grass).unwrap().chunks.clone();
+        assert_eq!(2, grass_chunks.len());
+        assert!(grass_chunks
+            .clone()
+            .all(|chunk| !resources.chunks.is_loaded(chunk)));
+        assert!((engine.resource_loader).load_blocking(grass, &mut engine.resource_db, &platform));
+        let chunk = engine.resource_db.chunks.get(grass_chunks.start).unwrap();
+        assert_eq!([1, 0, 1, 0], chunk.0[..4]);
+
+        // A failed reload leaves the database as it was.
+        let mut broken_db = audio_clip_database("step_wood", 1);
+        broken_db[4] ^= 0xFF; // the version, right after the magic number
+        platform.add_file("steps.db", broken_db);
+        assert!(matches!(
+            engine.reload_resources(&platform),
+            Err(ReloadError::Database {
+                index: 1,
+                error: ResourceDatabaseError::IncompatibleHeader(_),
+            }),
+        ));
+        assert_eq!(
+            Some(grass),
+            engine.resource_db.find_audio_clip("step_grass")
+        );
+        assert!(engine.resource_db.chunks.is_loaded(grass_chunks.start));
+
+        // Reloading reuses the memory of the old metadata when it fits.
+        platform.add_file("steps.db", audio_clip_database("step_grass", 2));
+        let allocated = ARENA.allocated();
+        for _ in 0..3 {
+            engine.reload_resources(&platform).unwrap();
+        }
+        assert_eq!(allocated, ARENA.allocated());
+        assert_eq!(
+            Some(grass),
+            engine.resource_db.find_audio_clip("step_grass")
+        );
+    }
+
+    #[test]
+    fn acquired_sprite_chunks_are_not_evicted() {
+        static ARENA: &LinearAllocator = static_allocator!(8 * 1024 * 1024);
+        let platform = TestPlatform::new(false);
+        // Only the first chunk fits, and it's loaded
+        let (mut engine, sprite, chunks) = engine_with_loaded_chunks(&platform, ARENA, 1, 1);
+        let loaded_chunks = chunks.start..chunks.start + 1;
+        let unloaded_chunk = chunks.start + 1;
+
+        let guard = engine.resource_db.acquire_sprite(sprite);
+        engine.resource_db.increment_ages();
+        engine.resource_db.increment_ages();
+        let sprite_chunks = &mut engine.resource_db.sprite_chunks;
+        assert!(sprite_chunks.insert(unloaded_chunk, || None).is_none());
+        for chunk in loaded_chunks {
+            assert!(engine.resource_db.sprite_chunks.get(chunk).is_some());
+        }
+
+        drop(guard);
+        engine.resource_db.increment_ages();
+        let sprite_chunks = &mut engine.resource_db.sprite_chunks;
+        assert!(sprite_chunks.insert(unloaded_chunk, || None).is_some());
+    }
+}
@@ -0,0 +1,161 @@
+// SPDX-FileCopyrightText: 2025 Jens Pitkänen <jens.pitkanen@helsinki.fi>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use core::ops::Range;
+
+use crate::{allocators::LinearAllocator, collections::FixedVec};
+
+use super::{NamedAsset, Reallocation};
+
+/// The metadata of all the assets of one type in a
+/// [`ResourceDatabase`](super::ResourceDatabase).
+///
+/// The assets' handles are indices into `assets`, and stay the same across
+/// [`ResourceDatabase::reload`](super::ResourceDatabase::reload)s for assets
+/// which still exist with the same name. Assets removed in a reload are left in
+/// place as `None`, and new assets are added to the end. `by_name` contains the
+/// indices of the existing assets sorted by the assets' names, for looking them
+/// up by name.
+pub(crate) struct AssetTable<T: 'static> {
+    assets: FixedVec<'static, Option<NamedAsset<T>>>,
+    by_name: FixedVec<'static, u32>,
+}
+
+impl<T: 'static> AssetTable<T> {
+    /// Creates a table of `assets`, which must all be `Some` and sorted by
+    /// name. The handle of each asset is its index in `assets`.
+    pub fn new(
+        arena: &'static LinearAllocator,
+        assets: FixedVec<'static, Option<NamedAsset<T>>>,
+    ) -> Option<AssetTable<T>> {
+        let mut by_name = FixedVec::new(arena, assets.len())?;
+        for i in 0..assets.len() as u32 {
+            by_name.push(i).unwrap();
+        }
+        Some(AssetTable { assets, by_name })
+    }
+
+    /// Finds the handles for the `new_assets`, which must all be `Some` and
+    /// sorted by name, and allocates the memory needed for replacing this
+    /// table's assets with them in [`AssetTable::reload`]. Each asset gets the
+    /// same handle it has in this table if this table has an asset with the
+    /// same name. The assets which don't exist in this table are given new
+    /// handles.
+    ///
+    /// The handles are allocated from `scratch_arena`. If the reloaded table
+    /// doesn't fit in this table's memory, more is allocated from `arena`.
+    pub fn prepare_reload<'s>(
+        &self,
+        arena: &'static LinearAllocator,
+        scratch_arena: &'s LinearAllocator,
+        new_assets: &[Option<NamedAsset<T>>],
+    ) -> Option<AssetTableReload<'s, T>> {
+        let mut handles = FixedVec::new(scratch_arena, new_assets.len())?;
+        let mut handle_count = self.assets.len();
+        for new_asset in new_assets {
+            let handle = self.find(name_of(new_asset)).unwrap_or_else(|| {
+                handle_count += 1;
+                handle_count - 1
+            });
+            handles.push(handle as u32).unwrap();
+        }
+        Some(AssetTableReload {
+            assets: Reallocation::new(arena, &self.assets, handle_count)?,
+            by_name: Reallocation::new(arena, &self.by_name, new_assets.len())?,
+            handles,
+            handle_count,
+        })
+    }
+
+    /// Replaces the assets of this table with the `new_assets`, using the
+    /// handles and memory from [`AssetTable::prepare_reload`]. The assets are
+    /// moved out of `new_assets`.
+    pub fn reload(
+        &mut self,
+        reload: AssetTableReload<'_, T>,
+        new_assets: &mut [Option<NamedAsset<T>>],
+    ) {
+        let AssetTableReload {
+            assets,
+            by_name,
+            handles,
+            handle_count,
+        } = reload;
+        assets.fill(&mut self.assets, (0..handle_count).map(|_| None));
+        for (&handle, new_asset) in handles.iter().zip(new_assets) {
+            self.assets[handle as usize] = new_asset.take();
+        }
+        by_name.fill(&mut self.by_name, handles.iter().copied());
+    }
+
+    /// Returns the amount of handles in this table, including the ones of
+    /// removed assets.
+    pub fn handle_count(&self) -> usize {
+        self.assets.len()
+    }
+
+    /// Returns the asset with the given handle, if it hasn't been removed.
+    pub fn get(&self, index: usize) -> Option<&NamedAsset<T>> {
+        self.assets.get(index)?.as_ref()
+    }
+
+    /// Returns the handle of the asset with the given name.
+    pub fn find(&self, name: &str) -> Option<usize> {
+        let i = (self.by_name)
+            .binary_search_by(|&index| self.name(index).cmp(name))
+            .ok()?;
+        Some(self.by_name[i] as usize)
+    }
+
+    /// Returns the existing assets and their handles, sorted by name.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &NamedAsset<T>)> {
+        (self.by_name.iter()).map(|&index| {
+            let asset = &self.assets[index as usize];
+            (index as usize, asset.as_ref().unwrap())
+        })
+    }
+
+    /// Returns the handles of the assets whose names start with `prefix`,
+    /// sorted by name.
+    pub fn find_prefix(&self, prefix: &str) -> impl Iterator<Item = usize> + '_ {
+        let range = self.prefix_range(prefix);
+        self.by_name[range].iter().map(|&index| index as usize)
+    }
+
+    fn prefix_range(&self, prefix: &str) -> Range<usize> {
+        let start = (self.by_name).partition_point(|&index| self.name(index) < prefix);
+        // The names starting with the prefix are all right after the names
+        // sorted before the prefix.
+        let len =
+            self.by_name[start..].partition_point(|&index| self.name(index).starts_with(prefix));
+        start..start + len
+    }
+
+    fn name(&self, index: u32) -> &str {
+        name_of(&self.assets[index as usize])
+    }
+}
+
+/// A reload of an [`AssetTable`], prepared with [`AssetTable::prepare_reload`]
+/// before modifying the table.
+pub(crate) struct AssetTableReload<'s, T: 'static> {
+    assets: Reallocation<Option<NamedAsset<T>>>,
+    by_name: Reallocation<u32>,
+    /// The handles of the new assets, in the same order as the new assets.
+    handles: FixedVec<'s, u32>,
+    handle_count: usize,
+}
+
+impl<T> AssetTableReload<'_, T> {
+    /// Returns the amount of handles the table will have after the reload,
+    /// including the ones of removed assets.
+    pub fn handle_count(&self) -> usize {
+        self.handle_count
+    }
+}
+
+fn name_of<T>(asset: &Option<NamedAsset<T>>) -> &str {
+    // by_name only contains the indices of existing assets
+    asset.as_ref().unwrap().name.as_str()
+}
@@ -2,13 +2,26 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+pub mod animation;
 pub mod audio_clip;
+pub mod font;
 pub mod sprite;
+pub mod tilemap;
 
 use core::ops::Range;
 
+use super::ResourceDatabase;
+
 macro_rules! gen_asset_handle_code {
-    ($asset_type:ident, $handle_name:ident, $find_fn:ident, $get_fn:ident, $field:ident) => {
+    (
+        $asset_type:ident,
+        $handle_name:ident,
+        $find_fn:ident,
+        $get_fn:ident,
+        $names_fn:ident,
+        $find_prefix_fn:ident,
+        $field:ident
+    ) => {
         pub use handle_impl::$handle_name;
         mod handle_impl {
             #[allow(unused_imports)] // used by docs
@@ -22,9 +35,17 @@ macro_rules! gen_asset_handle_code {
             #[doc = concat!("ResourceDatabase::", stringify!($find_fn))]
             #[doc = "`], and can be resolved into a borrow of the asset itself with [`"]
             #[doc = concat!("ResourceDatabase::", stringify!($get_fn))]
-            #[doc = "`]."]
-            #[derive(Clone, Copy, Debug)]
-            pub struct $handle_name(usize);
+            #[doc = "`].\n\nHandles stay valid across [`ResourceDatabase::reload`]s, as long as the database still has an asset with the same name."]
+            #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+            pub struct $handle_name(pub(crate) usize);
+            impl $crate::resources::AssetHandle for $handle_name {
+                fn get_asset(
+                    self,
+                    resources: &ResourceDatabase,
+                ) -> Option<&dyn $crate::resources::Asset> {
+                    Some(resources.$get_fn(self)?)
+                }
+            }
             impl $crate::resources::ResourceDatabase {
                 #[doc = "Returns a [`"]
                 #[doc = stringify!($handle_name)]
@@ -35,22 +56,37 @@ macro_rules! gen_asset_handle_code {
                 #[doc = "`] to access the actual asset at runtime."]
                 pub fn $find_fn(&self, name: &str) -> Option<$handle_name> {
                     profiling::function_scope!();
-                    let Ok(index) = self
-                        .$field
-                        .binary_search_by(|asset| asset.name.as_str().cmp(name))
-                    else {
-                        return None;
-                    };
-                    Some($handle_name(index))
+                    Some($handle_name(self.$field.find(name)?))
                 }
 
                 #[doc = "Returns the [`"]
                 #[doc = stringify!($asset_type)]
                 #[doc = "`] behind a handle previously queried with [`"]
                 #[doc = concat!("ResourceDatabase::", stringify!($find_fn))]
-                #[doc = "`]. Note that reusing handles between separate [`ResourceDatabase`]s will cause panics."]
-                pub fn $get_fn(&self, handle: $handle_name) -> &$asset_type {
-                    &self.$field[handle.0].asset
+                #[doc = "`], or `None` if the asset has been removed by a [`ResourceDatabase::reload`]. Note that reusing handles between separate [`ResourceDatabase`]s will return the wrong assets, or `None`."]
+                pub fn $get_fn(&self, handle: $handle_name) -> Option<&$asset_type> {
+                    Some(&self.$field.get(handle.0)?.asset)
+                }
+
+                #[doc = "Returns the names of all the [`"]
+                #[doc = stringify!($asset_type)]
+                #[doc = "`]s in the database, in lexicographic order (comparing the bytes of the names)."]
+                pub fn $names_fn(&self) -> impl Iterator<Item = &str> {
+                    self.$field.iter().map(|(_, asset)| asset.name.as_str())
+                }
+
+                #[doc = "Returns [`"]
+                #[doc = stringify!($handle_name)]
+                #[doc = "`]s for all the [`"]
+                #[doc = stringify!($asset_type)]
+                #[doc = "`]s whose names start with `prefix`, in the same order as [`"]
+                #[doc = concat!("ResourceDatabase::", stringify!($names_fn))]
+                #[doc = "`]. An empty prefix matches every asset.\n\nUnlike [`"]
+                #[doc = concat!("ResourceDatabase::", stringify!($find_fn))]
+                #[doc = "`], this doesn't require the whole name to match, which is useful for e.g. finding all the assets of a category, like \"ui/button\" finding \"ui/button_hover\". Like the exact lookup, the prefix is matched case-sensitively. The matching assets are found with binary searches, so this is cheap regardless of the amount of assets."]
+                pub fn $find_prefix_fn(&self, prefix: &str) -> impl Iterator<Item = $handle_name> + '_ {
+                    profiling::function_scope!();
+                    self.$field.find_prefix(prefix).map($handle_name)
                 }
             }
         }
@@ -72,3 +108,11 @@ pub trait Asset {
     /// Applies an offset to all sprite chunk references in the asset.
     fn offset_sprite_chunks(&mut self, offset: i32);
 }
+
+/// Trait for the handles of any assets, for writing asset management code
+/// which is generic over the particular asset type.
+pub trait AssetHandle: Copy {
+    /// Returns the asset behind this handle, or `None` if the asset has been
+    /// removed by a [`ResourceDatabase::reload`].
+    fn get_asset(self, resources: &ResourceDatabase) -> Option<&dyn Asset>;
+}
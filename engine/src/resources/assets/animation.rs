@@ -0,0 +1,78 @@
+// SPDX-FileCopyrightText: 2025 Jens Pitkänen <jens.pitkanen@helsinki.fi>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Asset type for frame-based animations, cut from a sprite sheet.
+
+use core::ops::Range;
+
+use arrayvec::ArrayVec;
+
+use super::{gen_asset_handle_code, sprite::SpriteAsset, Asset};
+
+gen_asset_handle_code!(
+    AnimationAsset,
+    AnimationHandle,
+    find_animation,
+    get_animation,
+    animation_names,
+    find_animations_with_prefix,
+    animations
+);
+
+/// The maximum amount of frames in an [`AnimationAsset`].
+pub const MAX_FRAMES: usize = 64;
+
+/// One frame of an [`AnimationAsset`]: a region of the sprite sheet, and how
+/// long it's shown for.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AnimationFrame {
+    /// The top-left corner of the frame within the sprite sheet, in pixels.
+    pub offset: (u16, u16),
+    /// The width and height of the frame in pixels.
+    pub size: (u16, u16),
+    /// How long the frame is shown for, in milliseconds.
+    pub duration_ms: u32,
+}
+
+/// What happens when an [`AnimationAsset`] reaches the end of its last frame.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AnimationMode {
+    /// The animation starts over from the first frame.
+    #[default]
+    Loop,
+    /// The animation stops on the last frame.
+    Once,
+}
+
+/// Sequence of frames cut from a sprite sheet.
+///
+/// Played back with
+/// [`AnimationPlayer`](crate::renderer::animation::AnimationPlayer).
+#[derive(Debug)]
+pub struct AnimationAsset {
+    /// The sprite sheet containing all the frames.
+    pub sheet: SpriteAsset,
+    /// The frames of the animation, in playback order.
+    pub frames: ArrayVec<AnimationFrame, MAX_FRAMES>,
+    /// Whether the animation loops or stops on the last frame.
+    pub mode: AnimationMode,
+}
+
+impl Asset for AnimationAsset {
+    fn get_chunks(&self) -> Option<Range<u32>> {
+        self.sheet.get_chunks()
+    }
+
+    fn offset_chunks(&mut self, offset: i32) {
+        self.sheet.offset_chunks(offset);
+    }
+
+    fn get_sprite_chunks(&self) -> Option<Range<u32>> {
+        self.sheet.get_sprite_chunks()
+    }
+
+    fn offset_sprite_chunks(&mut self, offset: i32) {
+        self.sheet.offset_sprite_chunks(offset);
+    }
+}
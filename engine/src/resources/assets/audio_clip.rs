@@ -13,6 +13,8 @@ gen_asset_handle_code!(
     AudioClipHandle,
     find_audio_clip,
     get_audio_clip,
+    audio_clip_names,
+    find_audio_clips_with_prefix,
     audio_clips
 );
 
@@ -0,0 +1,94 @@
+// SPDX-FileCopyrightText: 2025 Jens Pitkänen <jens.pitkanen@helsinki.fi>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Asset type for bitmap fonts, rasterized at a specific size at import time.
+
+use core::ops::Range;
+
+use arrayvec::ArrayVec;
+
+use super::{gen_asset_handle_code, Asset};
+
+gen_asset_handle_code!(
+    FontAsset,
+    FontHandle,
+    find_font,
+    get_font,
+    font_names,
+    find_fonts_with_prefix,
+    fonts
+);
+
+/// The first character included in each [`FontAsset`].
+pub const FIRST_GLYPH: char = ' ';
+
+/// The maximum amount of glyphs in a [`FontAsset`]. The glyphs are the
+/// printable ASCII characters, starting from [`FIRST_GLYPH`] and ending with
+/// `~`.
+pub const MAX_GLYPHS: usize = 95;
+
+/// The metrics and atlas location of a single character in a [`FontAsset`].
+///
+/// All the measurements are in pixels, at the size the font was rasterized at.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FontGlyph {
+    /// The sprite chunk containing the glyph's pixels.
+    pub sprite_chunk: u32,
+    /// The top-left corner of the glyph's pixels within the sprite chunk.
+    pub uv_offset: (u16, u16),
+    /// The width and height of the glyph's pixels within the sprite chunk. Zero
+    /// for glyphs without any visible pixels, e.g. space.
+    pub size: (u16, u16),
+    /// The offset from the pen position (on the top edge of the line) to the
+    /// top-left corner of the glyph.
+    pub bearing: (i16, i16),
+    /// How far the pen should move to the right after drawing this glyph.
+    pub advance: u16,
+}
+
+/// Drawable text style, a sprite atlas of glyphs and their metrics.
+///
+/// Drawn with [`DrawQueue::draw_text`](crate::renderer::DrawQueue::draw_text).
+#[derive(Debug)]
+pub struct FontAsset {
+    /// The distance between the top edges of two successive lines of text.
+    pub line_height: u16,
+    /// The sprite chunks containing the glyph atlas.
+    pub sprite_chunks: Range<u32>,
+    /// The glyphs of the font, the first one being for [`FIRST_GLYPH`] and the
+    /// rest following in order of their ASCII code.
+    pub glyphs: ArrayVec<FontGlyph, MAX_GLYPHS>,
+}
+
+impl FontAsset {
+    /// Returns the glyph for the character, if the font has one.
+    pub fn get_glyph(&self, c: char) -> Option<&FontGlyph> {
+        let index = (c as u32).checked_sub(FIRST_GLYPH as u32)?;
+        self.glyphs.get(index as usize)
+    }
+}
+
+impl Asset for FontAsset {
+    fn get_chunks(&self) -> Option<Range<u32>> {
+        None
+    }
+
+    fn offset_chunks(&mut self, _offset: i32) {}
+
+    fn get_sprite_chunks(&self) -> Option<Range<u32>> {
+        if self.sprite_chunks.is_empty() {
+            None
+        } else {
+            Some(self.sprite_chunks.clone())
+        }
+    }
+
+    fn offset_sprite_chunks(&mut self, offset: i32) {
+        self.sprite_chunks.start = (self.sprite_chunks.start as i32 + offset) as u32;
+        self.sprite_chunks.end = (self.sprite_chunks.end as i32 + offset) as u32;
+        for glyph in &mut self.glyphs {
+            glyph.sprite_chunk = (glyph.sprite_chunk as i32 + offset) as u32;
+        }
+    }
+}
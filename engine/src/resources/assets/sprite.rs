@@ -9,10 +9,20 @@
 use core::ops::Range;
 
 use arrayvec::ArrayVec;
+use platform::TextureFilter;
 
 use super::{gen_asset_handle_code, Asset};
+use crate::resources::NamedAsset;
 
-gen_asset_handle_code!(SpriteAsset, SpriteHandle, find_sprite, get_sprite, sprites);
+gen_asset_handle_code!(
+    SpriteAsset,
+    SpriteHandle,
+    find_sprite,
+    get_sprite,
+    sprite_names,
+    find_sprites_with_prefix,
+    sprites
+);
 
 /// The maximum amount of mip levels for a sprite.
 pub const MAX_MIPS: usize = 12;
@@ -25,7 +35,8 @@ pub const MAX_MIPS: usize = 12;
 /// having half the width and height of the previous mip.
 ///
 /// The sprites do not use hardware mipmapping. Multiple mip levels (especially
-/// the smaller ones) can share a single sprite chunk.
+/// the smaller ones) can share a single sprite chunk, as can multiple sprites
+/// if they were imported as a sprite atlas.
 #[derive(Debug)]
 pub enum SpriteMipLevel {
     /// A sprite contained within a single chunk. Unlike the other variant,
@@ -68,6 +79,29 @@ pub struct SpriteAsset {
     /// The actual specific-size sprites used for rendering depending on the
     /// size of the sprite on screen.
     pub mip_chain: ArrayVec<SpriteMipLevel, MAX_MIPS>,
+    /// The filtering used when the sprite is drawn at a different size than
+    /// its mip levels, e.g. [`TextureFilter::NearestNeighbor`] for keeping
+    /// pixel art sharp. Can be overridden per draw with
+    /// [`SpriteDrawOptions::filter`](crate::renderer::sprite::SpriteDrawOptions::filter).
+    pub filter: TextureFilter,
+}
+
+/// A [`SpriteAsset`] as stored in version 2 resource databases, which did not
+/// have [`SpriteAsset::filter`] yet.
+///
+/// Only used for reading older databases: the sprites get
+/// [`TextureFilter::Linear`], which is what all sprites were drawn with before
+/// the filter was configurable.
+#[derive(Debug)]
+pub struct SpriteAssetV2(pub SpriteAsset);
+
+impl From<NamedAsset<SpriteAssetV2>> for NamedAsset<SpriteAsset> {
+    fn from(NamedAsset { name, asset }: NamedAsset<SpriteAssetV2>) -> Self {
+        NamedAsset {
+            name,
+            asset: asset.0,
+        }
+    }
 }
 
 impl Asset for SpriteAsset {
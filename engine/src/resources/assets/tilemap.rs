@@ -0,0 +1,74 @@
+// SPDX-FileCopyrightText: 2025 Jens Pitkänen <jens.pitkanen@helsinki.fi>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Asset type for grids of tiles drawn from a tileset sprite.
+
+use core::ops::Range;
+
+use super::{gen_asset_handle_code, Asset};
+
+gen_asset_handle_code!(
+    TilemapAsset,
+    TilemapHandle,
+    find_tilemap,
+    get_tilemap,
+    tilemap_names,
+    find_tilemaps_with_prefix,
+    tilemaps
+);
+
+/// The tile index used for cells of a [`TilemapAsset`] without a tile.
+pub const EMPTY_TILE: u16 = 0;
+
+/// How the tiles are laid out in the tileset sprite of a [`TilemapAsset`].
+///
+/// Matches the tileset properties of the [Tiled](https://www.mapeditor.org/)
+/// map editor.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TilesetLayout {
+    /// The width and height of each tile, in pixels.
+    pub tile_size: (u16, u16),
+    /// The amount of pixels between the edges of the sprite and the tiles.
+    pub margin: u16,
+    /// The amount of pixels between adjacent tiles.
+    pub spacing: u16,
+    /// The amount of tiles on each row of the tileset.
+    pub columns: u16,
+}
+
+/// Grid of tiles, each cell referring to a tile in a tileset sprite.
+///
+/// The tiles themselves are stored in regular chunks, as [`u16`] tile indices
+/// in little-endian byte order, row by row. Index [`EMPTY_TILE`] is an empty
+/// cell, and index `n` above that refers to the `n`th tile of the tileset,
+/// counting from 1, row by row. Drawn with
+/// [`DrawQueue::draw_tilemap`](crate::renderer::DrawQueue::draw_tilemap).
+#[derive(Debug)]
+pub struct TilemapAsset {
+    /// The width of the map, in tiles.
+    pub width: u16,
+    /// The height of the map, in tiles.
+    pub height: u16,
+    /// The layout of the tileset sprite the tiles refer to.
+    pub tileset: TilesetLayout,
+    /// The chunks containing the tile indices.
+    pub chunks: Range<u32>,
+}
+
+impl Asset for TilemapAsset {
+    fn get_chunks(&self) -> Option<Range<u32>> {
+        Some(self.chunks.clone())
+    }
+
+    fn offset_chunks(&mut self, offset: i32) {
+        self.chunks.start = (self.chunks.start as i32 + offset) as u32;
+        self.chunks.end = (self.chunks.end as i32 + offset) as u32;
+    }
+
+    fn get_sprite_chunks(&self) -> Option<Range<u32>> {
+        None
+    }
+
+    fn offset_sprite_chunks(&mut self, _offset: i32) {}
+}
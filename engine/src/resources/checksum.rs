@@ -0,0 +1,89 @@
+// SPDX-FileCopyrightText: 2025 Jens Pitkänen <jens.pitkanen@helsinki.fi>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use core::hash::Hasher;
+
+/// Lookup table for [`Crc32::update`], for processing a byte at a time.
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB88320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// Hasher for the CRC-32 checksums stored in [`ResourceDatabaseHeader`].
+///
+/// This is the same CRC-32 variant as the one used by e.g. zlib and PNG, so the
+/// checksums can be verified with common tools.
+///
+/// [`ResourceDatabaseHeader`]: super::ResourceDatabaseHeader
+#[derive(Clone, Copy)]
+pub struct Crc32 {
+    state: u32,
+}
+
+impl Default for Crc32 {
+    fn default() -> Self {
+        Crc32::new()
+    }
+}
+
+impl Crc32 {
+    /// Creates a new hasher, which will return the checksum of an empty byte
+    /// slice if [`Crc32::finish`] is called right away.
+    pub const fn new() -> Crc32 {
+        Crc32 { state: 0xFFFFFFFF }
+    }
+
+    /// Feeds the bytes into the checksum. Calling this multiple times is
+    /// equivalent to calling it once with all the bytes concatenated.
+    pub fn update(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.state =
+                CRC32_TABLE[((self.state ^ *byte as u32) & 0xFF) as usize] ^ (self.state >> 8);
+        }
+    }
+
+    /// Returns the checksum of all the bytes passed into [`Crc32::update`].
+    pub fn finish(&self) -> u32 {
+        !self.state
+    }
+}
+
+impl Hasher for Crc32 {
+    fn finish(&self) -> u64 {
+        Crc32::finish(self) as u64
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.update(bytes);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Crc32;
+
+    #[test]
+    fn matches_reference_checksum() {
+        let mut crc = Crc32::new();
+        assert_eq!(0, crc.finish());
+        crc.update(b"1234");
+        crc.update(b"56789");
+        assert_eq!(0xCBF43926, crc.finish());
+    }
+}
@@ -41,6 +41,7 @@ impl ChunkData {
     /// Replaces the chunk contents with the given buffer, based on the
     /// [`ChunkDescriptor`] metadata.
     pub fn update(&mut self, descriptor: &ChunkDescriptor, buffer: &[u8]) {
+        profiling::function_scope!();
         let len = (descriptor.source_bytes.end - descriptor.source_bytes.start) as usize;
         self.0[..len].copy_from_slice(buffer);
     }
@@ -68,6 +69,7 @@ impl SpriteChunkData {
         buffer: &[u8],
         platform: &dyn Platform,
     ) {
+        profiling::function_scope!();
         platform.update_sprite(
             self.0,
             0,
@@ -5,22 +5,45 @@
 use core::{ops::Range, str};
 
 use arrayvec::{ArrayString, ArrayVec};
+use platform::TextureFilter;
 
 use super::{
+    animation::{AnimationAsset, AnimationFrame, AnimationMode, MAX_FRAMES},
     audio_clip::AudioClipAsset,
     chunks::{ChunkDescriptor, SpriteChunkDescriptor},
-    sprite::{SpriteAsset, SpriteMipLevel, MAX_MIPS},
-    NamedAsset, ResourceDatabaseHeader, ASSET_NAME_LENGTH,
+    font::{FontAsset, FontGlyph, MAX_GLYPHS},
+    serialize::string_length_prefix_size,
+    sprite::{SpriteAsset, SpriteAssetV2, SpriteMipLevel, MAX_MIPS},
+    tilemap::{TilemapAsset, TilesetLayout},
+    DeserializeError, NamedAsset, ResourceDatabaseHeader, ASSET_NAME_LENGTH,
+    RESOURCE_DB_MAGIC_NUMBER, RESOURCE_DB_MIN_VERSION, RESOURCE_DB_VERSION,
 };
 
 /// Trait for describing how a type can be parsed from a constant-size byte
 /// slice.
-pub trait Deserialize {
+pub trait Deserialize: Sized {
     /// The length of the buffer passed into [`Deserialize::deserialize`].
     const SERIALIZED_SIZE: usize;
     /// Deserializes the byte buffer into the struct. The length of `src` must
     /// match the same type's [`Deserialize::SERIALIZED_SIZE`] constant.
+    ///
+    /// ### Panics
+    ///
+    /// Panics if `src` is the wrong length, or does not contain a valid
+    /// serialized value. Use [`Deserialize::try_deserialize`] for data which
+    /// might not be valid.
     fn deserialize(src: &[u8]) -> Self;
+    /// Deserializes the byte buffer into the struct, returning an error if the
+    /// buffer is too short or does not contain a valid serialized value.
+    ///
+    /// Only the first [`Deserialize::SERIALIZED_SIZE`] bytes of `src` are read,
+    /// so this can be passed e.g. the whole rest of a file.
+    ///
+    /// The default implementation only checks the length, which is enough for
+    /// types that are valid for any bytes.
+    fn try_deserialize(src: &[u8]) -> Result<Self, DeserializeError> {
+        Ok(Self::deserialize(serialized_bytes::<Self>(src)?))
+    }
 }
 
 impl Deserialize for ChunkDescriptor {
@@ -49,37 +72,104 @@ impl Deserialize for SpriteChunkDescriptor {
 }
 
 impl Deserialize for ResourceDatabaseHeader {
-    const SERIALIZED_SIZE: usize = 18 + u32::SERIALIZED_SIZE * 4;
+    const SERIALIZED_SIZE: usize = 22 + u32::SERIALIZED_SIZE * 9;
     fn deserialize(src: &[u8]) -> Self {
         assert_eq!(Self::SERIALIZED_SIZE, src.len());
+        match Self::try_deserialize(src) {
+            Ok(header) => header,
+            Err(err) => panic!("invalid resource database header: {err}"),
+        }
+    }
+
+    /// Deserializes the header, also checking that the database is compatible
+    /// with this version of the engine.
+    fn try_deserialize(src: &[u8]) -> Result<Self, DeserializeError> {
+        use DeserializeError as Error;
+
         let mut cursor = 0;
 
+        let magic = try_deserialize::<u32>(src, &mut cursor)?;
+        if magic != RESOURCE_DB_MAGIC_NUMBER {
+            return Err(Error::InvalidMagicNumber { found: magic });
+        }
+        let version = try_deserialize::<u32>(src, &mut cursor)?;
+        if !(RESOURCE_DB_MIN_VERSION..=RESOURCE_DB_VERSION).contains(&version) {
+            return Err(Error::VersionMismatch { found: version });
+        }
+
+        // Older versions' headers are shorter, see
+        // ResourceDatabaseHeader::size_for_version.
+        let size = ResourceDatabaseHeader::size_for_version(version);
+        let src = src.get(..size).ok_or(Error::ShortBuffer {
+            len: src.len(),
+            expected: size,
+        })?;
+
         {
             use super::*;
             use platform::*;
 
-            let magic = deserialize::<u32>(src, &mut cursor);
-            assert_eq!(RESOURCE_DB_MAGIC_NUMBER, magic);
+            let check = |constant: &'static str, found: u32, expected: u32| {
+                if found == expected {
+                    Ok(())
+                } else {
+                    Err(Error::ConstantMismatch {
+                        constant,
+                        found,
+                        expected,
+                    })
+                }
+            };
+
             let chunk_size = deserialize::<u32>(src, &mut cursor);
-            assert_eq!(CHUNK_SIZE, chunk_size);
-            let sprite_chunk_width = deserialize::<u16>(src, &mut cursor);
-            assert_eq!(SPRITE_CHUNK_DIMENSIONS.0, sprite_chunk_width);
-            let sprite_chunk_height = deserialize::<u16>(src, &mut cursor);
-            assert_eq!(SPRITE_CHUNK_DIMENSIONS.1, sprite_chunk_height);
-            let sprite_chunk_format = deserialize::<u8>(src, &mut cursor);
-            assert_eq!(SPRITE_CHUNK_FORMAT as u8, sprite_chunk_format);
+            check("CHUNK_SIZE", chunk_size, CHUNK_SIZE)?;
+            let sprite_chunk_width = deserialize::<u16>(src, &mut cursor) as u32;
+            let sprite_chunk_height = deserialize::<u16>(src, &mut cursor) as u32;
+            let (expected_width, expected_height) = SPRITE_CHUNK_DIMENSIONS;
+            check(
+                "SPRITE_CHUNK_DIMENSIONS.0",
+                sprite_chunk_width,
+                expected_width as u32,
+            )?;
+            check(
+                "SPRITE_CHUNK_DIMENSIONS.1",
+                sprite_chunk_height,
+                expected_height as u32,
+            )?;
+            let sprite_chunk_format = deserialize::<u8>(src, &mut cursor) as u32;
+            check(
+                "SPRITE_CHUNK_FORMAT",
+                sprite_chunk_format,
+                SPRITE_CHUNK_FORMAT as u32,
+            )?;
             let audio_sample_rate = deserialize::<u32>(src, &mut cursor);
-            assert_eq!(AUDIO_SAMPLE_RATE, audio_sample_rate);
-            let audio_channels = deserialize::<u8>(src, &mut cursor);
-            assert_eq!((AUDIO_CHANNELS as u8), audio_channels);
+            check("AUDIO_SAMPLE_RATE", audio_sample_rate, AUDIO_SAMPLE_RATE)?;
+            // Audio clips are always stored in stereo, even if they're
+            // downmixed to mono for playback (see platform::AudioOutput).
+            let audio_channels = deserialize::<u8>(src, &mut cursor) as u32;
+            check("AUDIO_CHANNELS", audio_channels, AUDIO_CHANNELS as u32)?;
         }
 
-        Self {
+        Ok(Self {
+            version,
             chunks: deserialize::<u32>(src, &mut cursor),
             sprite_chunks: deserialize::<u32>(src, &mut cursor),
             sprites: deserialize::<u32>(src, &mut cursor),
             audio_clips: deserialize::<u32>(src, &mut cursor),
-        }
+            fonts: deserialize::<u32>(src, &mut cursor),
+            animations: if version < 4 {
+                0
+            } else {
+                deserialize::<u32>(src, &mut cursor)
+            },
+            tilemaps: if version < 5 {
+                0
+            } else {
+                deserialize::<u32>(src, &mut cursor)
+            },
+            metadata_checksum: deserialize::<u32>(src, &mut cursor),
+            chunk_data_checksum: deserialize::<u32>(src, &mut cursor),
+        })
     }
 }
 
@@ -88,12 +178,19 @@ impl<D: Deserialize> Deserialize for NamedAsset<D> {
         <ArrayString<ASSET_NAME_LENGTH> as Deserialize>::SERIALIZED_SIZE + D::SERIALIZED_SIZE;
     fn deserialize(src: &[u8]) -> Self {
         assert_eq!(Self::SERIALIZED_SIZE, src.len());
-        let mut cursor = 0;
-        Self {
-            name: deserialize::<ArrayString<ASSET_NAME_LENGTH>>(src, &mut cursor),
-            asset: deserialize::<D>(src, &mut cursor),
+        match Self::try_deserialize(src) {
+            Ok(asset) => asset,
+            Err(err) => panic!("invalid asset: {err}"),
         }
     }
+    fn try_deserialize(src: &[u8]) -> Result<Self, DeserializeError> {
+        let src = serialized_bytes::<Self>(src)?;
+        let mut cursor = 0;
+        Ok(Self {
+            name: try_deserialize::<ArrayString<ASSET_NAME_LENGTH>>(src, &mut cursor)?,
+            asset: try_deserialize::<D>(src, &mut cursor)?,
+        })
+    }
 }
 
 impl Deserialize for AudioClipAsset {
@@ -110,14 +207,66 @@ impl Deserialize for AudioClipAsset {
 }
 
 impl Deserialize for SpriteAsset {
+    const SERIALIZED_SIZE: usize = bool::SERIALIZED_SIZE
+        + <ArrayVec<SpriteMipLevel, MAX_MIPS> as Deserialize>::SERIALIZED_SIZE
+        + TextureFilter::SERIALIZED_SIZE;
+    fn deserialize(src: &[u8]) -> Self {
+        assert_eq!(Self::SERIALIZED_SIZE, src.len());
+        match Self::try_deserialize(src) {
+            Ok(sprite) => sprite,
+            Err(err) => panic!("invalid sprite: {err}"),
+        }
+    }
+    fn try_deserialize(src: &[u8]) -> Result<Self, DeserializeError> {
+        let src = serialized_bytes::<Self>(src)?;
+        let mut cursor = 0;
+        Ok(Self {
+            transparent: deserialize::<bool>(src, &mut cursor),
+            mip_chain: try_deserialize::<ArrayVec<SpriteMipLevel, MAX_MIPS>>(src, &mut cursor)?,
+            filter: try_deserialize::<TextureFilter>(src, &mut cursor)?,
+        })
+    }
+}
+
+impl Deserialize for SpriteAssetV2 {
     const SERIALIZED_SIZE: usize = bool::SERIALIZED_SIZE
         + <ArrayVec<SpriteMipLevel, MAX_MIPS> as Deserialize>::SERIALIZED_SIZE;
     fn deserialize(src: &[u8]) -> Self {
         assert_eq!(Self::SERIALIZED_SIZE, src.len());
+        match Self::try_deserialize(src) {
+            Ok(sprite) => sprite,
+            Err(err) => panic!("invalid sprite: {err}"),
+        }
+    }
+    fn try_deserialize(src: &[u8]) -> Result<Self, DeserializeError> {
+        let src = serialized_bytes::<Self>(src)?;
         let mut cursor = 0;
-        Self {
+        Ok(Self(SpriteAsset {
             transparent: deserialize::<bool>(src, &mut cursor),
-            mip_chain: deserialize::<ArrayVec<SpriteMipLevel, MAX_MIPS>>(src, &mut cursor),
+            mip_chain: try_deserialize::<ArrayVec<SpriteMipLevel, MAX_MIPS>>(src, &mut cursor)?,
+            filter: TextureFilter::Linear,
+        }))
+    }
+}
+
+impl Deserialize for TextureFilter {
+    const SERIALIZED_SIZE: usize = u8::SERIALIZED_SIZE;
+    fn deserialize(src: &[u8]) -> Self {
+        assert_eq!(Self::SERIALIZED_SIZE, src.len());
+        match Self::try_deserialize(src) {
+            Ok(filter) => filter,
+            Err(err) => panic!("invalid texture filter: {err}"),
+        }
+    }
+    fn try_deserialize(src: &[u8]) -> Result<Self, DeserializeError> {
+        let src = serialized_bytes::<Self>(src)?;
+        let mut cursor = 0;
+        match deserialize::<u8>(src, &mut cursor) {
+            0 => Ok(TextureFilter::NearestNeighbor),
+            1 => Ok(TextureFilter::Linear),
+            _ => Err(DeserializeError::InvalidValue {
+                reason: "unknown texture filter",
+            }),
         }
     }
 }
@@ -145,6 +294,135 @@ impl Deserialize for SpriteMipLevel {
     }
 }
 
+impl Deserialize for FontAsset {
+    const SERIALIZED_SIZE: usize = u16::SERIALIZED_SIZE
+        + <Range<u32> as Deserialize>::SERIALIZED_SIZE
+        + <ArrayVec<FontGlyph, MAX_GLYPHS> as Deserialize>::SERIALIZED_SIZE;
+    fn deserialize(src: &[u8]) -> Self {
+        assert_eq!(Self::SERIALIZED_SIZE, src.len());
+        match Self::try_deserialize(src) {
+            Ok(font) => font,
+            Err(err) => panic!("invalid font: {err}"),
+        }
+    }
+    fn try_deserialize(src: &[u8]) -> Result<Self, DeserializeError> {
+        let src = serialized_bytes::<Self>(src)?;
+        let mut cursor = 0;
+        Ok(Self {
+            line_height: deserialize::<u16>(src, &mut cursor),
+            sprite_chunks: deserialize::<Range<u32>>(src, &mut cursor),
+            glyphs: try_deserialize::<ArrayVec<FontGlyph, MAX_GLYPHS>>(src, &mut cursor)?,
+        })
+    }
+}
+
+impl Deserialize for FontGlyph {
+    const SERIALIZED_SIZE: usize = u32::SERIALIZED_SIZE
+        + <(u16, u16) as Deserialize>::SERIALIZED_SIZE * 2
+        + <(i16, i16) as Deserialize>::SERIALIZED_SIZE
+        + u16::SERIALIZED_SIZE;
+    fn deserialize(src: &[u8]) -> Self {
+        assert_eq!(Self::SERIALIZED_SIZE, src.len());
+        let mut cursor = 0;
+        Self {
+            sprite_chunk: deserialize::<u32>(src, &mut cursor),
+            uv_offset: deserialize::<(u16, u16)>(src, &mut cursor),
+            size: deserialize::<(u16, u16)>(src, &mut cursor),
+            bearing: deserialize::<(i16, i16)>(src, &mut cursor),
+            advance: deserialize::<u16>(src, &mut cursor),
+        }
+    }
+}
+
+impl Deserialize for AnimationAsset {
+    const SERIALIZED_SIZE: usize = SpriteAsset::SERIALIZED_SIZE
+        + <ArrayVec<AnimationFrame, MAX_FRAMES> as Deserialize>::SERIALIZED_SIZE
+        + AnimationMode::SERIALIZED_SIZE;
+    fn deserialize(src: &[u8]) -> Self {
+        assert_eq!(Self::SERIALIZED_SIZE, src.len());
+        match Self::try_deserialize(src) {
+            Ok(animation) => animation,
+            Err(err) => panic!("invalid animation: {err}"),
+        }
+    }
+    fn try_deserialize(src: &[u8]) -> Result<Self, DeserializeError> {
+        let src = serialized_bytes::<Self>(src)?;
+        let mut cursor = 0;
+        Ok(Self {
+            sheet: try_deserialize::<SpriteAsset>(src, &mut cursor)?,
+            frames: try_deserialize::<ArrayVec<AnimationFrame, MAX_FRAMES>>(src, &mut cursor)?,
+            mode: try_deserialize::<AnimationMode>(src, &mut cursor)?,
+        })
+    }
+}
+
+impl Deserialize for AnimationFrame {
+    const SERIALIZED_SIZE: usize =
+        <(u16, u16) as Deserialize>::SERIALIZED_SIZE * 2 + u32::SERIALIZED_SIZE;
+    fn deserialize(src: &[u8]) -> Self {
+        assert_eq!(Self::SERIALIZED_SIZE, src.len());
+        let mut cursor = 0;
+        Self {
+            offset: deserialize::<(u16, u16)>(src, &mut cursor),
+            size: deserialize::<(u16, u16)>(src, &mut cursor),
+            duration_ms: deserialize::<u32>(src, &mut cursor),
+        }
+    }
+}
+
+impl Deserialize for AnimationMode {
+    const SERIALIZED_SIZE: usize = u8::SERIALIZED_SIZE;
+    fn deserialize(src: &[u8]) -> Self {
+        assert_eq!(Self::SERIALIZED_SIZE, src.len());
+        match Self::try_deserialize(src) {
+            Ok(mode) => mode,
+            Err(err) => panic!("invalid animation mode: {err}"),
+        }
+    }
+    fn try_deserialize(src: &[u8]) -> Result<Self, DeserializeError> {
+        let src = serialized_bytes::<Self>(src)?;
+        let mut cursor = 0;
+        match deserialize::<u8>(src, &mut cursor) {
+            0 => Ok(AnimationMode::Loop),
+            1 => Ok(AnimationMode::Once),
+            _ => Err(DeserializeError::InvalidValue {
+                reason: "unknown animation mode",
+            }),
+        }
+    }
+}
+
+impl Deserialize for TilemapAsset {
+    const SERIALIZED_SIZE: usize = u16::SERIALIZED_SIZE * 2
+        + TilesetLayout::SERIALIZED_SIZE
+        + <Range<u32> as Deserialize>::SERIALIZED_SIZE;
+    fn deserialize(src: &[u8]) -> Self {
+        assert_eq!(Self::SERIALIZED_SIZE, src.len());
+        let mut cursor = 0;
+        Self {
+            width: deserialize::<u16>(src, &mut cursor),
+            height: deserialize::<u16>(src, &mut cursor),
+            tileset: deserialize::<TilesetLayout>(src, &mut cursor),
+            chunks: deserialize::<Range<u32>>(src, &mut cursor),
+        }
+    }
+}
+
+impl Deserialize for TilesetLayout {
+    const SERIALIZED_SIZE: usize =
+        <(u16, u16) as Deserialize>::SERIALIZED_SIZE + u16::SERIALIZED_SIZE * 3;
+    fn deserialize(src: &[u8]) -> Self {
+        assert_eq!(Self::SERIALIZED_SIZE, src.len());
+        let mut cursor = 0;
+        Self {
+            tile_size: deserialize::<(u16, u16)>(src, &mut cursor),
+            margin: deserialize::<u16>(src, &mut cursor),
+            spacing: deserialize::<u16>(src, &mut cursor),
+            columns: deserialize::<u16>(src, &mut cursor),
+        }
+    }
+}
+
 // Serialization helpers, at the bottom because they're very long, just so they
 // compile to something sane in debug builds.
 
@@ -157,17 +435,63 @@ pub fn deserialize<D: Deserialize>(src: &[u8], cursor: &mut usize) -> D {
     value
 }
 
+/// Deserializes the data from a byte slice into `D` with
+/// [`Deserialize::try_deserialize`], reading from the given cursor, and
+/// advancing it by the amount of bytes read if the data was valid.
+#[inline(always)]
+pub fn try_deserialize<D: Deserialize>(
+    src: &[u8],
+    cursor: &mut usize,
+) -> Result<D, DeserializeError> {
+    let value = D::try_deserialize(src.get(*cursor..).unwrap_or(&[]))?;
+    *cursor += D::SERIALIZED_SIZE;
+    Ok(value)
+}
+
+/// Returns the first [`Deserialize::SERIALIZED_SIZE`] bytes of `src`, or a
+/// [`DeserializeError::ShortBuffer`] if there isn't that many.
+#[inline(always)]
+fn serialized_bytes<D: Deserialize>(src: &[u8]) -> Result<&[u8], DeserializeError> {
+    src.get(..D::SERIALIZED_SIZE)
+        .ok_or(DeserializeError::ShortBuffer {
+            len: src.len(),
+            expected: D::SERIALIZED_SIZE,
+        })
+}
+
 impl<const LEN: usize> Deserialize for ArrayString<LEN> {
-    const SERIALIZED_SIZE: usize = u8::SERIALIZED_SIZE + LEN;
+    const SERIALIZED_SIZE: usize = string_length_prefix_size(LEN) + LEN;
     fn deserialize(src: &[u8]) -> Self {
         assert_eq!(Self::SERIALIZED_SIZE, src.len());
+        match Self::try_deserialize(src) {
+            Ok(string) => string,
+            Err(err) => panic!("invalid string: {err}"),
+        }
+    }
+    fn try_deserialize(src: &[u8]) -> Result<Self, DeserializeError> {
         assert!(
-            LEN < 0xFF,
-            "deserialization impl for ArrayString only supports string lengths up to 255",
+            LEN <= u16::MAX as usize,
+            "deserialization impl for ArrayString only supports string lengths up to 65535",
         );
-        let len = src[0] as usize;
-        assert!(len <= LEN, "serialized string cannot fit");
-        ArrayString::from(str::from_utf8(&src[1..1 + len]).unwrap()).unwrap()
+        let src = serialized_bytes::<Self>(src)?;
+        let mut cursor = 0;
+        let len = if string_length_prefix_size(LEN) == u8::SERIALIZED_SIZE {
+            deserialize::<u8>(src, &mut cursor) as usize
+        } else {
+            deserialize::<u16>(src, &mut cursor) as usize
+        };
+        if len > LEN {
+            return Err(DeserializeError::InvalidValue {
+                reason: "string length exceeds its capacity",
+            });
+        }
+        let Ok(string) = str::from_utf8(&src[cursor..cursor + len]) else {
+            return Err(DeserializeError::InvalidValue {
+                reason: "string is not valid UTF-8",
+            });
+        };
+        // does not panic: the length was checked to fit above
+        Ok(ArrayString::from(string).unwrap())
     }
 }
 
@@ -175,18 +499,29 @@ impl<T: Deserialize, const LEN: usize> Deserialize for ArrayVec<T, LEN> {
     const SERIALIZED_SIZE: usize = u8::SERIALIZED_SIZE + T::SERIALIZED_SIZE * LEN;
     fn deserialize(src: &[u8]) -> Self {
         assert_eq!(Self::SERIALIZED_SIZE, src.len());
+        match Self::try_deserialize(src) {
+            Ok(vec) => vec,
+            Err(err) => panic!("invalid vec: {err}"),
+        }
+    }
+    fn try_deserialize(src: &[u8]) -> Result<Self, DeserializeError> {
         assert!(
             LEN < 0xFF,
             "deserialization impl for ArrayVec only supports lengths up to 255",
         );
+        let src = serialized_bytes::<Self>(src)?;
         let mut cursor = 0;
         let len = deserialize::<u8>(src, &mut cursor) as usize;
-        assert!(len <= LEN, "serialized vec cannot fit");
+        if len > LEN {
+            return Err(DeserializeError::InvalidValue {
+                reason: "vec length exceeds its capacity",
+            });
+        }
         let mut vec = ArrayVec::new();
         for _ in 0..len {
-            vec.push(deserialize::<T>(src, &mut cursor));
+            vec.push(try_deserialize::<T>(src, &mut cursor)?);
         }
-        vec
+        Ok(vec)
     }
 }
 
@@ -223,6 +558,17 @@ impl Deserialize for (u16, u16) {
     }
 }
 
+impl Deserialize for (i16, i16) {
+    const SERIALIZED_SIZE: usize = i16::SERIALIZED_SIZE * 2;
+    #[inline]
+    fn deserialize(src: &[u8]) -> Self {
+        assert_eq!(Self::SERIALIZED_SIZE, src.len());
+        let i0 = i16::deserialize(&src[0..2]);
+        let i1 = i16::deserialize(&src[2..4]);
+        (i0, i1)
+    }
+}
+
 impl Deserialize for bool {
     const SERIALIZED_SIZE: usize = 1;
     #[inline]
@@ -254,6 +600,17 @@ impl Deserialize for u16 {
     }
 }
 
+impl Deserialize for i16 {
+    const SERIALIZED_SIZE: usize = 2;
+    #[inline]
+    fn deserialize(src: &[u8]) -> Self {
+        assert_eq!(Self::SERIALIZED_SIZE, src.len());
+        // Safety: all the indexes are covered by the assert above.
+        let bytes = unsafe { [*src.get_unchecked(0), *src.get_unchecked(1)] };
+        i16::from_le_bytes(bytes)
+    }
+}
+
 impl Deserialize for u32 {
     const SERIALIZED_SIZE: usize = 4;
     #[inline]
@@ -293,3 +650,166 @@ impl Deserialize for u64 {
         u64::from_le_bytes(bytes)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use arrayvec::ArrayString;
+    use platform::TextureFilter;
+
+    use crate::resources::{
+        animation::{AnimationAsset, AnimationFrame, AnimationMode},
+        deserialize::{deserialize, Deserialize},
+        serialize::{serialize, Serialize},
+        sprite::{SpriteAsset, SpriteMipLevel},
+        tilemap::{TilemapAsset, TilesetLayout},
+        DeserializeError, NamedAsset, ASSET_NAME_LENGTH,
+    };
+
+    #[test]
+    fn short_strings_have_a_single_byte_length() {
+        type Name = ArrayString<ASSET_NAME_LENGTH>;
+        assert_eq!(1 + ASSET_NAME_LENGTH, <Name as Serialize>::SERIALIZED_SIZE);
+
+        let name = Name::from("player").unwrap();
+        let mut buffer = [0; <Name as Serialize>::SERIALIZED_SIZE];
+        serialize::<Name>(&name, &mut buffer, &mut 0);
+        assert_eq!(b"\x06player", &buffer[..7]);
+        assert_eq!(name, deserialize::<Name>(&buffer, &mut 0));
+    }
+
+    #[test]
+    fn long_strings_round_trip() {
+        type LongString = ArrayString<400>;
+        assert_eq!(2 + 400, <LongString as Deserialize>::SERIALIZED_SIZE);
+
+        let mut string = LongString::new();
+        while string.len() < 300 {
+            string.push_str("0123456789");
+        }
+        let mut buffer = [0; <LongString as Serialize>::SERIALIZED_SIZE];
+        serialize::<LongString>(&string, &mut buffer, &mut 0);
+        assert_eq!(300u16.to_le_bytes(), buffer[..2]);
+        assert_eq!(string, deserialize::<LongString>(&buffer, &mut 0));
+    }
+
+    #[test]
+    fn truncated_and_corrupted_assets_are_rejected() {
+        type Sprite = NamedAsset<SpriteAsset>;
+        const SIZE: usize = <Sprite as Serialize>::SERIALIZED_SIZE;
+        let sprite = Sprite {
+            name: ArrayString::from("player").unwrap(),
+            asset: SpriteAsset {
+                transparent: false,
+                mip_chain: Default::default(),
+                filter: TextureFilter::NearestNeighbor,
+            },
+        };
+        let mut buffer = [0; SIZE];
+        serialize::<Sprite>(&sprite, &mut buffer, &mut 0);
+        assert!(Sprite::try_deserialize(&buffer).is_ok());
+
+        assert_eq!(
+            Some(DeserializeError::ShortBuffer {
+                len: SIZE - 1,
+                expected: SIZE,
+            }),
+            Sprite::try_deserialize(&buffer[..SIZE - 1]).err(),
+        );
+
+        let mut too_long_name = buffer;
+        too_long_name[0] = ASSET_NAME_LENGTH as u8 + 1;
+        assert!(matches!(
+            Sprite::try_deserialize(&too_long_name),
+            Err(DeserializeError::InvalidValue { .. }),
+        ));
+
+        let mut invalid_utf8_name = buffer;
+        invalid_utf8_name[1] = 0xFF;
+        assert!(matches!(
+            Sprite::try_deserialize(&invalid_utf8_name),
+            Err(DeserializeError::InvalidValue { .. }),
+        ));
+
+        let mip_count_offset = <ArrayString<ASSET_NAME_LENGTH> as Serialize>::SERIALIZED_SIZE + 1;
+        let mut too_many_mips = buffer;
+        too_many_mips[mip_count_offset] = 0xFE;
+        assert!(matches!(
+            Sprite::try_deserialize(&too_many_mips),
+            Err(DeserializeError::InvalidValue { .. }),
+        ));
+
+        let mut unknown_filter = buffer;
+        unknown_filter[SIZE - 1] = 0xFF;
+        assert!(matches!(
+            Sprite::try_deserialize(&unknown_filter),
+            Err(DeserializeError::InvalidValue { .. }),
+        ));
+    }
+
+    #[test]
+    fn animations_round_trip() {
+        const SIZE: usize = <AnimationAsset as Serialize>::SERIALIZED_SIZE;
+        let mut animation = AnimationAsset {
+            sheet: SpriteAsset {
+                transparent: true,
+                mip_chain: Default::default(),
+                filter: TextureFilter::NearestNeighbor,
+            },
+            frames: Default::default(),
+            mode: AnimationMode::Once,
+        };
+        animation
+            .sheet
+            .mip_chain
+            .push(SpriteMipLevel::SingleChunkSprite {
+                offset: (1, 1),
+                size: (64, 16),
+                sprite_chunk: 3,
+            });
+        for i in 0..4 {
+            animation.frames.push(AnimationFrame {
+                offset: (i * 16, 0),
+                size: (16, 16),
+                duration_ms: 100 + i as u32,
+            });
+        }
+        let mut buffer = [0; SIZE];
+        serialize::<AnimationAsset>(&animation, &mut buffer, &mut 0);
+
+        let deserialized = deserialize::<AnimationAsset>(&buffer, &mut 0);
+        assert_eq!(animation.frames, deserialized.frames);
+        assert_eq!(AnimationMode::Once, deserialized.mode);
+        assert!(deserialized.sheet.transparent);
+        assert_eq!(TextureFilter::NearestNeighbor, deserialized.sheet.filter);
+        assert_eq!(1, deserialized.sheet.mip_chain.len());
+
+        let mut unknown_mode = buffer;
+        unknown_mode[SIZE - 1] = 0xFF;
+        assert!(matches!(
+            AnimationAsset::try_deserialize(&unknown_mode),
+            Err(DeserializeError::InvalidValue { .. }),
+        ));
+    }
+
+    #[test]
+    fn tilemaps_round_trip() {
+        let tilemap = TilemapAsset {
+            width: 300,
+            height: 20,
+            tileset: TilesetLayout {
+                tile_size: (16, 8),
+                margin: 1,
+                spacing: 2,
+                columns: 7,
+            },
+            chunks: 4..5,
+        };
+        let mut buffer = [0; <TilemapAsset as Serialize>::SERIALIZED_SIZE];
+        serialize::<TilemapAsset>(&tilemap, &mut buffer, &mut 0);
+
+        let deserialized = deserialize::<TilemapAsset>(&buffer, &mut 0);
+        assert_eq!((300, 20), (deserialized.width, deserialized.height));
+        assert_eq!(tilemap.tileset, deserialized.tileset);
+        assert_eq!(4..5, deserialized.chunks);
+    }
+}
@@ -21,6 +21,7 @@ pub enum FileReadError {
 }
 
 struct LoadRequest {
+    file: FileHandle,
     first_byte: u64,
     size: usize,
 }
@@ -33,20 +34,19 @@ struct LoadTask {
 /// File reading utility.
 ///
 /// Can be used for both asynchronous and synchronous reads, as long as they're
-/// read in the same order they were queued up.
+/// read in the same order they were queued up. Each read can be from a
+/// different file, e.g. when reading from multiple resource databases.
 pub struct FileReader {
     staging_buffer: RingBuffer<'static, u8>,
     to_load_queue: Queue<'static, LoadRequest>,
     in_flight_queue: Queue<'static, LoadTask>,
-    file: FileHandle,
 }
 
 impl FileReader {
-    /// Creates a new [`FileReader`] for reading `file`, with a maximum of
-    /// `queue_capacity` concurrent read operations.
+    /// Creates a new [`FileReader`] with a maximum of `queue_capacity`
+    /// concurrent read operations.
     pub fn new(
         arena: &'static LinearAllocator,
-        file: FileHandle,
         staging_buffer_size: usize,
         queue_capacity: usize,
     ) -> Option<FileReader> {
@@ -54,7 +54,6 @@ impl FileReader {
             staging_buffer: RingBuffer::new(arena, staging_buffer_size)?,
             to_load_queue: Queue::new(arena, queue_capacity)?,
             in_flight_queue: Queue::new(arena, queue_capacity)?,
-            file,
         })
     }
 
@@ -64,26 +63,40 @@ impl FileReader {
         self.staging_buffer.capacity()
     }
 
-    /// Queues up a read operation starting at `first_byte`, reading `size`
-    /// bytes. Returns `true` if the request fit in the queue.
+    /// Queues up a read operation from `file` starting at `first_byte`,
+    /// reading `size` bytes. Returns `true` if the request fit in the queue.
     ///
     /// If `size` is larger than [`FileReader::staging_buffer_size`], this will
     /// always return `false`.
     #[must_use]
-    pub fn push_read(&mut self, first_byte: u64, size: usize) -> bool {
+    pub fn push_read(&mut self, file: FileHandle, first_byte: u64, size: usize) -> bool {
         if size > self.staging_buffer_size() {
             return false;
         }
 
         self.to_load_queue
-            .push_back(LoadRequest { first_byte, size })
+            .push_back(LoadRequest {
+                file,
+                first_byte,
+                size,
+            })
             .is_ok()
     }
 
+    /// Returns true if there are reads queued with [`FileReader::push_read`]
+    /// which haven't been started by [`FileReader::dispatch_reads`] yet, e.g.
+    /// due to the staging buffer being full.
+    pub fn has_undispatched_reads(&self) -> bool {
+        !self.to_load_queue.is_empty()
+    }
+
     /// Starts file read operations for the queued up loading requests.
     pub fn dispatch_reads(&mut self, platform: &dyn Platform) {
         profiling::function_scope!();
         while let Some(LoadRequest { size, .. }) = self.to_load_queue.peek_front() {
+            if self.in_flight_queue.is_full() {
+                break;
+            }
             profiling::scope!("dispatch");
             let Some(staging_slice) = self.staging_buffer.allocate(*size) else {
                 break;
@@ -91,11 +104,12 @@ impl FileReader {
             let (buffer, read_buffer_metadata) = staging_slice.into_parts();
 
             let LoadRequest {
+                file,
                 first_byte,
                 size: _,
             } = self.to_load_queue.pop_front().unwrap();
 
-            let file_read_task = platform.begin_file_read(self.file, first_byte, buffer);
+            let file_read_task = platform.begin_file_read(file, first_byte, buffer);
 
             self.in_flight_queue
                 .push_back(LoadTask {
@@ -2,13 +2,13 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
-use platform::Platform;
+use platform::{FileHandle, Platform};
 
 use crate::{allocators::LinearAllocator, collections::Queue};
 
 use super::{
     file_reader::{FileReadError, FileReader},
-    ChunkData, ResourceDatabase, SpriteChunkData,
+    AssetHandle, ChunkData, ResourceDatabase, SpriteChunkData,
 };
 
 #[derive(Debug, PartialEq, Eq)]
@@ -23,6 +23,31 @@ struct ChunkReadInfo {
     category: LoadCategory,
 }
 
+/// A read that has been queued, but not yet passed on to the [`FileReader`].
+#[derive(Debug)]
+struct PendingRead {
+    info: ChunkReadInfo,
+    file: FileHandle,
+    first_byte: u64,
+    size: usize,
+}
+
+/// The priority of a chunk load, see
+/// [`ResourceLoader::queue_chunk_with_priority`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoadPriority {
+    /// For chunks which are not needed yet, e.g. when streaming in assets in
+    /// the background.
+    Low,
+    /// For chunks which are needed as soon as possible, e.g. the chunks of a
+    /// sprite that's being drawn.
+    High,
+}
+
+/// The maximum amount of high priority reads dispatched in a row while there
+/// are low priority reads waiting, to avoid starving the low priority reads.
+const HIGH_PRIORITY_STREAK_LIMIT: u32 = 3;
+
 /// Asynchronous loader for resource chunks.
 ///
 /// Holds some staging memory where the chunk data is written by
@@ -30,13 +55,20 @@ struct ChunkReadInfo {
 /// [`ResourceLoader::dispatch_reads`]. The chunk data is read later to
 /// initialize chunks in [`ResourceLoader::finish_reads`]. Chunks are loaded in
 /// the order [`ResourceLoader::queue_chunk`] and
-/// [`ResourceLoader::queue_sprite_chunk`] are called.
+/// [`ResourceLoader::queue_sprite_chunk`] are called, except that
+/// [`LoadPriority::High`] loads are dispatched before [`LoadPriority::Low`]
+/// ones. To avoid starving the low priority loads, one of them is dispatched
+/// after every few high priority loads.
 ///
 /// Many asset usage related functions take this struct as a parameter for
 /// queueing up relevant chunks to be loaded.
 pub struct ResourceLoader {
     file_reader: FileReader,
+    /// The reads passed on to the file reader, in the same order.
     queued_reads: Queue<'static, ChunkReadInfo>,
+    high_priority_reads: Queue<'static, PendingRead>,
+    low_priority_reads: Queue<'static, PendingRead>,
+    high_priority_streak: u32,
 }
 
 impl ResourceLoader {
@@ -59,31 +91,59 @@ impl ResourceLoader {
         Some(ResourceLoader {
             file_reader,
             queued_reads: Queue::new(arena, total_chunks)?,
+            high_priority_reads: Queue::new(arena, total_chunks)?,
+            low_priority_reads: Queue::new(arena, total_chunks)?,
+            high_priority_streak: 0,
         })
     }
 
-    /// Queues the regular chunk at `chunk_index` to be loaded.
+    /// Queues the regular chunk at `chunk_index` to be loaded with
+    /// [`LoadPriority::High`].
     ///
     /// Note that this doesn't necessarily actually queue up a read operation,
     /// the chunk might not be queued for read if e.g. it's already been loaded,
     /// it's already been queued, or if the queue can't fit the request.
     pub fn queue_chunk(&mut self, chunk_index: u32, resources: &ResourceDatabase) {
-        self.queue_load(chunk_index, LoadCategory::Chunk, resources);
+        self.queue_chunk_with_priority(chunk_index, LoadPriority::High, resources);
     }
 
-    /// Queues the sprite chunk at `chunk_index` to be loaded.
+    /// Queues the regular chunk at `chunk_index` to be loaded with the given
+    /// priority. See [`ResourceLoader::queue_chunk`].
+    pub fn queue_chunk_with_priority(
+        &mut self,
+        chunk_index: u32,
+        priority: LoadPriority,
+        resources: &ResourceDatabase,
+    ) {
+        self.queue_load(chunk_index, LoadCategory::Chunk, priority, resources);
+    }
+
+    /// Queues the sprite chunk at `chunk_index` to be loaded with
+    /// [`LoadPriority::High`].
     ///
     /// Note that this doesn't necessarily actually queue up a read operation,
     /// the chunk might not be queued for read if e.g. it's already been loaded,
     /// it's already been queued, or if the queue can't fit the request.
     pub fn queue_sprite_chunk(&mut self, chunk_index: u32, resources: &ResourceDatabase) {
-        self.queue_load(chunk_index, LoadCategory::SpriteChunk, resources);
+        self.queue_sprite_chunk_with_priority(chunk_index, LoadPriority::High, resources);
+    }
+
+    /// Queues the sprite chunk at `chunk_index` to be loaded with the given
+    /// priority. See [`ResourceLoader::queue_sprite_chunk`].
+    pub fn queue_sprite_chunk_with_priority(
+        &mut self,
+        chunk_index: u32,
+        priority: LoadPriority,
+        resources: &ResourceDatabase,
+    ) {
+        self.queue_load(chunk_index, LoadCategory::SpriteChunk, priority, resources);
     }
 
     fn queue_load(
         &mut self,
         chunk_index: u32,
         category: LoadCategory,
+        priority: LoadPriority,
         resources: &ResourceDatabase,
     ) {
         profiling::function_scope!();
@@ -99,32 +159,74 @@ impl ResourceLoader {
         // Don't queue if the chunk has already been queued.
         let already_queued =
             |read: &ChunkReadInfo| read.chunk_index == chunk_index && read.category == category;
-        if self.queued_reads.iter().any(already_queued) {
+        if self.queued_reads.iter().any(already_queued)
+            || (self.high_priority_reads.iter()).any(|read| already_queued(&read.info))
+            || (self.low_priority_reads.iter()).any(|read| already_queued(&read.info))
+        {
             return;
         }
 
-        let chunk_source = match category {
-            LoadCategory::Chunk => &resources.chunk_descriptors[chunk_index as usize].source_bytes,
-            LoadCategory::SpriteChunk => {
-                &resources.sprite_chunk_descriptors[chunk_index as usize].source_bytes
-            }
+        let (file, source_bytes) = match category {
+            LoadCategory::Chunk => resources.chunk_source(chunk_index),
+            LoadCategory::SpriteChunk => resources.sprite_chunk_source(chunk_index),
         };
-        let first_byte = resources.chunk_data_offset + chunk_source.start;
-        let size = (chunk_source.end - chunk_source.start) as usize;
-        // Attempt to queue:
-        if !self.queued_reads.is_full() && self.file_reader.push_read(first_byte, size) {
-            self.queued_reads
-                .push_back(ChunkReadInfo {
-                    chunk_index,
-                    category,
-                })
-                .unwrap();
+        let first_byte = source_bytes.start;
+        let size = (source_bytes.end - source_bytes.start) as usize;
+        if size > self.file_reader.staging_buffer_size() {
+            return;
         }
+        // Attempt to queue:
+        let pending_reads = match priority {
+            LoadPriority::High => &mut self.high_priority_reads,
+            LoadPriority::Low => &mut self.low_priority_reads,
+        };
+        let _ = pending_reads.push_back(PendingRead {
+            info: ChunkReadInfo {
+                chunk_index,
+                category,
+            },
+            file,
+            first_byte,
+            size,
+        });
     }
 
     /// Starts file read operations for the queued up chunk loading requests.
     pub fn dispatch_reads(&mut self, platform: &dyn Platform) {
+        profiling::function_scope!();
+        // The reads are passed to the file reader one by one, as it dispatches
+        // them in FIFO order, and a low priority read waiting there for space
+        // in the staging buffer would hold up any high priority reads.
         self.file_reader.dispatch_reads(platform);
+        while !self.file_reader.has_undispatched_reads() && !self.queued_reads.is_full() {
+            profiling::scope!("dispatch pending read");
+            let Some(read) = self.pop_pending_read() else {
+                break;
+            };
+            let pushed = (self.file_reader).push_read(read.file, read.first_byte, read.size);
+            // does not panic: there are no undispatched reads, and the size was
+            // checked to fit in the staging buffer when the read was queued
+            assert!(pushed);
+            self.queued_reads.push_back(read.info).unwrap(); // does not panic: queued_reads was just checked to not be full
+            self.file_reader.dispatch_reads(platform);
+        }
+    }
+
+    /// Pops the next read to dispatch from the pending reads, preferring high
+    /// priority reads, up to [`HIGH_PRIORITY_STREAK_LIMIT`] at a time.
+    fn pop_pending_read(&mut self) -> Option<PendingRead> {
+        let low_priority_starving = self.high_priority_streak >= HIGH_PRIORITY_STREAK_LIMIT
+            && !self.low_priority_reads.is_empty();
+        if !low_priority_starving {
+            if let Some(read) = self.high_priority_reads.pop_front() {
+                if !self.low_priority_reads.is_empty() {
+                    self.high_priority_streak += 1;
+                }
+                return Some(read);
+            }
+        }
+        self.high_priority_streak = 0;
+        self.low_priority_reads.pop_front()
     }
 
     /// Checks for finished file read requests and writes their results into the
@@ -140,7 +242,102 @@ impl ResourceLoader {
     ) {
         profiling::function_scope!();
         for _ in 0..max_reads {
-            let read_result = self.file_reader.pop_read(platform, false, |source_bytes| {
+            if !self.finish_read(resources, platform, false) {
+                break;
+            }
+        }
+    }
+
+    /// Loads all of the asset's chunks, blocking until they're loaded. Useful
+    /// for e.g. loading screens, where drawing half-loaded assets is not
+    /// wanted. Any other queued up reads are finished along the way.
+    ///
+    /// To make room for the asset's chunks, the loaded chunks are aged with
+    /// [`ResourceDatabase::increment_ages`], so any chunks not used since then
+    /// (and not acquired) can be evicted.
+    ///
+    /// Returns false if the asset could not be fully loaded, e.g. if it has
+    /// more chunks than fit in the [`ResourceDatabase`] at once, if reading
+    /// the chunks failed, or if the asset has been removed by
+    /// [`ResourceDatabase::reload`].
+    pub fn load_blocking(
+        &mut self,
+        handle: impl AssetHandle,
+        resources: &mut ResourceDatabase,
+        platform: &dyn Platform,
+    ) -> bool {
+        profiling::function_scope!();
+        let Some(asset) = handle.get_asset(resources) else {
+            return false;
+        };
+        let chunks = asset.get_chunks().unwrap_or(0..0);
+        let sprite_chunks = asset.get_sprite_chunks().unwrap_or(0..0);
+        resources.increment_ages();
+
+        let mut previously_missing = usize::MAX;
+        loop {
+            // Checking if the chunks are loaded also resets their ages, which
+            // keeps them from being evicted by the chunks loaded after them.
+            let mut missing = 0;
+            for chunk in chunks.clone() {
+                if resources.chunks.get(chunk).is_none() {
+                    self.queue_chunk(chunk, resources);
+                    missing += 1;
+                }
+            }
+            for chunk in sprite_chunks.clone() {
+                if resources.sprite_chunks.get(chunk).is_none() {
+                    self.queue_sprite_chunk(chunk, resources);
+                    missing += 1;
+                }
+            }
+
+            if missing == 0 {
+                return true;
+            } else if missing >= previously_missing {
+                // The last round of reads didn't load anything, and trying
+                // again probably wouldn't either.
+                return false;
+            }
+            previously_missing = missing;
+
+            self.dispatch_reads(platform);
+            while self.finish_read(resources, platform, true) {
+                self.dispatch_reads(platform);
+            }
+        }
+    }
+
+    /// Finishes all the reads already passed on to the file reader, blocking
+    /// until they're done, and drops the queued loads which haven't been
+    /// dispatched yet. Used before reloading the [`ResourceDatabase`], as the
+    /// queued loads refer to the chunks of the database before the reload.
+    pub(crate) fn drain(&mut self, resources: &mut ResourceDatabase, platform: &dyn Platform) {
+        profiling::function_scope!();
+        while self.finish_read(resources, platform, true) {}
+        while self.high_priority_reads.pop_front().is_some() {}
+        while self.low_priority_reads.pop_front().is_some() {}
+        self.high_priority_streak = 0;
+    }
+
+    /// Returns the file reader used for loading the chunks.
+    pub(crate) fn file_reader(&mut self) -> &mut FileReader {
+        &mut self.file_reader
+    }
+
+    /// Finishes the read at the front of the queue, and writes the result into
+    /// the resource database. Returns false if there were no reads to finish,
+    /// or if `blocking` is false and the read has not finished yet.
+    fn finish_read(
+        &mut self,
+        resources: &mut ResourceDatabase,
+        platform: &dyn Platform,
+        blocking: bool,
+    ) -> bool {
+        profiling::function_scope!();
+        let read_result = self
+            .file_reader
+            .pop_read(platform, blocking, |source_bytes| {
                 profiling::scope!("process file read");
                 let ChunkReadInfo {
                     chunk_index,
@@ -150,6 +347,7 @@ impl ResourceLoader {
 
                 match category {
                     LoadCategory::Chunk => {
+                        profiling::scope!("load chunk");
                         let desc = &resources.chunk_descriptors[chunk_index as usize];
                         let init_fn = || Some(ChunkData::empty());
                         if let Some(dst) = resources.chunks.insert(chunk_index, init_fn) {
@@ -158,6 +356,7 @@ impl ResourceLoader {
                     }
 
                     LoadCategory::SpriteChunk => {
+                        profiling::scope!("load sprite chunk");
                         let desc = &resources.sprite_chunk_descriptors[chunk_index as usize];
                         let init_fn = || SpriteChunkData::empty(platform);
                         if let Some(dst) = resources.sprite_chunks.insert(chunk_index, init_fn) {
@@ -167,16 +366,98 @@ impl ResourceLoader {
                 }
             });
 
-            match read_result {
-                Ok(_) => {}
-                Err(FileReadError::NoReadsQueued | FileReadError::WouldBlock) => break,
-                Err(err) => {
-                    let info = self.queued_reads.pop_front().unwrap();
-                    platform.println(format_args!(
-                        "resource loader read ({info:?}) failed: {err:?}"
-                    ));
-                }
+        match read_result {
+            Ok(_) => true,
+            Err(FileReadError::NoReadsQueued | FileReadError::WouldBlock) => false,
+            Err(err) => {
+                let info = self.queued_reads.pop_front().unwrap();
+                platform.println(format_args!(
+                    "resource loader read ({info:?}) failed: {err:?}"
+                ));
+                true
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        allocators::LinearAllocator,
+        static_allocator,
+        test_platform::{engine_with_loaded_chunks, TestPlatform},
+        Engine, EngineLimits,
+    };
+
+    use super::{LoadPriority, HIGH_PRIORITY_STREAK_LIMIT};
+
+    #[test]
+    fn high_priority_reads_are_dispatched_first() {
+        static ARENA: &LinearAllocator = static_allocator!(64 * 1024 * 1024);
+        let platform = TestPlatform::new(false);
+        let mut engine = Engine::new(&platform, ARENA, EngineLimits::DEFAULT);
+        let (resources, loader) = (&engine.resource_db, &mut engine.resource_loader);
+
+        let background_chunks = 0..resources.sprite_chunk_descriptors.len() as u32;
+        for chunk in background_chunks.clone() {
+            loader.queue_sprite_chunk_with_priority(chunk, LoadPriority::Low, resources);
+        }
+        loader.queue_chunk_with_priority(0, LoadPriority::High, resources);
+        loader.dispatch_reads(&platform);
+
+        (engine.resource_loader).finish_reads(&mut engine.resource_db, &platform, 1);
+        let resources = &engine.resource_db;
+        assert!(resources.chunks.get(0).is_some());
+        assert!(background_chunks
+            .clone()
+            .all(|chunk| resources.sprite_chunks.get(chunk).is_none()));
+    }
+
+    #[test]
+    fn low_priority_reads_are_not_starved() {
+        static ARENA: &LinearAllocator = static_allocator!(64 * 1024 * 1024);
+        let platform = TestPlatform::new(false);
+        let mut engine = Engine::new(&platform, ARENA, EngineLimits::DEFAULT);
+        let (resources, loader) = (&engine.resource_db, &mut engine.resource_loader);
+
+        let urgent_chunks = 0..resources.sprite_chunk_descriptors.len() as u32;
+        assert!(urgent_chunks.len() > HIGH_PRIORITY_STREAK_LIMIT as usize);
+        loader.queue_chunk_with_priority(0, LoadPriority::Low, resources);
+        for chunk in urgent_chunks {
+            loader.queue_sprite_chunk(chunk, resources);
+        }
+        loader.dispatch_reads(&platform);
+
+        // After the streak limit, the low priority read should get through.
+        let limit = HIGH_PRIORITY_STREAK_LIMIT;
+        (engine.resource_loader).finish_reads(&mut engine.resource_db, &platform, limit as usize);
+        let resources = &engine.resource_db;
+        assert!((0..limit).all(|chunk| resources.sprite_chunks.get(chunk).is_some()));
+        assert!(resources.chunks.get(0).is_none());
+
+        (engine.resource_loader).finish_reads(&mut engine.resource_db, &platform, 1);
+        let resources = &engine.resource_db;
+        assert!(resources.chunks.get(0).is_some());
+        assert!(resources.sprite_chunks.get(limit).is_none());
+    }
+
+    #[test]
+    fn multi_chunk_sprites_can_be_loaded_blocking() {
+        static ARENA: &LinearAllocator = static_allocator!(8 * 1024 * 1024);
+        let platform = TestPlatform::new(false);
+        let (mut engine, sprite, chunks) = engine_with_loaded_chunks(&platform, ARENA, 0, 16);
+
+        let (resources, loader) = (&mut engine.resource_db, &mut engine.resource_loader);
+        assert!(loader.load_blocking(sprite, resources, &platform));
+        assert!(chunks
+            .clone()
+            .all(|chunk| resources.sprite_chunks.get(chunk).is_some()));
+
+        // The sprite can't be loaded when it doesn't fit, but this shouldn't
+        // loop forever either.
+        let max_loaded = chunks.len() as u32 - 1;
+        let (mut engine, _, _) = engine_with_loaded_chunks(&platform, ARENA, 0, max_loaded);
+        let (resources, loader) = (&mut engine.resource_db, &mut engine.resource_loader);
+        assert!(!loader.load_blocking(sprite, resources, &platform));
+    }
+}
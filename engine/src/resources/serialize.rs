@@ -5,11 +5,15 @@
 use core::ops::Range;
 
 use arrayvec::{ArrayString, ArrayVec};
+use platform::TextureFilter;
 
 use super::{
+    animation::{AnimationAsset, AnimationFrame, AnimationMode, MAX_FRAMES},
     audio_clip::AudioClipAsset,
     chunks::{ChunkDescriptor, SpriteChunkDescriptor},
+    font::{FontAsset, FontGlyph, MAX_GLYPHS},
     sprite::{SpriteAsset, SpriteMipLevel, MAX_MIPS},
+    tilemap::{TilemapAsset, TilesetLayout},
     NamedAsset, ResourceDatabaseHeader, ASSET_NAME_LENGTH,
 };
 
@@ -51,16 +55,31 @@ impl Serialize for SpriteChunkDescriptor {
 }
 
 impl Serialize for ResourceDatabaseHeader {
-    const SERIALIZED_SIZE: usize = 18 + u32::SERIALIZED_SIZE * 4;
+    const SERIALIZED_SIZE: usize = 22 + u32::SERIALIZED_SIZE * 9;
+    /// Serializes the header in the current version's layout, so `version`
+    /// should be [`RESOURCE_DB_VERSION`](super::RESOURCE_DB_VERSION).
     fn serialize(&self, dst: &mut [u8]) {
         assert_eq!(Self::SERIALIZED_SIZE, dst.len());
         let mut cursor = 0;
+        let ResourceDatabaseHeader {
+            version,
+            chunks,
+            sprite_chunks,
+            sprites,
+            audio_clips,
+            fonts,
+            animations,
+            tilemaps,
+            metadata_checksum,
+            chunk_data_checksum,
+        } = self;
 
         {
             use super::*;
             use platform::*;
 
             serialize::<u32>(&RESOURCE_DB_MAGIC_NUMBER, dst, &mut cursor);
+            serialize::<u32>(version, dst, &mut cursor);
             serialize::<u32>(&CHUNK_SIZE, dst, &mut cursor);
             serialize::<u16>(&SPRITE_CHUNK_DIMENSIONS.0, dst, &mut cursor);
             serialize::<u16>(&SPRITE_CHUNK_DIMENSIONS.1, dst, &mut cursor);
@@ -69,16 +88,15 @@ impl Serialize for ResourceDatabaseHeader {
             serialize::<u8>(&(AUDIO_CHANNELS as u8), dst, &mut cursor);
         }
 
-        let ResourceDatabaseHeader {
-            chunks,
-            sprite_chunks,
-            sprites,
-            audio_clips,
-        } = self;
         serialize::<u32>(chunks, dst, &mut cursor);
         serialize::<u32>(sprite_chunks, dst, &mut cursor);
         serialize::<u32>(sprites, dst, &mut cursor);
         serialize::<u32>(audio_clips, dst, &mut cursor);
+        serialize::<u32>(fonts, dst, &mut cursor);
+        serialize::<u32>(animations, dst, &mut cursor);
+        serialize::<u32>(tilemaps, dst, &mut cursor);
+        serialize::<u32>(metadata_checksum, dst, &mut cursor);
+        serialize::<u32>(chunk_data_checksum, dst, &mut cursor);
     }
 }
 
@@ -107,17 +125,33 @@ impl Serialize for AudioClipAsset {
 }
 
 impl Serialize for SpriteAsset {
-    const SERIALIZED_SIZE: usize =
-        bool::SERIALIZED_SIZE + <ArrayVec<SpriteMipLevel, MAX_MIPS> as Serialize>::SERIALIZED_SIZE;
+    const SERIALIZED_SIZE: usize = bool::SERIALIZED_SIZE
+        + <ArrayVec<SpriteMipLevel, MAX_MIPS> as Serialize>::SERIALIZED_SIZE
+        + TextureFilter::SERIALIZED_SIZE;
     fn serialize(&self, dst: &mut [u8]) {
         assert_eq!(Self::SERIALIZED_SIZE, dst.len());
         let mut cursor = 0;
         let SpriteAsset {
             transparent,
             mip_chain,
+            filter,
         } = self;
         serialize::<bool>(transparent, dst, &mut cursor);
         serialize::<ArrayVec<SpriteMipLevel, MAX_MIPS>>(mip_chain, dst, &mut cursor);
+        serialize::<TextureFilter>(filter, dst, &mut cursor);
+    }
+}
+
+impl Serialize for TextureFilter {
+    const SERIALIZED_SIZE: usize = u8::SERIALIZED_SIZE;
+    fn serialize(&self, dst: &mut [u8]) {
+        assert_eq!(Self::SERIALIZED_SIZE, dst.len());
+        let mut cursor = 0;
+        let filter = match self {
+            TextureFilter::NearestNeighbor => 0,
+            TextureFilter::Linear => 1,
+        };
+        serialize::<u8>(&filter, dst, &mut cursor);
     }
 }
 
@@ -151,6 +185,134 @@ impl Serialize for SpriteMipLevel {
     }
 }
 
+impl Serialize for FontAsset {
+    const SERIALIZED_SIZE: usize = u16::SERIALIZED_SIZE
+        + <Range<u32> as Serialize>::SERIALIZED_SIZE
+        + <ArrayVec<FontGlyph, MAX_GLYPHS> as Serialize>::SERIALIZED_SIZE;
+    fn serialize(&self, dst: &mut [u8]) {
+        assert_eq!(Self::SERIALIZED_SIZE, dst.len());
+        let mut cursor = 0;
+        let FontAsset {
+            line_height,
+            sprite_chunks,
+            glyphs,
+        } = self;
+        serialize::<u16>(line_height, dst, &mut cursor);
+        serialize::<Range<u32>>(sprite_chunks, dst, &mut cursor);
+        serialize::<ArrayVec<FontGlyph, MAX_GLYPHS>>(glyphs, dst, &mut cursor);
+    }
+}
+
+impl Serialize for FontGlyph {
+    const SERIALIZED_SIZE: usize = u32::SERIALIZED_SIZE
+        + <(u16, u16) as Serialize>::SERIALIZED_SIZE * 2
+        + <(i16, i16) as Serialize>::SERIALIZED_SIZE
+        + u16::SERIALIZED_SIZE;
+    fn serialize(&self, dst: &mut [u8]) {
+        assert_eq!(Self::SERIALIZED_SIZE, dst.len());
+        let mut cursor = 0;
+        let FontGlyph {
+            sprite_chunk,
+            uv_offset,
+            size,
+            bearing,
+            advance,
+        } = self;
+        serialize::<u32>(sprite_chunk, dst, &mut cursor);
+        serialize::<(u16, u16)>(uv_offset, dst, &mut cursor);
+        serialize::<(u16, u16)>(size, dst, &mut cursor);
+        serialize::<(i16, i16)>(bearing, dst, &mut cursor);
+        serialize::<u16>(advance, dst, &mut cursor);
+    }
+}
+
+impl Serialize for AnimationAsset {
+    const SERIALIZED_SIZE: usize = SpriteAsset::SERIALIZED_SIZE
+        + <ArrayVec<AnimationFrame, MAX_FRAMES> as Serialize>::SERIALIZED_SIZE
+        + AnimationMode::SERIALIZED_SIZE;
+    fn serialize(&self, dst: &mut [u8]) {
+        assert_eq!(Self::SERIALIZED_SIZE, dst.len());
+        let mut cursor = 0;
+        let AnimationAsset {
+            sheet,
+            frames,
+            mode,
+        } = self;
+        serialize::<SpriteAsset>(sheet, dst, &mut cursor);
+        serialize::<ArrayVec<AnimationFrame, MAX_FRAMES>>(frames, dst, &mut cursor);
+        serialize::<AnimationMode>(mode, dst, &mut cursor);
+    }
+}
+
+impl Serialize for AnimationFrame {
+    const SERIALIZED_SIZE: usize =
+        <(u16, u16) as Serialize>::SERIALIZED_SIZE * 2 + u32::SERIALIZED_SIZE;
+    fn serialize(&self, dst: &mut [u8]) {
+        assert_eq!(Self::SERIALIZED_SIZE, dst.len());
+        let mut cursor = 0;
+        let AnimationFrame {
+            offset,
+            size,
+            duration_ms,
+        } = self;
+        serialize::<(u16, u16)>(offset, dst, &mut cursor);
+        serialize::<(u16, u16)>(size, dst, &mut cursor);
+        serialize::<u32>(duration_ms, dst, &mut cursor);
+    }
+}
+
+impl Serialize for AnimationMode {
+    const SERIALIZED_SIZE: usize = u8::SERIALIZED_SIZE;
+    fn serialize(&self, dst: &mut [u8]) {
+        assert_eq!(Self::SERIALIZED_SIZE, dst.len());
+        let mut cursor = 0;
+        let mode = match self {
+            AnimationMode::Loop => 0,
+            AnimationMode::Once => 1,
+        };
+        serialize::<u8>(&mode, dst, &mut cursor);
+    }
+}
+
+impl Serialize for TilemapAsset {
+    const SERIALIZED_SIZE: usize = u16::SERIALIZED_SIZE * 2
+        + TilesetLayout::SERIALIZED_SIZE
+        + <Range<u32> as Serialize>::SERIALIZED_SIZE;
+    fn serialize(&self, dst: &mut [u8]) {
+        assert_eq!(Self::SERIALIZED_SIZE, dst.len());
+        let mut cursor = 0;
+        let TilemapAsset {
+            width,
+            height,
+            tileset,
+            chunks,
+        } = self;
+        serialize::<u16>(width, dst, &mut cursor);
+        serialize::<u16>(height, dst, &mut cursor);
+        serialize::<TilesetLayout>(tileset, dst, &mut cursor);
+        serialize::<Range<u32>>(chunks, dst, &mut cursor);
+    }
+}
+
+impl Serialize for TilesetLayout {
+    const SERIALIZED_SIZE: usize =
+        <(u16, u16) as Serialize>::SERIALIZED_SIZE + u16::SERIALIZED_SIZE * 3;
+    fn serialize(&self, dst: &mut [u8]) {
+        assert_eq!(Self::SERIALIZED_SIZE, dst.len());
+        let mut cursor = 0;
+        let TilesetLayout {
+            tile_size,
+            margin,
+            spacing,
+            columns,
+        } = self;
+        serialize::<(u16, u16)>(tile_size, dst, &mut cursor);
+        serialize::<u16>(margin, dst, &mut cursor);
+        serialize::<u16>(spacing, dst, &mut cursor);
+        serialize::<u16>(columns, dst, &mut cursor);
+    }
+}
+
 // Serialization helpers, at the bottom because they're very long, just so they
 // compile to something sane in debug builds.
 
@@ -162,16 +324,37 @@ pub fn serialize<S: Serialize>(value: &S, dst: &mut [u8], cursor: &mut usize) {
     *cursor += S::SERIALIZED_SIZE;
 }
 
+/// Returns the size of the length prefix of a serialized string with the
+/// capacity `max_len`.
+///
+/// Strings which can be up to 255 bytes long use a single byte, to keep the
+/// format of e.g. asset names unchanged from when they were the only option,
+/// while longer strings use a `u16`. The prefix width only depends on the
+/// string type, so serialized strings stay readable as long as the string types
+/// don't change.
+pub(super) const fn string_length_prefix_size(max_len: usize) -> usize {
+    if max_len <= u8::MAX as usize {
+        u8::SERIALIZED_SIZE
+    } else {
+        u16::SERIALIZED_SIZE
+    }
+}
+
 impl<const LEN: usize> Serialize for ArrayString<LEN> {
-    const SERIALIZED_SIZE: usize = u8::SERIALIZED_SIZE + LEN;
+    const SERIALIZED_SIZE: usize = string_length_prefix_size(LEN) + LEN;
     fn serialize(&self, dst: &mut [u8]) {
         assert_eq!(Self::SERIALIZED_SIZE, dst.len());
         assert!(
-            LEN < 0xFF,
-            "deserialization impl for ArrayString only supports string lengths up to 255",
+            LEN <= u16::MAX as usize,
+            "serialization impl for ArrayString only supports string lengths up to 65535",
         );
-        (self.len() as u8).serialize(&mut dst[0..1]);
-        dst[1..1 + self.len()].copy_from_slice(self.as_bytes());
+        let mut cursor = 0;
+        if string_length_prefix_size(LEN) == u8::SERIALIZED_SIZE {
+            serialize::<u8>(&(self.len() as u8), dst, &mut cursor);
+        } else {
+            serialize::<u16>(&(self.len() as u16), dst, &mut cursor);
+        }
+        dst[cursor..cursor + self.len()].copy_from_slice(self.as_bytes());
     }
 }
 
@@ -221,6 +404,16 @@ impl Serialize for (u16, u16) {
     }
 }
 
+impl Serialize for (i16, i16) {
+    const SERIALIZED_SIZE: usize = i16::SERIALIZED_SIZE * 2;
+    #[inline]
+    fn serialize(&self, dst: &mut [u8]) {
+        assert_eq!(Self::SERIALIZED_SIZE, dst.len());
+        self.0.serialize(&mut dst[0..2]);
+        self.1.serialize(&mut dst[2..4]);
+    }
+}
+
 impl Serialize for bool {
     const SERIALIZED_SIZE: usize = 1;
     #[inline]
@@ -256,6 +449,20 @@ impl Serialize for u16 {
     }
 }
 
+impl Serialize for i16 {
+    const SERIALIZED_SIZE: usize = 2;
+    #[inline]
+    fn serialize(&self, dst: &mut [u8]) {
+        assert_eq!(Self::SERIALIZED_SIZE, dst.len());
+        let [a, b] = self.to_le_bytes();
+        // Safety: all the indexes are covered by the assert above.
+        unsafe {
+            *dst.get_unchecked_mut(0) = a;
+            *dst.get_unchecked_mut(1) = b;
+        }
+    }
+}
+
 impl Serialize for u32 {
     const SERIALIZED_SIZE: usize = 4;
     #[inline]
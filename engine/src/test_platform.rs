@@ -4,19 +4,108 @@
 
 extern crate std;
 
-use core::{cell::Cell, fmt::Arguments, time::Duration};
+use core::{
+    cell::{Cell, Ref, RefCell, RefMut},
+    fmt::{Arguments, Write},
+    ops::{ControlFlow, Range},
+    time::Duration,
+};
+
+use std::{string::String, vec::Vec};
 
+use arrayvec::ArrayString;
 use platform::{
-    ActionCategory, Box, Button, DrawSettings2D, FileHandle, FileReadTask, InputDevice,
-    InputDevices, Instant, PixelFormat, Platform, Semaphore, SpriteRef, TaskChannel, ThreadState,
-    Vertex2D, AUDIO_CHANNELS, AUDIO_SAMPLE_RATE,
+    ActionCategory, AudioOutput, Box, Button, ButtonNameString, DisplayInfo, DisplayInfoList,
+    DrawSettings2D, EngineCallbacks, FileHandle, FileReadTask, FileWriteTask, FullscreenMode,
+    GamepadKind, InputDevice, InputDevices, Instant, PathString, PixelFormat, Platform,
+    PresentMode, Semaphore, SpriteRef, TaskChannel, ThreadState, Vertex2D, WindowId,
+    AUDIO_CHANNELS, AUDIO_SAMPLE_RATE,
+};
+
+use crate::{
+    allocators::LinearAllocator,
+    resources::{
+        audio_clip::AudioClipAsset, sprite::SpriteHandle, Asset, ChunkDescriptor, Crc32,
+        NamedAsset, ResourceDatabaseHeader, Serialize, AUDIO_SAMPLES_PER_CHUNK,
+        RESOURCE_DB_VERSION,
+    },
+    Engine, EngineLimits,
 };
 
+/// The width of [`TestPlatform::framebuffer`], and [`Platform::draw_area`].
+pub const FRAMEBUFFER_WIDTH: usize = 320;
+/// The height of [`TestPlatform::framebuffer`], and [`Platform::draw_area`].
+pub const FRAMEBUFFER_HEIGHT: usize = 240;
+
+/// The file handle of the "resources.db" file.
+const RESOURCES_DB_HANDLE: u64 = 4321;
+/// The file handle of the first file added with [`TestPlatform::add_file`].
+const EXTRA_FILE_HANDLES_START: u64 = 5000;
+
 /// Simple non-interactive [`Platform`] implementation for use in tests.
+///
+/// Draws are rasterized in software into an in-memory framebuffer, which can be
+/// inspected with [`TestPlatform::framebuffer`]. Draws into windows other than
+/// [`WindowId::MAIN`] are only recorded, not rasterized.
 #[derive(Debug)]
 pub struct TestPlatform {
     current_time: Cell<Instant>,
     threads: usize,
+    draw_calls: RefCell<Vec<DrawCall>>,
+    /// The amount of windows created, including the main window.
+    window_count: Cell<u64>,
+    active_window: Cell<WindowId>,
+    framebuffer: RefCell<Vec<u8>>,
+    sprites: RefCell<Vec<TestSprite>>,
+    fullscreen_mode: Cell<FullscreenMode>,
+    present_mode: Cell<PresentMode>,
+    resources_db: RefCell<Vec<u8>>,
+    resources_db_missing: Cell<bool>,
+    /// Files added with [`TestPlatform::add_file`] or written with
+    /// [`Platform::begin_file_write`], with the file handle of each being
+    /// [`EXTRA_FILE_HANDLES_START`] plus its index.
+    extra_files: RefCell<Vec<(String, Vec<u8>)>>,
+    file_reads: RefCell<Vec<TestFileRead>>,
+    failing_file_reads: RefCell<Vec<usize>>,
+    file_read_latency: Cell<u32>,
+    exit_requested: Cell<bool>,
+}
+
+/// A file read started with [`Platform::begin_file_read`] on a
+/// [`TestPlatform`], indexed by the task id of the [`FileReadTask`].
+#[derive(Debug)]
+struct TestFileRead {
+    first_byte: u64,
+    fail: bool,
+    /// How many more times [`Platform::is_file_read_finished`] should return
+    /// false for this read.
+    pending_polls: u32,
+}
+
+/// A sprite created with [`Platform::create_sprite`] on a [`TestPlatform`].
+#[derive(Debug)]
+struct TestSprite {
+    width: u16,
+    height: u16,
+    /// RGBA pixels, allocated on the first [`Platform::update_sprite`] to avoid
+    /// allocating memory for sprites which are never used.
+    pixels: Vec<u8>,
+}
+
+/// The parameters of a [`Platform::draw_2d`] call made to a [`TestPlatform`].
+#[derive(Debug)]
+pub struct DrawCall {
+    /// The window which was active during the draw call.
+    pub window: WindowId,
+    /// The vertices passed into the draw call.
+    pub vertices: Vec<Vertex2D>,
+    /// The indices passed into the draw call.
+    pub indices: Vec<u32>,
+    /// The addresses of the vertex and index slices passed into the draw
+    /// call, for checking their alignment.
+    pub buffer_addresses: (usize, usize),
+    /// The settings passed into the draw call.
+    pub settings: DrawSettings2D,
 }
 
 impl TestPlatform {
@@ -28,7 +117,67 @@ impl TestPlatform {
         TestPlatform {
             current_time: Cell::new(Instant::reference()),
             threads: if multi_threaded { 3 } else { 1 },
+            draw_calls: RefCell::new(Vec::new()),
+            window_count: Cell::new(1),
+            active_window: Cell::new(WindowId::MAIN),
+            framebuffer: RefCell::new(std::vec![0; FRAMEBUFFER_WIDTH * FRAMEBUFFER_HEIGHT * 4]),
+            sprites: RefCell::new(Vec::new()),
+            fullscreen_mode: Cell::new(FullscreenMode::Windowed),
+            present_mode: Cell::new(PresentMode::Vsync),
+            resources_db: RefCell::new(include_bytes!("../../example/resources.db").to_vec()),
+            resources_db_missing: Cell::new(false),
+            extra_files: RefCell::new(Vec::new()),
+            file_reads: RefCell::new(Vec::new()),
+            failing_file_reads: RefCell::new(Vec::new()),
+            file_read_latency: Cell::new(0),
+            exit_requested: Cell::new(false),
+        }
+    }
+
+    /// Returns the draw calls made since the last call to this function, in
+    /// the order they were made.
+    pub fn take_draw_calls(&self) -> Vec<DrawCall> {
+        self.draw_calls.take()
+    }
+
+    /// Returns the RGBA pixels drawn so far, in rows of [`FRAMEBUFFER_WIDTH`]
+    /// pixels, from the top-left.
+    ///
+    /// The draws are rasterized by sampling each pixel at its center, with the
+    /// nearest texel of the sprite (regardless of the
+    /// [`TextureFilter`](platform::TextureFilter)), so the results are exact
+    /// enough to compare against expected colors. Sprites which haven't been
+    /// updated with [`Platform::update_sprite`] are opaque white.
+    pub fn framebuffer(&self) -> Ref<'_, [u8]> {
+        Ref::map(self.framebuffer.borrow(), |framebuffer| &framebuffer[..])
+    }
+
+    /// Clears the framebuffer to transparent black, e.g. between frames.
+    pub fn clear_framebuffer(&self) {
+        self.framebuffer.borrow_mut().fill(0);
+    }
+
+    /// Runs frames of the engine until `run_game_frame` returns
+    /// [`ControlFlow::Break`], returning the value it broke with, or until
+    /// [`Platform::exit`] is called, returning `None`.
+    ///
+    /// Matches the game loops of real platforms, except that no events are
+    /// sent and the time does not advance between frames.
+    pub fn run_game_loop<E: EngineCallbacks, T>(
+        &self,
+        engine: &mut E,
+        mut run_game_frame: impl FnMut(Instant, &dyn Platform, &mut E) -> ControlFlow<T>,
+    ) -> Option<T> {
+        while !self.exit_requested.get() {
+            let mut flow = ControlFlow::Continue(());
+            engine.run_frame(self, &mut |timestamp, platform, engine| {
+                flow = run_game_frame(timestamp, platform, engine);
+            });
+            if let ControlFlow::Break(value) = flow {
+                return Some(value);
+            }
         }
+        None
     }
 
     /// Sets the time returned by [`TestPlatform::elapsed`] in milliseconds.
@@ -36,26 +185,130 @@ impl TestPlatform {
         self.current_time
             .set(Instant::reference() + Duration::from_millis(new_millis));
     }
+
+    /// Returns the contents of the "resources.db" file, which can be modified
+    /// to test how the engine handles e.g. corrupted files. Reads past the end
+    /// of the file fail.
+    pub fn resources_db_mut(&self) -> RefMut<'_, Vec<u8>> {
+        self.resources_db.borrow_mut()
+    }
+
+    /// Adds a file which can be opened with [`Platform::open_file`] using
+    /// `path`, e.g. for testing additional resource databases. If the file has
+    /// already been added, its contents are replaced, and the handles already
+    /// opened for it read the new contents.
+    pub fn add_file(&self, path: &'static str, contents: Vec<u8>) {
+        let mut extra_files = self.extra_files.borrow_mut();
+        match extra_files.iter_mut().find(|(name, _)| *name == path) {
+            Some((_, old_contents)) => *old_contents = contents,
+            None => extra_files.push((String::from(path), contents)),
+        }
+    }
+
+    /// Makes [`Platform::open_file`] fail for the "resources.db" file, as if it
+    /// didn't exist.
+    pub fn remove_resources_db(&self) {
+        self.resources_db_missing.set(true);
+    }
+
+    /// Makes the `n`th file read started after this call fail, counting from
+    /// 0. The read is started as usual, but [`Platform::finish_file_read`]
+    /// returns an `Err`.
+    pub fn fail_nth_file_read(&self, n: usize) {
+        let read_index = self.file_reads.borrow().len() + n;
+        self.failing_file_reads.borrow_mut().push(read_index);
+    }
+
+    /// Makes [`Platform::is_file_read_finished`] return false `polls` times for
+    /// each file read started after this call, before returning true.
+    /// [`Platform::finish_file_read`] still finishes the reads immediately,
+    /// like a blocking wait would.
+    pub fn set_file_read_latency(&self, polls: u32) {
+        self.file_read_latency.set(polls);
+    }
 }
 
 impl Platform for TestPlatform {
+    fn create_window(&self, _title: &str, _width: u32, _height: u32) -> Option<WindowId> {
+        let window = WindowId::new(self.window_count.get());
+        self.window_count.set(window.inner() + 1);
+        Some(window)
+    }
+
+    fn set_active_window(&self, window: WindowId) {
+        if window.inner() < self.window_count.get() {
+            self.active_window.set(window);
+        }
+    }
+
     fn draw_area(&self) -> (f32, f32) {
-        (320.0, 240.0)
+        (FRAMEBUFFER_WIDTH as f32, FRAMEBUFFER_HEIGHT as f32)
     }
 
     fn draw_scale_factor(&self) -> f32 {
         1.5
     }
 
-    fn draw_2d(&self, _vertices: &[Vertex2D], _indices: &[u32], _settings: DrawSettings2D) {}
+    fn set_fullscreen(&self, mode: FullscreenMode) {
+        self.fullscreen_mode.set(mode);
+    }
+
+    fn is_fullscreen(&self) -> bool {
+        self.fullscreen_mode.get() != FullscreenMode::Windowed
+    }
+
+    fn set_present_mode(&self, mode: PresentMode) {
+        self.present_mode.set(mode);
+    }
+
+    fn displays(&self) -> DisplayInfoList {
+        let mut displays = DisplayInfoList::new();
+        displays.push(DisplayInfo {
+            width: FRAMEBUFFER_WIDTH as u32,
+            height: FRAMEBUFFER_HEIGHT as u32,
+            refresh_rate: 60,
+            primary: true,
+            current: true,
+        });
+        displays
+    }
+
+    fn draw_2d(&self, vertices: &[Vertex2D], indices: &[u32], settings: DrawSettings2D) {
+        let window = self.active_window.get();
+        if window == WindowId::MAIN {
+            let sprites = self.sprites.borrow();
+            let texture = (settings.sprite)
+                .and_then(|sprite| sprites.get(sprite.inner() as usize))
+                .filter(|sprite| !sprite.pixels.is_empty());
+            rasterizer::draw_triangles(
+                &mut self.framebuffer.borrow_mut(),
+                vertices,
+                indices,
+                texture,
+                &settings,
+            );
+        }
+
+        self.draw_calls.borrow_mut().push(DrawCall {
+            window,
+            vertices: vertices.to_vec(),
+            indices: indices.to_vec(),
+            buffer_addresses: (vertices.as_ptr() as usize, indices.as_ptr() as usize),
+            settings,
+        });
+    }
 
     fn create_sprite(&self, width: u16, height: u16, format: PixelFormat) -> Option<SpriteRef> {
-        let fmt = match format {
-            PixelFormat::Rgba => 1,
-        };
-        Some(SpriteRef::new(
-            (fmt << 32) | ((width as u64) << 16) | (height as u64),
-        ))
+        match format {
+            PixelFormat::Rgba => {}
+        }
+        let mut sprites = self.sprites.borrow_mut();
+        sprites.push(TestSprite {
+            width,
+            height,
+            pixels: Vec::new(),
+        });
+        Some(SpriteRef::new(sprites.len() as u64 - 1))
     }
 
     fn update_sprite(
@@ -67,21 +320,35 @@ impl Platform for TestPlatform {
         height: u16,
         pixels: &[u8],
     ) {
-        let fmt = sprite.inner() >> 32;
-        let tex_width = ((sprite.inner() >> 16) & 0xFFFF) as u16;
-        let tex_height = (sprite.inner() & 0xFFFF) as u16;
-        assert!(x + width <= tex_width, "out of bounds sprite update");
-        assert!(y + height <= tex_height, "out of bounds sprite update");
-        match fmt {
-            1 => assert_eq!(width as u64 * height as u64 * 4, pixels.len() as u64),
-            _ => panic!("got an invalid SpriteRef, not from TestPlatform::create_sprite?"),
+        let mut sprites = self.sprites.borrow_mut();
+        let Some(sprite) = sprites.get_mut(sprite.inner() as usize) else {
+            panic!("got an invalid SpriteRef, not from TestPlatform::create_sprite?");
+        };
+        assert!(x + width <= sprite.width, "out of bounds sprite update");
+        assert!(y + height <= sprite.height, "out of bounds sprite update");
+        assert_eq!(width as u64 * height as u64 * 4, pixels.len() as u64);
+
+        let stride = sprite.width as usize * 4;
+        if sprite.pixels.is_empty() {
+            sprite.pixels = std::vec![0xFF; stride * sprite.height as usize];
+        }
+        let (x, width) = (x as usize * 4, width as usize * 4);
+        for (row, src) in pixels.chunks_exact(width).enumerate() {
+            let start = x + (y as usize + row) * stride;
+            sprite.pixels[start..start + width].copy_from_slice(src);
         }
     }
 
     fn open_file(&self, path: &str) -> Option<FileHandle> {
         match path {
-            "resources.db" => Some(FileHandle::new(4321)),
-            _ => None,
+            "resources.db" if !self.resources_db_missing.get() => {
+                Some(FileHandle::new(RESOURCES_DB_HANDLE))
+            }
+            _ => {
+                let extra_files = self.extra_files.borrow();
+                let index = extra_files.iter().position(|(name, _)| *name == path)?;
+                Some(FileHandle::new(EXTRA_FILE_HANDLES_START + index as u64))
+            }
         }
     }
 
@@ -91,25 +358,87 @@ impl Platform for TestPlatform {
         first_byte: u64,
         buffer: Box<[u8]>,
     ) -> FileReadTask {
-        FileReadTask::new(file, first_byte, buffer)
+        let mut file_reads = self.file_reads.borrow_mut();
+        let read_index = file_reads.len();
+        file_reads.push(TestFileRead {
+            first_byte,
+            fail: self.failing_file_reads.borrow().contains(&read_index),
+            pending_polls: self.file_read_latency.get(),
+        });
+        FileReadTask::new(file, read_index as u64, buffer)
     }
 
-    fn is_file_read_finished(&self, _task: &FileReadTask) -> bool {
-        true
+    fn is_file_read_finished(&self, task: &FileReadTask) -> bool {
+        let mut file_reads = self.file_reads.borrow_mut();
+        let read = &mut file_reads[task.task_id() as usize];
+        if read.pending_polls > 0 {
+            read.pending_polls -= 1;
+            false
+        } else {
+            true
+        }
     }
 
     fn finish_file_read(&self, task: FileReadTask) -> Result<Box<[u8]>, Box<[u8]>> {
-        static RESOURCES_DB: &[u8] = include_bytes!("../../example/resources.db");
-        if task.file().inner() != 4321 {
-            // Safety: this impl never shares the buffer anywhere.
-            return Err(unsafe { task.into_inner() });
-        }
-        let first_byte = task.task_id() as usize;
-        // Safety: never shared this buffer.
+        let file = task.file().inner();
+        let read = &self.file_reads.borrow()[task.task_id() as usize];
+        let first_byte = read.first_byte as usize;
+        // Safety: this impl never shares the buffer anywhere.
         let mut buffer = unsafe { task.into_inner() };
         let len = buffer.len();
-        buffer.copy_from_slice(&RESOURCES_DB[first_byte..first_byte + len]);
-        Ok(buffer)
+        let resources_db = self.resources_db.borrow();
+        let extra_files = self.extra_files.borrow();
+        let contents = if file == RESOURCES_DB_HANDLE {
+            Some(&resources_db[..])
+        } else {
+            let index = file.wrapping_sub(EXTRA_FILE_HANDLES_START) as usize;
+            extra_files.get(index).map(|(_, contents)| &contents[..])
+        };
+        match contents.and_then(|contents| contents.get(first_byte..first_byte + len)) {
+            Some(src) if !read.fail => {
+                buffer.copy_from_slice(src);
+                Ok(buffer)
+            }
+            _ => Err(buffer),
+        }
+    }
+
+    fn cancel_file_read(&self, task: FileReadTask) -> Box<[u8]> {
+        // Safety: this impl never shares the buffer anywhere.
+        unsafe { task.into_inner() }
+    }
+
+    fn begin_file_write(&self, path: &str, first_byte: u64, buffer: Box<[u8]>) -> FileWriteTask {
+        // The write is done right away, since the buffer is only borrowed here.
+        let mut extra_files = self.extra_files.borrow_mut();
+        let index = match extra_files.iter().position(|(name, _)| *name == path) {
+            Some(index) => index,
+            None => {
+                extra_files.push((String::from(path), Vec::new()));
+                extra_files.len() - 1
+            }
+        };
+        let contents = &mut extra_files[index].1;
+        let start = first_byte as usize;
+        let end = start + buffer.len();
+        if contents.len() < end {
+            contents.resize(end, 0);
+        }
+        contents[start..end].copy_from_slice(&buffer);
+        FileWriteTask::new(0, buffer)
+    }
+
+    fn is_file_write_finished(&self, _task: &FileWriteTask) -> bool {
+        true
+    }
+
+    fn finish_file_write(&self, task: FileWriteTask) -> Result<Box<[u8]>, Box<[u8]>> {
+        // Safety: this impl never shares the buffer anywhere.
+        Ok(unsafe { task.into_inner() })
+    }
+
+    fn persistent_data_dir(&self, _org: &str, _app: &str) -> Option<PathString> {
+        None
     }
 
     fn create_semaphore(&self) -> Semaphore {
@@ -134,14 +463,9 @@ impl Platform for TestPlatform {
                     task.signal_panic();
                 }
 
-                'send_result: loop {
-                    match result_sender.send(task) {
-                        Ok(()) => break 'send_result,
-                        Err(task_) => {
-                            std::thread::sleep(Duration::from_millis(1));
-                            task = task_;
-                        }
-                    }
+                // Only fails if the semaphore woke up spuriously, so retry.
+                while let Err(task_) = result_sender.send_blocking(task) {
+                    task = task_;
                 }
 
                 if let Err(err) = result {
@@ -165,6 +489,10 @@ impl Platform for TestPlatform {
         );
     }
 
+    fn audio_output(&self) -> AudioOutput {
+        AudioOutput::Stereo
+    }
+
     fn audio_playback_position(&self) -> (u64, Instant) {
         let time = self.current_time.get();
         let micros = time
@@ -192,6 +520,36 @@ impl Platform for TestPlatform {
         }
     }
 
+    fn gamepad_kind(&self, _device: InputDevice) -> GamepadKind {
+        GamepadKind::Generic
+    }
+
+    fn button_name(&self, button: Button) -> ButtonNameString {
+        let mut name = ButtonNameString::new();
+        // does not panic: "Button " and a u64 fit in the 32 bytes
+        write!(name, "Button {}", button.inner()).unwrap();
+        name
+    }
+
+    fn set_rumble(
+        &self,
+        _device: InputDevice,
+        _low_freq: f32,
+        _high_freq: f32,
+        _duration: Duration,
+    ) {
+    }
+
+    fn clipboard_text<'a>(&self, _buffer: &'a mut [u8]) -> Option<&'a str> {
+        None
+    }
+
+    fn set_clipboard_text(&self, _text: &str) {}
+
+    fn start_text_input(&self) {}
+
+    fn stop_text_input(&self) {}
+
     fn now(&self) -> Instant {
         self.current_time.get()
     }
@@ -202,7 +560,246 @@ impl Platform for TestPlatform {
         if !clean {
             panic!("TestPlatform::exit({clean}) was called (test ran into an error?)");
         }
+        self.exit_requested.set(true);
+    }
+}
+
+mod rasterizer {
+    use platform::{BlendMode, DrawSettings2D, Vertex2D};
+
+    use super::{TestSprite, FRAMEBUFFER_HEIGHT, FRAMEBUFFER_WIDTH};
+
+    /// Draws the triangles into the RGBA framebuffer, sampling each pixel at
+    /// its center.
+    pub fn draw_triangles(
+        framebuffer: &mut [u8],
+        vertices: &[Vertex2D],
+        indices: &[u32],
+        texture: Option<&TestSprite>,
+        settings: &DrawSettings2D,
+    ) {
+        let [clip_x, clip_y, clip_w, clip_h] = settings.clip_area.unwrap_or([
+            0.0,
+            0.0,
+            FRAMEBUFFER_WIDTH as f32,
+            FRAMEBUFFER_HEIGHT as f32,
+        ]);
+
+        for triangle in indices.chunks_exact(3) {
+            let a = &vertices[triangle[0] as usize];
+            let mut b = &vertices[triangle[1] as usize];
+            let mut c = &vertices[triangle[2] as usize];
+
+            // Make the winding consistent, so that the edges shared between
+            // triangles always go in opposite directions, which the tie-break
+            // in `covers` relies on.
+            let area = edge(a, b, c.x, c.y);
+            if area == 0.0 {
+                continue;
+            } else if area < 0.0 {
+                (b, c) = (c, b);
+            }
+            let area = area.abs();
+
+            // The range of pixels whose centers are within the bounding box of
+            // the triangle and the clip area
+            let min_x = a.x.min(b.x).min(c.x).max(clip_x);
+            let max_x = a.x.max(b.x).max(c.x).min(clip_x + clip_w);
+            let min_y = a.y.min(b.y).min(c.y).max(clip_y);
+            let max_y = a.y.max(b.y).max(c.y).min(clip_y + clip_h);
+            let pixels_x = pixel_range(min_x, max_x, FRAMEBUFFER_WIDTH);
+            let pixels_y = pixel_range(min_y, max_y, FRAMEBUFFER_HEIGHT);
+
+            for y in pixels_y {
+                for x in pixels_x.clone() {
+                    let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+                    let (w_a, w_b, w_c) =
+                        (edge(b, c, px, py), edge(c, a, px, py), edge(a, b, px, py));
+                    if !(covers(w_a, b, c) && covers(w_b, c, a) && covers(w_c, a, b)) {
+                        continue;
+                    }
+                    let (w_a, w_b, w_c) = (w_a / area, w_b / area, w_c / area);
+                    let lerp = |f: fn(&Vertex2D) -> f32| w_a * f(a) + w_b * f(b) + w_c * f(c);
+
+                    let mut color = [
+                        lerp(|v| v.r as f32),
+                        lerp(|v| v.g as f32),
+                        lerp(|v| v.b as f32),
+                        lerp(|v| v.a as f32),
+                    ];
+                    if let Some(texture) = texture {
+                        let texel = sample(texture, lerp(|v| v.u), lerp(|v| v.v));
+                        for (channel, texel) in color.iter_mut().zip(texel) {
+                            *channel *= texel as f32 / 255.0;
+                        }
+                    }
+
+                    let i = (x + y * FRAMEBUFFER_WIDTH) * 4;
+                    blend(&mut framebuffer[i..i + 4], color, settings.blend_mode);
+                }
+            }
+        }
     }
+
+    /// Returns twice the signed area of the triangle `(from, to, (x, y))`,
+    /// which is positive when the point is on the same side of the edge as
+    /// the rest of a consistently wound triangle.
+    fn edge(from: &Vertex2D, to: &Vertex2D, x: f32, y: f32) -> f32 {
+        (to.x - from.x) * (y - from.y) - (to.y - from.y) * (x - from.x)
+    }
+
+    /// Returns true if a pixel with the given [`edge`] value for the edge is on
+    /// the inner side of the edge. Pixels exactly on the edge are only drawn
+    /// for edges going in one direction, so that pixels on an edge shared by
+    /// two triangles are drawn once.
+    fn covers(edge: f32, from: &Vertex2D, to: &Vertex2D) -> bool {
+        let (dx, dy) = (to.x - from.x, to.y - from.y);
+        edge > 0.0 || (edge == 0.0 && (dy > 0.0 || (dy == 0.0 && dx > 0.0)))
+    }
+
+    /// Returns the range of pixels whose centers are between `min` and `max`.
+    fn pixel_range(min: f32, max: f32, len: usize) -> core::ops::Range<usize> {
+        // Float to int casts saturate, so negative values turn into 0.
+        let start = ((min - 0.5).ceil() as usize).min(len);
+        let end = ((max - 0.5).ceil() as usize).min(len);
+        start..end.max(start)
+    }
+
+    /// Returns the texel nearest to the texture coordinates.
+    fn sample(texture: &TestSprite, u: f32, v: f32) -> [u8; 4] {
+        let x = ((u * texture.width as f32) as usize).min(texture.width as usize - 1);
+        let y = ((v * texture.height as f32) as usize).min(texture.height as usize - 1);
+        let i = (x + y * texture.width as usize) * 4;
+        let mut texel = [0; 4];
+        texel.copy_from_slice(&texture.pixels[i..i + 4]);
+        texel
+    }
+
+    /// Blends the color into the pixel as specified by the [`BlendMode`].
+    fn blend(dst: &mut [u8], src: [f32; 4], blend_mode: BlendMode) {
+        let src_alpha = src[3] / 255.0;
+        let blended = match blend_mode {
+            BlendMode::None => src,
+            BlendMode::Blend => {
+                let dst_factor = 1.0 - src_alpha;
+                [
+                    src[0] * src_alpha + dst[0] as f32 * dst_factor,
+                    src[1] * src_alpha + dst[1] as f32 * dst_factor,
+                    src[2] * src_alpha + dst[2] as f32 * dst_factor,
+                    src[3] + dst[3] as f32 * dst_factor,
+                ]
+            }
+            BlendMode::Add => [
+                src[0] * src_alpha + dst[0] as f32,
+                src[1] * src_alpha + dst[1] as f32,
+                src[2] * src_alpha + dst[2] as f32,
+                dst[3] as f32,
+            ],
+            BlendMode::Multiply => {
+                let dst_factor = 1.0 - src_alpha;
+                [
+                    (src[0] / 255.0 + dst_factor) * dst[0] as f32,
+                    (src[1] / 255.0 + dst_factor) * dst[1] as f32,
+                    (src[2] / 255.0 + dst_factor) * dst[2] as f32,
+                    dst[3] as f32,
+                ]
+            }
+            BlendMode::PremultipliedAlpha => {
+                let dst_factor = 1.0 - src_alpha;
+                [
+                    src[0] + dst[0] as f32 * dst_factor,
+                    src[1] + dst[1] as f32 * dst_factor,
+                    src[2] + dst[2] as f32 * dst_factor,
+                    src[3] + dst[3] as f32 * dst_factor,
+                ]
+            }
+        };
+        for (dst, value) in dst.iter_mut().zip(blended) {
+            // Float to int casts saturate, clamping the values to 0..=255.
+            *dst = (value + 0.5) as u8;
+        }
+    }
+}
+
+/// Returns the bytes of a resource database containing just one audio clip
+/// with the given name and amount of chunks. The samples in each chunk are the
+/// index of the chunk plus one.
+pub fn audio_clip_database(name: &str, chunks: u32) -> Vec<u8> {
+    fn serialized<S: Serialize>(value: &S) -> Vec<u8> {
+        let mut bytes = std::vec![0; S::SERIALIZED_SIZE];
+        value.serialize(&mut bytes);
+        bytes
+    }
+    fn checksum(bytes: &[u8]) -> u32 {
+        let mut crc = Crc32::new();
+        crc.update(bytes);
+        crc.finish()
+    }
+
+    let mut metadata = Vec::new();
+    let mut chunk_data = Vec::new();
+    for i in 0..chunks {
+        let start = chunk_data.len() as u64;
+        let sample = [i as i16 + 1; AUDIO_CHANNELS];
+        for _ in 0..AUDIO_SAMPLES_PER_CHUNK {
+            chunk_data.extend_from_slice(bytemuck::bytes_of(&sample));
+        }
+        let source_bytes = start..chunk_data.len() as u64;
+        metadata.extend(serialized(&ChunkDescriptor { source_bytes }));
+    }
+    metadata.extend(serialized(&NamedAsset {
+        name: ArrayString::from(name).unwrap(),
+        asset: AudioClipAsset {
+            samples: chunks * AUDIO_SAMPLES_PER_CHUNK as u32,
+            chunks: 0..chunks,
+        },
+    }));
+
+    let header = ResourceDatabaseHeader {
+        version: RESOURCE_DB_VERSION,
+        chunks,
+        sprite_chunks: 0,
+        sprites: 0,
+        audio_clips: 1,
+        fonts: 0,
+        animations: 0,
+        tilemaps: 0,
+        metadata_checksum: checksum(&metadata),
+        chunk_data_checksum: checksum(&chunk_data),
+    };
+    [serialized(&header), metadata, chunk_data].concat()
+}
+
+/// Creates an engine with room for `max_loaded` sprite chunks, and loads the
+/// first `count` sprite chunks of the "player" sprite, which is made up of
+/// multiple chunks. Returns the engine, the sprite, and all of its chunks.
+pub fn engine_with_loaded_chunks<'a>(
+    platform: &'a TestPlatform,
+    arena: &'static LinearAllocator,
+    count: u32,
+    max_loaded: u32,
+) -> (Engine<'a>, SpriteHandle, Range<u32>) {
+    let limits = EngineLimits {
+        frame_arena_size: 256 * 1024,
+        resource_database_loaded_chunks_count: 4,
+        resource_database_loaded_sprite_chunks_count: max_loaded,
+        resource_database_buffer_size: 512 * 1024,
+        ..EngineLimits::DEFAULT
+    };
+    let mut engine = Engine::new(platform, arena, limits);
+    let sprite = engine.resource_db.find_sprite("player").unwrap();
+    let chunks = (engine.resource_db.get_sprite(sprite).unwrap())
+        .get_sprite_chunks()
+        .unwrap();
+    assert!(chunks.len() > 1, "test requires a sprite with many chunks");
+    assert!(chunks.len() >= count as usize, "test sprite is too small");
+    for chunk in chunks.start..chunks.start + count {
+        let resources = &engine.resource_db;
+        engine.resource_loader.queue_sprite_chunk(chunk, resources);
+    }
+    engine.resource_loader.dispatch_reads(platform);
+    (engine.resource_loader).finish_reads(&mut engine.resource_db, platform, 128);
+    (engine, sprite, chunks)
 }
 
 mod semaphore {
@@ -230,6 +827,44 @@ mod semaphore {
             }
             *value_lock -= 1;
         }
+
+        // Locking doesn't wait for increments, as the lock is only held
+        // briefly to update the value. Using try_lock instead could miss
+        // increments if the lock happens to be held by another thread.
+        fn try_decrement(&self) -> bool {
+            let mut value_lock = self.value.lock().unwrap();
+            if *value_lock > 0 {
+                *value_lock -= 1;
+                true
+            } else {
+                false
+            }
+        }
+
+        fn decrement_timeout(&self, timeout: core::time::Duration) -> bool {
+            let deadline = std::time::Instant::now().checked_add(timeout);
+            let mut value_lock = self.value.lock().unwrap();
+            // Loop to handle spurious wakeups, and increments which were taken
+            // by another thread before this one got the lock.
+            while *value_lock == 0 {
+                let Some(deadline) = deadline else {
+                    // The timeout is too long to have a deadline, so it can't
+                    // elapse either.
+                    value_lock = self.condvar.wait(value_lock).unwrap();
+                    continue;
+                };
+                let now = std::time::Instant::now();
+                if now >= deadline {
+                    return false;
+                }
+                (value_lock, _) = self
+                    .condvar
+                    .wait_timeout(value_lock, deadline - now)
+                    .unwrap();
+            }
+            *value_lock -= 1;
+            true
+        }
     }
 
     pub fn create() -> platform::Semaphore {
@@ -242,3 +877,154 @@ mod semaphore {
         unsafe { platform::Semaphore::new(semaphore, None) }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use core::time::Duration;
+
+    use platform::{BlendMode, Box, DrawSettings2D, PixelFormat, Platform, Vertex2D, WindowId};
+
+    use super::{TestPlatform, FRAMEBUFFER_WIDTH};
+
+    #[test]
+    fn try_decrement_only_succeeds_after_an_increment() {
+        let platform = TestPlatform::new(false);
+        let semaphore = platform.create_semaphore();
+        assert!(!semaphore.try_decrement());
+        semaphore.increment();
+        assert!(semaphore.try_decrement());
+        assert!(!semaphore.try_decrement());
+    }
+
+    #[test]
+    fn decrement_timeout_gives_up_without_an_increment() {
+        let platform = TestPlatform::new(false);
+        let semaphore = platform.create_semaphore();
+        assert!(!semaphore.decrement_timeout(Duration::from_millis(10)));
+        semaphore.increment();
+        assert!(semaphore.decrement_timeout(Duration::from_millis(10)));
+    }
+
+    #[test]
+    fn decrement_timeout_accepts_timeouts_without_a_deadline() {
+        let platform = TestPlatform::new(false);
+        let semaphore = platform.create_semaphore();
+        semaphore.increment();
+        assert!(semaphore.decrement_timeout(Duration::MAX));
+    }
+
+    #[test]
+    fn written_files_can_be_read_back() {
+        let platform = TestPlatform::new(false);
+        let write = |first_byte: u64, bytes: &[u8]| {
+            let buffer = Box::from_mut(bytes.to_vec().leak());
+            let task = platform.begin_file_write("save.dat", first_byte, buffer);
+            assert!(platform.is_file_write_finished(&task));
+            platform.finish_file_write(task).unwrap();
+        };
+        write(0, b"hello, world");
+        write(7, b"there");
+
+        let file = platform.open_file("save.dat").unwrap();
+        let buffer = Box::from_mut(std::vec![0; 12].leak());
+        let task = platform.begin_file_read(file, 0, buffer);
+        assert!(platform.is_file_read_finished(&task));
+        assert_eq!(b"hello, there", &*platform.finish_file_read(task).unwrap());
+    }
+
+    #[test]
+    fn draws_go_to_the_active_window() {
+        let platform = TestPlatform::new(false);
+        let debug_window = platform.create_window("Debug", 320, 240).unwrap();
+        assert_ne!(WindowId::MAIN, debug_window);
+        let vertices = [
+            Vertex2D::new(0.0, 0.0, 0.0, 0.0),
+            Vertex2D::new(8.0, 0.0, 1.0, 0.0),
+            Vertex2D::new(8.0, 8.0, 1.0, 1.0),
+        ];
+
+        platform.set_active_window(debug_window);
+        platform.draw_2d(&vertices, &[0, 1, 2], DrawSettings2D::default());
+        assert!(platform.framebuffer().iter().all(|&c| c == 0));
+
+        platform.set_active_window(WindowId::MAIN);
+        platform.draw_2d(&vertices, &[0, 1, 2], DrawSettings2D::default());
+        assert!(platform.framebuffer().iter().any(|&c| c != 0));
+
+        let draw_calls = platform.take_draw_calls();
+        assert_eq!(2, draw_calls.len());
+        assert_eq!(debug_window, draw_calls[0].window);
+        assert_eq!(WindowId::MAIN, draw_calls[1].window);
+    }
+
+    #[test]
+    fn multiply_darkens_except_where_transparent() {
+        let platform = TestPlatform::new(false);
+        let quad = |x: f32, [r, g, b, a]: [u8; 4]| {
+            [(0.0, 0.0), (4.0, 0.0), (4.0, 4.0), (0.0, 4.0)].map(|(dx, y)| Vertex2D {
+                r,
+                g,
+                b,
+                a,
+                ..Vertex2D::new(x + dx, y, 0.0, 0.0)
+            })
+        };
+        let draw = |vertices: [Vertex2D; 4], blend_mode| {
+            let settings = DrawSettings2D {
+                blend_mode,
+                ..Default::default()
+            };
+            platform.draw_2d(&vertices, &[0, 1, 2, 0, 2, 3], settings);
+        };
+
+        let background = [200, 100, 50, 255];
+        draw(quad(0.0, background), BlendMode::None);
+        draw(quad(4.0, background), BlendMode::None);
+        draw(quad(0.0, [128, 128, 128, 255]), BlendMode::Multiply);
+        draw(quad(4.0, [0, 0, 0, 0]), BlendMode::Multiply);
+
+        let framebuffer = platform.framebuffer();
+        let pixel = |x: usize| [0, 1, 2, 3].map(|c| framebuffer[x * 4 + c]);
+        assert_eq!([100, 50, 25, 255], pixel(1));
+        assert_eq!(background, pixel(5));
+    }
+
+    #[test]
+    fn sprites_are_sampled_within_the_clip_area() {
+        let platform = TestPlatform::new(false);
+        let sprite = platform.create_sprite(2, 1, PixelFormat::Rgba).unwrap();
+        let (red, blue) = ([0xFF, 0, 0, 0xFF], [0, 0, 0xFF, 0xFF]);
+        platform.update_sprite(sprite, 0, 0, 2, 1, &[red, blue].concat());
+
+        // The left half of the quad shows the red texel, the right half blue,
+        // and the clip area cuts off the leftmost 2 and topmost 3 columns and
+        // rows.
+        let vertices = [
+            Vertex2D::new(0.0, 0.0, 0.0, 0.0),
+            Vertex2D::new(8.0, 0.0, 1.0, 0.0),
+            Vertex2D::new(8.0, 8.0, 1.0, 1.0),
+            Vertex2D::new(0.0, 8.0, 0.0, 1.0),
+        ];
+        let settings = DrawSettings2D {
+            sprite: Some(sprite),
+            clip_area: Some([2.0, 3.0, 100.0, 100.0]),
+            ..Default::default()
+        };
+        platform.draw_2d(&vertices, &[0, 1, 2, 0, 2, 3], settings);
+
+        let framebuffer = platform.framebuffer();
+        let pixel = |x: usize, y: usize| {
+            let i = (x + y * FRAMEBUFFER_WIDTH) * 4;
+            [0, 1, 2, 3].map(|c| framebuffer[i + c])
+        };
+        assert_eq!([0; 4], pixel(1, 5));
+        assert_eq!([0; 4], pixel(5, 2));
+        assert_eq!(red, pixel(2, 3));
+        assert_eq!(red, pixel(3, 7));
+        assert_eq!(blue, pixel(4, 3));
+        assert_eq!(blue, pixel(7, 7));
+        assert_eq!([0; 4], pixel(8, 7));
+    }
+}
@@ -2,6 +2,8 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+use std::ops::ControlFlow;
+
 use bytemuck::{Pod, Zeroable};
 use engine::{
     allocators::LinearAllocator,
@@ -21,7 +23,7 @@ use platform::{ActionCategory, Event, Instant, Platform};
 fn main() {
     use engine::{
         allocators::{static_allocator, LinearAllocator},
-        Engine, EngineLimits,
+        Engine, EngineLimits, Game as _,
     };
     use platform_sdl2::Sdl2Platform;
 
@@ -40,11 +42,19 @@ fn main() {
 
     static PERSISTENT_ARENA: &LinearAllocator = static_allocator!(64 * 1024 * 1024);
     let mut engine = Engine::new(&platform, PERSISTENT_ARENA, EngineLimits::DEFAULT);
-    let game_arena = LinearAllocator::new(PERSISTENT_ARENA, 8 * 1024 * 1024).unwrap();
-    let mut game = Game::new(&game_arena, &engine);
-    platform.run_game_loop(&mut engine, |_, platform, engine| {
-        run_frame(&mut game, platform, engine);
-    });
+    let mut game_arena = LinearAllocator::new(PERSISTENT_ARENA, 8 * 1024 * 1024).unwrap();
+    loop {
+        // The previous game (if any) has been dropped at this point, so its
+        // memory can be reused for the reinitialized game.
+        game_arena.reset();
+        let mut game = Game::init(&game_arena, &engine, ());
+        let result = platform.run_game_loop(&mut engine, |timestamp, platform, engine| {
+            game.run_frame(timestamp, platform, engine)
+        });
+        if !matches!(result, Some(Some(()))) {
+            break;
+        }
+    }
 }
 
 #[cfg(not(any(feature = "sdl2")))]
@@ -169,8 +179,10 @@ struct Game<'a> {
     scene: Scene<'a>,
 }
 
-impl<'a> Game<'a> {
-    fn new(arena: &'a LinearAllocator, engine: &Engine) -> Self {
+impl<'a> engine::Game<'a> for Game<'a> {
+    type InitParams = ();
+
+    fn init(arena: &'a LinearAllocator, engine: &Engine, _: ()) -> Self {
         let player_sprite = engine.resource_db.find_sprite("player").unwrap();
         let whack_sound = engine.resource_db.find_audio_clip("whack").unwrap();
 
@@ -186,69 +198,83 @@ impl<'a> Game<'a> {
             prev_frame: Instant::reference(),
         }
     }
+
+    fn run_frame(
+        &mut self,
+        timestamp: Instant,
+        platform: &dyn Platform,
+        engine: &mut Engine,
+    ) -> ControlFlow<Option<()>> {
+        run_frame(self, timestamp, platform, engine);
+        ControlFlow::Continue(())
+    }
 }
 
-fn run_frame(game: &mut Game, platform: &dyn Platform, engine: &mut Engine) {
-    let now = platform.now();
-    let delta_millis = now
-        .duration_since(game.prev_frame)
-        .unwrap()
-        .as_millis()
-        .min(50) as i32;
+fn run_frame(game: &mut Game, now: Instant, platform: &dyn Platform, engine: &mut Engine) {
+    // The engine's timestamps don't advance while it's paused, so this is only
+    // clamped to avoid e.g. the ball going through the players after lagspikes.
+    let delta_millis = game.prev_frame.elapsed(now).as_millis().min(50) as i32;
     game.prev_frame = now;
 
     let (screen_width, screen_height) = platform.draw_area();
     let scale_factor = platform.draw_scale_factor();
-    let mut draw_queue = DrawQueue::new(&engine.frame_arena, 100, scale_factor).unwrap();
 
     let mut reset_game_requested = false;
-    for event in &*engine.event_queue {
-        match event.event {
-            Event::DigitalInputPressed(device, _) | Event::DigitalInputReleased(device, _) => {
-                {
-                    if game
-                        .player_inputs
-                        .iter()
-                        .any(|input| input.device == device)
-                    {
-                        continue;
-                    }
+    let mut focused = None;
+    for event in &engine.event_queue {
+        if let Event::FocusChanged { focused: focus, .. } = event.event {
+            focused = Some(focus);
+        }
 
-                    reset_game_requested = true;
-                    game.player_inputs.push(InputDeviceState {
-                        device,
-                        actions: [
-                            // Input::MoveUp
-                            ActionState {
-                                kind: ActionKind::Held,
-                                mapping: platform
-                                    .default_button_for_action(ActionCategory::Up, device),
-                                disabled: false,
-                                pressed: false,
-                            },
-                            // Input::MoveDown
-                            ActionState {
-                                kind: ActionKind::Held,
-                                mapping: platform
-                                    .default_button_for_action(ActionCategory::Down, device),
-                                disabled: false,
-                                pressed: false,
-                            },
-                            // Input::Reset
-                            ActionState {
-                                kind: ActionKind::Instant,
-                                mapping: platform
-                                    .default_button_for_action(ActionCategory::Jump, device),
-                                disabled: false,
-                                pressed: false,
-                            },
-                        ],
-                    });
-                }
+        if let Event::DigitalInputPressed(device, _) | Event::DigitalInputReleased(device, _) =
+            event.event
+        {
+            if game
+                .player_inputs
+                .iter()
+                .any(|input| input.device == device)
+            {
+                continue;
             }
+
+            reset_game_requested = true;
+            game.player_inputs.push(InputDeviceState {
+                device,
+                actions: [
+                    // Input::MoveUp
+                    ActionState::new(
+                        ActionKind::Held,
+                        platform.default_button_for_action(ActionCategory::Up, device),
+                    ),
+                    // Input::MoveDown
+                    ActionState::new(
+                        ActionKind::Held,
+                        platform.default_button_for_action(ActionCategory::Down, device),
+                    ),
+                    // Input::Reset
+                    ActionState {
+                        secondary_mapping: platform
+                            .default_button_for_action(ActionCategory::Pause, device),
+                        ..ActionState::new(
+                            ActionKind::Instant,
+                            platform.default_button_for_action(ActionCategory::Jump, device),
+                        )
+                    },
+                ],
+                analog_actions: [],
+            });
         }
     }
 
+    // Pause the game while the window is not focused
+    match focused {
+        Some(false) => engine.pause(),
+        Some(true) => engine.resume(),
+        None => {}
+    }
+
+    let mut draw_queue = DrawQueue::new(&engine.frame_arena, 100, scale_factor).unwrap();
+
     for input in &mut game.player_inputs {
         input.update(&mut engine.event_queue);
     }
@@ -381,7 +407,7 @@ fn run_frame(game: &mut Game, platform: &dyn Platform, engine: &mut Engine) {
                     }
                     engine
                         .audio_mixer
-                        .play_clip(0, game.whack_sound, true, &engine.resource_db);
+                        .play_clip(0, game.whack_sound, 0, &engine.resource_db);
                 } else {
                     pos.x += dx;
                     pos.y += dy;
@@ -394,7 +420,9 @@ fn run_frame(game: &mut Game, platform: &dyn Platform, engine: &mut Engine) {
     game.scene.run_system(define_system!(
         |_, sprites: &[Sprite], positions: &[Position], colliders: &[Collider]| {
             for ((sprite, pos), collider) in sprites.iter().zip(positions).zip(colliders) {
-                let sprite = engine.resource_db.get_sprite(game.sprites[sprite.0]);
+                let Some(sprite) = engine.resource_db.get_sprite(game.sprites[sprite.0]) else {
+                    continue;
+                };
                 debug_assert!(sprite.draw(
                     Rect::around(
                         pos.x as f32,
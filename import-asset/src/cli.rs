@@ -25,6 +25,44 @@ pub enum Command {
         /// The image file to import
         #[bpaf(argument("FILE"), complete_shell(ShellComp::File { mask: None }))]
         file: PathBuf,
+        /// Draw the sprite with nearest neighbor filtering instead of linear
+        /// filtering by default, to keep pixel art sharp when scaled
+        #[serde(default)]
+        nearest_neighbor: bool,
+    },
+    /// Adds all the images in a directory into the resource database as
+    /// sprites, named after the files (without the extensions)
+    #[bpaf(command("add-sprite-dir"))]
+    AddSpriteDir {
+        /// The directory containing the image files to import
+        #[bpaf(argument("DIR"), complete_shell(ShellComp::Dir { mask: None }))]
+        dir: PathBuf,
+        /// Prepended to the file names to make up the names of the sprites
+        #[bpaf(argument("PREFIX"))]
+        name_prefix: Option<String>,
+        /// Draw the sprites with nearest neighbor filtering instead of linear
+        /// filtering by default, to keep pixel art sharp when scaled
+        #[serde(default)]
+        nearest_neighbor: bool,
+    },
+    /// Packs all the images in a directory into shared sprite chunks, and adds
+    /// them into the resource database as sprites named after the files
+    /// (without the extensions). Saves memory compared to add-sprite-dir when
+    /// there's many small sprites, but each image must fit in a sprite chunk.
+    #[bpaf(command("add-sprite-atlas"))]
+    AddSpriteAtlas {
+        /// The name of the atlas (used to remove the sprites, not to load them)
+        name: ArrayString<ASSET_NAME_LENGTH>,
+        /// The directory containing the image files to import
+        #[bpaf(argument("DIR"), complete_shell(ShellComp::Dir { mask: None }))]
+        dir: PathBuf,
+        /// Prepended to the file names to make up the names of the sprites
+        #[bpaf(argument("PREFIX"))]
+        name_prefix: Option<String>,
+        /// Draw the sprites with nearest neighbor filtering instead of linear
+        /// filtering by default, to keep pixel art sharp when scaled
+        #[serde(default)]
+        nearest_neighbor: bool,
     },
     /// Adds a new audio clip into the resource database
     #[bpaf(command("add-audio"))]
@@ -39,6 +77,68 @@ pub enum Command {
         #[bpaf(argument("NUMBER"))]
         track: Option<usize>,
     },
+    /// Adds a new bitmap font into the resource database
+    #[bpaf(command("add-font"))]
+    AddFont {
+        /// The name of the font (used to load it in game code)
+        name: ArrayString<ASSET_NAME_LENGTH>,
+        /// The TrueType or OpenType font file to import
+        #[bpaf(argument("FILE"), complete_shell(ShellComp::File { mask: None }))]
+        file: PathBuf,
+        /// The size to rasterize the glyphs at, in pixels per em
+        #[bpaf(argument("PIXELS"))]
+        size: u16,
+    },
+    /// Adds a new animation into the resource database, cut from a sprite
+    /// sheet with the frames listed in a JSON file next to it (the same path
+    /// with a .json extension)
+    #[bpaf(command("add-animation"))]
+    AddAnimation {
+        /// The name of the animation (used to load it in game code)
+        name: ArrayString<ASSET_NAME_LENGTH>,
+        /// The sprite sheet image file to import
+        #[bpaf(argument("FILE"), complete_shell(ShellComp::File { mask: None }))]
+        file: PathBuf,
+        /// Draw the animation with nearest neighbor filtering instead of linear
+        /// filtering by default, to keep pixel art sharp when scaled
+        #[serde(default)]
+        nearest_neighbor: bool,
+    },
+    /// Adds a new tilemap into the resource database, from a map exported from
+    /// the Tiled map editor as JSON or CSV (the tileset sprite is imported
+    /// separately, e.g. with add-sprite)
+    #[bpaf(command("add-tilemap"))]
+    AddTilemap {
+        /// The name of the tilemap (used to load it in game code)
+        name: ArrayString<ASSET_NAME_LENGTH>,
+        /// The .json, .tmj or .csv map file to import
+        #[bpaf(argument("FILE"), complete_shell(ShellComp::File { mask: None }))]
+        file: PathBuf,
+        /// The width of the tiles in pixels (required for CSV maps, overrides
+        /// the tileset's value for JSON maps, like the other tileset options)
+        #[bpaf(argument("PIXELS"))]
+        tile_width: Option<u16>,
+        /// The height of the tiles in pixels
+        #[bpaf(argument("PIXELS"))]
+        tile_height: Option<u16>,
+        /// The amount of pixels between the edges of the tileset and the tiles
+        /// (defaults to 0 for CSV maps)
+        #[bpaf(argument("PIXELS"))]
+        margin: Option<u16>,
+        /// The amount of pixels between adjacent tiles in the tileset (defaults
+        /// to 0 for CSV maps)
+        #[bpaf(argument("PIXELS"))]
+        spacing: Option<u16>,
+        /// The amount of tiles on each row of the tileset
+        #[bpaf(argument("TILES"))]
+        columns: Option<u16>,
+    },
+    /// Removes an asset from the resource database
+    #[bpaf(command("remove"))]
+    Remove {
+        /// The name of the asset to remove
+        name: ArrayString<ASSET_NAME_LENGTH>,
+    },
 }
 
 impl Command {
@@ -47,8 +147,17 @@ impl Command {
     pub fn asset_name(&self) -> Option<&str> {
         match self {
             Command::Reimport {} => None,
+            // Imports multiple assets, each with their own AddSprite command
+            Command::AddSpriteDir { .. } => None,
+            // Removes the asset's import command rather than replacing it
+            Command::Remove { .. } => None,
             Command::AddSprite { name, .. } => Some(name),
+            // Not an asset itself, but imported and removed like one
+            Command::AddSpriteAtlas { name, .. } => Some(name),
             Command::AddAudioClip { name, .. } => Some(name),
+            Command::AddFont { name, .. } => Some(name),
+            Command::AddAnimation { name, .. } => Some(name),
+            Command::AddTilemap { name, .. } => Some(name),
         }
     }
 }
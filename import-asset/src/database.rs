@@ -2,12 +2,20 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
-use std::io::{self, Cursor, Write};
+use std::{
+    io::{self, Cursor, Write},
+    ops::Range,
+};
 
 use anyhow::Context;
 use engine::resources::{
-    audio_clip::AudioClipAsset, sprite::SpriteAsset, Asset, ChunkDescriptor, Deserialize,
-    NamedAsset, ResourceDatabaseHeader, Serialize, SpriteChunkDescriptor,
+    animation::AnimationAsset,
+    audio_clip::AudioClipAsset,
+    font::FontAsset,
+    sprite::{SpriteAsset, SpriteAssetV2},
+    tilemap::TilemapAsset,
+    try_deserialize, Asset, ChunkDescriptor, Crc32, Deserialize, NamedAsset,
+    ResourceDatabaseHeader, Serialize, SpriteChunkDescriptor, RESOURCE_DB_VERSION,
 };
 use tracing::{debug, trace};
 
@@ -19,8 +27,11 @@ pub struct RelatedChunkData {
 }
 
 impl RelatedChunkData {
-    fn new<T: Asset>(
-        asset: &mut T,
+    /// Copies the chunks of the assets out of the database, and offsets the
+    /// assets' chunk indices to point into the copied chunks. The assets can
+    /// share chunks, e.g. sprites packed into an atlas.
+    fn new(
+        assets: &mut [&mut dyn Asset],
         chunk_descs: &[ChunkDescriptor],
         sprite_chunk_descs: &[SpriteChunkDescriptor],
         chunk_data: &[u8],
@@ -29,7 +40,7 @@ impl RelatedChunkData {
         let mut related_sprite_chunks = Vec::new();
         let mut related_chunk_data = Vec::new();
 
-        if let Some(chunk_range) = asset.get_chunks() {
+        if let Some(chunk_range) = range_union(assets.iter().map(|asset| asset.get_chunks())) {
             debug!("Reading chunk range: {chunk_range:?}");
             let start = related_chunks.len() as u32;
             for i in chunk_range.clone() {
@@ -43,11 +54,14 @@ impl RelatedChunkData {
                 desc.source_bytes = start..end;
                 related_chunks.push(desc);
             }
-            asset.offset_chunks(start as i32 - chunk_range.start as i32);
+            for asset in &mut *assets {
+                asset.offset_chunks(start as i32 - chunk_range.start as i32);
+            }
             debug!("Copied over {} chunks.", related_chunks.len());
         }
 
-        if let Some(chunk_range) = asset.get_sprite_chunks() {
+        let sprite_chunk_ranges = assets.iter().map(|asset| asset.get_sprite_chunks());
+        if let Some(chunk_range) = range_union(sprite_chunk_ranges) {
             debug!("Reading sprite chunk range: {chunk_range:?}");
             let start = related_sprite_chunks.len() as u32;
             for i in chunk_range.clone() {
@@ -61,7 +75,9 @@ impl RelatedChunkData {
                 desc.source_bytes = start..end;
                 related_sprite_chunks.push(desc);
             }
-            asset.offset_chunks(start as i32 - chunk_range.start as i32);
+            for asset in &mut *assets {
+                asset.offset_sprite_chunks(start as i32 - chunk_range.start as i32);
+            }
             debug!("Copied over {} sprite chunks.", related_sprite_chunks.len());
         }
 
@@ -87,6 +103,12 @@ pub struct Database {
     // Asset metadata
     pub sprites: Vec<(NamedAsset<SpriteAsset>, RelatedChunkData)>,
     pub audio_clips: Vec<(NamedAsset<AudioClipAsset>, RelatedChunkData)>,
+    pub fonts: Vec<(NamedAsset<FontAsset>, RelatedChunkData)>,
+    pub animations: Vec<(NamedAsset<AnimationAsset>, RelatedChunkData)>,
+    pub tilemaps: Vec<(NamedAsset<TilemapAsset>, RelatedChunkData)>,
+    /// Groups of sprites sharing sprite chunks, i.e. the sprites imported with
+    /// [`sprite::import_atlas`](crate::importers::sprite::import_atlas).
+    pub sprite_atlases: Vec<(Vec<NamedAsset<SpriteAsset>>, RelatedChunkData)>,
 }
 
 impl Database {
@@ -94,9 +116,19 @@ impl Database {
         if let Some(db) = db_file {
             debug!("Parsing the database.");
 
-            let mut cursor = 0;
-            let header = read_deserializable::<ResourceDatabaseHeader>(db, &mut cursor)
+            let header = ResourceDatabaseHeader::try_deserialize(db)
+                .map_err(|err| anyhow::anyhow!("{err}"))
                 .context("Failed to read resource database header")?;
+            let header_size = ResourceDatabaseHeader::size_for_version(header.version);
+            let metadata = db
+                .get(header_size..header.chunk_data_offset() as usize)
+                .context("Resource database is truncated")?;
+            if checksum(metadata) != header.metadata_checksum {
+                return Err(anyhow::anyhow!(
+                    "Resource database asset metadata does not match its checksum"
+                ));
+            }
+            let mut cursor = header_size;
 
             let mut chunk_descriptors = Vec::with_capacity(header.chunks as usize);
             for _ in 0..header.chunks {
@@ -113,6 +145,13 @@ impl Database {
             }
 
             let chunk_data = &db[header.chunk_data_offset() as usize..];
+            let referenced_chunk_data =
+                referenced_chunk_data(&chunk_descriptors, &sprite_chunk_descriptors, chunk_data);
+            if checksum(referenced_chunk_data) != header.chunk_data_checksum {
+                return Err(anyhow::anyhow!(
+                    "Resource database chunk data does not match its checksum"
+                ));
+            }
             debug!(
                 "The database seems to have {} bytes of chunk data.",
                 chunk_data.len(),
@@ -124,31 +163,78 @@ impl Database {
                     let mut vec = Vec::with_capacity(len);
                     debug!("Reading {} {}.", len, stringify!($field));
                     for i in 0..len {
-                        let mut asset: NamedAsset<$asset_type> =
-                            read_deserializable(db, &mut cursor).with_context(|| {
-                                format!("Failed to read {}[{}]", stringify!($field), i)
-                            })?;
+                        let asset: NamedAsset<$asset_type> = read_deserializable(db, &mut cursor)
+                            .with_context(|| {
+                            format!("Failed to read {}[{}]", stringify!($field), i)
+                        })?;
                         trace!("Read {}[{}]: {:?}", stringify!($field), i, asset);
-                        let related_chunk_data = RelatedChunkData::new(
-                            &mut asset.asset,
-                            &chunk_descriptors,
-                            &sprite_chunk_descriptors,
-                            &chunk_data,
-                        );
-                        vec.push((asset, related_chunk_data));
+                        vec.push(asset);
                     }
                     vec
                 }};
             }
 
-            Ok(Database {
-                sprites: read_deserializable_vec!(SpriteAsset, header, sprites),
-                audio_clips: read_deserializable_vec!(AudioClipAsset, header, audio_clips),
-            })
+            let related_chunk_data = |assets: &mut [&mut dyn Asset]| {
+                RelatedChunkData::new(
+                    assets,
+                    &chunk_descriptors,
+                    &sprite_chunk_descriptors,
+                    chunk_data,
+                )
+            };
+            macro_rules! with_related_chunk_data {
+                ($assets:expr) => {
+                    ($assets.into_iter())
+                        .map(|mut asset| {
+                            let related_chunk_data = related_chunk_data(&mut [&mut asset.asset]);
+                            (asset, related_chunk_data)
+                        })
+                        .collect::<Vec<_>>()
+                };
+            }
+
+            // Version 2 sprites don't have a filter, they're upgraded to the
+            // current version when the database is written back to disk
+            let sprites: Vec<NamedAsset<SpriteAsset>> = if header.version < 3 {
+                let sprites = read_deserializable_vec!(SpriteAssetV2, header, sprites);
+                sprites.into_iter().map(NamedAsset::from).collect()
+            } else {
+                read_deserializable_vec!(SpriteAsset, header, sprites)
+            };
+            let audio_clips = read_deserializable_vec!(AudioClipAsset, header, audio_clips);
+            let fonts = read_deserializable_vec!(FontAsset, header, fonts);
+            let animations = read_deserializable_vec!(AnimationAsset, header, animations);
+            let tilemaps = read_deserializable_vec!(TilemapAsset, header, tilemaps);
+
+            let mut database = Database {
+                sprites: Vec::new(),
+                audio_clips: with_related_chunk_data!(audio_clips),
+                fonts: with_related_chunk_data!(fonts),
+                animations: with_related_chunk_data!(animations),
+                tilemaps: with_related_chunk_data!(tilemaps),
+                sprite_atlases: Vec::new(),
+            };
+            for mut group in group_by_shared_sprite_chunks(sprites) {
+                if group.len() == 1 {
+                    database.sprites.extend(with_related_chunk_data!(group));
+                } else {
+                    debug!("Found {} sprites sharing sprite chunks.", group.len());
+                    let mut assets = (group.iter_mut())
+                        .map(|sprite| &mut sprite.asset as &mut dyn Asset)
+                        .collect::<Vec<_>>();
+                    let related_chunk_data = related_chunk_data(&mut assets);
+                    database.sprite_atlases.push((group, related_chunk_data));
+                }
+            }
+            Ok(database)
         } else {
             Ok(Database {
                 sprites: Vec::new(),
                 audio_clips: Vec::new(),
+                fonts: Vec::new(),
+                animations: Vec::new(),
+                tilemaps: Vec::new(),
+                sprite_atlases: Vec::new(),
             })
         }
     }
@@ -156,6 +242,50 @@ impl Database {
     pub fn clear(&mut self) {
         self.sprites.clear();
         self.audio_clips.clear();
+        self.fonts.clear();
+        self.animations.clear();
+        self.tilemaps.clear();
+        self.sprite_atlases.clear();
+    }
+
+    /// Removes the asset with the given name, along with its chunks. Returns
+    /// false if there was no such asset.
+    ///
+    /// Sprites in atlases leave their sprite chunks in the database until the
+    /// whole atlas is removed, since the other sprites might be using them.
+    pub fn remove(&mut self, name: &str) -> bool {
+        let len_before = self.len();
+        self.sprites
+            .retain(|(asset, _)| asset.name.as_str() != name);
+        self.audio_clips
+            .retain(|(asset, _)| asset.name.as_str() != name);
+        self.fonts.retain(|(asset, _)| asset.name.as_str() != name);
+        self.animations
+            .retain(|(asset, _)| asset.name.as_str() != name);
+        self.tilemaps
+            .retain(|(asset, _)| asset.name.as_str() != name);
+        self.remove_from_sprite_atlases(name);
+        self.len() < len_before
+    }
+
+    /// Removes the sprite with the given name from the sprite atlases, and the
+    /// atlas itself if it was the last sprite in it.
+    pub fn remove_from_sprite_atlases(&mut self, name: &str) {
+        for (atlas, _) in &mut self.sprite_atlases {
+            atlas.retain(|asset| asset.name.as_str() != name);
+        }
+        self.sprite_atlases.retain(|(atlas, _)| !atlas.is_empty());
+    }
+
+    /// Returns the total amount of assets in the database.
+    fn len(&self) -> usize {
+        let atlased_sprites = self.sprite_atlases.iter().map(|(atlas, _)| atlas.len());
+        self.sprites.len()
+            + self.audio_clips.len()
+            + self.fonts.len()
+            + self.animations.len()
+            + self.tilemaps.len()
+            + atlased_sprites.sum::<usize>()
     }
 
     pub fn write_into(self, db_file: &mut impl Write) -> anyhow::Result<()> {
@@ -167,17 +297,20 @@ impl Database {
         let mut sprite_chunk_descriptors = Vec::new();
         let mut chunk_data = Vec::new();
 
-        let mut append_chunk_data = |asset: &mut dyn Asset, asset_chunk_data: RelatedChunkData| {
+        // Appends the chunks of the assets, which share the chunk data.
+        let mut append_chunk_data = |assets: &mut [&mut dyn Asset], data: RelatedChunkData| {
             let offset = chunk_data.len();
-            asset.offset_chunks(chunk_descriptors.len() as i32);
-            asset.offset_sprite_chunks(sprite_chunk_descriptors.len() as i32);
+            for asset in &mut *assets {
+                asset.offset_chunks(chunk_descriptors.len() as i32);
+                asset.offset_sprite_chunks(sprite_chunk_descriptors.len() as i32);
+            }
 
             trace!(
-                "Copying over {} chunks for this asset's range, {:?}.",
-                asset_chunk_data.chunks.len(),
-                asset.get_chunks(),
+                "Copying over {} chunks for these assets' range, {:?}.",
+                data.chunks.len(),
+                range_union(assets.iter().map(|asset| asset.get_chunks())),
             );
-            for chunk_desc in asset_chunk_data.chunks {
+            for chunk_desc in data.chunks {
                 let mut source_bytes = chunk_desc.source_bytes.clone();
                 source_bytes.end += offset as u64;
                 source_bytes.start += offset as u64;
@@ -185,11 +318,11 @@ impl Database {
             }
 
             trace!(
-                "Copying over {} sprite chunks for this asset's range, {:?}.",
-                asset_chunk_data.sprite_chunks.len(),
-                asset.get_sprite_chunks(),
+                "Copying over {} sprite chunks for these assets' range, {:?}.",
+                data.sprite_chunks.len(),
+                range_union(assets.iter().map(|asset| asset.get_sprite_chunks())),
             );
-            for sprite_chunk_desc in asset_chunk_data.sprite_chunks {
+            for sprite_chunk_desc in data.sprite_chunks {
                 let SpriteChunkDescriptor {
                     region_width,
                     region_height,
@@ -205,15 +338,22 @@ impl Database {
                 });
             }
 
-            chunk_data.extend_from_slice(asset_chunk_data.chunk_data.get_ref());
+            chunk_data.extend_from_slice(data.chunk_data.get_ref());
         };
 
         let mut sprites = (self.sprites.into_iter())
             .map(|(mut asset, asset_chunk_data)| {
-                append_chunk_data(&mut asset.asset, asset_chunk_data);
+                append_chunk_data(&mut [&mut asset.asset], asset_chunk_data);
                 asset
             })
             .collect::<Vec<_>>();
+        for (mut atlas, asset_chunk_data) in self.sprite_atlases {
+            let mut assets = (atlas.iter_mut())
+                .map(|sprite| &mut sprite.asset as &mut dyn Asset)
+                .collect::<Vec<_>>();
+            append_chunk_data(&mut assets, asset_chunk_data);
+            sprites.extend(atlas);
+        }
         let sprites_count = sprites.len();
         sprites.sort();
         sprites.dedup();
@@ -221,7 +361,7 @@ impl Database {
 
         let mut audio_clips = (self.audio_clips.into_iter())
             .map(|(mut asset, asset_chunk_data)| {
-                append_chunk_data(&mut asset.asset, asset_chunk_data);
+                append_chunk_data(&mut [&mut asset.asset], asset_chunk_data);
                 asset
             })
             .collect::<Vec<_>>();
@@ -230,21 +370,49 @@ impl Database {
         audio_clips.dedup();
         assert_eq!(audio_clip_count, audio_clips.len());
 
-        let header = ResourceDatabaseHeader {
-            chunks: chunk_descriptors.len() as u32,
-            sprite_chunks: sprite_chunk_descriptors.len() as u32,
-            sprites: sprites.len() as u32,
-            audio_clips: audio_clips.len() as u32,
-        };
-        write_serializable(&header, &mut buffer, db_file)
-            .context("Failed to write the resource database header")?;
+        let mut fonts = (self.fonts.into_iter())
+            .map(|(mut asset, asset_chunk_data)| {
+                append_chunk_data(&mut [&mut asset.asset], asset_chunk_data);
+                asset
+            })
+            .collect::<Vec<_>>();
+        let font_count = fonts.len();
+        fonts.sort();
+        fonts.dedup();
+        assert_eq!(font_count, fonts.len());
+
+        let mut animations = (self.animations.into_iter())
+            .map(|(mut asset, asset_chunk_data)| {
+                append_chunk_data(&mut [&mut asset.asset], asset_chunk_data);
+                asset
+            })
+            .collect::<Vec<_>>();
+        let animation_count = animations.len();
+        animations.sort();
+        animations.dedup();
+        assert_eq!(animation_count, animations.len());
+
+        let mut tilemaps = (self.tilemaps.into_iter())
+            .map(|(mut asset, asset_chunk_data)| {
+                append_chunk_data(&mut [&mut asset.asset], asset_chunk_data);
+                asset
+            })
+            .collect::<Vec<_>>();
+        let tilemap_count = tilemaps.len();
+        tilemaps.sort();
+        tilemaps.dedup();
+        assert_eq!(tilemap_count, tilemaps.len());
+
+        // The metadata is serialized before the header, since the header
+        // contains its checksum.
+        let mut metadata = Vec::new();
 
         macro_rules! write_serializable_vec {
             ($vec:expr) => {
                 debug!("Writing {}, len: {}.", stringify!($vec), $vec.len());
                 for (i, serializable) in $vec.iter().enumerate() {
                     trace!("Writing {}[{}]: {:?}", stringify!($vec), i, serializable);
-                    write_serializable(serializable, &mut buffer, db_file)
+                    write_serializable(serializable, &mut buffer, &mut metadata)
                         .with_context(|| format!("Failed to write {}[{}]", stringify!($vec), i))?;
                 }
             };
@@ -254,6 +422,31 @@ impl Database {
         write_serializable_vec!(&sprite_chunk_descriptors);
         write_serializable_vec!(&sprites);
         write_serializable_vec!(&audio_clips);
+        write_serializable_vec!(&fonts);
+        write_serializable_vec!(&animations);
+        write_serializable_vec!(&tilemaps);
+
+        let header = ResourceDatabaseHeader {
+            version: RESOURCE_DB_VERSION,
+            chunks: chunk_descriptors.len() as u32,
+            sprite_chunks: sprite_chunk_descriptors.len() as u32,
+            sprites: sprites.len() as u32,
+            audio_clips: audio_clips.len() as u32,
+            fonts: fonts.len() as u32,
+            animations: animations.len() as u32,
+            tilemaps: tilemaps.len() as u32,
+            metadata_checksum: checksum(&metadata),
+            chunk_data_checksum: checksum(referenced_chunk_data(
+                &chunk_descriptors,
+                &sprite_chunk_descriptors,
+                &chunk_data,
+            )),
+        };
+        write_serializable(&header, &mut buffer, db_file)
+            .context("Failed to write the resource database header")?;
+        db_file
+            .write_all(&metadata)
+            .context("Failed to write the asset metadata")?;
 
         debug!("Writing chunk data, {} bytes.", chunk_data.len());
         db_file
@@ -264,6 +457,61 @@ impl Database {
     }
 }
 
+/// Returns the smallest range containing all the given ranges, or None if
+/// there's no ranges.
+fn range_union(ranges: impl Iterator<Item = Option<Range<u32>>>) -> Option<Range<u32>> {
+    ranges
+        .flatten()
+        .reduce(|a, b| a.start.min(b.start)..a.end.max(b.end))
+}
+
+/// Splits the sprites into groups which share sprite chunks with each other,
+/// i.e. sprite atlases, and groups of one sprite for the rest. The groups are
+/// ordered by their sprite chunks.
+fn group_by_shared_sprite_chunks(
+    mut sprites: Vec<NamedAsset<SpriteAsset>>,
+) -> Vec<Vec<NamedAsset<SpriteAsset>>> {
+    sprites.sort_by_key(|sprite| sprite.asset.get_sprite_chunks().map(|range| range.start));
+    let mut groups: Vec<Vec<NamedAsset<SpriteAsset>>> = Vec::new();
+    // The end of the sprite chunks used by the last group
+    let mut group_end = None;
+    for sprite in sprites {
+        let range = sprite.asset.get_sprite_chunks();
+        let end = range.as_ref().map(|range| range.end);
+        if matches!((range, group_end), (Some(range), Some(group_end)) if range.start < group_end) {
+            // does not panic: group_end is only set after pushing a group
+            groups.last_mut().unwrap().push(sprite);
+            group_end = group_end.max(end);
+        } else {
+            groups.push(vec![sprite]);
+            group_end = end;
+        }
+    }
+    groups
+}
+
+fn checksum(bytes: &[u8]) -> u32 {
+    let mut crc = Crc32::new();
+    crc.update(bytes);
+    crc.finish()
+}
+
+/// Returns the part of the chunk data block covered by
+/// [`ResourceDatabaseHeader::chunk_data_checksum`], i.e. everything up to the
+/// end of the last chunk.
+fn referenced_chunk_data<'a>(
+    chunk_descs: &[ChunkDescriptor],
+    sprite_chunk_descs: &[SpriteChunkDescriptor],
+    chunk_data: &'a [u8],
+) -> &'a [u8] {
+    let end = (chunk_descs.iter().map(|chunk| &chunk.source_bytes))
+        .chain(sprite_chunk_descs.iter().map(|chunk| &chunk.source_bytes))
+        .map(|source_bytes| source_bytes.end)
+        .max()
+        .unwrap_or(0);
+    &chunk_data[..(end as usize).min(chunk_data.len())]
+}
+
 fn write_serializable<S: Serialize>(
     serializable: &S,
     buffer: &mut Vec<u8>,
@@ -283,9 +531,205 @@ fn write_serializable<S: Serialize>(
     Ok(())
 }
 
-fn read_deserializable<D: Deserialize>(src: &[u8], cursor: &mut usize) -> io::Result<D> {
-    let start = *cursor;
-    let end = start + D::SERIALIZED_SIZE;
-    *cursor = end;
-    Ok(D::deserialize(&src[start..end]))
+fn read_deserializable<D: Deserialize>(src: &[u8], cursor: &mut usize) -> anyhow::Result<D> {
+    try_deserialize::<D>(src, cursor).map_err(|err| anyhow::anyhow!("{err}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Write};
+
+    use arrayvec::{ArrayString, ArrayVec};
+    use engine::resources::{
+        animation::{AnimationAsset, AnimationFrame, AnimationMode},
+        sprite::{SpriteAsset, SpriteMipLevel},
+        Asset, NamedAsset, SpriteChunkDescriptor,
+    };
+    use image::{DynamicImage, RgbaImage};
+    use platform::TextureFilter;
+
+    use crate::importers;
+
+    use super::{Database, RelatedChunkData};
+
+    /// Creates a sprite asset with `chunk_count` sprite chunks, each chunk's
+    /// data being `chunk_count` bytes of `fill`.
+    fn sprite(
+        name: &str,
+        fill: u8,
+        chunk_count: u32,
+    ) -> (NamedAsset<SpriteAsset>, RelatedChunkData) {
+        let mut data = RelatedChunkData::empty();
+        for _ in 0..chunk_count {
+            let start = data.chunk_data.position();
+            data.chunk_data
+                .write_all(&vec![fill; chunk_count as usize])
+                .unwrap();
+            data.sprite_chunks.push(SpriteChunkDescriptor {
+                region_width: 1,
+                region_height: 1,
+                source_bytes: start..data.chunk_data.position(),
+            });
+        }
+        let mut mip_chain = ArrayVec::new();
+        mip_chain.push(SpriteMipLevel::MultiChunkSprite {
+            size: (1, 1),
+            sprite_chunks: 0..chunk_count,
+        });
+        let asset = SpriteAsset {
+            transparent: false,
+            mip_chain,
+            filter: TextureFilter::Linear,
+        };
+        let name = ArrayString::from(name).unwrap();
+        (NamedAsset { name, asset }, data)
+    }
+
+    fn write_and_reopen(db: Database) -> Database {
+        let mut db_file = Cursor::new(Vec::new());
+        db.write_into(&mut db_file).unwrap();
+        Database::new(Some(db_file.get_ref())).unwrap()
+    }
+
+    #[test]
+    fn sprites_added_to_existing_database_stay_loadable() {
+        let sprites = [("a", 1, 2), ("b", 2, 3), ("c", 3, 1)];
+        let mut db = Database::new(None).unwrap();
+        for (name, fill, chunk_count) in sprites {
+            db.sprites.push(sprite(name, fill, chunk_count));
+            db = write_and_reopen(db);
+        }
+
+        assert_eq!(sprites.len(), db.sprites.len());
+        for ((name, fill, chunk_count), (sprite, data)) in sprites.into_iter().zip(&db.sprites) {
+            assert_eq!(name, sprite.name.as_str());
+            assert_eq!(Some(0..chunk_count), sprite.asset.get_sprite_chunks());
+            assert_eq!(chunk_count as usize, data.sprite_chunks.len());
+            let expected_data = vec![fill; (chunk_count * chunk_count) as usize];
+            assert_eq!(&expected_data, data.chunk_data.get_ref());
+        }
+    }
+
+    #[test]
+    fn sprite_atlases_stay_shared_when_reopened() {
+        // Two sprites sharing the same two sprite chunks, like import_atlas
+        // creates, and a standalone sprite after them
+        let (a, atlas_data) = sprite("a", 1, 2);
+        let (mut b, _) = sprite("b", 1, 2);
+        b.asset.mip_chain[0] = SpriteMipLevel::SingleChunkSprite {
+            offset: (0, 0),
+            size: (1, 1),
+            sprite_chunk: 1,
+        };
+        let mut db = Database::new(None).unwrap();
+        db.sprite_atlases.push((vec![a, b], atlas_data));
+        db.sprites.push(sprite("c", 3, 1));
+        let mut db = write_and_reopen(db);
+
+        assert_eq!(1, db.sprites.len());
+        assert_eq!(1, db.sprite_atlases.len());
+        let (atlas, data) = &db.sprite_atlases[0];
+        let names = atlas.iter().map(|sprite| sprite.name.as_str());
+        assert!(names.eq(["a", "b"]));
+        assert_eq!(Some(0..2), atlas[0].asset.get_sprite_chunks());
+        assert_eq!(Some(1..2), atlas[1].asset.get_sprite_chunks());
+        assert_eq!(&vec![1; 4], data.chunk_data.get_ref());
+
+        // The last sprite of the atlas is a standalone sprite once reopened,
+        // and the chunk only used by the removed sprite is dropped
+        assert!(db.remove("a"));
+        let db = write_and_reopen(db);
+        assert_eq!(2, db.sprites.len());
+        assert!(db.sprite_atlases.is_empty());
+        let (b, data) = (db.sprites.iter())
+            .find(|(sprite, _)| sprite.name.as_str() == "b")
+            .unwrap();
+        assert_eq!(Some(0..1), b.asset.get_sprite_chunks());
+        assert_eq!(&vec![1; 2], data.chunk_data.get_ref());
+    }
+
+    #[test]
+    fn removed_sprites_are_not_written() {
+        let mut db = Database::new(None).unwrap();
+        db.sprites.push(sprite("a", 1, 2));
+        db.sprites.push(sprite("b", 2, 3));
+        let mut db = write_and_reopen(db);
+
+        assert!(db.remove("a"));
+        assert!(!db.remove("a"));
+        let db = write_and_reopen(db);
+
+        assert_eq!(1, db.sprites.len());
+        let (sprite, data) = &db.sprites[0];
+        assert_eq!("b", sprite.name.as_str());
+        assert_eq!(Some(0..3), sprite.asset.get_sprite_chunks());
+        assert_eq!(&vec![2; 9], data.chunk_data.get_ref());
+    }
+
+    #[test]
+    fn imported_sprite_filter_survives_reopening() {
+        let image = DynamicImage::ImageRgba8(RgbaImage::new(4, 4));
+        let mut db = Database::new(None).unwrap();
+        for (name, filter) in [
+            ("pixel_art", TextureFilter::NearestNeighbor),
+            ("smooth", TextureFilter::Linear),
+        ] {
+            let mut data = RelatedChunkData::empty();
+            let mut sprites =
+                importers::sprite::import_atlas(std::slice::from_ref(&image), filter, &mut data)
+                    .unwrap();
+            let asset = sprites.pop().unwrap();
+            let name = ArrayString::from(name).unwrap();
+            db.sprites.push((NamedAsset { name, asset }, data));
+        }
+        let db = write_and_reopen(db);
+
+        let filter = |name: &str| {
+            let (sprite, _) = (db.sprites.iter())
+                .find(|(sprite, _)| sprite.name.as_str() == name)
+                .unwrap();
+            sprite.asset.filter
+        };
+        assert_eq!(TextureFilter::NearestNeighbor, filter("pixel_art"));
+        assert_eq!(TextureFilter::Linear, filter("smooth"));
+    }
+
+    #[test]
+    fn animations_survive_reopening_next_to_sprites() {
+        let (sheet, data) = sprite("walk", 4, 2);
+        let mut frames = ArrayVec::new();
+        frames.push(AnimationFrame {
+            offset: (0, 0),
+            size: (1, 1),
+            duration_ms: 120,
+        });
+        let asset = AnimationAsset {
+            sheet: sheet.asset,
+            frames,
+            mode: AnimationMode::Once,
+        };
+        let mut db = Database::new(None).unwrap();
+        db.sprites.push(sprite("a", 1, 3));
+        db.animations.push((
+            NamedAsset {
+                name: sheet.name,
+                asset,
+            },
+            data,
+        ));
+        let mut db = write_and_reopen(db);
+
+        assert_eq!(1, db.animations.len());
+        let (animation, data) = &db.animations[0];
+        assert_eq!("walk", animation.name.as_str());
+        assert_eq!(AnimationMode::Once, animation.asset.mode);
+        assert_eq!(120, animation.asset.frames[0].duration_ms);
+        assert_eq!(Some(0..2), animation.asset.get_sprite_chunks());
+        assert_eq!(&vec![4; 4], data.chunk_data.get_ref());
+
+        assert!(db.remove("walk"));
+        let db = write_and_reopen(db);
+        assert!(db.animations.is_empty());
+        assert_eq!(1, db.sprites.len());
+    }
 }
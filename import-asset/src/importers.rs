@@ -2,5 +2,8 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+pub mod animation;
 pub mod audio_clip;
+pub mod font;
 pub mod sprite;
+pub mod tilemap;
@@ -0,0 +1,168 @@
+// SPDX-FileCopyrightText: 2025 Jens Pitkänen <jens.pitkanen@helsinki.fi>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::{fs, path::Path};
+
+use anyhow::Context;
+use arrayvec::ArrayVec;
+use engine::resources::{
+    animation::{AnimationAsset, AnimationFrame, AnimationMode, MAX_FRAMES},
+    sprite::SpriteMipLevel,
+};
+use platform::TextureFilter;
+use serde::Deserialize;
+
+use crate::database::RelatedChunkData;
+
+/// The contents of the JSON file describing the frames of an animation.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AnimationFile {
+    #[serde(default)]
+    mode: Mode,
+    frames: Vec<Frame>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+enum Mode {
+    #[default]
+    Loop,
+    Once,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Frame {
+    x: u16,
+    y: u16,
+    width: u16,
+    height: u16,
+    duration_ms: u32,
+}
+
+/// Imports the sprite sheet at `image_path` as an animation, with the frames
+/// read from the JSON file next to it, i.e. the same path with the extension
+/// replaced with `json`.
+pub fn import(
+    image_path: &Path,
+    filter: TextureFilter,
+    db: &mut RelatedChunkData,
+) -> anyhow::Result<AnimationAsset> {
+    let frames_path = image_path.with_extension("json");
+    let frames_json = fs::read_to_string(&frames_path).with_context(|| {
+        format!(
+            "Failed to open the animation's frame file: {}",
+            frames_path.display()
+        )
+    })?;
+
+    let sheet = super::sprite::import(image_path, filter, db)?;
+    let sheet_size = match &sheet.mip_chain[0] {
+        SpriteMipLevel::SingleChunkSprite { size, .. }
+        | SpriteMipLevel::MultiChunkSprite { size, .. } => *size,
+    };
+
+    let (frames, mode) = parse_frames(&frames_json, sheet_size)
+        .with_context(|| format!("Invalid animation frame file: {}", frames_path.display()))?;
+
+    Ok(AnimationAsset {
+        sheet,
+        frames,
+        mode,
+    })
+}
+
+/// Parses the frames out of the animation JSON, checking that they're all
+/// within the `(width, height)` sized sprite sheet.
+fn parse_frames(
+    json: &str,
+    (sheet_width, sheet_height): (u16, u16),
+) -> anyhow::Result<(ArrayVec<AnimationFrame, MAX_FRAMES>, AnimationMode)> {
+    let file: AnimationFile = serde_json::from_str(json).context("Failed to parse the JSON")?;
+
+    if file.frames.is_empty() {
+        return Err(anyhow::anyhow!("Animations must have at least one frame"));
+    }
+    if file.frames.len() > MAX_FRAMES {
+        return Err(anyhow::anyhow!(
+            "Animations can have at most {MAX_FRAMES} frames, this one has {}",
+            file.frames.len(),
+        ));
+    }
+
+    let mut frames = ArrayVec::new();
+    for (i, frame) in file.frames.into_iter().enumerate() {
+        if frame.width == 0 || frame.height == 0 {
+            return Err(anyhow::anyhow!("Frame {i} has no area"));
+        }
+        let right = frame.x as u32 + frame.width as u32;
+        let bottom = frame.y as u32 + frame.height as u32;
+        if right > sheet_width as u32 || bottom > sheet_height as u32 {
+            return Err(anyhow::anyhow!(
+                "Frame {i} extends past the {sheet_width}x{sheet_height} sprite sheet"
+            ));
+        }
+        frames.push(AnimationFrame {
+            offset: (frame.x, frame.y),
+            size: (frame.width, frame.height),
+            duration_ms: frame.duration_ms,
+        });
+    }
+
+    let mode = match file.mode {
+        Mode::Loop => AnimationMode::Loop,
+        Mode::Once => AnimationMode::Once,
+    };
+
+    Ok((frames, mode))
+}
+
+#[cfg(test)]
+mod tests {
+    use engine::resources::animation::{AnimationFrame, AnimationMode};
+
+    use super::parse_frames;
+
+    #[test]
+    fn frames_are_parsed_and_checked_against_the_sheet() {
+        let json = r#"{
+            "mode": "once",
+            "frames": [
+                { "x": 0, "y": 0, "width": 16, "height": 16, "duration_ms": 100 },
+                { "x": 16, "y": 0, "width": 16, "height": 16, "duration_ms": 50 }
+            ]
+        }"#;
+        let (frames, mode) = parse_frames(json, (32, 16)).unwrap();
+        assert_eq!(AnimationMode::Once, mode);
+        assert_eq!(
+            &[
+                AnimationFrame {
+                    offset: (0, 0),
+                    size: (16, 16),
+                    duration_ms: 100,
+                },
+                AnimationFrame {
+                    offset: (16, 0),
+                    size: (16, 16),
+                    duration_ms: 50,
+                },
+            ],
+            frames.as_slice(),
+        );
+
+        // Too small sprite sheet for the second frame
+        assert!(parse_frames(json, (24, 16)).is_err());
+        // No frames
+        assert!(parse_frames(r#"{ "frames": [] }"#, (32, 16)).is_err());
+    }
+
+    #[test]
+    fn animations_loop_by_default() {
+        let json =
+            r#"{ "frames": [{ "x": 0, "y": 0, "width": 1, "height": 1, "duration_ms": 1 }] }"#;
+        let (_, mode) = parse_frames(json, (1, 1)).unwrap();
+        assert_eq!(AnimationMode::Loop, mode);
+    }
+}
@@ -3,6 +3,7 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
 use std::{
+    f32::consts::FRAC_1_SQRT_2,
     fs::File,
     io::{ErrorKind, Seek, Write},
     path::Path,
@@ -13,8 +14,9 @@ use engine::resources::{audio_clip::AudioClipAsset, ChunkDescriptor, AUDIO_SAMPL
 use platform::{AUDIO_CHANNELS, AUDIO_SAMPLE_RATE};
 use symphonia::{
     core::{
-        audio::{AudioBuffer, Channels, Signal, SignalSpec},
+        audio::{AudioBuffer, Signal},
         codecs::DecoderOptions,
+        conv::IntoSample,
         errors::Error as SymphoniaError,
         formats::FormatOptions,
         io::{MediaSourceStream, MediaSourceStreamOptions},
@@ -111,7 +113,13 @@ fn read_audio_file(
         .make(&track.codec_params, &DecoderOptions::default())
         .context("Failed to create a decoder for the audio")?;
 
-    let mut samples = Vec::new();
+    assert_eq!(
+        2, AUDIO_CHANNELS,
+        "this conversion step assumes simple stereo audio buffers",
+    );
+
+    let mut sample_rate = None;
+    let mut frames = Vec::new();
     loop {
         let packet = match source.format.next_packet() {
             Ok(packet) => packet,
@@ -124,7 +132,8 @@ fn read_audio_file(
             // These are recoverable according to Decode::decoder docs.
             Err(SymphoniaError::IoError(_)) | Err(SymphoniaError::DecodeError(_)) => continue,
             Err(SymphoniaError::ResetRequired) => {
-                samples.clear();
+                frames.clear();
+                sample_rate = None;
                 decoder = codecs
                     .make(decoder.codec_params(), &DecoderOptions::default())
                     .context("Failed to recreate a decoder for the audio")?;
@@ -144,25 +153,118 @@ fn read_audio_file(
             decoded.spec(),
         );
 
-        assert_eq!(
-            2, AUDIO_CHANNELS,
-            "this conversion step assumes simple stereo audio buffers",
-        );
-        let mut converted = AudioBuffer::<i16>::new(
-            decoded.capacity() as u64,
-            SignalSpec {
-                rate: AUDIO_SAMPLE_RATE,
-                channels: Channels::FRONT_LEFT | Channels::FRONT_RIGHT,
-            },
-        );
+        let rate = decoded.spec().rate;
+        if *sample_rate.get_or_insert(rate) != rate {
+            return Err(anyhow::anyhow!(
+                "The sample rate of the audio changes mid-stream, which is not supported"
+            ));
+        }
+
+        let mut converted = decoded.make_equivalent::<f32>();
         decoded.convert(&mut converted);
-        trace!("Converted audio to a engine-native signal spec.");
+        append_stereo_frames(&converted, &mut frames);
+    }
 
-        samples.reserve(converted.frames());
-        for (&left, &right) in converted.chan(0).iter().zip(converted.chan(1)) {
-            samples.push([left, right]);
-        }
+    let sample_rate = sample_rate.unwrap_or(AUDIO_SAMPLE_RATE);
+    if sample_rate != AUDIO_SAMPLE_RATE {
+        debug!("Resampling audio from {sample_rate} Hz to {AUDIO_SAMPLE_RATE} Hz.");
+        frames = resample(&frames, sample_rate, AUDIO_SAMPLE_RATE);
     }
 
+    let samples = (frames.into_iter())
+        .map(|frame| frame.map(|sample| sample.into_sample()))
+        .collect();
     Ok(samples)
 }
+
+/// Appends the frames of `buffer` to `dst` as stereo frames: mono audio is
+/// played from both speakers, and any channels past the first two (e.g. center
+/// and surround channels) are mixed into both the left and the right channel.
+fn append_stereo_frames(buffer: &AudioBuffer<f32>, dst: &mut Vec<[f32; AUDIO_CHANNELS]>) {
+    let channels = buffer.spec().channels.count();
+    dst.reserve(buffer.frames());
+    match channels {
+        0 => {}
+        1 => dst.extend(buffer.chan(0).iter().map(|&sample| [sample, sample])),
+        _ => {
+            for i in 0..buffer.frames() {
+                let extra: f32 = (2..channels).map(|c| buffer.chan(c)[i]).sum();
+                let extra = extra * FRAC_1_SQRT_2;
+                dst.push([buffer.chan(0)[i] + extra, buffer.chan(1)[i] + extra]);
+            }
+        }
+    }
+}
+
+/// Resamples the frames from `src_rate` to `dst_rate` with linear
+/// interpolation. The duration of the audio stays the same, so the pitch is not
+/// affected.
+fn resample(
+    frames: &[[f32; AUDIO_CHANNELS]],
+    src_rate: u32,
+    dst_rate: u32,
+) -> Vec<[f32; AUDIO_CHANNELS]> {
+    let (src_rate, dst_rate) = (src_rate as u64, dst_rate as u64);
+    let dst_len = frames.len() as u64 * dst_rate / src_rate;
+    (0..dst_len)
+        .map(|i| {
+            // The position of this frame in the source, in 1/dst_rate units
+            let src_position = i * src_rate;
+            let index = (src_position / dst_rate) as usize;
+            let t = (src_position % dst_rate) as f32 / dst_rate as f32;
+            let a = frames[index];
+            let b = frames.get(index + 1).copied().unwrap_or(a);
+            [0, 1].map(|c| a[c] + (b[c] - a[c]) * t)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{f32::consts::TAU, path::Path};
+
+    use platform::AUDIO_SAMPLE_RATE;
+    use symphonia::core::audio::{AudioBuffer, Channels, Signal, SignalSpec};
+
+    use crate::database::RelatedChunkData;
+
+    use super::{append_stereo_frames, import, resample};
+
+    #[test]
+    fn ogg_vorbis_clip_is_resampled_to_engine_sample_rate() {
+        // A silent mono clip with 22016 frames at 22050 Hz.
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("test-data/silence.ogg");
+        let mut db = RelatedChunkData::empty();
+        let clip = import(&path, None, &mut db).unwrap();
+        let expected_len = 22016 * AUDIO_SAMPLE_RATE as u64 / 22050;
+        assert_eq!(expected_len, clip.samples as u64);
+        assert_eq!(expected_len * 4, db.chunk_data.get_ref().len() as u64);
+    }
+
+    #[test]
+    fn resampling_does_not_shift_pitch() {
+        let sine = |rate: u32, i: usize| (TAU * 440.0 * i as f32 / rate as f32).sin();
+        let frames = (0..2205).map(|i| [sine(22050, i); 2]).collect::<Vec<_>>();
+        let resampled = resample(&frames, 22050, 48000);
+        assert_eq!(4800, resampled.len());
+        // The last few frames are past the last source frame, so there's
+        // nothing to interpolate towards
+        for (i, frame) in resampled.iter().enumerate().take(4797) {
+            assert!(
+                (frame[0] - sine(48000, i)).abs() < 0.005,
+                "frame {i}: {frame:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn mono_is_duplicated_to_stereo() {
+        let spec = SignalSpec::new(22050, Channels::FRONT_LEFT);
+        let mut buffer = AudioBuffer::<f32>::new(2, spec);
+        buffer.render_reserved(Some(2));
+        buffer.chan_mut(0).copy_from_slice(&[0.25, -0.5]);
+        let mut frames = Vec::new();
+        append_stereo_frames(&buffer, &mut frames);
+        assert_eq!(vec![[0.25, 0.25], [-0.5, -0.5]], frames);
+    }
+}
@@ -0,0 +1,170 @@
+// SPDX-FileCopyrightText: 2025 Jens Pitkänen <jens.pitkanen@helsinki.fi>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::{
+    fs,
+    io::{Seek, Write},
+    path::Path,
+};
+
+use anyhow::Context;
+use arrayvec::ArrayVec;
+use engine::resources::{
+    font::{FontAsset, FontGlyph, FIRST_GLYPH, MAX_GLYPHS},
+    SpriteChunkDescriptor, SPRITE_CHUNK_DIMENSIONS, SPRITE_CHUNK_FORMAT,
+};
+use fontdue::{Font, FontSettings};
+use tracing::trace;
+
+use crate::database::RelatedChunkData;
+
+/// Bytes per pixel in the sprite chunk format, the only format used within
+/// this module.
+const BPP: usize = SPRITE_CHUNK_FORMAT.bytes_per_pixel();
+const CHUNK_WIDTH: usize = SPRITE_CHUNK_DIMENSIONS.0 as usize;
+const CHUNK_HEIGHT: usize = SPRITE_CHUNK_DIMENSIONS.1 as usize;
+const CHUNK_STRIDE: usize = CHUNK_WIDTH * BPP;
+
+/// Transparent white, to avoid dark fringes when the glyphs' edges are
+/// filtered.
+const CLEAR_PIXEL: [u8; 4] = [0xFF, 0xFF, 0xFF, 0];
+
+pub fn import(font_path: &Path, size: u16, db: &mut RelatedChunkData) -> anyhow::Result<FontAsset> {
+    assert_eq!(
+        4, BPP,
+        "font import logic needs updating for non-rgba engine sprite format"
+    );
+
+    if size == 0 {
+        return Err(anyhow::anyhow!("Font size must be at least one pixel"));
+    }
+
+    let font_bytes = fs::read(font_path).context("Failed to open font file for importing")?;
+    let px = size as f32;
+    let font = Font::from_bytes(
+        font_bytes,
+        FontSettings {
+            scale: px,
+            ..FontSettings::default()
+        },
+    )
+    .map_err(|err| anyhow::anyhow!("Failed to read font file as a font: {err}"))?;
+    let line_metrics = font
+        .horizontal_line_metrics(px)
+        .context("Font does not support horizontal text layout")?;
+    let ascent = line_metrics.ascent.round() as i32;
+
+    let mut pending_pixels = CLEAR_PIXEL.repeat(CHUNK_WIDTH * CHUNK_HEIGHT);
+    let first_chunk_index = db.sprite_chunks.len() as u32;
+    let mut pending_chunk_index = first_chunk_index;
+    // The region of the pending chunk used up so far
+    let mut pending_chunk_width = 0;
+    let mut pending_chunk_height = 0;
+    // The shelf the next glyph will be placed on
+    let (mut shelf_x, mut shelf_y, mut shelf_height) = (0, 0, 0);
+
+    // Writes out the pending chunk into `db`.
+    let mut flush_pending_chunk = |width: usize,
+                                   height: usize,
+                                   pixels: &[u8],
+                                   chunk_index: &mut u32| {
+        let start = db.chunk_data.stream_position().unwrap();
+        for y in 0..height {
+            let row = &pixels[y * CHUNK_STRIDE..y * CHUNK_STRIDE + width * BPP];
+            db.chunk_data.write_all(row).unwrap();
+        }
+        let end = db.chunk_data.stream_position().unwrap();
+        trace!("Writing out a {width}x{height} glyph atlas chunk at (this asset's) chunk index {chunk_index} and byte range {start}..{end}.");
+        db.sprite_chunks.push(SpriteChunkDescriptor {
+            region_width: width as u16,
+            region_height: height as u16,
+            source_bytes: start..end,
+        });
+        *chunk_index += 1;
+    };
+
+    let mut glyphs = ArrayVec::new();
+    for i in 0..MAX_GLYPHS {
+        let c = char::from(FIRST_GLYPH as u8 + i as u8);
+        let (metrics, coverage) = font.rasterize(c, px);
+        let (width, height) = (metrics.width, metrics.height);
+
+        let mut glyph = FontGlyph {
+            sprite_chunk: pending_chunk_index,
+            uv_offset: (0, 0),
+            size: (0, 0),
+            bearing: (
+                metrics.xmin as i16,
+                (ascent - metrics.ymin - height as i32) as i16,
+            ),
+            advance: metrics.advance_width.round() as u16,
+        };
+
+        if width > 0 && height > 0 {
+            // The width and height of the glyph including a 1px transparent
+            // border, to avoid bleeding from neighboring glyphs
+            let req_w = width + 2;
+            let req_h = height + 2;
+            if req_w > CHUNK_WIDTH || req_h > CHUNK_HEIGHT {
+                return Err(anyhow::anyhow!(
+                    "Glyph {c:?} does not fit in a sprite chunk, try a smaller font size"
+                ));
+            }
+
+            // Start a new shelf if this one is full, and a new chunk if there's
+            // no room for a new shelf
+            if shelf_x + req_w > CHUNK_WIDTH {
+                (shelf_x, shelf_y, shelf_height) = (0, shelf_y + shelf_height, 0);
+            }
+            if shelf_y + req_h > CHUNK_HEIGHT {
+                flush_pending_chunk(
+                    pending_chunk_width,
+                    pending_chunk_height,
+                    &pending_pixels,
+                    &mut pending_chunk_index,
+                );
+                pending_pixels = CLEAR_PIXEL.repeat(CHUNK_WIDTH * CHUNK_HEIGHT);
+                (pending_chunk_width, pending_chunk_height) = (0, 0);
+                (shelf_x, shelf_y, shelf_height) = (0, 0, 0);
+            }
+
+            let (x0, y0) = (shelf_x + 1, shelf_y + 1);
+            for y in 0..height {
+                for x in 0..width {
+                    let alpha = coverage[x + y * width];
+                    let i = (x0 + x) * BPP + (y0 + y) * CHUNK_STRIDE;
+                    pending_pixels[i..i + BPP].copy_from_slice(&[0xFF, 0xFF, 0xFF, alpha]);
+                }
+            }
+
+            glyph.sprite_chunk = pending_chunk_index;
+            glyph.uv_offset = (x0 as u16, y0 as u16);
+            glyph.size = (width as u16, height as u16);
+
+            shelf_x += req_w;
+            shelf_height = shelf_height.max(req_h);
+            pending_chunk_width = pending_chunk_width.max(shelf_x);
+            pending_chunk_height = pending_chunk_height.max(shelf_y + shelf_height);
+        }
+
+        trace!("Imported glyph {c:?}: {glyph:?}");
+        glyphs.push(glyph);
+    }
+
+    // Flush out the final chunk
+    if pending_chunk_width > 0 {
+        flush_pending_chunk(
+            pending_chunk_width,
+            pending_chunk_height,
+            &pending_pixels,
+            &mut pending_chunk_index,
+        );
+    }
+
+    Ok(FontAsset {
+        line_height: line_metrics.new_line_size.ceil() as u16,
+        sprite_chunks: first_chunk_index..pending_chunk_index,
+        glyphs,
+    })
+}
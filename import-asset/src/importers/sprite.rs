@@ -5,9 +5,11 @@
 mod pixels;
 
 use std::{
+    cmp::Reverse,
     fs,
     io::{Seek, Write},
     path::Path,
+    thread,
 };
 
 use anyhow::Context;
@@ -18,6 +20,7 @@ use engine::resources::{
 };
 use image::{imageops::FilterType, load_from_memory, DynamicImage};
 use pixels::Pixels;
+use platform::TextureFilter;
 use tracing::trace;
 
 use crate::database::RelatedChunkData;
@@ -30,16 +33,181 @@ const CHUNK_HEIGHT: usize = SPRITE_CHUNK_DIMENSIONS.1 as usize;
 const CHUNK_STRIDE: usize = CHUNK_WIDTH * BPP;
 const CHUNK_BYTES: usize = CHUNK_STRIDE * CHUNK_HEIGHT;
 
-pub fn import(image_path: &Path, db: &mut RelatedChunkData) -> anyhow::Result<SpriteAsset> {
-    // TODO: find out why this sometimes results in an unloadable database (i.e.
-    // when adding a sprite into an existing db, that db becomes unreadable
-    // because there's sprite chunk references outside the actual existing
-    // sprite chunks, but removing the db file and recreating it entirely with
-    // reimport fixes it).
+pub fn import(
+    image_path: &Path,
+    filter: TextureFilter,
+    db: &mut RelatedChunkData,
+) -> anyhow::Result<SpriteAsset> {
+    let image = load_image(image_path)?;
+    import_image(&image, filter, db, true)
+}
+
+pub fn load_image(image_path: &Path) -> anyhow::Result<DynamicImage> {
     let image_bytes = fs::read(image_path).context("Failed to open sprite file for importing")?;
-    let image = load_from_memory(&image_bytes)
-        .context("Failed to read image file as an image (unsupported format?)")?;
+    load_from_memory(&image_bytes)
+        .context("Failed to read image file as an image (unsupported format?)")
+}
+
+/// Returns true if the image is small enough to be packed with
+/// [`import_atlas`], i.e. it fits in a single sprite chunk with its border.
+pub fn fits_in_atlas(image: &DynamicImage) -> bool {
+    image.width() as usize + 2 <= CHUNK_WIDTH && image.height() as usize + 2 <= CHUNK_HEIGHT
+}
+
+/// Imports the images as sprites which share sprite chunks, returning the
+/// sprites in the same order as the images.
+///
+/// Unlike [`import`], which starts a new sprite chunk for each sprite, this
+/// packs all the mip levels of all the sprites into as few sprite chunks as it
+/// can, which saves a lot of memory when there's many small sprites. The
+/// sprites are drawn exactly the same as if they were imported one by one, and
+/// the same images always result in the same chunks.
+///
+/// Each image must [fit in a single sprite chunk](fits_in_atlas).
+pub fn import_atlas(
+    images: &[DynamicImage],
+    filter: TextureFilter,
+    db: &mut RelatedChunkData,
+) -> anyhow::Result<Vec<SpriteAsset>> {
+    struct Mip {
+        sprite: usize,
+        level: usize,
+        width: usize,
+        height: usize,
+        pixels: Vec<u8>,
+    }
+
+    // Render every mip level of every sprite up front, so that they can be
+    // packed in any order
+    let mut sprites = Vec::with_capacity(images.len());
+    let mut mips = Vec::new();
+    for (sprite, image) in images.iter().enumerate() {
+        if image.width() == 0 || image.height() == 0 {
+            return Err(anyhow::anyhow!("Sprite must have at least one pixel"));
+        }
+        if !fits_in_atlas(image) {
+            return Err(anyhow::anyhow!(
+                "Sprite #{sprite} is too large to be packed into an atlas"
+            ));
+        }
+
+        let mut transparent = false;
+        let mip_sizes = mip_sizes(image.width() as usize, image.height() as usize);
+        for (level, &(width, height)) in mip_sizes.iter().enumerate() {
+            let mut pixels = std::vec![0; width * height * BPP];
+            render_sprite(image, width, height, width * BPP, &mut pixels);
+            let tex = Pixels::new(&mut pixels, width * BPP, width, height).unwrap();
+            transparent |= tex.has_transparent_pixels();
+            mips.push(Mip {
+                sprite,
+                level,
+                width,
+                height,
+                pixels,
+            });
+        }
+
+        sprites.push(SpriteAsset {
+            transparent,
+            mip_chain: ArrayVec::new(),
+            filter,
+        });
+    }
+
+    // Shelf packing works best when the shelves are filled with similarly
+    // sized sprites, so pack the tallest ones first. The sort is stable, so
+    // equally sized mips are packed in the order of the images, which keeps
+    // the results deterministic.
+    mips.sort_by_key(|mip| Reverse((mip.height, mip.width)));
+
+    let mut pending_pixels = std::vec![0; CHUNK_BYTES];
+    let mut pending_chunk_tex =
+        Pixels::new(&mut pending_pixels, CHUNK_STRIDE, CHUNK_WIDTH, CHUNK_HEIGHT).unwrap();
+    let mut pending_chunk_index = db.sprite_chunks.len() as u32;
+    // The region of the pending chunk used up so far
+    let mut pending_chunk_width = 0;
+    let mut pending_chunk_height = 0;
+    // The shelf the next mip will be placed on
+    let (mut shelf_x, mut shelf_y, mut shelf_height) = (0, 0, 0);
 
+    let mut placed_mips = Vec::with_capacity(mips.len());
+    for mut mip in mips {
+        // The width and height of the mip including the borders
+        let req_w = mip.width + 2;
+        let req_h = mip.height + 2;
+
+        // Start a new shelf if this one is full, and a new chunk if there's no
+        // room for a new shelf
+        if shelf_x + req_w > CHUNK_WIDTH {
+            (shelf_x, shelf_y, shelf_height) = (0, shelf_y + shelf_height, 0);
+        }
+        if shelf_y + req_h > CHUNK_HEIGHT {
+            flush_chunk(
+                db,
+                pending_chunk_width,
+                pending_chunk_height,
+                &pending_chunk_tex,
+                &mut pending_chunk_index,
+            );
+            pending_chunk_tex.pixels.fill(0);
+            (pending_chunk_width, pending_chunk_height) = (0, 0);
+            (shelf_x, shelf_y, shelf_height) = (0, 0, 0);
+        }
+
+        let tex = Pixels::new(&mut mip.pixels, mip.width * BPP, mip.width, mip.height).unwrap();
+        let mut dst_with_border = pending_chunk_tex
+            .subregion(shelf_x, shelf_y, req_w, req_h)
+            .unwrap();
+        let mut dst = dst_with_border.shrink().unwrap();
+        dst.copy_from(&tex);
+        dst_with_border.fill_border();
+
+        let mip_level = SpriteMipLevel::SingleChunkSprite {
+            offset: (shelf_x as u16 + 1, shelf_y as u16 + 1),
+            size: (mip.width as u16, mip.height as u16),
+            sprite_chunk: pending_chunk_index,
+        };
+        trace!(
+            "Packed mip level {} of sprite #{}: {mip_level:?}",
+            mip.level,
+            mip.sprite,
+        );
+        placed_mips.push((mip.sprite, mip.level, mip_level));
+
+        shelf_x += req_w;
+        shelf_height = shelf_height.max(req_h);
+        pending_chunk_width = pending_chunk_width.max(shelf_x);
+        pending_chunk_height = pending_chunk_height.max(shelf_y + shelf_height);
+    }
+
+    // Flush out the final chunk
+    if pending_chunk_width > 0 {
+        flush_chunk(
+            db,
+            pending_chunk_width,
+            pending_chunk_height,
+            &pending_chunk_tex,
+            &mut pending_chunk_index,
+        );
+    }
+
+    placed_mips.sort_by_key(|(sprite, level, _)| (*sprite, *level));
+    for (sprite, _, mip_level) in placed_mips {
+        sprites[sprite].mip_chain.push(mip_level);
+    }
+
+    Ok(sprites)
+}
+
+/// Imports the sprite from an already loaded image. If `parallel` is true, the
+/// mip levels are resized on separate threads, otherwise on this one. The
+/// results are the same either way.
+fn import_image(
+    image: &DynamicImage,
+    filter: TextureFilter,
+    db: &mut RelatedChunkData,
+    parallel: bool,
+) -> anyhow::Result<SpriteAsset> {
     let width = image.width() as u16;
     let height = image.height() as u16;
 
@@ -55,25 +223,6 @@ pub fn import(image_path: &Path, db: &mut RelatedChunkData) -> anyhow::Result<Sp
         Pixels::new(&mut pending_pixels, CHUNK_STRIDE, CHUNK_WIDTH, CHUNK_HEIGHT).unwrap();
     let mut pending_chunk_index = db.sprite_chunks.len() as u32;
 
-    // Writes out the pending chunk into `output_chunks` and `chunk_data`.
-    let mut flush_pending_chunk = |width: usize,
-                                   height: usize,
-                                   tex: &Pixels,
-                                   chunk_index: &mut u32| {
-        let start = db.chunk_data.stream_position().unwrap();
-        for y in 0..height {
-            db.chunk_data.write_all(&tex.row(y)[..width * BPP]).unwrap();
-        }
-        let end = db.chunk_data.stream_position().unwrap();
-        trace!("Writing out a {width}x{height} sprite chunk at (this asset's) chunk index {chunk_index} and byte range {start}..{end}.");
-        db.sprite_chunks.push(SpriteChunkDescriptor {
-            region_width: width as u16,
-            region_height: height as u16,
-            source_bytes: start..end,
-        });
-        *chunk_index += 1;
-    };
-
     // Allocates space from the sprite chunk (or multiple, if needed), and
     // writes out the relevant chunks' data.
     let mut allocate = |mut tex: pixels::Pixels| -> SpriteMipLevel {
@@ -147,7 +296,8 @@ pub fn import(image_path: &Path, db: &mut RelatedChunkData) -> anyhow::Result<Sp
                 // Flush out the pending chunk (either from a previous
                 // iteration or a whole another allocate-call)
                 if pending_chunk_width > 0 {
-                    flush_pending_chunk(
+                    flush_chunk(
+                        db,
                         pending_chunk_width,
                         pending_chunk_height,
                         &pending_chunk_tex,
@@ -190,27 +340,37 @@ pub fn import(image_path: &Path, db: &mut RelatedChunkData) -> anyhow::Result<Sp
         }
     };
 
-    // Write out each mip level (calls `allocate` a bunch of times, which
-    // uses the pending chunk and flushes it as it runs out of room)
-    let (mut width, mut height) = (width as usize, height as usize);
-    let mut pixels = std::vec![0u8; width * height * BPP];
+    // Render each mip level from the original image. Resizing big images is
+    // slow, and the mip levels don't depend on each other, so they can be
+    // rendered in parallel.
+    let mip_sizes = mip_sizes(width as usize, height as usize);
+    let mut mip_pixels = (mip_sizes.iter())
+        .map(|&(width, height)| std::vec![0u8; width * height * BPP])
+        .collect::<Vec<_>>();
+    let render = |(&(width, height), pixels): (&(usize, usize), &mut Vec<u8>)| {
+        render_sprite(image, width, height, width * BPP, pixels);
+    };
+    if parallel {
+        thread::scope(|scope| {
+            for mip in mip_sizes.iter().zip(&mut mip_pixels) {
+                scope.spawn(move || render(mip));
+            }
+        });
+    } else {
+        mip_sizes.iter().zip(&mut mip_pixels).for_each(render);
+    }
+
+    // Write out each mip level in order (calls `allocate` a bunch of times,
+    // which uses the pending chunk and flushes it as it runs out of room)
     let mut mip_chain = ArrayVec::new();
-    for _ in 0..MAX_MIPS {
-        let stride = width * BPP;
-        let pixels = &mut pixels[..height * stride];
-        let tex = pixels::Pixels::new(pixels, stride, width, height).unwrap();
-        render_sprite(&image, tex.width, tex.height, tex.stride, tex.pixels);
+    for (&(width, height), pixels) in mip_sizes.iter().zip(&mut mip_pixels) {
+        let tex = pixels::Pixels::new(pixels, width * BPP, width, height).unwrap();
         mip_chain.push(allocate(tex));
-
-        (width, height) = (width.div_ceil(2), height.div_ceil(2));
-        if width == 1 && height == 1 {
-            break;
-        }
-        (width, height) = (width.max(2), height.max(2));
     }
 
     // Flush out the final chunk
-    flush_pending_chunk(
+    flush_chunk(
+        db,
         pending_chunk_width,
         pending_chunk_height,
         &pending_chunk_tex,
@@ -220,9 +380,48 @@ pub fn import(image_path: &Path, db: &mut RelatedChunkData) -> anyhow::Result<Sp
     Ok(SpriteAsset {
         transparent,
         mip_chain,
+        filter,
     })
 }
 
+/// Writes out the top-left `width` by `height` region of the pending chunk into
+/// `db`, and increments `chunk_index`.
+fn flush_chunk(
+    db: &mut RelatedChunkData,
+    width: usize,
+    height: usize,
+    tex: &Pixels,
+    chunk_index: &mut u32,
+) {
+    let start = db.chunk_data.stream_position().unwrap();
+    for y in 0..height {
+        db.chunk_data.write_all(&tex.row(y)[..width * BPP]).unwrap();
+    }
+    let end = db.chunk_data.stream_position().unwrap();
+    trace!("Writing out a {width}x{height} sprite chunk at (this asset's) chunk index {chunk_index} and byte range {start}..{end}.");
+    db.sprite_chunks.push(SpriteChunkDescriptor {
+        region_width: width as u16,
+        region_height: height as u16,
+        source_bytes: start..end,
+    });
+    *chunk_index += 1;
+}
+
+/// Returns the dimensions of each mip level of a sprite with the given size,
+/// starting with the full size sprite.
+fn mip_sizes(mut width: usize, mut height: usize) -> ArrayVec<(usize, usize), MAX_MIPS> {
+    let mut sizes = ArrayVec::new();
+    for _ in 0..MAX_MIPS {
+        sizes.push((width, height));
+        (width, height) = (width.div_ceil(2), height.div_ceil(2));
+        if width == 1 && height == 1 {
+            break;
+        }
+        (width, height) = (width.max(2), height.max(2));
+    }
+    sizes
+}
+
 fn render_sprite(
     image: &DynamicImage,
     width: usize,
@@ -246,3 +445,97 @@ fn render_sprite(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use engine::resources::sprite::SpriteMipLevel;
+    use image::{DynamicImage, Rgba, RgbaImage};
+    use platform::TextureFilter;
+
+    use crate::database::RelatedChunkData;
+
+    use super::{import_atlas, import_image, BPP};
+
+    /// Returns the pixels of a single-chunk mip level, including its border.
+    fn mip_pixels(db: &RelatedChunkData, mip: &SpriteMipLevel) -> Vec<u8> {
+        let SpriteMipLevel::SingleChunkSprite {
+            offset,
+            size,
+            sprite_chunk,
+        } = mip
+        else {
+            panic!("expected a single-chunk sprite, got {mip:?}");
+        };
+        let chunk = &db.sprite_chunks[*sprite_chunk as usize];
+        let data = &db.chunk_data.get_ref()[chunk.source_bytes.start as usize..];
+        let stride = chunk.region_width as usize * BPP;
+        let (x0, y0) = (offset.0 as usize - 1, offset.1 as usize - 1);
+        let (x1, y1) = (x0 + size.0 as usize + 2, y0 + size.1 as usize + 2);
+        let mut pixels = Vec::new();
+        for y in y0..y1 {
+            pixels.extend_from_slice(&data[x0 * BPP + y * stride..x1 * BPP + y * stride]);
+        }
+        pixels
+    }
+
+    #[test]
+    fn small_sprites_share_a_chunk_and_match_standalone_imports() {
+        let images = (0..6)
+            .map(|i| {
+                let image = RgbaImage::from_fn(16, 16, |x, y| {
+                    Rgba([x as u8 * 16, y as u8 * 16, i * 40, 255 - (x * y) as u8])
+                });
+                DynamicImage::ImageRgba8(image)
+            })
+            .collect::<Vec<_>>();
+
+        let mut atlas_db = RelatedChunkData::empty();
+        let atlas = import_atlas(&images, TextureFilter::Linear, &mut atlas_db).unwrap();
+        assert_eq!(1, atlas_db.sprite_chunks.len());
+
+        let mut repacked_db = RelatedChunkData::empty();
+        let repacked = import_atlas(&images, TextureFilter::Linear, &mut repacked_db).unwrap();
+        assert_eq!(format!("{atlas:?}"), format!("{repacked:?}"));
+        assert_eq!(atlas_db.chunk_data, repacked_db.chunk_data);
+
+        for (image, sprite) in images.iter().zip(&atlas) {
+            let mut standalone_db = RelatedChunkData::empty();
+            let standalone =
+                import_image(image, TextureFilter::Linear, &mut standalone_db, false).unwrap();
+            assert_eq!(standalone.transparent, sprite.transparent);
+            assert_eq!(standalone.mip_chain.len(), sprite.mip_chain.len());
+            for (standalone_mip, mip) in standalone.mip_chain.iter().zip(&sprite.mip_chain) {
+                assert!(matches!(
+                    mip,
+                    SpriteMipLevel::SingleChunkSprite {
+                        sprite_chunk: 0,
+                        ..
+                    },
+                ));
+                assert_eq!(
+                    mip_pixels(&standalone_db, standalone_mip),
+                    mip_pixels(&atlas_db, mip),
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn parallel_import_matches_serial_import() {
+        let image = RgbaImage::from_fn(300, 170, |x, y| {
+            Rgba([x as u8, y as u8, (x ^ y) as u8, (x + y) as u8])
+        });
+        let image = DynamicImage::ImageRgba8(image);
+
+        let mut serial_db = RelatedChunkData::empty();
+        let serial = import_image(&image, TextureFilter::Linear, &mut serial_db, false).unwrap();
+        let mut parallel_db = RelatedChunkData::empty();
+        let parallel = import_image(&image, TextureFilter::Linear, &mut parallel_db, true).unwrap();
+
+        assert!(serial.mip_chain.len() > 1);
+        assert_eq!(format!("{serial:?}"), format!("{parallel:?}"));
+        let (serial_chunks, parallel_chunks) = (serial_db.sprite_chunks, parallel_db.sprite_chunks);
+        assert_eq!(format!("{serial_chunks:?}"), format!("{parallel_chunks:?}"));
+        assert_eq!(serial_db.chunk_data, parallel_db.chunk_data);
+    }
+}
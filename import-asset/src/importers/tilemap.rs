@@ -0,0 +1,326 @@
+// SPDX-FileCopyrightText: 2025 Jens Pitkänen <jens.pitkanen@helsinki.fi>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::{
+    fs,
+    io::{Seek, Write},
+    path::Path,
+};
+
+use anyhow::Context;
+use engine::resources::{
+    tilemap::{TilemapAsset, TilesetLayout, EMPTY_TILE},
+    ChunkDescriptor, TILES_PER_CHUNK,
+};
+use serde::Deserialize;
+use tracing::debug;
+
+use crate::database::RelatedChunkData;
+
+/// Tiled stores tile flipping and rotation in the highest bits of the global
+/// tile ids, which are not supported by the engine.
+const TILED_FLAG_BITS: u32 = 0xF000_0000;
+
+/// Tileset layout properties given on the command line. These are required for
+/// CSV maps, and override the embedded tileset's properties of JSON maps.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LayoutOverrides {
+    pub tile_width: Option<u16>,
+    pub tile_height: Option<u16>,
+    pub margin: Option<u16>,
+    pub spacing: Option<u16>,
+    pub columns: Option<u16>,
+}
+
+/// The parts of a map exported by Tiled as JSON that are relevant here.
+#[derive(Deserialize)]
+struct TiledMap {
+    width: u16,
+    height: u16,
+    #[serde(default)]
+    infinite: bool,
+    layers: Vec<TiledLayer>,
+    tilesets: Vec<TiledTileset>,
+}
+
+#[derive(Deserialize)]
+struct TiledLayer {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    data: serde_json::Value,
+}
+
+#[derive(Deserialize)]
+struct TiledTileset {
+    firstgid: u32,
+    source: Option<String>,
+    tilewidth: Option<u16>,
+    tileheight: Option<u16>,
+    #[serde(default)]
+    margin: u16,
+    #[serde(default)]
+    spacing: u16,
+    columns: Option<u16>,
+}
+
+/// Parsed tilemap before it's written into chunks.
+#[derive(Debug, PartialEq)]
+struct Tiles {
+    width: u16,
+    height: u16,
+    layout: TilesetLayout,
+    tiles: Vec<u16>,
+}
+
+/// Imports a map exported from the [Tiled](https://www.mapeditor.org/) map
+/// editor, either as JSON (with the tileset embedded in the map, and the tile
+/// layer format set to CSV), or as CSV. JSON maps are read from their first
+/// tile layer. The tileset sprite itself is imported separately.
+pub fn import(
+    map_path: &Path,
+    overrides: LayoutOverrides,
+    db: &mut RelatedChunkData,
+) -> anyhow::Result<TilemapAsset> {
+    let map_file = fs::read_to_string(map_path).context("Failed to open tilemap file")?;
+    let is_csv = map_path.extension().is_some_and(|ext| ext == "csv");
+    let Tiles {
+        width,
+        height,
+        layout,
+        tiles,
+    } = if is_csv {
+        parse_csv(&map_file, overrides).context("Failed to read tilemap from the CSV file")?
+    } else {
+        parse_json(&map_file, overrides).context("Failed to read tilemap from the JSON file")?
+    };
+
+    let chunk_start = db.chunks.len() as u32;
+    for tiles_chunk in tiles.chunks(TILES_PER_CHUNK) {
+        let chunk_data_start = db.chunk_data.stream_position().unwrap();
+        for tile in tiles_chunk {
+            db.chunk_data.write_all(&tile.to_le_bytes()).unwrap();
+        }
+        let chunk_data_end = db.chunk_data.stream_position().unwrap();
+        db.chunks.push(ChunkDescriptor {
+            source_bytes: chunk_data_start..chunk_data_end,
+        });
+    }
+    let chunk_end = db.chunks.len() as u32;
+    debug!(
+        "Created {} chunks ({}..{}) for a {}x{} tilemap asset from {}.",
+        chunk_end - chunk_start,
+        chunk_start,
+        chunk_end,
+        width,
+        height,
+        map_path.display(),
+    );
+
+    Ok(TilemapAsset {
+        width,
+        height,
+        tileset: layout,
+        chunks: chunk_start..chunk_end,
+    })
+}
+
+/// Parses a map exported with Tiled's CSV export, which writes each row of
+/// tiles on its own line, with local tile ids starting from 0, and -1 for empty
+/// cells.
+fn parse_csv(csv: &str, overrides: LayoutOverrides) -> anyhow::Result<Tiles> {
+    let missing = |arg: &str| anyhow::anyhow!("CSV maps need the tileset's {arg} as an argument");
+    let layout = TilesetLayout {
+        tile_size: (
+            overrides.tile_width.ok_or_else(|| missing("tile width"))?,
+            overrides
+                .tile_height
+                .ok_or_else(|| missing("tile height"))?,
+        ),
+        margin: overrides.margin.unwrap_or(0),
+        spacing: overrides.spacing.unwrap_or(0),
+        columns: overrides.columns.ok_or_else(|| missing("column count"))?,
+    };
+
+    let mut width = None;
+    let mut height = 0;
+    let mut tiles = Vec::new();
+    for (y, line) in csv.lines().enumerate() {
+        let line = line.trim().trim_end_matches(',');
+        if line.is_empty() {
+            continue;
+        }
+        let row_start = tiles.len();
+        for cell in line.split(',') {
+            let id = (cell.trim().parse::<i32>())
+                .with_context(|| format!("Invalid tile id on line {}: {cell:?}", y + 1))?;
+            let tile = match id {
+                -1 => EMPTY_TILE,
+                0..=0xFFFE => id as u16 + 1,
+                _ => return Err(anyhow::anyhow!("Tile id {id} is out of range")),
+            };
+            tiles.push(tile);
+        }
+        let row_width = tiles.len() - row_start;
+        if *width.get_or_insert(row_width) != row_width {
+            return Err(anyhow::anyhow!(
+                "Line {} has {row_width} tiles, while the lines before it have {}",
+                y + 1,
+                width.unwrap(),
+            ));
+        }
+        height += 1;
+    }
+
+    Ok(Tiles {
+        width: to_map_size(width.unwrap_or(0))?,
+        height: to_map_size(height)?,
+        layout,
+        tiles,
+    })
+}
+
+/// Parses a map exported with Tiled's JSON export.
+fn parse_json(json: &str, overrides: LayoutOverrides) -> anyhow::Result<Tiles> {
+    let map: TiledMap = serde_json::from_str(json).context("Failed to parse the JSON")?;
+    if map.infinite {
+        return Err(anyhow::anyhow!("Infinite maps are not supported"));
+    }
+
+    let [tileset] = &map.tilesets[..] else {
+        return Err(anyhow::anyhow!(
+            "Maps must use exactly one tileset, this one has {}",
+            map.tilesets.len(),
+        ));
+    };
+    if let Some(source) = &tileset.source {
+        return Err(anyhow::anyhow!(
+            "The tileset {source:?} is not embedded in the map, embed it in Tiled to import the map",
+        ));
+    }
+    let missing = |property: &str| anyhow::anyhow!("The tileset does not have a {property}");
+    let layout = TilesetLayout {
+        tile_size: (
+            (overrides.tile_width.or(tileset.tilewidth)).ok_or_else(|| missing("tilewidth"))?,
+            (overrides.tile_height.or(tileset.tileheight)).ok_or_else(|| missing("tileheight"))?,
+        ),
+        margin: overrides.margin.unwrap_or(tileset.margin),
+        spacing: overrides.spacing.unwrap_or(tileset.spacing),
+        columns: (overrides.columns.or(tileset.columns)).ok_or_else(|| missing("columns"))?,
+    };
+
+    let layer = (map.layers.iter())
+        .find(|layer| layer.kind == "tilelayer")
+        .context("The map does not have any tile layers")?;
+    let data = (layer.data.as_array()).context(
+        "The tile layer's data is not an array of tile ids (is the layer format set to CSV?)",
+    )?;
+    if data.len() != map.width as usize * map.height as usize {
+        return Err(anyhow::anyhow!(
+            "The tile layer has {} tiles, but the map is {}x{} tiles",
+            data.len(),
+            map.width,
+            map.height,
+        ));
+    }
+
+    let mut tiles = Vec::with_capacity(data.len());
+    for gid in data {
+        let gid = (gid.as_u64())
+            .and_then(|gid| u32::try_from(gid).ok())
+            .with_context(|| format!("Invalid tile id: {gid}"))?;
+        let gid = gid & !TILED_FLAG_BITS;
+        let tile = if gid == 0 {
+            EMPTY_TILE
+        } else {
+            (gid.checked_sub(tileset.firstgid))
+                .and_then(|id| u16::try_from(id + 1).ok())
+                .with_context(|| format!("Tile id {gid} is out of the tileset's range"))?
+        };
+        tiles.push(tile);
+    }
+
+    Ok(Tiles {
+        width: map.width,
+        height: map.height,
+        layout,
+        tiles,
+    })
+}
+
+fn to_map_size(tiles: usize) -> anyhow::Result<u16> {
+    u16::try_from(tiles)
+        .map_err(|_| anyhow::anyhow!("The map is too large ({tiles} tiles on one side)"))
+}
+
+#[cfg(test)]
+mod tests {
+    use engine::resources::tilemap::TilesetLayout;
+
+    use super::{parse_csv, parse_json, LayoutOverrides, Tiles};
+
+    #[test]
+    fn csv_maps_use_the_layout_from_the_arguments() {
+        let csv = "0,1,-1\n-1,2,3\n";
+        let overrides = LayoutOverrides {
+            tile_width: Some(16),
+            tile_height: Some(8),
+            columns: Some(4),
+            ..LayoutOverrides::default()
+        };
+        let expected = Tiles {
+            width: 3,
+            height: 2,
+            layout: TilesetLayout {
+                tile_size: (16, 8),
+                margin: 0,
+                spacing: 0,
+                columns: 4,
+            },
+            tiles: vec![1, 2, 0, 0, 3, 4],
+        };
+        assert_eq!(expected, parse_csv(csv, overrides).unwrap());
+
+        assert!(parse_csv(csv, LayoutOverrides::default()).is_err());
+        assert!(parse_csv("0,1\n0\n", overrides).is_err());
+    }
+
+    #[test]
+    fn json_maps_use_the_embedded_tileset() {
+        let json = r#"{
+            "width": 2, "height": 2, "infinite": false,
+            "layers": [
+                { "type": "objectgroup", "objects": [] },
+                { "type": "tilelayer", "data": [5, 0, 6, 2147483655] }
+            ],
+            "tilesets": [{
+                "firstgid": 5, "tilewidth": 16, "tileheight": 16,
+                "margin": 1, "spacing": 2, "columns": 8
+            }]
+        }"#;
+        let expected = Tiles {
+            width: 2,
+            height: 2,
+            layout: TilesetLayout {
+                tile_size: (16, 16),
+                margin: 1,
+                spacing: 2,
+                columns: 8,
+            },
+            // The last tile is flipped horizontally, which is ignored
+            tiles: vec![1, 0, 2, 3],
+        };
+        assert_eq!(
+            expected,
+            parse_json(json, LayoutOverrides::default()).unwrap()
+        );
+
+        let overrides = LayoutOverrides {
+            spacing: Some(0),
+            ..LayoutOverrides::default()
+        };
+        let tiles = parse_json(json, overrides).unwrap();
+        assert_eq!(0, tiles.layout.spacing);
+    }
+}
@@ -10,6 +10,7 @@ mod settings;
 use std::{
     fs::{self, File},
     io::BufWriter,
+    path::{Path, PathBuf},
     str::FromStr,
 };
 
@@ -17,7 +18,8 @@ use anyhow::Context;
 use arrayvec::ArrayString;
 use cli::Command;
 use database::{Database, RelatedChunkData};
-use engine::resources::NamedAsset;
+use engine::resources::{NamedAsset, ASSET_NAME_LENGTH};
+use platform::TextureFilter;
 use settings::ImportSettings;
 use tracing::{info, warn};
 use tracing_subscriber::util::SubscriberInitExt;
@@ -38,7 +40,16 @@ fn main() -> anyhow::Result<()> {
     // `file_lock` feature is in FCP, so it might be possible relatively soon.
     info!("Reading database from: {}", opts.database.display());
     let db_file = fs::read(&opts.database).ok();
-    let mut database = Database::new(db_file.as_deref()).context("Failed to read database file")?;
+    let mut database = match Database::new(db_file.as_deref()) {
+        Ok(database) => database,
+        // Reimporting doesn't need the existing database, so an unreadable
+        // (e.g. outdated) database can simply be replaced
+        Err(err) if opts.command == (Command::Reimport {}) => {
+            warn!("Discarding the existing database, it could not be read: {err:#}");
+            Database::new(None)?
+        }
+        Err(err) => return Err(err.context("Failed to read database file")),
+    };
 
     process_command(&opts.command, &mut settings, &mut database)?;
 
@@ -89,13 +100,19 @@ fn process_command(
             return Ok(());
         }
 
-        Command::AddSprite { name, file } => {
+        Command::AddSprite {
+            name,
+            file,
+            nearest_neighbor,
+        } => {
             info!("Importing sprite \"{}\" from: {}", name, file.display());
             let mut related_chunk_data = RelatedChunkData::empty();
             let name = ArrayString::from_str(name).unwrap();
-            let asset = importers::sprite::import(file, &mut related_chunk_data)
+            let filter = texture_filter(*nearest_neighbor);
+            let asset = importers::sprite::import(file, filter, &mut related_chunk_data)
                 .context("Failed to import sprite")?;
             let asset_and_data = (NamedAsset { name, asset }, related_chunk_data);
+            db.remove_from_sprite_atlases(&name);
             if let Some(existing_asset) = db.sprites.iter_mut().find(|a| a.0.name == name) {
                 *existing_asset = asset_and_data;
             } else {
@@ -103,6 +120,139 @@ fn process_command(
             }
         }
 
+        Command::AddSpriteDir {
+            dir,
+            name_prefix,
+            nearest_neighbor,
+        } => {
+            info!("Importing sprites from directory: {}", dir.display());
+            let prefix = name_prefix_or_default(name_prefix.as_deref())?;
+            let files = sorted_files_in_dir(dir)?;
+
+            // Each sprite is imported with its own AddSprite command, which
+            // also adds them to the import settings, so that reimports don't
+            // pick up any new files in the directory by surprise
+            let mut imported_names = Vec::new();
+            for file in files {
+                let Some(name) = sprite_name_from_file(prefix, &file) else {
+                    continue;
+                };
+                if imported_names.contains(&name) {
+                    warn!(
+                        "Skipping {}, another file in the directory was already imported as \"{name}\".",
+                        file.display(),
+                    );
+                    continue;
+                }
+                let command = Command::AddSprite {
+                    name,
+                    file: file.clone(),
+                    nearest_neighbor: *nearest_neighbor,
+                };
+                if let Err(err) = process_command(&command, settings, db) {
+                    warn!("Skipping {}: {err:#}", file.display());
+                    continue;
+                }
+                imported_names.push(name);
+            }
+
+            info!(
+                "Imported {} sprites from the directory.",
+                imported_names.len()
+            );
+            return Ok(());
+        }
+
+        Command::AddSpriteAtlas {
+            name,
+            dir,
+            name_prefix,
+            nearest_neighbor,
+        } => {
+            info!(
+                "Importing sprite atlas \"{}\" from directory: {}",
+                name,
+                dir.display(),
+            );
+            let mut names = Vec::new();
+            let mut images = Vec::new();
+            for (sprite_name, file) in atlas_sprite_files(dir, name_prefix.as_deref())? {
+                let image = match importers::sprite::load_image(&file) {
+                    Ok(image) => image,
+                    Err(err) => {
+                        warn!("Skipping {}: {err:#}", file.display());
+                        continue;
+                    }
+                };
+                if !importers::sprite::fits_in_atlas(&image) {
+                    warn!(
+                        "Skipping {}, it's too large for a sprite atlas, add it with add-sprite instead.",
+                        file.display(),
+                    );
+                    continue;
+                }
+                names.push(sprite_name);
+                images.push(image);
+            }
+
+            let mut related_chunk_data = RelatedChunkData::empty();
+            let filter = texture_filter(*nearest_neighbor);
+            let sprites = importers::sprite::import_atlas(&images, filter, &mut related_chunk_data)
+                .context("Failed to import sprite atlas")?;
+            let mut atlas = Vec::with_capacity(sprites.len());
+            for (name, asset) in names.into_iter().zip(sprites) {
+                // Replace existing sprites like AddSprite does
+                db.sprites.retain(|(sprite, _)| sprite.name != name);
+                db.remove_from_sprite_atlases(&name);
+                atlas.push(NamedAsset { name, asset });
+            }
+            info!(
+                "Packed {} sprites into {} sprite chunks.",
+                atlas.len(),
+                related_chunk_data.sprite_chunks.len(),
+            );
+            if !atlas.is_empty() {
+                db.sprite_atlases.push((atlas, related_chunk_data));
+            }
+        }
+
+        Command::Remove { name } => {
+            info!("Removing asset \"{}\".", name);
+            let mut removed_from_db = db.remove(name);
+
+            // Atlases aren't assets in the database, only their sprites are
+            let atlas = imports.iter().find_map(|import| match import {
+                Command::AddSpriteAtlas {
+                    name: atlas_name,
+                    dir,
+                    name_prefix,
+                    ..
+                } if atlas_name == name => Some((dir, name_prefix.as_deref())),
+                _ => None,
+            });
+            if let Some((dir, name_prefix)) = atlas {
+                match atlas_sprite_files(dir, name_prefix) {
+                    Ok(files) => {
+                        for (sprite_name, _) in files {
+                            removed_from_db |= db.remove(&sprite_name);
+                        }
+                    }
+                    Err(err) => warn!(
+                        "Could not find the sprites of the atlas, they will be left in the database until the next reimport: {err:#}"
+                    ),
+                }
+            }
+
+            let imports_before = imports.len();
+            imports.retain(|import| import.asset_name() != Some(name.as_str()));
+            if !removed_from_db && imports.len() == imports_before {
+                return Err(anyhow::anyhow!(
+                    "No asset named \"{name}\" was found in the database or the import settings"
+                ));
+            }
+            return Ok(());
+        }
+
         Command::AddAudioClip { name, file, track } => {
             info!("Importing audio clip \"{}\" from: {}", name, file.display());
             let mut related_chunk_data = RelatedChunkData::empty();
@@ -116,6 +266,68 @@ fn process_command(
                 db.audio_clips.push(asset_and_data);
             }
         }
+
+        Command::AddFont { name, file, size } => {
+            info!("Importing font \"{}\" from: {}", name, file.display());
+            let mut related_chunk_data = RelatedChunkData::empty();
+            let name = ArrayString::from_str(name).unwrap();
+            let asset = importers::font::import(file, *size, &mut related_chunk_data)
+                .context("Failed to import font")?;
+            let asset_and_data = (NamedAsset { name, asset }, related_chunk_data);
+            if let Some(existing_asset) = db.fonts.iter_mut().find(|a| a.0.name == name) {
+                *existing_asset = asset_and_data;
+            } else {
+                db.fonts.push(asset_and_data);
+            }
+        }
+
+        Command::AddAnimation {
+            name,
+            file,
+            nearest_neighbor,
+        } => {
+            info!("Importing animation \"{}\" from: {}", name, file.display());
+            let mut related_chunk_data = RelatedChunkData::empty();
+            let name = ArrayString::from_str(name).unwrap();
+            let filter = texture_filter(*nearest_neighbor);
+            let asset = importers::animation::import(file, filter, &mut related_chunk_data)
+                .context("Failed to import animation")?;
+            let asset_and_data = (NamedAsset { name, asset }, related_chunk_data);
+            if let Some(existing_asset) = db.animations.iter_mut().find(|a| a.0.name == name) {
+                *existing_asset = asset_and_data;
+            } else {
+                db.animations.push(asset_and_data);
+            }
+        }
+
+        Command::AddTilemap {
+            name,
+            file,
+            tile_width,
+            tile_height,
+            margin,
+            spacing,
+            columns,
+        } => {
+            info!("Importing tilemap \"{}\" from: {}", name, file.display());
+            let mut related_chunk_data = RelatedChunkData::empty();
+            let name = ArrayString::from_str(name).unwrap();
+            let overrides = importers::tilemap::LayoutOverrides {
+                tile_width: *tile_width,
+                tile_height: *tile_height,
+                margin: *margin,
+                spacing: *spacing,
+                columns: *columns,
+            };
+            let asset = importers::tilemap::import(file, overrides, &mut related_chunk_data)
+                .context("Failed to import tilemap")?;
+            let asset_and_data = (NamedAsset { name, asset }, related_chunk_data);
+            if let Some(existing_asset) = db.tilemaps.iter_mut().find(|a| a.0.name == name) {
+                *existing_asset = asset_and_data;
+            } else {
+                db.tilemaps.push(asset_and_data);
+            }
+        }
     }
 
     // In case the command operated on an asset, update the command in the import settings.
@@ -129,3 +341,107 @@ fn process_command(
 
     Ok(())
 }
+
+/// Returns the filter to import sprites with, based on the `nearest_neighbor`
+/// switch of the sprite import commands.
+fn texture_filter(nearest_neighbor: bool) -> TextureFilter {
+    if nearest_neighbor {
+        TextureFilter::NearestNeighbor
+    } else {
+        TextureFilter::Linear
+    }
+}
+
+/// Returns the name prefix, or an empty string if there's none, or an error if
+/// the prefix is too long to fit in an asset name.
+fn name_prefix_or_default(name_prefix: Option<&str>) -> anyhow::Result<&str> {
+    let prefix = name_prefix.unwrap_or("");
+    if prefix.len() >= ASSET_NAME_LENGTH {
+        return Err(anyhow::anyhow!(
+            "The name prefix must be shorter than {ASSET_NAME_LENGTH} bytes"
+        ));
+    }
+    Ok(prefix)
+}
+
+/// Returns the paths of the files in the directory, sorted.
+fn sorted_files_in_dir(dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir).context("Failed to read the sprite directory")? {
+        let path = entry.context("Failed to read the sprite directory")?.path();
+        if path.is_file() {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Returns the files in the directory to be packed into a sprite atlas, along
+/// with the names of their sprites. Files which would end up with the same name
+/// as an earlier file are skipped.
+fn atlas_sprite_files(
+    dir: &Path,
+    name_prefix: Option<&str>,
+) -> anyhow::Result<Vec<(ArrayString<ASSET_NAME_LENGTH>, PathBuf)>> {
+    let prefix = name_prefix_or_default(name_prefix)?;
+    let mut sprite_files: Vec<(ArrayString<ASSET_NAME_LENGTH>, PathBuf)> = Vec::new();
+    for file in sorted_files_in_dir(dir)? {
+        let Some(name) = sprite_name_from_file(prefix, &file) else {
+            continue;
+        };
+        if sprite_files
+            .iter()
+            .any(|(other_name, _)| *other_name == name)
+        {
+            warn!(
+                "Skipping {}, another file in the directory was already named \"{name}\".",
+                file.display(),
+            );
+            continue;
+        }
+        sprite_files.push((name, file));
+    }
+    Ok(sprite_files)
+}
+
+/// Returns the file's name without the extension, prefixed with `prefix`, and
+/// truncated to fit in [`ASSET_NAME_LENGTH`] if needed. Returns `None` if the
+/// file name is not valid UTF-8.
+fn sprite_name_from_file(prefix: &str, file: &Path) -> Option<ArrayString<ASSET_NAME_LENGTH>> {
+    let Some(stem) = file.file_stem().and_then(|stem| stem.to_str()) else {
+        warn!(
+            "Skipping {}, the file name is not valid UTF-8.",
+            file.display()
+        );
+        return None;
+    };
+
+    let full_name = format!("{prefix}{stem}");
+    let mut name = ArrayString::new();
+    for c in full_name.chars() {
+        if name.try_push(c).is_err() {
+            warn!(
+                "The name \"{full_name}\" is too long for an asset name, truncating it to \"{name}\".",
+            );
+            break;
+        }
+    }
+    Some(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::sprite_name_from_file;
+
+    #[test]
+    fn sprite_names_are_prefixed_and_truncated() {
+        let name = sprite_name_from_file("ui_", Path::new("sprites/button.png"));
+        assert_eq!(Some("ui_button"), name.as_deref());
+
+        let name = sprite_name_from_file("ui_", Path::new("a_very_long_file_name_äöå.png"));
+        assert_eq!(Some("ui_a_very_long_file_name_ä"), name.as_deref());
+    }
+}
@@ -3,44 +3,54 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
 use std::{
-    cell::{Cell, RefCell},
-    ffi::{c_int, c_void},
+    cell::{Cell, Ref, RefCell},
+    ffi::{c_char, c_int, c_void, CStr},
     fmt::Arguments,
-    fs::File,
-    io::{self, Read, Seek, SeekFrom},
+    fs::{File, OpenOptions},
+    io::{self, Read, Seek, SeekFrom, Write},
+    ops::ControlFlow,
     panic,
-    path::PathBuf,
+    path::{Path, PathBuf},
     process::exit,
     ptr::{addr_of, null_mut},
-    str::FromStr,
+    str::{self, FromStr},
     sync::{Arc, Condvar, Mutex},
     thread::{self, JoinHandle},
     time::Duration,
 };
 
 use platform::{
-    ActionCategory, Button, DrawSettings2D, EngineCallbacks, FileHandle, FileReadTask, InputDevice,
-    InputDevices, Platform, Vertex2D, AUDIO_CHANNELS, AUDIO_SAMPLE_RATE,
+    ActionCategory, AudioOutput, Axis, Button, ButtonNameString, DisplayInfo, DisplayInfoList,
+    DrawSettings2D, EngineCallbacks, FileHandle, FileReadTask, FileWriteTask, FullscreenMode,
+    GamepadKind, InputDevice, InputDevices, PathString, Platform, PresentMode, TextInputString,
+    Vertex2D, WindowId, AUDIO_CHANNELS, AUDIO_SAMPLE_RATE,
 };
 use sdl2::{
     audio::{AudioCallback, AudioDevice, AudioSpec, AudioSpecDesired},
-    controller::Button as SdlButton,
-    event::Event,
+    controller::{Axis as SdlAxis, Button as SdlButton},
+    event::{Event, WindowEvent},
     keyboard::{Keycode, Mod, Scancode},
+    mouse::MouseButton,
     pixels::{Color, PixelFormatEnum},
     rect::Rect,
     render::{Texture, TextureAccess, TextureCreator, WindowCanvas},
-    video::WindowContext,
+    video::{FullscreenType, WindowContext},
     AudioSubsystem, Sdl, TimerSubsystem,
 };
 use sdl2_sys::{
-    SDL_BlendMode, SDL_Color, SDL_GameController, SDL_GameControllerGetType,
-    SDL_GameControllerOpen, SDL_GameControllerType, SDL_GetTicks64, SDL_RenderGeometryRaw,
-    SDL_Renderer, SDL_ScaleMode, SDL_SetTextureBlendMode, SDL_SetTextureScaleMode,
+    SDL_BlendFactor, SDL_BlendMode, SDL_BlendOperation, SDL_Color, SDL_GameController,
+    SDL_GameControllerGetType, SDL_GameControllerOpen, SDL_GameControllerRumble,
+    SDL_GameControllerType, SDL_GetTicks64, SDL_PumpEvents, SDL_RenderGeometryRaw,
+    SDL_RenderSetVSync, SDL_Renderer, SDL_ScaleMode, SDL_SetTextureScaleMode, SDL_Texture,
 };
 
+/// The index of [`Hid::Mouse`] in [`Sdl2Platform::hids`], which is always the
+/// second device, after the keyboard.
+const MOUSE_DEVICE: u64 = 1;
+
 enum Hid {
     Keyboard,
+    Mouse,
     Gamepad {
         /// An opened SDL game controller which we'll never close. Unfortunate,
         /// but shouldn't cause any major issues.
@@ -64,6 +74,75 @@ struct FileHolder {
     source: FileReadSource,
     tasks: Vec<(u64, FileRead)>,
     task_id_counter: u64,
+    /// Threads of cancelled reads which were still running when cancelled.
+    /// Joined once they finish, see [`FileHolder::join_cancelled_reads`].
+    cancelled_reads: Vec<JoinHandle<Result<Vec<u8>, io::Error>>>,
+}
+
+impl FileHolder {
+    fn new(source: FileReadSource) -> FileHolder {
+        FileHolder {
+            source,
+            tasks: Vec::new(),
+            task_id_counter: 0,
+            cancelled_reads: Vec::new(),
+        }
+    }
+
+    /// Starts reading `len` bytes starting from `first_byte`, returning the
+    /// task id of the read.
+    fn begin_read(&mut self, first_byte: u64, len: usize) -> u64 {
+        self.join_cancelled_reads();
+        let id = self.task_id_counter;
+        self.task_id_counter += 1;
+        let read = match &self.source {
+            FileReadSource::Path(path) => {
+                let path = path.clone();
+                FileRead::Threaded(std::thread::spawn(move || {
+                    read_file_at(&path, first_byte, len)
+                }))
+            }
+            FileReadSource::Embedded(bytes) => {
+                FileRead::Embedded(&bytes[first_byte as usize..first_byte as usize + len])
+            }
+        };
+        self.tasks.push((id, read));
+        id
+    }
+
+    /// Removes the read from the list of tasks and returns it.
+    fn take_read(&mut self, task_id: u64) -> Option<FileRead> {
+        let idx = self.tasks.iter().position(|(id, _)| *id == task_id)?;
+        Some(self.tasks.swap_remove(idx).1)
+    }
+
+    /// Removes the read from the list of tasks without waiting for it to
+    /// finish. Its thread is joined later if it's still running.
+    fn cancel_read(&mut self, task_id: u64) -> Option<()> {
+        if let FileRead::Threaded(join_handle) = self.take_read(task_id)? {
+            // The result is not used, so the read bytes are not copied anywhere.
+            self.cancelled_reads.push(join_handle);
+        }
+        self.join_cancelled_reads();
+        Some(())
+    }
+
+    /// Joins the threads of cancelled reads which have finished.
+    fn join_cancelled_reads(&mut self) {
+        let mut i = 0;
+        while i < self.cancelled_reads.len() {
+            if self.cancelled_reads[i].is_finished() {
+                let _ = self.cancelled_reads.swap_remove(i).join();
+            } else {
+                i += 1;
+            }
+        }
+    }
+}
+
+struct FileWrites {
+    tasks: Vec<(u64, JoinHandle<Result<(), io::Error>>)>,
+    task_id_counter: u64,
 }
 
 struct AudioBufferState {
@@ -79,22 +158,41 @@ struct AudioBufferState {
 
 type SharedAudioBuffer = Arc<Mutex<AudioBufferState>>;
 
+/// A window, along with its renderer and the renderer's copies of the sprites.
+struct Sdl2Window {
+    /// The textures backing the [`platform::SpriteRef`]s, indexed by the
+    /// SpriteRefs. Each window has its own copy of every texture, since SDL
+    /// textures can only be used with the renderer that created them.
+    textures: Vec<Texture<'static>>,
+    texture_creator: &'static TextureCreator<WindowContext>,
+    canvas: WindowCanvas,
+}
+
 /// The [`Platform`] impl for the SDL2 based platform.
 pub struct Sdl2Platform {
     sdl_context: Sdl,
     _time: TimerSubsystem,
     _audio: AudioSubsystem,
     audio_device: Option<AudioDevice<AudioCallbackImpl>>,
-    canvas: RefCell<WindowCanvas>,
+    /// The windows of the game, indexed by [`WindowId`]. The main window is
+    /// always the first one.
+    windows: RefCell<Vec<Sdl2Window>>,
+    /// Index of the window in `windows` which is drawn into.
+    active_window: Cell<usize>,
+    /// The pixels of each sprite, indexed by the [`platform::SpriteRef`]s,
+    /// for uploading the existing sprites into new windows, since SDL
+    /// textures can't be read back.
+    sprite_pixels: RefCell<Vec<Vec<u8>>>,
+    present_mode: Cell<PresentMode>,
     exit_requested: Cell<bool>,
-    texture_creator: &'static TextureCreator<WindowContext>,
-    textures: RefCell<Vec<Texture<'static>>>,
     /// List of input devices. Devices are never removed, so the InputDevice ids
     /// used for this platform are indices to this list.
     hids: RefCell<Vec<Hid>>,
     embedded_files: Vec<(&'static str, &'static [u8])>,
     files: RefCell<Vec<FileHolder>>,
+    file_writes: RefCell<FileWrites>,
     shared_audio_buffer: SharedAudioBuffer,
+    audio_output: AudioOutput,
 }
 
 impl Drop for Sdl2Platform {
@@ -112,11 +210,20 @@ impl Drop for Sdl2Platform {
 
 impl Sdl2Platform {
     pub fn new(title: &str) -> Sdl2Platform {
+        Sdl2Platform::with_audio_output(title, AudioOutput::Stereo)
+    }
+
+    /// Like [`Sdl2Platform::new`], but with mono audio output if `audio_output`
+    /// is [`AudioOutput::Mono`].
+    pub fn with_audio_output(title: &str, audio_output: AudioOutput) -> Sdl2Platform {
         let sdl_context = sdl2::init().expect("SDL 2 library should be able to init");
 
         let video = sdl_context
             .video()
             .expect("SDL video subsystem should be able to init");
+        // SDL enables text input by default on some platforms, but it should
+        // be off until the game asks for it with start_text_input.
+        video.text_input().stop();
         let mut window = video.window(title, 960, 540);
         #[cfg(not(target_os = "emscripten"))]
         let window = window.allow_highdpi(); // seems broken on emscripten
@@ -150,7 +257,10 @@ impl Sdl2Platform {
             None,
             &AudioSpecDesired {
                 freq: Some(AUDIO_SAMPLE_RATE as i32),
-                channels: Some(2),
+                channels: Some(match audio_output {
+                    AudioOutput::Stereo => 2,
+                    AudioOutput::Mono => 1,
+                }),
                 samples: None,
             },
             |spec| AudioCallbackImpl::new(spec, shared_audio_buffer.clone()),
@@ -170,14 +280,24 @@ impl Sdl2Platform {
             _time: time,
             _audio: audio,
             audio_device,
-            canvas: RefCell::new(canvas),
+            windows: RefCell::new(vec![Sdl2Window {
+                textures: Vec::new(),
+                texture_creator,
+                canvas,
+            }]),
+            active_window: Cell::new(0),
+            sprite_pixels: RefCell::new(Vec::new()),
+            present_mode: Cell::new(PresentMode::Vsync),
             exit_requested: Cell::new(false),
-            texture_creator,
-            textures: RefCell::new(Vec::new()),
-            hids: RefCell::new(vec![Hid::Keyboard]),
+            hids: RefCell::new(vec![Hid::Keyboard, Hid::Mouse]),
             embedded_files: Vec::new(),
             files: RefCell::new(Vec::new()),
+            file_writes: RefCell::new(FileWrites {
+                tasks: Vec::new(),
+                task_id_counter: 0,
+            }),
             shared_audio_buffer,
+            audio_output,
         }
     }
 
@@ -198,15 +318,62 @@ impl Sdl2Platform {
         None
     }
 
+    fn get_window_by_sdl_window_id(&self, window_id: u32) -> Option<WindowId> {
+        let windows = self.windows.borrow();
+        (windows.iter())
+            .position(|window| window.canvas.window().id() == window_id)
+            .map(|i| WindowId::new(i as u64))
+    }
+
+    /// Returns the canvas of the main window, for accessing the parts of SDL
+    /// which are shared by all the windows, like the video subsystem.
+    fn main_canvas(&self) -> Ref<'_, WindowCanvas> {
+        Ref::map(self.windows.borrow(), |windows| &windows[0].canvas)
+    }
+
+    /// Returns the canvas of the window set with
+    /// [`Platform::set_active_window`].
+    fn active_canvas(&self) -> Ref<'_, WindowCanvas> {
+        let active = self.active_window.get();
+        Ref::map(self.windows.borrow(), |windows| &windows[active].canvas)
+    }
+
+    fn window_size(&self, window: WindowId) -> (f32, f32) {
+        let windows = self.windows.borrow();
+        let (w, h) = windows[window.inner() as usize].canvas.window().size();
+        (w as f32, h as f32)
+    }
+
+    fn request_exit(&self) {
+        // Closing the main window can cause both a window close event and a
+        // quit event, only the first one needs to do anything.
+        if self.exit_requested.replace(true) {
+            return;
+        }
+        thread::spawn(|| {
+            // Force-exit the process after 1s, cleanup is pretty optional anyway.
+            thread::sleep(Duration::from_secs(1));
+            eprintln!("Resource cleanup is taking too long, exiting non-gracefully.");
+            std::process::exit(1);
+        });
+    }
+
     pub fn embed_file(&mut self, path: &'static str, bytes: &'static [u8]) {
         self.embedded_files.push((path, bytes));
     }
 
-    pub fn run_game_loop<E: EngineCallbacks>(
+    /// Runs frames of the engine until `run_game_frame` returns
+    /// [`ControlFlow::Break`], returning the value it broke with, or until the
+    /// user closes the window or [`Platform::exit`] is called, returning
+    /// `None`.
+    ///
+    /// The game loop can be started again after it has returned, e.g. to
+    /// reinitialize the game.
+    pub fn run_game_loop<E: EngineCallbacks, T>(
         &self,
         engine: &mut E,
-        mut run_game_frame: impl FnMut(platform::Instant, &dyn Platform, &mut E),
-    ) {
+        mut run_game_frame: impl FnMut(platform::Instant, &dyn Platform, &mut E) -> ControlFlow<T>,
+    ) -> Option<T> {
         // Init the subsystem. The subsystem is actually used, just through the FFI
         // calls, since the subsystem doesn't expose everything we need (e.g. game
         // controller type).
@@ -219,20 +386,14 @@ impl Sdl2Platform {
             .event_pump()
             .expect("SDL 2 event pump should init without issue");
 
+        // The point in time the next frame should start at, when the frame
+        // rate is capped with PresentMode::FrameCap.
+        let mut next_frame_start = std::time::Instant::now();
+
         while !self.exit_requested.get() {
             for event in event_pump.poll_iter() {
                 match event {
-                    Event::Quit { .. } => {
-                        self.exit_requested.set(true);
-                        thread::spawn(|| {
-                            // Force-exit the process after 1s, cleanup is pretty optional anyway.
-                            thread::sleep(Duration::from_secs(1));
-                            eprintln!(
-                                "Resource cleanup is taking too long, exiting non-gracefully."
-                            );
-                            std::process::exit(1);
-                        });
-                    }
+                    Event::Quit { .. } => self.request_exit(),
                     Event::KeyDown {
                         keycode: Some(Keycode::Q),
                         keymod,
@@ -241,6 +402,56 @@ impl Sdl2Platform {
                         self.exit_requested.set(true);
                     }
 
+                    Event::Window {
+                        timestamp,
+                        window_id,
+                        win_event,
+                    } => {
+                        let Some(window) = self.get_window_by_sdl_window_id(window_id) else {
+                            continue;
+                        };
+                        let event = match win_event {
+                            WindowEvent::SizeChanged(_, _) => {
+                                // Not using the size from the event to make
+                                // sure the size matches draw_area exactly.
+                                let (width, height) = self.window_size(window);
+                                platform::Event::Resized {
+                                    window,
+                                    width,
+                                    height,
+                                }
+                            }
+                            WindowEvent::FocusGained => platform::Event::FocusChanged {
+                                window,
+                                focused: true,
+                            },
+                            WindowEvent::FocusLost => platform::Event::FocusChanged {
+                                window,
+                                focused: false,
+                            },
+                            // SDL only sends a quit event when the last window
+                            // is closed, so closing the main window needs to be
+                            // handled here, while other windows are just hidden.
+                            WindowEvent::Close if window == WindowId::MAIN => {
+                                self.request_exit();
+                                continue;
+                            }
+                            WindowEvent::Close => {
+                                let mut windows = self.windows.borrow_mut();
+                                let sdl_window =
+                                    windows[window.inner() as usize].canvas.window_mut();
+                                sdl_window.hide();
+                                continue;
+                            }
+                            _ => continue,
+                        };
+                        engine.event(
+                            event,
+                            platform::Instant::reference()
+                                + Duration::from_millis(timestamp as u64),
+                        );
+                    }
+
                     Event::ControllerDeviceAdded { which, .. } => {
                         // Safety: ffi call.
                         let controller = unsafe { SDL_GameControllerOpen(which as i32) };
@@ -300,6 +511,86 @@ impl Sdl2Platform {
                         );
                     }
 
+                    Event::TextInput {
+                        timestamp, text, ..
+                    } => {
+                        // SDL's text input events are shorter than 32 bytes,
+                        // but split the text up just in case.
+                        let mut remaining = text.as_str();
+                        while !remaining.is_empty() {
+                            let mut len = remaining.len().min(32);
+                            while !remaining.is_char_boundary(len) {
+                                len -= 1;
+                            }
+                            let (head, tail) = remaining.split_at(len);
+                            remaining = tail;
+                            engine.event(
+                                platform::Event::TextInput {
+                                    text: TextInputString::from(head).unwrap(),
+                                },
+                                platform::Instant::reference()
+                                    + Duration::from_millis(timestamp as u64),
+                            );
+                        }
+                    }
+
+                    Event::MouseMotion {
+                        timestamp,
+                        window_id,
+                        x,
+                        y,
+                        ..
+                    } => {
+                        let Some(window) = self.get_window_by_sdl_window_id(window_id) else {
+                            continue;
+                        };
+                        // SDL's mouse coordinates are relative to the window,
+                        // in the same units as the window size, which is what
+                        // draw_area returns as well.
+                        engine.event(
+                            platform::Event::PointerMoved {
+                                device: InputDevice::new(MOUSE_DEVICE),
+                                window,
+                                x: x as f32,
+                                y: y as f32,
+                            },
+                            platform::Instant::reference()
+                                + Duration::from_millis(timestamp as u64),
+                        );
+                    }
+
+                    Event::MouseButtonDown {
+                        timestamp,
+                        mouse_btn,
+                        ..
+                    } => {
+                        engine.event(
+                            platform::Event::PointerButton {
+                                device: InputDevice::new(MOUSE_DEVICE),
+                                button: button_for_mouse(mouse_btn),
+                                pressed: true,
+                            },
+                            platform::Instant::reference()
+                                + Duration::from_millis(timestamp as u64),
+                        );
+                    }
+
+                    Event::MouseButtonUp {
+                        timestamp,
+                        mouse_btn,
+                        ..
+                    } => {
+                        engine.event(
+                            platform::Event::PointerButton {
+                                device: InputDevice::new(MOUSE_DEVICE),
+                                button: button_for_mouse(mouse_btn),
+                                pressed: false,
+                            },
+                            platform::Instant::reference()
+                                + Duration::from_millis(timestamp as u64),
+                        );
+                    }
+
                     Event::ControllerButtonDown {
                         timestamp,
                         which,
@@ -334,38 +625,130 @@ impl Sdl2Platform {
                         }
                     }
 
+                    Event::ControllerAxisMotion {
+                        timestamp,
+                        which,
+                        axis,
+                        value,
+                    } => {
+                        if let Some(device) = self.get_input_device_by_sdl_joystick_id(which) {
+                            engine.event(
+                                platform::Event::AnalogInputChanged(
+                                    device,
+                                    axis_for_gamepad(axis),
+                                    (value as f32 / i16::MAX as f32).max(-1.0),
+                                ),
+                                platform::Instant::reference()
+                                    + Duration::from_millis(timestamp as u64),
+                            );
+                        }
+                    }
+
                     _ => {}
                 }
             }
 
-            {
-                let mut canvas = self.canvas.borrow_mut();
-                canvas.set_draw_color(Color::BLACK);
-                canvas.clear();
+            for window in self.windows.borrow_mut().iter_mut() {
+                window.canvas.set_draw_color(Color::BLACK);
+                window.canvas.clear();
             }
 
-            engine.run_frame(self, &mut run_game_frame);
+            let mut flow = ControlFlow::Continue(());
+            engine.run_frame(self, &mut |timestamp, platform, engine| {
+                flow = run_game_frame(timestamp, platform, engine);
+            });
 
-            {
-                let mut canvas = self.canvas.borrow_mut();
-                canvas.present();
+            for window in self.windows.borrow_mut().iter_mut() {
+                window.canvas.present();
+            }
+
+            if let PresentMode::FrameCap(fps @ 1..) = self.present_mode.get() {
+                let now = std::time::Instant::now();
+                let frame_length = Duration::from_secs(1) / fps;
+                // Scheduling from the previous deadline avoids drifting below
+                // the cap, but if a frame ran late, start over from now
+                // instead of running a burst of frames to catch up.
+                next_frame_start = (next_frame_start + frame_length).max(now);
+                thread::sleep(next_frame_start - now);
+            }
+
+            if let ControlFlow::Break(value) = flow {
+                return Some(value);
             }
         }
+
+        None
     }
 }
 
 impl Platform for Sdl2Platform {
-    fn draw_area(&self) -> (f32, f32) {
-        let (w, h) = {
-            let canvas = self.canvas.borrow();
-            canvas.window().size()
+    fn create_window(&self, title: &str, width: u32, height: u32) -> Option<WindowId> {
+        let video = self.main_canvas().window().subsystem().clone();
+        let mut window = video.window(title, width, height);
+        #[cfg(not(target_os = "emscripten"))]
+        let window = window.allow_highdpi(); // seems broken on emscripten
+        let window = match window.position_centered().resizable().build() {
+            Ok(window) => window,
+            Err(err) => {
+                eprintln!("Failed to create a window: {err}");
+                return None;
+            }
         };
-        (w as f32, h as f32)
+        // Not using vsync here, since waiting for vsync on every window's
+        // present would divide the frame rate by the amount of windows. The
+        // main window's present mode paces the frames for all windows.
+        let canvas = match window.into_canvas().build() {
+            Ok(canvas) => canvas,
+            Err(err) => {
+                eprintln!("Failed to create a renderer for a window: {err}");
+                return None;
+            }
+        };
+        let texture_creator = Box::leak(Box::new(canvas.texture_creator()));
+
+        // Create this window's copies of the existing sprites, so that the
+        // same SpriteRefs work with every window.
+        let mut windows = self.windows.borrow_mut();
+        let sprite_pixels = self.sprite_pixels.borrow();
+        let mut textures = Vec::with_capacity(windows[0].textures.len());
+        for (texture, pixels) in windows[0].textures.iter().zip(&*sprite_pixels) {
+            let query = texture.query();
+            let mut texture = texture_creator
+                .create_texture(
+                    query.format,
+                    TextureAccess::Streaming,
+                    query.width,
+                    query.height,
+                )
+                .ok()?;
+            let pitch = query.width as usize * query.format.byte_size_per_pixel();
+            if let Err(err) = texture.update(None, pixels, pitch) {
+                eprintln!("Failed to copy a sprite to a new window: {err}");
+            }
+            textures.push(texture);
+        }
+
+        windows.push(Sdl2Window {
+            textures,
+            texture_creator,
+            canvas,
+        });
+        Some(WindowId::new(windows.len() as u64 - 1))
+    }
+
+    fn set_active_window(&self, window: WindowId) {
+        if (window.inner() as usize) < self.windows.borrow().len() {
+            self.active_window.set(window.inner() as usize);
+        }
+    }
+
+    fn draw_area(&self) -> (f32, f32) {
+        self.window_size(WindowId::new(self.active_window.get() as u64))
     }
 
     fn draw_scale_factor(&self) -> f32 {
         let (scaled_width, pixel_width) = {
-            let canvas = self.canvas.borrow();
+            let canvas = self.active_canvas();
             let (scaled_w, _) = canvas.window().size();
             let (pixel_w, _) = canvas.window().drawable_size();
             (scaled_w, pixel_w)
@@ -373,6 +756,88 @@ impl Platform for Sdl2Platform {
         pixel_width as f32 / scaled_width as f32
     }
 
+    fn set_fullscreen(&self, mode: FullscreenMode) {
+        let fullscreen_type = match mode {
+            FullscreenMode::Windowed => FullscreenType::Off,
+            FullscreenMode::Borderless => FullscreenType::Desktop,
+            FullscreenMode::Exclusive => FullscreenType::True,
+        };
+        {
+            let mut windows = self.windows.borrow_mut();
+            let canvas = &mut windows[self.active_window.get()].canvas;
+            // SDL restores the previous windowed size when leaving fullscreen.
+            if let Err(err) = canvas.window_mut().set_fullscreen(fullscreen_type) {
+                eprintln!("Failed to set fullscreen mode to {mode:?}: {err}");
+            }
+        }
+        // Some video drivers only update the window size once they've
+        // processed the resulting window events, so pump them here to make
+        // draw_area up to date. The events will be handled in the game loop.
+        // Safety: ffi call, and this is called on the main thread, like all of
+        // the Platform functions.
+        unsafe { SDL_PumpEvents() };
+    }
+
+    fn is_fullscreen(&self) -> bool {
+        let canvas = self.active_canvas();
+        canvas.window().fullscreen_state() != FullscreenType::Off
+    }
+
+    fn set_present_mode(&self, mode: PresentMode) {
+        let vsync = matches!(mode, PresentMode::Vsync);
+        {
+            // Only the main window is presented with vsync, see create_window.
+            let canvas = self.main_canvas();
+            // This only reconfigures the existing renderer, so the textures
+            // backing the SpriteRefs stay valid.
+            let renderer: *mut SDL_Renderer = canvas.raw();
+            // Safety: ffi call, and the renderer is valid as it's owned by the
+            // canvas.
+            if unsafe { SDL_RenderSetVSync(renderer, vsync as c_int) } != 0 {
+                eprintln!(
+                    "Failed to set present mode to {mode:?}: {}",
+                    sdl2::get_error()
+                );
+            }
+        }
+        self.present_mode.set(mode);
+    }
+
+    fn displays(&self) -> DisplayInfoList {
+        let mut displays = DisplayInfoList::new();
+        let canvas = self.active_canvas();
+        let window = canvas.window();
+        let video = window.subsystem();
+        // SDL refreshes its list of displays as it processes the display
+        // events, so querying it here is enough to pick up hotplugged
+        // displays.
+        let display_count = match video.num_video_displays() {
+            Ok(count) => count,
+            Err(err) => {
+                eprintln!("Failed to query the number of displays: {err}");
+                return displays;
+            }
+        };
+        let current_display = window.display_index().ok();
+        for i in 0..display_count {
+            let Ok(mode) = video.current_display_mode(i) else {
+                continue;
+            };
+            let display = DisplayInfo {
+                width: mode.w.max(0) as u32,
+                height: mode.h.max(0) as u32,
+                refresh_rate: mode.refresh_rate.max(0) as u32,
+                // SDL always lists the primary display first.
+                primary: i == 0,
+                current: current_display == Some(i),
+            };
+            if displays.try_push(display).is_err() {
+                break;
+            }
+        }
+        displays
+    }
+
     fn draw_2d(&self, vertices: &[Vertex2D], indices: &[u32], settings: DrawSettings2D) {
         if vertices.len() < 3 || indices.len() < 3 {
             return;
@@ -389,18 +854,16 @@ impl Platform for Sdl2Platform {
         let num_indices = indices.len() as c_int;
 
         {
-            let mut canvas = self.canvas.borrow_mut();
-            let textures = self.textures.borrow();
+            let mut windows = self.windows.borrow_mut();
+            let Sdl2Window {
+                textures, canvas, ..
+            } = &mut windows[self.active_window.get()];
 
             // Update draw settings
             let clip_rect = settings
                 .clip_area
                 .map(|[x, y, w, h]| Rect::new(x as i32, y as i32, w as u32, h as u32));
-            let blend_mode = match settings.blend_mode {
-                platform::BlendMode::None => SDL_BlendMode::SDL_BLENDMODE_NONE,
-                platform::BlendMode::Blend => SDL_BlendMode::SDL_BLENDMODE_BLEND,
-                platform::BlendMode::Add => SDL_BlendMode::SDL_BLENDMODE_ADD,
-            };
+            let blend_mode = sdl_blend_mode(settings.blend_mode);
             let scale_mode = match settings.texture_filter {
                 platform::TextureFilter::NearestNeighbor => SDL_ScaleMode::SDL_ScaleModeNearest,
                 platform::TextureFilter::Linear => SDL_ScaleMode::SDL_ScaleModeLinear,
@@ -455,16 +918,22 @@ impl Platform for Sdl2Platform {
             // Unsure why ABGR8888 reads `[r, g, b, a, r, ...]` correctly, but here we are.
             platform::PixelFormat::Rgba => PixelFormatEnum::ABGR8888,
         };
-        let texture = self
-            .texture_creator
-            .create_texture(fmt, TextureAccess::Streaming, width as u32, height as u32)
-            .ok()?;
-        let texture_index = {
-            let mut textures = self.textures.borrow_mut();
-            let idx = textures.len();
+        let mut windows = self.windows.borrow_mut();
+        // Every window gets a copy of the sprite, and only if they all could
+        // be created, so that the SpriteRef is valid for all the windows.
+        let mut textures = Vec::with_capacity(windows.len());
+        for window in windows.iter() {
+            let texture = (window.texture_creator)
+                .create_texture(fmt, TextureAccess::Streaming, width as u32, height as u32)
+                .ok()?;
             textures.push(texture);
-            idx
-        };
+        }
+        let texture_index = windows[0].textures.len();
+        for (window, texture) in windows.iter_mut().zip(textures) {
+            window.textures.push(texture);
+        }
+        let size = width as usize * height as usize * fmt.byte_size_per_pixel();
+        self.sprite_pixels.borrow_mut().push(vec![0; size]);
         Some(platform::SpriteRef::new(texture_index as u64))
     }
 
@@ -477,15 +946,32 @@ impl Platform for Sdl2Platform {
         height: u16,
         pixels: &[u8],
     ) {
-        let mut textures = self.textures.borrow_mut();
-        if let Some(tex) = textures.get_mut(texture.inner() as usize) {
-            let bpp = tex.query().format.byte_size_per_pixel();
-            if let Err(err) = tex.update(
-                Rect::new(x as i32, y as i32, width as u32, height as u32),
-                pixels,
-                width as usize * bpp,
-            ) {
-                println!("[Sdl2Platform::update_sprite]: sprite update failed: {err}");
+        let mut windows = self.windows.borrow_mut();
+        let mut sprite_pixels = self.sprite_pixels.borrow_mut();
+        if let (Some(tex), Some(sprite_pixels)) = (
+            windows[0].textures.get(texture.inner() as usize),
+            sprite_pixels.get_mut(texture.inner() as usize),
+        ) {
+            let query = tex.query();
+            let bpp = query.format.byte_size_per_pixel();
+            let (tex_pitch, row_len) = (query.width as usize * bpp, width as usize * bpp);
+            let src_rows = pixels.chunks_exact(row_len).take(height as usize);
+            let dst_rows = sprite_pixels.chunks_exact_mut(tex_pitch).skip(y as usize);
+            for (src, dst) in src_rows.zip(dst_rows) {
+                let x = x as usize * bpp;
+                dst[x..x + row_len].copy_from_slice(src);
+            }
+        }
+        for window in windows.iter_mut() {
+            if let Some(tex) = window.textures.get_mut(texture.inner() as usize) {
+                let bpp = tex.query().format.byte_size_per_pixel();
+                if let Err(err) = tex.update(
+                    Rect::new(x as i32, y as i32, width as u32, height as u32),
+                    pixels,
+                    width as usize * bpp,
+                ) {
+                    println!("[Sdl2Platform::update_sprite]: sprite update failed: {err}");
+                }
             }
         }
     }
@@ -507,11 +993,7 @@ impl Platform for Sdl2Platform {
                 FileReadSource::Path(path)
             };
 
-            files.push(FileHolder {
-                source,
-                tasks: Vec::new(),
-                task_id_counter: 0,
-            });
+            files.push(FileHolder::new(source));
             FileHandle::new(i)
         };
         Some(handle)
@@ -529,25 +1011,7 @@ impl Platform for Sdl2Platform {
             let file = files
                 .get_mut(file.inner() as usize)
                 .expect("invalid FileHandle");
-            let id = file.task_id_counter;
-            file.task_id_counter += 1;
-            let read = match &file.source {
-                FileReadSource::Path(path) => {
-                    let path = path.clone();
-                    let mut buffer_on_thread = vec![0; buffer.len()];
-                    FileRead::Threaded(std::thread::spawn(move || {
-                        let mut file = File::open(path)?;
-                        file.seek(SeekFrom::Start(first_byte))?;
-                        file.read_exact(&mut buffer_on_thread)?;
-                        Ok(buffer_on_thread)
-                    }))
-                }
-                FileReadSource::Embedded(bytes) => FileRead::Embedded(
-                    &bytes[first_byte as usize..first_byte as usize + buffer.len()],
-                ),
-            };
-            file.tasks.push((id, read));
-            id
+            file.begin_read(first_byte, buffer.len())
         };
         FileReadTask::new(file, id, buffer)
     }
@@ -575,12 +1039,10 @@ impl Platform for Sdl2Platform {
             let file = files
                 .get_mut(task.file().inner() as usize)
                 .expect("invalid FileHandle");
-            let Some(idx) = file.tasks.iter().position(|(id, _)| *id == task.task_id()) else {
+            let Some(read) = file.take_read(task.task_id()) else {
                 panic!("tried to finish a read task with an invalid task id?");
             };
 
-            let (_, read) = file.tasks.swap_remove(idx);
-
             // Safety: this implementation does not share the borrow in the first place.
             let mut buffer = unsafe { task.into_inner() };
 
@@ -604,6 +1066,84 @@ impl Platform for Sdl2Platform {
         Ok(written_buffer)
     }
 
+    fn cancel_file_read(&self, task: FileReadTask) -> platform::Box<[u8]> {
+        let mut files = self.files.borrow_mut();
+        let file = files
+            .get_mut(task.file().inner() as usize)
+            .expect("invalid FileHandle");
+        if file.cancel_read(task.task_id()).is_none() {
+            panic!("tried to cancel a read task with an invalid task id?");
+        }
+        // Safety: the read threads write into their own buffers, and the
+        // buffer is only written into in finish_file_read, which can't be
+        // called for this task anymore.
+        unsafe { task.into_inner() }
+    }
+
+    fn begin_file_write(
+        &self,
+        path: &str,
+        first_byte: u64,
+        buffer: platform::Box<[u8]>,
+    ) -> FileWriteTask {
+        // Like reading, this is not an efficient implementation.
+        let mut file_writes = self.file_writes.borrow_mut();
+        let id = file_writes.task_id_counter;
+        file_writes.task_id_counter += 1;
+        let path = PathBuf::from(path);
+        let buffer_on_thread = buffer.to_vec();
+        let join_handle =
+            std::thread::spawn(move || write_file_at(&path, first_byte, &buffer_on_thread));
+        file_writes.tasks.push((id, join_handle));
+        FileWriteTask::new(id, buffer)
+    }
+
+    fn is_file_write_finished(&self, task: &FileWriteTask) -> bool {
+        let file_writes = self.file_writes.borrow();
+        let Some((_, join_handle)) =
+            (file_writes.tasks.iter()).find(|(id, _)| *id == task.task_id())
+        else {
+            panic!("tried to poll a write task with an invalid task id?");
+        };
+        join_handle.is_finished()
+    }
+
+    fn finish_file_write(
+        &self,
+        task: FileWriteTask,
+    ) -> Result<platform::Box<[u8]>, platform::Box<[u8]>> {
+        let join_handle = {
+            let mut file_writes = self.file_writes.borrow_mut();
+            let Some(idx) = (file_writes.tasks.iter()).position(|(id, _)| *id == task.task_id())
+            else {
+                panic!("tried to finish a write task with an invalid task id?");
+            };
+            file_writes.tasks.swap_remove(idx).1
+        };
+
+        // Safety: this implementation does not share the borrow in the first place.
+        let buffer = unsafe { task.into_inner() };
+
+        match join_handle.join().unwrap() {
+            Ok(()) => Ok(buffer),
+            Err(err) => {
+                println!("[Sdl2Platform::finish_file_write]: could not write file: {err}");
+                Err(buffer)
+            }
+        }
+    }
+
+    fn persistent_data_dir(&self, org: &str, app: &str) -> Option<PathString> {
+        // SDL creates the directory if it doesn't exist.
+        match sdl2::filesystem::pref_path(org, app) {
+            Ok(path) => PathString::from(&path).ok(),
+            Err(err) => {
+                println!("[Sdl2Platform::persistent_data_dir]: could not get pref path: {err}");
+                None
+            }
+        }
+    }
+
     fn create_semaphore(&self) -> platform::Semaphore {
         struct Semaphore {
             value: Mutex<u32>,
@@ -624,6 +1164,44 @@ impl Platform for Sdl2Platform {
                 }
                 *value_lock -= 1;
             }
+
+            // Locking doesn't wait for increments, as the lock is only held
+            // briefly to update the value. Using try_lock instead could miss
+            // increments if the lock happens to be held by another thread.
+            fn try_decrement(&self) -> bool {
+                let mut value_lock = self.value.lock().unwrap();
+                if *value_lock > 0 {
+                    *value_lock -= 1;
+                    true
+                } else {
+                    false
+                }
+            }
+
+            fn decrement_timeout(&self, timeout: Duration) -> bool {
+                let deadline = std::time::Instant::now().checked_add(timeout);
+                let mut value_lock = self.value.lock().unwrap();
+                // Loop to handle spurious wakeups, and increments which were taken
+                // by another thread before this one got the lock.
+                while *value_lock == 0 {
+                    let Some(deadline) = deadline else {
+                        // The timeout is too long to have a deadline, so it
+                        // can't elapse either.
+                        value_lock = self.condvar.wait(value_lock).unwrap();
+                        continue;
+                    };
+                    let now = std::time::Instant::now();
+                    if now >= deadline {
+                        return false;
+                    }
+                    (value_lock, _) = self
+                        .condvar
+                        .wait_timeout(value_lock, deadline - now)
+                        .unwrap();
+                }
+                *value_lock -= 1;
+                true
+            }
         }
 
         let semaphore: &'static mut Semaphore = Box::leak(Box::new(Semaphore {
@@ -662,14 +1240,13 @@ impl Platform for Sdl2Platform {
                     task.signal_panic();
                 }
 
-                'send_result: loop {
-                    match result_sender.send(task) {
-                        Ok(()) => break 'send_result,
-                        Err(task_) => {
-                            thread::sleep(Duration::from_millis(1));
-                            task = task_;
-                        }
-                    }
+                // Parks the thread until the main thread makes room for the
+                // result, and only fails if the semaphore woke up spuriously,
+                // so retry. If the main thread stops joining tasks while
+                // exiting, this thread stays parked, which doesn't block the
+                // exit as pool threads are never joined.
+                while let Err(task_) = result_sender.send_blocking(task) {
+                    task = task_;
                 }
 
                 if let Err(err) = result {
@@ -709,14 +1286,23 @@ impl Platform for Sdl2Platform {
         dst_samples.extend_from_slice(samples);
     }
 
+    fn audio_output(&self) -> AudioOutput {
+        self.audio_output
+    }
+
     fn audio_playback_position(&self) -> (u64, platform::Instant) {
         // Offset the playback position forwards enough that any new sounds
         // played by the engine don't start too early (which would pop)
         let latency_offset = {
-            let canvas = self.canvas.borrow();
-            let fps = (canvas.window().display_mode().map(|dm| dm.refresh_rate))
+            let canvas = self.main_canvas();
+            let mut fps = (canvas.window().display_mode().map(|dm| dm.refresh_rate))
                 .unwrap_or(60)
                 .max(60);
+            // With a frame cap, frames can be longer than the refresh rate
+            // would suggest, and the offset needs to cover a whole frame.
+            if let PresentMode::FrameCap(cap @ 1..) = self.present_mode.get() {
+                fps = fps.min(cap as i32);
+            }
             AUDIO_SAMPLE_RATE as u64 / fps as u64
         };
 
@@ -765,6 +1351,15 @@ impl Platform for Sdl2Platform {
                     ActionCategory::ActSecondary => button_for_scancode(Scancode::Z),
                     ActionCategory::Pause => button_for_scancode(Scancode::Escape),
                 },
+                Hid::Mouse => match action {
+                    ActionCategory::Accept | ActionCategory::ActPrimary => {
+                        button_for_mouse(MouseButton::Left)
+                    }
+                    ActionCategory::Cancel | ActionCategory::ActSecondary => {
+                        button_for_mouse(MouseButton::Right)
+                    }
+                    _ => return None,
+                },
                 Hid::Gamepad { controller, .. } => match action {
                     ActionCategory::Up => button_for_gamepad(SdlButton::DPadUp),
                     ActionCategory::Down => button_for_gamepad(SdlButton::DPadDown),
@@ -797,6 +1392,85 @@ impl Platform for Sdl2Platform {
         }
     }
 
+    fn gamepad_kind(&self, device: InputDevice) -> GamepadKind {
+        let hids = self.hids.borrow();
+        if let Some(Hid::Gamepad {
+            controller,
+            connected: true,
+            ..
+        }) = hids.get(device.inner() as usize)
+        {
+            gamepad_kind(*controller)
+        } else {
+            GamepadKind::Generic
+        }
+    }
+
+    fn button_name(&self, button: Button) -> ButtonNameString {
+        button_name(button)
+    }
+
+    fn set_rumble(&self, device: InputDevice, low_freq: f32, high_freq: f32, duration: Duration) {
+        let hids = self.hids.borrow();
+        if let Some(Hid::Gamepad {
+            controller,
+            connected: true,
+            ..
+        }) = hids.get(device.inner() as usize)
+        {
+            // NaN is mapped to 0 by the cast.
+            let to_sdl_intensity =
+                |intensity: f32| (intensity.clamp(0.0, 1.0) * u16::MAX as f32) as u16;
+            let duration_ms = duration.as_millis().min(u32::MAX as u128) as u32;
+            // Safety: ffi call, the controller is never closed. If the
+            // controller doesn't support rumble, this returns an error, which
+            // is fine to ignore.
+            unsafe {
+                SDL_GameControllerRumble(
+                    *controller,
+                    to_sdl_intensity(low_freq),
+                    to_sdl_intensity(high_freq),
+                    duration_ms,
+                )
+            };
+        }
+    }
+
+    fn clipboard_text<'a>(&self, buffer: &'a mut [u8]) -> Option<&'a str> {
+        let text = {
+            let canvas = self.main_canvas();
+            let clipboard = canvas.window().subsystem().clipboard();
+            if !clipboard.has_clipboard_text() {
+                return None;
+            }
+            clipboard.clipboard_text().ok()?
+        };
+        let mut len = text.len().min(buffer.len());
+        while !text.is_char_boundary(len) {
+            len -= 1;
+        }
+        buffer[..len].copy_from_slice(&text.as_bytes()[..len]);
+        str::from_utf8(&buffer[..len]).ok()
+    }
+
+    fn set_clipboard_text(&self, text: &str) {
+        let canvas = self.main_canvas();
+        let clipboard = canvas.window().subsystem().clipboard();
+        if let Err(err) = clipboard.set_clipboard_text(text) {
+            println!("[Sdl2Platform::set_clipboard_text]: could not set clipboard: {err}");
+        }
+    }
+
+    fn start_text_input(&self) {
+        let canvas = self.main_canvas();
+        canvas.window().subsystem().text_input().start();
+    }
+
+    fn stop_text_input(&self) {
+        let canvas = self.main_canvas();
+        canvas.window().subsystem().text_input().stop();
+    }
+
     fn now(&self) -> platform::Instant {
         current_time()
     }
@@ -823,49 +1497,216 @@ fn current_time() -> platform::Instant {
     platform::Instant::reference() + Duration::from_millis(unsafe { SDL_GetTicks64() })
 }
 
+// Rendering helpers:
+
+// Declared here with plain int blend modes, since sdl2_sys declares them as an
+// enum, and custom blend modes are not valid values of it.
+extern "C" {
+    fn SDL_ComposeCustomBlendMode(
+        src_color_factor: SDL_BlendFactor,
+        dst_color_factor: SDL_BlendFactor,
+        color_operation: SDL_BlendOperation,
+        src_alpha_factor: SDL_BlendFactor,
+        dst_alpha_factor: SDL_BlendFactor,
+        alpha_operation: SDL_BlendOperation,
+    ) -> c_int;
+    fn SDL_SetTextureBlendMode(texture: *mut SDL_Texture, blend_mode: c_int) -> c_int;
+}
+
+fn sdl_blend_mode(blend_mode: platform::BlendMode) -> c_int {
+    match blend_mode {
+        platform::BlendMode::None => SDL_BlendMode::SDL_BLENDMODE_NONE as c_int,
+        platform::BlendMode::Blend => SDL_BlendMode::SDL_BLENDMODE_BLEND as c_int,
+        platform::BlendMode::Add => SDL_BlendMode::SDL_BLENDMODE_ADD as c_int,
+        platform::BlendMode::Multiply => SDL_BlendMode::SDL_BLENDMODE_MUL as c_int,
+        platform::BlendMode::PremultipliedAlpha => {
+            use SDL_BlendFactor::{SDL_BLENDFACTOR_ONE, SDL_BLENDFACTOR_ONE_MINUS_SRC_ALPHA};
+            use SDL_BlendOperation::SDL_BLENDOPERATION_ADD;
+            // Safety: ffi call, which just packs the arguments into an int.
+            unsafe {
+                SDL_ComposeCustomBlendMode(
+                    SDL_BLENDFACTOR_ONE,
+                    SDL_BLENDFACTOR_ONE_MINUS_SRC_ALPHA,
+                    SDL_BLENDOPERATION_ADD,
+                    SDL_BLENDFACTOR_ONE,
+                    SDL_BLENDFACTOR_ONE_MINUS_SRC_ALPHA,
+                    SDL_BLENDOPERATION_ADD,
+                )
+            }
+        }
+    }
+}
+
 // Keyboard/gamepad input helpers:
 
 fn flip_accept_cancel(controller: *mut SDL_GameController) -> bool {
+    gamepad_kind(controller) == GamepadKind::Switch
+}
+
+fn gamepad_kind(controller: *mut SDL_GameController) -> GamepadKind {
     // Safety: controller is not null (checked when we get the pointer from an
     // event).
     let controller_type = unsafe { SDL_GameControllerGetType(controller) };
-    matches!(
-        controller_type,
+    match controller_type {
+        SDL_GameControllerType::SDL_CONTROLLER_TYPE_XBOX360
+        | SDL_GameControllerType::SDL_CONTROLLER_TYPE_XBOXONE => GamepadKind::Xbox,
+        SDL_GameControllerType::SDL_CONTROLLER_TYPE_PS3
+        | SDL_GameControllerType::SDL_CONTROLLER_TYPE_PS4
+        | SDL_GameControllerType::SDL_CONTROLLER_TYPE_PS5 => GamepadKind::PlayStation,
         SDL_GameControllerType::SDL_CONTROLLER_TYPE_NINTENDO_SWITCH_PRO
-            | SDL_GameControllerType::SDL_CONTROLLER_TYPE_NINTENDO_SWITCH_JOYCON_LEFT
-            | SDL_GameControllerType::SDL_CONTROLLER_TYPE_NINTENDO_SWITCH_JOYCON_RIGHT
-            | SDL_GameControllerType::SDL_CONTROLLER_TYPE_NINTENDO_SWITCH_JOYCON_PAIR
-    )
+        | SDL_GameControllerType::SDL_CONTROLLER_TYPE_NINTENDO_SWITCH_JOYCON_LEFT
+        | SDL_GameControllerType::SDL_CONTROLLER_TYPE_NINTENDO_SWITCH_JOYCON_RIGHT
+        | SDL_GameControllerType::SDL_CONTROLLER_TYPE_NINTENDO_SWITCH_JOYCON_PAIR => {
+            GamepadKind::Switch
+        }
+        _ => GamepadKind::Generic,
+    }
 }
 
 fn button_for_scancode(scancode: Scancode) -> Button {
     Button::new((1 << 32) | scancode as u64)
 }
 
+fn read_file_at(path: &Path, first_byte: u64, len: usize) -> Result<Vec<u8>, io::Error> {
+    let mut buffer = vec![0; len];
+    let mut file = File::open(path)?;
+    file.seek(SeekFrom::Start(first_byte))?;
+    file.read_exact(&mut buffer)?;
+    Ok(buffer)
+}
+
+fn write_file_at(path: &Path, first_byte: u64, bytes: &[u8]) -> Result<(), io::Error> {
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)?;
+    file.seek(SeekFrom::Start(first_byte))?;
+    file.write_all(bytes)?;
+    file.sync_data()
+}
+
 fn button_for_gamepad(gamepad_button: SdlButton) -> Button {
     Button::new((2 << 32) | gamepad_button as u64)
 }
 
+fn axis_for_gamepad(gamepad_axis: SdlAxis) -> Axis {
+    Axis::new((2 << 32) | gamepad_axis as u64)
+}
+
+fn button_for_mouse(mouse_button: MouseButton) -> Button {
+    Button::new((3 << 32) | mouse_button as u64)
+}
+
+/// The names of the gamepad buttons, indexed by the SDL_GameControllerButton
+/// values encoded by [`button_for_gamepad`].
+const GAMEPAD_BUTTON_NAMES: [&str; 21] = [
+    "A Button",
+    "B Button",
+    "X Button",
+    "Y Button",
+    "Back Button",
+    "Guide Button",
+    "Start Button",
+    "Left Stick",
+    "Right Stick",
+    "Left Shoulder",
+    "Right Shoulder",
+    "D-Pad Up",
+    "D-Pad Down",
+    "D-Pad Left",
+    "D-Pad Right",
+    "Misc Button",
+    "Paddle 1",
+    "Paddle 2",
+    "Paddle 3",
+    "Paddle 4",
+    "Touchpad",
+];
+
+/// The names of the mouse buttons, indexed by the MouseButton values encoded
+/// by [`button_for_mouse`].
+const MOUSE_BUTTON_NAMES: [&str; 6] = [
+    "Unknown Mouse Button",
+    "Left Mouse Button",
+    "Middle Mouse Button",
+    "Right Mouse Button",
+    "Mouse Button 4",
+    "Mouse Button 5",
+];
+
+/// Decodes a [`Button`] created by [`button_for_scancode`],
+/// [`button_for_gamepad`] or [`button_for_mouse`] into a human-readable name.
+fn button_name(button: Button) -> ButtonNameString {
+    extern "C" {
+        // Declared here with a plain int parameter, since sdl2_sys declares
+        // the parameter as an enum, and the gaps in its values would make
+        // passing arbitrary values undefined behavior.
+        fn SDL_GetScancodeName(scancode: c_int) -> *const c_char;
+    }
+
+    let tag = button.inner() >> 32;
+    let value = button.inner() & 0xFFFF_FFFF;
+    let name = match tag {
+        1 => {
+            // Safety: ffi call. SDL returns a pointer to a static string, which
+            // is empty for values which aren't valid scancodes.
+            let name = unsafe { CStr::from_ptr(SDL_GetScancodeName(value as c_int)) };
+            name.to_str().unwrap_or("")
+        }
+        2 => GAMEPAD_BUTTON_NAMES
+            .get(value as usize)
+            .copied()
+            .unwrap_or(""),
+        3 => MOUSE_BUTTON_NAMES
+            .get(value as usize)
+            .copied()
+            .unwrap_or(""),
+        _ => "",
+    };
+    let name = if name.is_empty() {
+        "Unknown Button"
+    } else {
+        name
+    };
+
+    let mut len = name.len().min(ButtonNameString::new().capacity());
+    while !name.is_char_boundary(len) {
+        len -= 1;
+    }
+    // does not panic: the length was clamped to the capacity above
+    ButtonNameString::from(&name[..len]).unwrap()
+}
+
 // Audio helpers:
 
 struct AudioCallbackImpl {
     shared_audio_buffer: SharedAudioBuffer,
+    /// The sample rate of the audio device, which may differ from
+    /// [`AUDIO_SAMPLE_RATE`], in which case the audio is resampled.
+    device_sample_rate: u64,
+    /// The fractional part of the playback position, in units of
+    /// `1 / device_sample_rate` samples. Always zero when the device's sample
+    /// rate matches [`AUDIO_SAMPLE_RATE`].
+    resampling_phase: u64,
+    /// The amount of channels of the audio device, 1 for mono and
+    /// [`AUDIO_CHANNELS`] for stereo.
+    device_channels: usize,
 }
 
 impl AudioCallbackImpl {
     fn new(spec: AudioSpec, shared_audio_buffer: SharedAudioBuffer) -> AudioCallbackImpl {
-        assert_eq!(
-            AUDIO_SAMPLE_RATE as i32, spec.freq,
-            "platform-sdl2 doesn't support resampling audio",
-        );
-
-        assert_eq!(
-            AUDIO_CHANNELS as u8, spec.channels,
-            "platform-sdl2 doesn't support resampling audio",
+        let device_channels = spec.channels as usize;
+        assert!(
+            device_channels == 1 || device_channels == AUDIO_CHANNELS,
+            "platform-sdl2 only supports mono and stereo audio devices",
         );
 
         AudioCallbackImpl {
             shared_audio_buffer,
+            device_sample_rate: spec.freq.max(1) as u64,
+            resampling_phase: 0,
+            device_channels,
         }
     }
 }
@@ -875,25 +1716,194 @@ impl AudioCallback for AudioCallbackImpl {
     fn callback(&mut self, dst_samples: &mut [Self::Channel]) {
         let mut src = self.shared_audio_buffer.lock().unwrap();
         let src_samples = &src.buffer;
-
-        let mut samples_played_back = 0;
-        for (src, dst) in src_samples
-            .iter()
-            .zip(dst_samples.chunks_exact_mut(AUDIO_CHANNELS))
+        let src_rate = AUDIO_SAMPLE_RATE as u64;
+        let dst_rate = self.device_sample_rate;
+
+        // Linear interpolation between the engine's samples. Samples that the
+        // engine hasn't provided are played back as silence.
+        let mut dst_frames = 0;
+        for (i, dst) in dst_samples
+            .chunks_exact_mut(self.device_channels)
+            .enumerate()
         {
-            dst.copy_from_slice(src);
-            samples_played_back += 1;
+            let src_position = self.resampling_phase + i as u64 * src_rate;
+            let src_index = (src_position / dst_rate) as usize;
+            let fraction = (src_position % dst_rate) as i64;
+            let a = src_samples.get(src_index).unwrap_or(&[0; AUDIO_CHANNELS]);
+            let b = src_samples
+                .get(src_index + 1)
+                .unwrap_or(&[0; AUDIO_CHANNELS]);
+            let mut frame = [0; AUDIO_CHANNELS];
+            for ((dst, a), b) in frame.iter_mut().zip(a).zip(b) {
+                let (a, b) = (*a as i64, *b as i64);
+                *dst = (a + (b - a) * fraction / dst_rate as i64) as i16;
+            }
+            if let [dst] = dst {
+                // The engine has already downmixed the channels if it's aware
+                // of the mono output, but averaging them doesn't hurt.
+                let sum = frame.iter().map(|sample| *sample as i32).sum::<i32>();
+                *dst = (sum / AUDIO_CHANNELS as i32) as i16;
+            } else {
+                dst.copy_from_slice(&frame);
+            }
+            dst_frames += 1;
         }
 
-        src.buffer.splice(0..samples_played_back as usize, None);
-
-        let leftover_dst = &mut dst_samples[samples_played_back as usize * 2..];
-        if !leftover_dst.is_empty() {
-            leftover_dst.fill(0);
-            samples_played_back += leftover_dst.len() as u64 / 2;
-        }
+        let end_position = self.resampling_phase + dst_frames * src_rate;
+        let samples_played_back = end_position / dst_rate;
+        self.resampling_phase = end_position % dst_rate;
 
+        let drained_samples = (samples_played_back as usize).min(src.buffer.len());
+        src.buffer.splice(0..drained_samples, None);
         src.position += samples_played_back;
         src.sync_timestamp = current_time();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs,
+        sync::{Arc, Mutex},
+    };
+
+    use platform::{Button, AUDIO_CHANNELS, AUDIO_SAMPLE_RATE};
+    use sdl2::{
+        audio::{AudioCallback, AudioFormat, AudioSpec},
+        controller::Button as SdlButton,
+        keyboard::Scancode,
+        mouse::MouseButton,
+    };
+
+    use super::{
+        button_for_gamepad, button_for_mouse, button_for_scancode, button_name, current_time,
+        read_file_at, write_file_at, AudioBufferState, AudioCallbackImpl, FileHolder,
+        FileReadSource,
+    };
+
+    #[test]
+    fn buttons_are_named() {
+        assert_eq!("Space", &button_name(button_for_scancode(Scancode::Space)));
+        assert_eq!("A Button", &button_name(button_for_gamepad(SdlButton::A)));
+        assert_eq!(
+            "D-Pad Up",
+            &button_name(button_for_gamepad(SdlButton::DPadUp))
+        );
+        assert_eq!(
+            "Left Mouse Button",
+            &button_name(button_for_mouse(MouseButton::Left))
+        );
+    }
+
+    #[test]
+    fn unknown_buttons_get_a_placeholder_name() {
+        // 1 is in the gap between SDL_SCANCODE_UNKNOWN and SDL_SCANCODE_A.
+        assert_eq!("Unknown Button", &button_name(Button::new((1 << 32) | 1)));
+        assert_eq!(
+            "Unknown Button",
+            &button_name(Button::new((2 << 32) | 1000))
+        );
+        assert_eq!("Unknown Button", &button_name(Button::new(u64::MAX)));
+    }
+
+    #[test]
+    fn audio_is_resampled_to_device_sample_rate() {
+        let shared_audio_buffer = Arc::new(Mutex::new(AudioBufferState {
+            position: 0,
+            sync_timestamp: current_time(),
+            buffer: (0..480).map(|i| [i * 10, -i * 10]).collect(),
+        }));
+        let spec = AudioSpec {
+            freq: 44100,
+            format: AudioFormat::S16LSB,
+            channels: AUDIO_CHANNELS as u8,
+            silence: 0,
+            samples: 441,
+            size: 441 * 4,
+        };
+        let mut callback = AudioCallbackImpl::new(spec, shared_audio_buffer.clone());
+
+        // 10 milliseconds at 44.1 kHz, which should consume 10 milliseconds at 48 kHz
+        let mut dst = vec![0i16; 441 * AUDIO_CHANNELS];
+        callback.callback(&mut dst);
+
+        for (i, frame) in dst.chunks_exact(AUDIO_CHANNELS).enumerate() {
+            let expected = i as f32 * 48000.0 / 44100.0 * 10.0;
+            assert!((frame[0] as f32 - expected).abs() <= 1.0, "{i}: {frame:?}");
+            assert!((frame[1] as f32 + expected).abs() <= 1.0, "{i}: {frame:?}");
+        }
+
+        let shared_audio_buffer = shared_audio_buffer.lock().unwrap();
+        assert_eq!(480, shared_audio_buffer.position);
+        assert!(shared_audio_buffer.buffer.is_empty());
+    }
+
+    #[test]
+    fn audio_is_downmixed_for_mono_devices() {
+        let shared_audio_buffer = Arc::new(Mutex::new(AudioBufferState {
+            position: 0,
+            sync_timestamp: current_time(),
+            buffer: (0..480).map(|i| [i * 10, i * 20]).collect(),
+        }));
+        let spec = AudioSpec {
+            freq: AUDIO_SAMPLE_RATE as i32,
+            format: AudioFormat::S16LSB,
+            channels: 1,
+            silence: 0,
+            samples: 480,
+            size: 480 * 2,
+        };
+        let mut callback = AudioCallbackImpl::new(spec, shared_audio_buffer.clone());
+
+        let mut dst = vec![0i16; 480];
+        callback.callback(&mut dst);
+
+        for (i, sample) in dst.iter().enumerate() {
+            assert_eq!(i as i16 * 15, *sample);
+        }
+        assert_eq!(480, shared_audio_buffer.lock().unwrap().position);
+    }
+
+    #[test]
+    fn written_files_can_be_read_back() {
+        let path = std::env::temp_dir().join(format!(
+            "platform-sdl2-file-write-test-{}",
+            std::process::id()
+        ));
+
+        write_file_at(&path, 0, b"hello, world").unwrap();
+        write_file_at(&path, 7, b"there").unwrap();
+        let read_result = read_file_at(&path, 0, 12);
+        let _ = fs::remove_file(&path);
+
+        assert_eq!(b"hello, there", &*read_result.unwrap());
+    }
+
+    #[test]
+    fn cancelled_reads_are_joined_once_finished() {
+        let path = std::env::temp_dir().join(format!(
+            "platform-sdl2-file-read-cancel-test-{}",
+            std::process::id()
+        ));
+        write_file_at(&path, 0, b"hello, world").unwrap();
+
+        let mut file = FileHolder::new(FileReadSource::Path(path.clone()));
+        let cancelled = file.begin_read(0, 5);
+        let finished = file.begin_read(7, 5);
+        assert_eq!(Some(()), file.cancel_read(cancelled));
+        assert_eq!(None, file.cancel_read(cancelled));
+        assert_eq!(1, file.tasks.len());
+
+        while !file.cancelled_reads.is_empty() {
+            std::thread::yield_now();
+            file.join_cancelled_reads();
+        }
+        let Some(super::FileRead::Threaded(read)) = file.take_read(finished) else {
+            panic!("the read of the file on disk should be threaded");
+        };
+        let read_result = read.join().unwrap();
+        let _ = fs::remove_file(&path);
+
+        assert_eq!(b"world", &*read_result.unwrap());
+    }
+}
@@ -4,8 +4,12 @@
 
 //! Static memory based single-producer single-consumer channel for
 //! communication between threads.
+//!
+//! See [`mpsc`] for a variant which allows multiple senders.
 
-use core::sync::atomic::{AtomicUsize, Ordering};
+pub mod mpsc;
+
+use core::sync::atomic::{fence, AtomicBool, AtomicUsize, Ordering};
 
 pub use crossbeam_utils::CachePadded;
 pub use sync_unsafe_cell::SyncUnsafeCell;
@@ -27,6 +31,37 @@ struct SharedChannelState<T: 'static + Sync> {
     /// Incremented on every [`Sender::send`], decrement on in every
     /// [`Receiver::recv`] and [`Receiver::try_recv`].
     write_semaphore: &'static Semaphore,
+    /// Incremented by the [`Receiver`] after it has made room in the queue, if
+    /// `sender_waiting` is set, and decremented by [`Sender::send_blocking`]
+    /// while waiting for room.
+    read_semaphore: &'static Semaphore,
+    /// Set by [`Sender::send_blocking`] before waiting on `read_semaphore`,
+    /// and cleared by whichever side gets to it first after that. The
+    /// [`Receiver`] only increments `read_semaphore` if it's the one to clear
+    /// this, so receives don't need to touch the semaphore when nothing is
+    /// waiting for them.
+    sender_waiting: &'static AtomicBool,
+}
+
+impl<T: Sync> SharedChannelState<T> {
+    fn len(&self) -> usize {
+        if self.queue.len() <= 1 {
+            // This channel does not have any capacity, so it's always empty.
+            return 0;
+        }
+
+        // Acquire-load both offsets, to get values at least as fresh as the
+        // ones the other side has released. Either side might be in the middle
+        // of sending or receiving though, so the result is only exact when
+        // called from the side that isn't being used at the moment.
+        let read_offset = self.read_offset.load(Ordering::Acquire);
+        let write_offset = self.write_offset.load(Ordering::Acquire);
+
+        // The write offset is always "after" or equal to the read offset,
+        // modulo the queue length, so this is the distance between them,
+        // accounting for the write offset possibly having wrapped around.
+        (write_offset + self.queue.len() - read_offset) % self.queue.len()
+    }
 }
 
 /// Return type of [`channel_from_parts`].
@@ -42,15 +77,20 @@ pub fn channel_from_parts<T: Sync>(
     write_offset: &'static mut AtomicUsize,
     read_offset: &'static mut AtomicUsize,
     write_semaphore: &'static mut Semaphore,
+    read_semaphore: &'static mut Semaphore,
+    sender_waiting: &'static mut AtomicBool,
 ) -> Channel<T> {
     read_offset.store(0, Ordering::Release);
     write_offset.store(0, Ordering::Release);
+    sender_waiting.store(false, Ordering::Release);
     let sender = Sender {
         ch: SharedChannelState {
             queue,
             read_offset,
             write_offset,
             write_semaphore,
+            read_semaphore,
+            sender_waiting,
         },
     };
     let receiver = Receiver {
@@ -59,6 +99,8 @@ pub fn channel_from_parts<T: Sync>(
             read_offset,
             write_offset,
             write_semaphore,
+            read_semaphore,
+            sender_waiting,
         },
     };
     (sender, receiver)
@@ -77,6 +119,19 @@ impl<T: Sync> Sender<T> {
         self.ch.queue.len() - 1
     }
 
+    /// Returns how many sent values are waiting to be received.
+    ///
+    /// The receiver may be receiving values at the same time, so the actual
+    /// amount may already be lower by the time this returns.
+    pub fn len(&self) -> usize {
+        self.ch.len()
+    }
+
+    /// Returns true if there are no sent values waiting to be received.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
     /// Sends the value into the channel if there's room.
     pub fn send(&mut self, value: T) -> Result<(), T> {
         if self.ch.queue.len() <= 1 {
@@ -141,6 +196,51 @@ impl<T: Sync> Sender<T> {
 
         Ok(())
     }
+
+    /// Sends the value into the channel, waiting for the receiver to make room
+    /// for it if the channel is full.
+    ///
+    /// Returns the value back in an `Err` if the channel is still full after
+    /// waiting. This only happens if the platform's semaphores don't actually
+    /// block (i.e. the platform is single-threaded), or if they wake up
+    /// spuriously, so on a multi-threaded platform, this can be retried.
+    pub fn send_blocking(&mut self, value: T) -> Result<(), T> {
+        let value = match self.send(value) {
+            Ok(()) => return Ok(()),
+            Err(value) => value,
+        };
+
+        // 1. Signal the receiver that we're about to wait. The fence pairs with
+        //    the one in `Receiver::recv_impl`, ordering this store before the
+        //    load of the read offset in the send below: either the receiver
+        //    sees this flag after it has made room, or the send below sees the
+        //    room the receiver made.
+        self.ch.sender_waiting.store(true, Ordering::Relaxed);
+        fence(Ordering::SeqCst);
+
+        // 2. Try again, in case the receiver made room before seeing the flag.
+        let value = match self.send(value) {
+            Ok(()) => {
+                // If the receiver has cleared the flag, it has incremented (or
+                // is about to increment) the semaphore, which needs to be
+                // matched here to avoid a stale increment waking up the next
+                // send_blocking call.
+                if !self.ch.sender_waiting.swap(false, Ordering::Relaxed) {
+                    self.ch.read_semaphore.decrement();
+                }
+                return Ok(());
+            }
+            Err(value) => value,
+        };
+
+        // 3. Wait for the receiver to make room. If this returns without a
+        //    matching increment, the flag is still set, so clear it to avoid
+        //    an increment without a matching decrement later.
+        self.ch.read_semaphore.decrement();
+        self.ch.sender_waiting.store(false, Ordering::Relaxed);
+
+        self.send(value)
+    }
 }
 
 /// One endpoint of a channel, which can be used to receive [`Sync`] and
@@ -156,6 +256,19 @@ impl<T: Sync> Receiver<T> {
         self.ch.queue.len() - 1
     }
 
+    /// Returns how many sent values are waiting to be received.
+    ///
+    /// The sender may be sending values at the same time, so the actual amount
+    /// may already be higher by the time this returns.
+    pub fn len(&self) -> usize {
+        self.ch.len()
+    }
+
+    /// Returns true if there are no sent values waiting to be received.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
     /// Blocks until the sender sends something, and then returns that value.
     #[track_caller]
     pub fn recv(&mut self) -> T {
@@ -225,6 +338,24 @@ impl<T: Sync> Receiver<T> {
             .read_offset
             .store(next_read_offset, Ordering::Release);
 
+        // 5. Wake up the sender if it's waiting for room in the queue. This
+        //    side stores `read_offset` and then loads `sender_waiting`, while
+        //    `Sender::send_blocking` stores `sender_waiting` and then loads
+        //    `read_offset` (in its second `send`). Acquire and release don't
+        //    order a store before a later load of another atomic, so without
+        //    a SeqCst fence between them on both sides, both loads could see
+        //    the old values: the sender would go on to wait, and this side
+        //    would not increment the semaphore to wake it up. With the fences,
+        //    at least one of the loads sees the other side's store. The fence
+        //    is needed on every receive, since the sender may start waiting at
+        //    any point.
+        fence(Ordering::SeqCst);
+        if self.ch.sender_waiting.load(Ordering::Relaxed)
+            && self.ch.sender_waiting.swap(false, Ordering::Relaxed)
+        {
+            self.ch.read_semaphore.increment();
+        }
+
         Some(value)
     }
 }
@@ -279,8 +410,17 @@ pub fn leak_channel<T: Sync>(capacity: usize) -> (Sender<T>, Receiver<T>) {
     let read_offset = Box::leak(Box::new(AtomicUsize::new(0)));
     let write_offset = Box::leak(Box::new(AtomicUsize::new(0)));
     let write_semaphore = Box::leak(Box::new(Semaphore::single_threaded()));
-
-    channel_from_parts(queue, write_offset, read_offset, write_semaphore)
+    let read_semaphore = Box::leak(Box::new(Semaphore::single_threaded()));
+    let sender_waiting = Box::leak(Box::new(AtomicBool::new(false)));
+
+    channel_from_parts(
+        queue,
+        write_offset,
+        read_offset,
+        write_semaphore,
+        read_semaphore,
+        sender_waiting,
+    )
 }
 
 #[cfg(test)]
@@ -332,6 +472,41 @@ mod tests {
         }
     }
 
+    #[test]
+    fn single_threaded_send_blocking_fails_on_full_queue() {
+        let (mut tx, mut rx) = leak_channel::<u32>(1);
+        tx.send_blocking(12).unwrap();
+        assert_eq!(Err(34), tx.send_blocking(34));
+        assert_eq!(12, rx.recv());
+        tx.send_blocking(56).unwrap();
+        assert_eq!(56, rx.recv());
+    }
+
+    #[test]
+    fn len_is_correct_across_wrapping() {
+        let (mut tx, mut rx) = leak_channel::<u32>(3);
+        assert!(tx.is_empty() && rx.is_empty());
+
+        tx.send(1).unwrap();
+        tx.send(2).unwrap();
+        assert_eq!((2, 2), (tx.len(), rx.len()));
+        assert_eq!(1, rx.recv());
+        assert_eq!((1, 1), (tx.len(), rx.len()));
+
+        // The write offset wraps to the start of the queue here.
+        tx.send(3).unwrap();
+        tx.send(4).unwrap();
+        assert_eq!((3, 3), (tx.len(), rx.len()));
+        assert_eq!(Err(5), tx.send(5));
+        assert_eq!(3, rx.len());
+
+        assert_eq!(2, rx.recv());
+        assert_eq!(3, rx.recv());
+        assert_eq!((1, 1), (tx.len(), rx.len()));
+        assert_eq!(4, rx.recv());
+        assert!(tx.is_empty() && rx.is_empty());
+    }
+
     #[test]
     fn wraps_around() {
         let (mut tx, mut rx) = leak_channel::<u32>(2);
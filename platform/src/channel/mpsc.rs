@@ -0,0 +1,399 @@
+// SPDX-FileCopyrightText: 2025 Jens Pitkänen <jens.pitkanen@helsinki.fi>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Static memory based multi-producer single-consumer channel for
+//! communication between threads.
+//!
+//! This is a separate implementation from the single-producer
+//! [`channel`](super) so that the single-producer case doesn't need to pay for
+//! the compare-and-swap loop required to coordinate multiple senders. The
+//! algorithm is the bounded queue described by Dmitry Vyukov, where each slot
+//! has a sequence number that tells the senders and the receiver whose turn it
+//! is to access the slot.
+
+use core::{
+    hint::spin_loop,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use crossbeam_utils::CachePadded;
+
+use super::SyncUnsafeCell;
+use crate::Semaphore;
+
+/// One slot of a multi-producer channel's queue. Passed into
+/// [`channel_from_parts`] in a slice, and not otherwise used directly.
+pub struct Slot<T: Sync> {
+    /// If this is equal to the position (a non-wrapping offset, i.e. the index
+    /// to the queue before the modulo) of a sender, the slot is free for that
+    /// sender to write into. If this is equal to the position of the receiver
+    /// plus one, the slot contains a value for the receiver to read.
+    sequence: AtomicUsize,
+    /// The value sent into this slot. Only accessed by the sender or the
+    /// receiver that the sequence number allows to access it.
+    value: SyncUnsafeCell<Option<T>>,
+}
+
+impl<T: Sync> Slot<T> {
+    /// Creates an empty slot.
+    pub const fn new() -> Slot<T> {
+        Slot {
+            sequence: AtomicUsize::new(0),
+            value: SyncUnsafeCell::new(None),
+        }
+    }
+}
+
+impl<T: Sync> Default for Slot<T> {
+    fn default() -> Self {
+        Slot::new()
+    }
+}
+
+struct SharedChannelState<T: 'static + Sync> {
+    /// The slots containing the actual elements.
+    queue: &'static [CachePadded<Slot<T>>],
+    /// The position of the oldest pushed element, i.e. the next one to be
+    /// received. Only mutated by the [`Receiver`]. The index to `queue` is this
+    /// value modulo the length of the queue.
+    read_position: &'static CachePadded<AtomicUsize>,
+    /// The position where the next element is pushed. Senders reserve
+    /// positions by incrementing this with a compare-and-swap. The index to
+    /// `queue` is this value modulo the length of the queue.
+    write_position: &'static CachePadded<AtomicUsize>,
+    /// Incremented on every successful [`Sender::send`], decremented in every
+    /// [`Receiver::recv`].
+    write_semaphore: &'static Semaphore,
+}
+
+impl<T: Sync> Clone for SharedChannelState<T> {
+    fn clone(&self) -> Self {
+        SharedChannelState { ..*self }
+    }
+}
+
+/// Return type of [`channel_from_parts`].
+pub type Channel<T> = (Sender<T>, Receiver<T>);
+
+/// Creates a new multi-producer channel from its raw parts.
+///
+/// Unlike the single-producer channel, the queue does not need an extra slot,
+/// so a channel with room for 3 buffered elements should have a slice of length
+/// 3 as the `queue`.
+///
+/// ### Panics
+///
+/// Panics if any of the slots contain a value, i.e. if the slots are reused
+/// from a channel which still had undelivered values in it.
+pub fn channel_from_parts<T: Sync>(
+    queue: &'static mut [CachePadded<Slot<T>>],
+    write_position: &'static mut CachePadded<AtomicUsize>,
+    read_position: &'static mut CachePadded<AtomicUsize>,
+    write_semaphore: &'static mut Semaphore,
+) -> Channel<T> {
+    for (i, slot) in queue.iter_mut().enumerate() {
+        *slot.sequence.get_mut() = i;
+        // Safety: we have a mutable borrow of the slot, so nothing else can be
+        // accessing the value.
+        let value = unsafe { &*slot.value.get() };
+        assert!(value.is_none(), "slots should be empty");
+    }
+    *read_position.get_mut() = 0;
+    *write_position.get_mut() = 0;
+    let ch = SharedChannelState {
+        queue,
+        read_position,
+        write_position,
+        write_semaphore,
+    };
+    (Sender { ch: ch.clone() }, Receiver { ch })
+}
+
+/// One sending endpoint of a multi-producer channel, which can be used to send
+/// [`Sync`] and `'static` values to another thread. Can be cloned to create
+/// more senders for the same channel.
+pub struct Sender<T: 'static + Sync> {
+    ch: SharedChannelState<T>,
+}
+
+impl<T: Sync> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        Sender {
+            ch: self.ch.clone(),
+        }
+    }
+}
+
+impl<T: Sync> Sender<T> {
+    /// Returns how many values could be sent to be buffered up before the other
+    /// side receives them.
+    pub fn capacity(&self) -> usize {
+        self.ch.queue.len()
+    }
+
+    /// Sends the value into the channel if there's room.
+    ///
+    /// Other senders might be sending at the same time, in which case this
+    /// retries until either the value has been sent, or the channel is
+    /// observed to be full.
+    pub fn send(&mut self, value: T) -> Result<(), T> {
+        let len = self.ch.queue.len();
+        if len == 0 {
+            // This channel does not have any capacity, always fail.
+            return Err(value);
+        }
+
+        // 1. Reserve a position. This is relaxed, since the slot's sequence
+        //    number is what synchronizes the accesses to the slot, the
+        //    position is just used to pick between the slots.
+        let mut position = self.ch.write_position.load(Ordering::Relaxed);
+        let slot = loop {
+            let slot = &self.ch.queue[position % len];
+
+            // Acquire-load the sequence number, so that if the slot was freed
+            // by the receiver, its read of the previous value (the `take()` in
+            // try_recv) happens before our write below.
+            let sequence = slot.sequence.load(Ordering::Acquire);
+
+            // The difference between the sequence number and the position, as
+            // a signed number to handle the positions wrapping around at
+            // usize::MAX.
+            let diff = sequence.wrapping_sub(position) as isize;
+            if diff == 0 {
+                // The slot is free for this position, try to reserve it. If
+                // another sender reserved the position first, try again with
+                // the position they moved the write position to.
+                match self.ch.write_position.compare_exchange_weak(
+                    position,
+                    position.wrapping_add(1),
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => break slot,
+                    Err(current_position) => position = current_position,
+                }
+            } else if diff < 0 {
+                // The slot still contains the value sent a lap ago, i.e. the
+                // receiver hasn't gotten here yet, so the queue is full.
+                return Err(value);
+            } else {
+                // Another sender has already reserved this position (and
+                // incremented the write position, possibly after our load),
+                // try again with a fresh position.
+                position = self.ch.write_position.load(Ordering::Relaxed);
+            }
+        };
+
+        // 2. Write the value.
+        {
+            // Safety: the slot's sequence number matched our position, and we
+            // were the one to move the write position past it, so no other
+            // sender will access this slot until it has made a lap around the
+            // queue. The receiver won't access the slot until we update the
+            // sequence number in step 3.
+            let slot_value = unsafe { &mut *slot.value.get() };
+            assert!(
+                slot_value.is_none(),
+                "slot should not be populated since the receiver has freed it",
+            );
+            *slot_value = Some(value);
+        }
+
+        // 3. Release-store the sequence number, handing the slot over to the
+        //    receiver along with the value written above.
+        slot.sequence
+            .store(position.wrapping_add(1), Ordering::Release);
+
+        self.ch.write_semaphore.increment();
+
+        Ok(())
+    }
+}
+
+/// The receiving endpoint of a multi-producer channel, which can be used to
+/// receive [`Sync`] and `'static` values from other threads.
+pub struct Receiver<T: 'static + Sync> {
+    ch: SharedChannelState<T>,
+}
+
+impl<T: Sync> Receiver<T> {
+    /// Returns how many values could be buffered up to be received without any
+    /// recv calls.
+    pub fn capacity(&self) -> usize {
+        self.ch.queue.len()
+    }
+
+    /// Blocks until a sender sends something, and then returns that value.
+    ///
+    /// ### Panics
+    ///
+    /// Panics if nothing has been sent, and the platform's semaphores do not
+    /// block (i.e. the platform is single-threaded).
+    #[track_caller]
+    pub fn recv(&mut self) -> T {
+        self.ch.write_semaphore.decrement();
+        loop {
+            if let Some(value) = self.try_recv() {
+                return value;
+            }
+
+            // The semaphore was incremented by a sender, but the value at the
+            // read position might not be written yet, if the sender that
+            // reserved it is still writing it (and a sender with a later
+            // position finished first). If no sender has reserved the read
+            // position though, nothing has been sent.
+            let read_position = self.ch.read_position.load(Ordering::Relaxed);
+            let write_position = self.ch.write_position.load(Ordering::Relaxed);
+            assert_ne!(
+                read_position, write_position,
+                "send should've been called before this receive",
+            );
+            spin_loop();
+        }
+    }
+
+    /// Returns the oldest sent value, if one has been sent and not yet
+    /// received.
+    pub fn try_recv(&mut self) -> Option<T> {
+        let len = self.ch.queue.len();
+        if len == 0 {
+            // This channel does not have any capacity, nothing to receive.
+            return None;
+        }
+
+        // 1. Since this is a single-consumer channel (and thus we have a mut
+        //    self), the read position can't change until we change it.
+        let position = self.ch.read_position.load(Ordering::Relaxed);
+        let slot = &self.ch.queue[position % len];
+
+        // 2. Acquire-load the sequence number, so that if the sender has
+        //    handed the slot over to us, their write of the value happens
+        //    before our read below.
+        let sequence = slot.sequence.load(Ordering::Acquire);
+        if sequence != position.wrapping_add(1) {
+            // The value for this position hasn't been written yet.
+            return None;
+        }
+
+        // 3. Read the value.
+        let value = {
+            // Safety: the sequence number being position + 1 means that the
+            // sender that reserved this position has finished writing, and no
+            // sender will access this slot before we update the sequence number
+            // in step 4.
+            let slot_value = unsafe { &mut *slot.value.get() };
+            slot_value
+                .take()
+                .expect("slot should be populated due to the sequence number")
+        };
+
+        // 4. Release-store the sequence number, handing the slot over to the
+        //    sender which gets the position on the next lap around the queue.
+        slot.sequence
+            .store(position.wrapping_add(len), Ordering::Release);
+        (self.ch.read_position).store(position.wrapping_add(1), Ordering::Relaxed);
+
+        Some(value)
+    }
+}
+
+/// Allocates the memory for a multi-producer channel with the given capacity
+/// using `alloc` and leaks the memory to create a channel.
+///
+/// This is just for tests.
+#[doc(hidden)]
+#[cfg(test)]
+pub fn leak_channel<T: Sync>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+    extern crate alloc;
+    use alloc::boxed::Box;
+    use alloc::vec::Vec;
+
+    let mut queue_vec = Vec::with_capacity(capacity);
+    for _ in 0..capacity {
+        queue_vec.push(CachePadded::new(Slot::new()));
+    }
+    let queue = Box::leak(queue_vec.into_boxed_slice());
+    let read_position = Box::leak(Box::new(CachePadded::new(AtomicUsize::new(0))));
+    let write_position = Box::leak(Box::new(CachePadded::new(AtomicUsize::new(0))));
+    let write_semaphore = Box::leak(Box::new(Semaphore::single_threaded()));
+
+    channel_from_parts(queue, write_position, read_position, write_semaphore)
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use std::{thread, vec::Vec};
+
+    use super::leak_channel;
+
+    #[test]
+    fn handles_full_queue_and_wraps_around() {
+        const CAP: usize = 3;
+        let (mut tx, mut rx) = leak_channel::<usize>(CAP);
+        let mut tx2 = tx.clone();
+        for lap in 0..3 {
+            for i in 0..CAP {
+                tx.send(lap * 10 + i).unwrap();
+            }
+            assert_eq!(Err(123), tx2.send(123));
+            for i in 0..CAP {
+                assert_eq!(lap * 10 + i, rx.recv());
+            }
+            assert_eq!(None, rx.try_recv());
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn panics_on_single_threaded_recv_without_a_preceding_send() {
+        let (_, mut rx) = leak_channel::<u32>(1);
+        let _ = rx.recv();
+    }
+
+    #[test]
+    #[cfg_attr(target_os = "emscripten", ignore)]
+    fn stress_test_with_many_producers() {
+        const PRODUCERS: usize = 4;
+        const VALUES_PER_PRODUCER: usize = 10_000;
+        // A small capacity to make the queue run full often.
+        let (tx, mut rx) = leak_channel::<(usize, usize)>(4);
+
+        let producers = (0..PRODUCERS)
+            .map(|producer| {
+                let mut tx = tx.clone();
+                thread::spawn(move || {
+                    for i in 0..VALUES_PER_PRODUCER {
+                        let mut value = (producer, i);
+                        while let Err(rejected) = tx.send(value) {
+                            value = rejected;
+                            thread::yield_now();
+                        }
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        // Each producer's values should arrive in the order they were sent,
+        // and every value should arrive exactly once.
+        let mut next_values = [0; PRODUCERS];
+        let mut received = 0;
+        while received < PRODUCERS * VALUES_PER_PRODUCER {
+            if let Some((producer, i)) = rx.try_recv() {
+                assert_eq!(next_values[producer], i);
+                next_values[producer] += 1;
+                received += 1;
+            } else {
+                thread::yield_now();
+            }
+        }
+        assert_eq!(None, rx.try_recv());
+
+        for producer in producers {
+            producer.join().unwrap();
+        }
+        assert_eq!([VALUES_PER_PRODUCER; PRODUCERS], next_values);
+    }
+}
@@ -2,6 +2,8 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+use crate::{TextInputString, WindowId};
+
 /// An input event sent by the platform to the engine for handling.
 pub enum Event {
     /// Emitted when a digital input (a button, or a key, but not a thumbstick)
@@ -10,6 +12,79 @@ pub enum Event {
     /// Emitted when a digital input (a button, or a key, but not a thumbstick)
     /// is pressed released.
     DigitalInputReleased(InputDevice, Button),
+    /// Emitted when an analog input (e.g. a thumbstick axis or a trigger)
+    /// changes position. The value is normalized to be between -1 and 1, or
+    /// between 0 and 1 for inputs that only go one way, like triggers.
+    AnalogInputChanged(InputDevice, Axis, f32),
+    /// Emitted when a pointer (e.g. a mouse cursor) moves. The coordinates are
+    /// in the same coordinate system as [`Platform::draw_area`], so they
+    /// should be multiplied by [`Platform::draw_scale_factor`] to get the
+    /// position in framebuffer pixels.
+    ///
+    /// [`Platform::draw_area`]: crate::Platform::draw_area
+    /// [`Platform::draw_scale_factor`]: crate::Platform::draw_scale_factor
+    PointerMoved {
+        /// The pointing device that moved.
+        device: InputDevice,
+        /// The window the pointer's coordinates are relative to.
+        window: WindowId,
+        /// The new horizontal position of the pointer, from the left edge.
+        x: f32,
+        /// The new vertical position of the pointer, from the top edge.
+        y: f32,
+    },
+    /// Emitted when a button on a pointing device (e.g. a mouse button) is
+    /// pressed or released. The position of the pointer at the time is the one
+    /// from the latest [`Event::PointerMoved`].
+    PointerButton {
+        /// The pointing device whose button was pressed or released.
+        device: InputDevice,
+        /// The button that was pressed or released.
+        button: Button,
+        /// True if the button was pressed down, false if it was released.
+        pressed: bool,
+    },
+    /// Emitted when the size of the drawable area changes, e.g. when the
+    /// window is resized. The size is the same as the one returned by
+    /// [`Platform::draw_area`] after the resize.
+    ///
+    /// [`Platform::draw_area`]: crate::Platform::draw_area
+    Resized {
+        /// The window which was resized.
+        window: WindowId,
+        /// The new width of the drawable area.
+        width: f32,
+        /// The new height of the drawable area.
+        height: f32,
+    },
+    /// Emitted when the game gains or loses input focus, e.g. when the player
+    /// switches to another window. Games might want to pause when they lose
+    /// focus.
+    ///
+    /// With multiple windows, moving focus from one of the game's windows to
+    /// another causes two events, one for losing focus and one for gaining it.
+    FocusChanged {
+        /// The window which gained or lost focus.
+        window: WindowId,
+        /// True if the game now has focus, false if it lost it.
+        focused: bool,
+    },
+    /// Emitted when the player types text while text input is enabled with
+    /// [`Platform::start_text_input`]. Not emitted while text input is
+    /// disabled.
+    ///
+    /// The keys pressed to type the text still cause
+    /// [`Event::DigitalInputPressed`] and [`Event::DigitalInputReleased`]
+    /// events as usual, so games should ignore those (e.g. by disabling their
+    /// actions) while a text field is being edited.
+    ///
+    /// [`Platform::start_text_input`]: crate::Platform::start_text_input
+    TextInput {
+        /// The typed text, which may contain multiple characters, e.g. when
+        /// composed with an input method editor. Longer texts are split into
+        /// multiple events.
+        text: TextInputString,
+    },
 }
 
 /// A button or key on a specific input device.
@@ -31,6 +106,25 @@ impl Button {
     }
 }
 
+/// An analog input (e.g. one axis of a thumbstick) on a specific input device.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Axis(u64);
+
+impl Axis {
+    /// Creates a new [`Axis`]. Should only be created in the platform
+    /// implementation, which also knows how the inner value is going to be
+    /// used.
+    pub fn new(id: u64) -> Axis {
+        Axis(id)
+    }
+
+    /// Returns the inner value passed into [`Axis::new`]. Generally only
+    /// relevant to the platform implementation.
+    pub fn inner(self) -> u64 {
+        self.0
+    }
+}
+
 /// A specific input device.
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct InputDevice(u64);
@@ -68,3 +162,21 @@ pub enum ActionCategory {
     ActSecondary,
     Pause,
 }
+
+/// The family of a gamepad, used to pick the right button labels and glyphs to
+/// show the player, e.g. "Press A" or "Press Cross".
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum GamepadKind {
+    /// Any device which isn't recognized as one of the other kinds, including
+    /// devices which aren't gamepads at all (e.g. keyboards).
+    #[default]
+    Generic,
+    /// An Xbox controller, or one with the same button layout.
+    Xbox,
+    /// A PlayStation controller.
+    PlayStation,
+    /// A Nintendo Switch controller. Note that the face buttons are labeled
+    /// differently than on Xbox controllers: the right face button is A, and
+    /// the bottom one is B.
+    Switch,
+}
@@ -2,6 +2,10 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+use core::ptr;
+
+use bytemuck::Pod;
+
 use crate::Box;
 
 /// Platform-specific file handle.
@@ -60,7 +64,50 @@ impl FileReadTask {
 
     /// Deconstructs this into the inner buffer. Intended for platform layers
     /// implementing
-    /// [`Platform::finish_file_read`](crate::Platform::finish_file_read).
+    /// [`Platform::finish_file_read`](crate::Platform::finish_file_read) and
+    /// [`Platform::cancel_file_read`](crate::Platform::cancel_file_read).
+    ///
+    /// ### Safety
+    ///
+    /// The platform may have shared a pointer to this buffer with e.g. the
+    /// kernel for async writing. The caller must ensure that when calling this
+    /// function, such a shared pointer will not be used anymore, as this
+    /// function makes said memory writable again (not owned and hidden in this
+    /// struct).
+    pub unsafe fn into_inner(self) -> Box<[u8]> {
+        self.buffer
+    }
+}
+
+/// Handle to an asynchronous file writing operation.
+pub struct FileWriteTask {
+    task_id: u64,
+    buffer: Box<[u8]>,
+}
+
+impl FileWriteTask {
+    /// Creates a new [`FileWriteTask`] with the task id differentiating
+    /// different [`FileWriteTask`]s. The platform implementation should create
+    /// and keep track of these.
+    pub fn new(task_id: u64, buffer: Box<[u8]>) -> FileWriteTask {
+        FileWriteTask { task_id, buffer }
+    }
+
+    /// Returns the task id for this particular task, the same one passed into
+    /// [`FileWriteTask::new`].
+    pub fn task_id(&self) -> u64 {
+        self.task_id
+    }
+
+    /// Returns the size of the buffer, i.e. the amount of bytes written by this
+    /// task.
+    pub fn write_size(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Deconstructs this into the inner buffer. Intended for platform layers
+    /// implementing
+    /// [`Platform::finish_file_write`](crate::Platform::finish_file_write).
     ///
     /// ### Safety
     ///
@@ -73,3 +120,60 @@ impl FileReadTask {
         self.buffer
     }
 }
+
+/// Reinterprets a buffer of bytes, e.g. one returned by
+/// [`Platform::finish_file_read`](crate::Platform::finish_file_read), as a
+/// buffer of `T`s without copying.
+///
+/// Returns the buffer back in an `Err` if it's not aligned for `T`, or if its
+/// length is not a multiple of the size of `T`.
+pub fn try_cast_boxed_slice<T: Pod>(mut buffer: Box<[u8]>) -> Result<Box<[T]>, Box<[u8]>> {
+    let Ok(typed) = bytemuck::try_cast_slice_mut::<u8, T>(&mut buffer) else {
+        return Err(buffer);
+    };
+    let typed_ptr: *mut [T] = ptr::slice_from_raw_parts_mut(typed.as_mut_ptr(), typed.len());
+    let _ = buffer.into_ptr();
+    // Safety: the pointer points to the same memory as the original box, which
+    // has just been consumed without dropping, so the memory is owned by the
+    // new box. Pod types are valid for any bytes, and bytemuck has checked the
+    // alignment and length.
+    Ok(unsafe { Box::from_ptr(typed_ptr) })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Box;
+
+    use super::try_cast_boxed_slice;
+
+    #[test]
+    fn aligned_buffers_are_cast_in_place() {
+        let mut backing = [0x01020304u32; 4];
+        let bytes: &mut [u8] = bytemuck::cast_slice_mut(&mut backing);
+        let bytes_ptr = bytes.as_mut_ptr();
+        // Safety: `bytes` is not used after this.
+        let buffer = unsafe { Box::from_ptr(&raw mut *bytes) };
+
+        let typed = try_cast_boxed_slice::<u32>(buffer).unwrap();
+        assert_eq!([0x01020304; 4], *typed);
+        assert_eq!(bytes_ptr as *const u32, typed.as_ptr());
+    }
+
+    #[test]
+    fn misaligned_or_uneven_buffers_are_rejected() {
+        let mut backing = [0u32; 4];
+        let bytes: &mut [u8] = bytemuck::cast_slice_mut(&mut backing);
+
+        // Safety: the boxes don't outlive `backing`, and they don't overlap.
+        let (misaligned, uneven) = unsafe {
+            let misaligned = Box::from_ptr(&raw mut bytes[1..5]);
+            let uneven = Box::from_ptr(&raw mut bytes[8..14]);
+            (misaligned, uneven)
+        };
+
+        let misaligned = try_cast_boxed_slice::<u32>(misaligned).unwrap_err();
+        assert_eq!(4, misaligned.len());
+        let uneven = try_cast_boxed_slice::<u32>(uneven).unwrap_err();
+        assert_eq!(6, uneven.len());
+    }
+}
@@ -24,9 +24,9 @@ mod semaphore;
 pub mod thread_pool;
 mod time;
 
-use arrayvec::ArrayVec;
+use arrayvec::{ArrayString, ArrayVec};
 
-use core::fmt::Arguments;
+use core::{fmt::Arguments, time::Duration};
 
 pub use boxed::*;
 pub use input::*;
@@ -40,14 +40,53 @@ pub use time::*;
 pub const AUDIO_SAMPLE_RATE: u32 = 48000;
 
 /// The amount of channels of audio data played back by the engine.
+///
+/// The engine always mixes audio in stereo, but it can be downmixed to mono
+/// for playback, see [`AudioOutput`].
 pub const AUDIO_CHANNELS: usize = 2;
 
+/// The speaker layout the audio is played back with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AudioOutput {
+    /// The left and right channels are played back separately.
+    Stereo,
+    /// The channels are mixed together and played back as one channel. The
+    /// engine does the downmixing, so both channels of the samples passed to
+    /// [`Platform::update_audio_buffer`] are the same, and the platform can
+    /// play either one.
+    Mono,
+}
+
 /// Shorthand for an [`ArrayVec`] of [`InputDevice`].
 ///
 /// Exported so that platforms don't need to explicitly depend on [`arrayvec`]
 /// just for the [`Platform::input_devices`] typing.
 pub type InputDevices = ArrayVec<InputDevice, 15>;
 
+/// Shorthand for an [`ArrayString`] long enough for most file paths.
+///
+/// Exported so that platforms don't need to explicitly depend on [`arrayvec`]
+/// just for the [`Platform::persistent_data_dir`] typing.
+pub type PathString = ArrayString<512>;
+
+/// Shorthand for the [`ArrayString`] in [`Event::TextInput`].
+///
+/// Exported so that platforms don't need to explicitly depend on [`arrayvec`]
+/// just for creating [`Event::TextInput`] events.
+pub type TextInputString = ArrayString<32>;
+
+/// Shorthand for the [`ArrayString`] returned by [`Platform::button_name`].
+///
+/// Exported so that platforms don't need to explicitly depend on [`arrayvec`]
+/// just for the [`Platform::button_name`] typing.
+pub type ButtonNameString = ArrayString<32>;
+
+/// Shorthand for an [`ArrayVec`] of [`DisplayInfo`].
+///
+/// Exported so that platforms don't need to explicitly depend on [`arrayvec`]
+/// just for the [`Platform::displays`] typing.
+pub type DisplayInfoList = ArrayVec<DisplayInfo, 8>;
+
 /// Interface to the engine for the platform implementation.
 ///
 /// Used to allow engine to do its thing each frame, and to pass events to it.
@@ -60,6 +99,9 @@ pub trait EngineCallbacks {
     );
 
     /// Handle an event.
+    ///
+    /// Platforms should send input events as well as window events, like
+    /// [`Event::Resized`] and [`Event::FocusChanged`], through this function.
     fn event(&mut self, event: Event, timestamp: Instant);
 }
 
@@ -76,6 +118,22 @@ pub trait EngineCallbacks {
 /// performance-wise, and will hopefully help with compilation times by avoiding
 /// generics.
 pub trait Platform {
+    /// Creates a new window, e.g. for a debug view alongside the game, and
+    /// returns its id. Returns None if the window could not be created, or if
+    /// the platform doesn't support multiple windows.
+    ///
+    /// The window is drawn into after it's made the active window with
+    /// [`Platform::set_active_window`]. Existing [`SpriteRef`]s, along with
+    /// their contents, can be used in the new window.
+    fn create_window(&self, title: &str, width: u32, height: u32) -> Option<WindowId>;
+
+    /// Sets the window which [`Platform::draw_2d`], [`Platform::draw_area`],
+    /// [`Platform::draw_scale_factor`], [`Platform::set_fullscreen`] and
+    /// [`Platform::is_fullscreen`] operate on, until the next call to this
+    /// function. The active window is initially [`WindowId::MAIN`]. Ids of
+    /// windows that don't exist are ignored.
+    fn set_active_window(&self, window: WindowId);
+
     /// Get the current screen size. Could be physical pixels, could be
     /// "logical" pixels, depends on the platform, but it's the same coordinate
     /// system as the [`Vertex2D`]es passed into [`Platform::draw_2d`].
@@ -87,6 +145,40 @@ pub trait Platform {
     /// rendering).
     fn draw_scale_factor(&self) -> f32;
 
+    /// Switch between fullscreen and windowed modes. Switching back to
+    /// [`FullscreenMode::Windowed`] should restore the window to the size it
+    /// was before going fullscreen.
+    ///
+    /// [`Platform::draw_area`] and [`Platform::draw_scale_factor`] should
+    /// reflect the new mode as soon as this function returns.
+    fn set_fullscreen(&self, mode: FullscreenMode);
+
+    /// Returns true if the game is currently in either of the fullscreen
+    /// modes, i.e. not [`FullscreenMode::Windowed`].
+    fn is_fullscreen(&self) -> bool;
+
+    /// Sets how frames are presented, which also determines how the frame
+    /// rate is paced. Switching modes must not invalidate any existing
+    /// [`SpriteRef`]s. The default mode is [`PresentMode::Vsync`].
+    ///
+    /// ### Implementation note
+    ///
+    /// [`Platform::audio_playback_position`] needs to be offset far enough
+    /// forwards to cover the length of a frame. If the offset is based on the
+    /// display's refresh rate, a [`PresentMode::FrameCap`] lower than the
+    /// refresh rate should be taken into account, as frames will be longer
+    /// than the refresh rate would suggest.
+    fn set_present_mode(&self, mode: PresentMode);
+
+    /// Get a list of the currently connected displays. The list should be up
+    /// to date with displays being connected and disconnected while the game
+    /// is running, so it shouldn't be cached for long.
+    ///
+    /// At most one of the displays is marked [`DisplayInfo::primary`], and at
+    /// most one is marked [`DisplayInfo::current`]. Platforms without any
+    /// knowledge of displays can return an empty list.
+    fn displays(&self) -> DisplayInfoList;
+
     /// Render out a pile of possibly textured 2D triangles.
     fn draw_2d(&self, vertices: &[Vertex2D], indices: &[u32], settings: DrawSettings2D);
 
@@ -120,9 +212,10 @@ pub trait Platform {
     /// offset `first_byte`.
     ///
     /// Implementations can assume that `'a` will last until
-    /// [`Platform::finish_file_read`] is called with the task returned from
-    /// this function, since [`FileReadTask`] can't (safely) be dropped without
-    /// it getting called.
+    /// [`Platform::finish_file_read`] or [`Platform::cancel_file_read`] is
+    /// called with the task returned from this function, since
+    /// [`FileReadTask`] can't (safely) be dropped without one of them getting
+    /// called.
     #[must_use]
     fn begin_file_read(&self, file: FileHandle, first_byte: u64, buffer: Box<[u8]>)
         -> FileReadTask;
@@ -137,6 +230,49 @@ pub trait Platform {
     /// guaranteed.
     fn finish_file_read(&self, task: FileReadTask) -> Result<Box<[u8]>, Box<[u8]>>;
 
+    /// Abandons the read task without waiting for it to finish, if possible,
+    /// and returns the buffer for reuse. The buffer contents are not
+    /// guaranteed.
+    ///
+    /// Like [`Platform::finish_file_read`], implementations must ensure that
+    /// the buffer will not be written into by the read operation after this
+    /// returns. Any resources related to the read (e.g. background threads)
+    /// should still be cleaned up eventually.
+    fn cancel_file_read(&self, task: FileReadTask) -> Box<[u8]>;
+
+    /// Start an asynchronous write operation to write the contents of `buffer`
+    /// into the file at `path`, starting at offset `first_byte`. The file is
+    /// created if it doesn't exist, and any existing contents outside of the
+    /// written range are left as is.
+    ///
+    /// Implementations can assume that the buffer will not be accessed until
+    /// [`Platform::finish_file_write`] is called with the task returned from
+    /// this function, since [`FileWriteTask`] can't (safely) be dropped
+    /// without it getting called.
+    #[must_use]
+    fn begin_file_write(&self, path: &str, first_byte: u64, buffer: Box<[u8]>) -> FileWriteTask;
+
+    /// Returns true if the write task has finished (in success or failure),
+    /// false if it's still pending.
+    fn is_file_write_finished(&self, task: &FileWriteTask) -> bool;
+
+    /// Blocks until the write task finishes, and returns the buffer that was
+    /// written to the file. If the write fails, the buffer is returned wrapped
+    /// in an `Err`, and the file contents are not guaranteed.
+    fn finish_file_write(&self, task: FileWriteTask) -> Result<Box<[u8]>, Box<[u8]>>;
+
+    /// Returns the path to a directory where the game can write persistent
+    /// files, like save files and settings, creating the directory if it
+    /// doesn't exist yet. The path ends with a path separator, so file names
+    /// can be appended to it directly, and the resulting paths can be used with
+    /// [`Platform::open_file`] and [`Platform::begin_file_write`].
+    ///
+    /// The directory is specific to the given organization and application
+    /// names, which should not change between versions of the game. Returns
+    /// None if the platform doesn't have such a directory, or if the path does
+    /// not fit in a [`PathString`].
+    fn persistent_data_dir(&self, org: &str, app: &str) -> Option<PathString>;
+
     /// Creates a semaphore.
     ///
     /// Multi-threaded platforms should use [`Semaphore::new`] and implement the
@@ -171,12 +307,17 @@ pub trait Platform {
     /// position where the samples start.
     ///
     /// Each sample should be a tuple containing the left and right channels'
-    /// audio samples for stereo playback, in that order.
+    /// audio samples for stereo playback, in that order. If
+    /// [`Platform::audio_output`] is [`AudioOutput::Mono`], the channels
+    /// contain the same downmixed sample.
     ///
     /// The playback position where the platform will start reading can be
     /// queried with [`Platform::audio_playback_position`].
     fn update_audio_buffer(&self, first_position: u64, samples: &[[i16; AUDIO_CHANNELS]]);
 
+    /// Returns the speaker layout the platform plays audio back with.
+    fn audio_output(&self) -> AudioOutput;
+
     /// Returns the playback position of the next sample the platform will play,
     /// and the timestamp which it should be considered to be synchronized with.
     ///
@@ -185,6 +326,10 @@ pub trait Platform {
     fn audio_playback_position(&self) -> (u64, Instant);
 
     /// Get a list of the currently connected input devices.
+    ///
+    /// Pointing devices (e.g. a mouse) are included, and their
+    /// [`Event::PointerMoved`] and [`Event::PointerButton`] events use the
+    /// same [`InputDevice`] as returned here.
     fn input_devices(&self) -> InputDevices;
 
     /// Get the default button for one of the generic action categories for the
@@ -195,6 +340,45 @@ pub trait Platform {
         device: InputDevice,
     ) -> Option<Button>;
 
+    /// Get the kind of gamepad the given input device is, for showing the
+    /// right button prompts. Returns [`GamepadKind::Generic`] for devices
+    /// which are not gamepads, like keyboards, and for disconnected or
+    /// unrecognized gamepads.
+    fn gamepad_kind(&self, device: InputDevice) -> GamepadKind;
+
+    /// Get a human-readable name for the button, e.g. "Space" or "D-Pad Up",
+    /// for showing in a controls rebinding menu. Buttons the platform doesn't
+    /// recognize get a placeholder name instead of causing a panic.
+    fn button_name(&self, button: Button) -> ButtonNameString;
+
+    /// Start rumbling the given input device for the given duration, replacing
+    /// any previous rumble. The intensities of the low and high frequency
+    /// motors are between 0 and 1, and values outside that range are clamped.
+    /// Setting both intensities to 0 stops the rumble.
+    ///
+    /// Does nothing for devices that don't support rumble, like keyboards.
+    fn set_rumble(&self, device: InputDevice, low_freq: f32, high_freq: f32, duration: Duration);
+
+    /// Writes the text currently in the clipboard into `buffer`, and returns
+    /// the written part of the buffer as a string. If the text doesn't fit in
+    /// the buffer, only the beginning of it is returned, cut at a character
+    /// boundary. Returns None if the clipboard is empty, or the platform
+    /// doesn't have a clipboard.
+    fn clipboard_text<'a>(&self, buffer: &'a mut [u8]) -> Option<&'a str>;
+
+    /// Replaces the contents of the clipboard with the given text. Does
+    /// nothing if the platform doesn't have a clipboard.
+    fn set_clipboard_text(&self, text: &str);
+
+    /// Enables text input, causing [`Event::TextInput`] events to be sent when
+    /// the player types, and possibly showing an on-screen keyboard. Text input
+    /// is disabled by default.
+    fn start_text_input(&self);
+
+    /// Disables text input, so that [`Event::TextInput`] events are no longer
+    /// sent.
+    fn stop_text_input(&self);
+
     /// Returns the current point in time according to the platform
     /// implementation.
     fn now(&self) -> Instant;
@@ -122,6 +122,28 @@ impl SpriteRef {
     }
 }
 
+/// Platform-specific window reference.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct WindowId(u64);
+
+impl WindowId {
+    /// The window the game starts with. Platforms must use this id for it.
+    pub const MAIN: WindowId = WindowId(0);
+
+    /// Creates a new [`WindowId`]. Should only be created in the platform
+    /// implementation, which also knows how the inner value is going to be
+    /// used.
+    pub fn new(id: u64) -> WindowId {
+        WindowId(id)
+    }
+
+    /// Returns the inner value passed into [`WindowId::new`]. Generally only
+    /// relevant to the platform implementation.
+    pub fn inner(self) -> u64 {
+        self.0
+    }
+}
+
 /// How drawn pixels are blended with the previously drawn pixels.
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum BlendMode {
@@ -140,10 +162,24 @@ pub enum BlendMode {
     /// Where `dst` is the color of the framebuffer, and `src` is the color
     /// being drawn on it.
     Add,
+    /// `dstRGB = (srcRGB * dstRGB) + (dstRGB * (1 - srcA))`  
+    /// `dstA = dstA`
+    ///
+    /// Where `dst` is the color of the framebuffer, and `src` is the color
+    /// being drawn on it. Darkens the framebuffer, e.g. for shadows, except
+    /// where `src` is transparent black, which leaves the framebuffer as is.
+    Multiply,
+    /// `dstRGB = srcRGB + (dstRGB * (1 - srcA))`  
+    /// `dstA = srcA + (dstA * (1 - srcA))`
+    ///
+    /// Where `dst` is the color of the framebuffer, and `src` is the color
+    /// being drawn on it. Like [`BlendMode::Blend`], but for sprites whose
+    /// colors have already been multiplied by their alpha.
+    PremultipliedAlpha,
 }
 
 /// How the texture is filtered when magnified or minified.
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum TextureFilter {
     /// No blending, just picks the pixel from the texture which is nearest to
     /// the sampled position. When rendering at a higher resolution than the
@@ -156,6 +192,55 @@ pub enum TextureFilter {
     Linear,
 }
 
+/// How the game's window covers the screen.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum FullscreenMode {
+    /// A regular window, which doesn't cover the whole screen.
+    #[default]
+    Windowed,
+    /// A borderless window covering the whole screen, without changing the
+    /// display's video mode. Switching to and from this mode is fast.
+    Borderless,
+    /// "Real" fullscreen, where the display's video mode is changed to match
+    /// the game. Switching to and from this mode may be slow.
+    Exclusive,
+}
+
+/// How finished frames are presented, i.e. what paces the frame rate of the
+/// game loop.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PresentMode {
+    /// Frames are presented in sync with the display's refresh rate, which
+    /// avoids tearing, and caps the frame rate to the refresh rate.
+    #[default]
+    Vsync,
+    /// Frames are presented as soon as they're finished, without any cap on
+    /// the frame rate. May cause tearing.
+    Immediate,
+    /// Frames are presented as soon as they're finished, but the game loop
+    /// waits between frames to run at most the given amount of frames per
+    /// second. May cause tearing. A cap of 0 is treated as
+    /// [`PresentMode::Immediate`].
+    FrameCap(u32),
+}
+
+/// Information about a connected display, as returned by
+/// [`Platform::displays`](crate::Platform::displays).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DisplayInfo {
+    /// The width of the display's current video mode, in pixels.
+    pub width: u32,
+    /// The height of the display's current video mode, in pixels.
+    pub height: u32,
+    /// The refresh rate of the display's current video mode, in hertz, or 0
+    /// if the platform doesn't know it.
+    pub refresh_rate: u32,
+    /// True if this is the primary display of the system.
+    pub primary: bool,
+    /// True if the game's window is currently (mostly) on this display.
+    pub current: bool,
+}
+
 /// Descriptions of pixel data layouts, used to interpret the byte arrays passed
 /// into uploading functions.
 #[derive(Debug)]
@@ -2,6 +2,8 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+use core::time::Duration;
+
 /// A trait to implement a semaphore object.
 pub trait SemaphoreImpl: Sync {
     /// Increments the semaphore.
@@ -15,12 +17,33 @@ pub trait SemaphoreImpl: Sync {
     /// is actually in the expected state, and panic or otherwise error out if
     /// it's not.
     fn decrement(&self);
+    /// Decrements the semaphore if the internal counter is positive, returning
+    /// true if it did, and false otherwise. Should never wait for an
+    /// increment.
+    ///
+    /// Single-threaded platforms can implement this by always returning true,
+    /// for the same reasons as with [`SemaphoreImpl::decrement`].
+    fn try_decrement(&self) -> bool;
+    /// Decrements the semaphore, waiting for an increment for at most
+    /// `timeout` if the internal counter is already at zero. Returns true if
+    /// the semaphore was decremented.
+    ///
+    /// Single-threaded platforms can implement this as
+    /// [`SemaphoreImpl::try_decrement`], as there's no other thread which
+    /// could increment the semaphore during the wait.
+    fn decrement_timeout(&self, timeout: Duration) -> bool;
 }
 
 struct SingleThreadedSemaphore;
 impl SemaphoreImpl for SingleThreadedSemaphore {
     fn increment(&self) {}
     fn decrement(&self) {}
+    fn try_decrement(&self) -> bool {
+        true
+    }
+    fn decrement_timeout(&self, _timeout: Duration) -> bool {
+        self.try_decrement()
+    }
 }
 
 /// Atomically counting semaphore for efficiently waiting on other threads,
@@ -89,6 +112,31 @@ impl Semaphore {
         // the whole lifetime of the semaphore.
         unsafe { &(*self.semaphore_ptr) }.decrement();
     }
+
+    /// Decrements the semaphore's count if it's positive, returning true if it
+    /// was decremented. Unlike [`Semaphore::decrement`], this never waits for
+    /// an increment, so it can be used to poll whether e.g. a result from
+    /// another thread is available.
+    ///
+    /// Like [`Semaphore::decrement`], this can return true without a matching
+    /// increment on single-threaded platforms.
+    pub fn try_decrement(&self) -> bool {
+        // Safety: the constructor requires the pointer to be valid to use for
+        // the whole lifetime of the semaphore.
+        unsafe { &(*self.semaphore_ptr) }.try_decrement()
+    }
+
+    /// Decrements the semaphore's count, waiting for at most `timeout` for an
+    /// increment if the count is zero. Returns true if the semaphore was
+    /// decremented, false if the timeout elapsed first.
+    ///
+    /// Useful for waits which should not hang forever if the other side never
+    /// increments the semaphore, e.g. because the thread doing so panicked.
+    pub fn decrement_timeout(&self, timeout: Duration) -> bool {
+        // Safety: the constructor requires the pointer to be valid to use for
+        // the whole lifetime of the semaphore.
+        unsafe { &(*self.semaphore_ptr) }.decrement_timeout(timeout)
+    }
 }
 
 impl Drop for Semaphore {
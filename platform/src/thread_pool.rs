@@ -46,6 +46,9 @@ pub struct TaskInFlight {
     /// panicked. This will cause the join function to panic with "a thread in
     /// the thread pool panicked" when joining this task.
     thread_panicked: bool,
+    /// Optional description of the task, included in the panic message if the
+    /// thread running this task panicked.
+    label: Option<&'static str>,
 }
 
 impl TaskInFlight {
@@ -77,7 +80,10 @@ impl TaskInFlight {
     /// [`ThreadPool::spawn_task`] exactly.
     unsafe fn join<T>(mut self, run_if_not_finished: bool) -> Box<T> {
         if self.thread_panicked {
-            panic!("a thread in the thread pool panicked");
+            match self.label {
+                Some(label) => panic!("a thread in the thread pool panicked (task: {label})"),
+                None => panic!("a thread in the thread pool panicked"),
+            }
         }
 
         if !self.finished && run_if_not_finished {
@@ -233,12 +239,80 @@ impl ThreadPool {
         &mut self,
         data: Box<T>,
         func: fn(&mut T),
+    ) -> Result<TaskHandle<T>, Box<T>> {
+        self.spawn_labeled_task(data, func, None)
+    }
+
+    /// Like [`ThreadPool::spawn_task`], but with a label describing the task,
+    /// which is included in the panic message of [`ThreadPool::join_task`] if
+    /// the thread running the task panics.
+    pub fn spawn_labeled_task<T>(
+        &mut self,
+        data: Box<T>,
+        func: fn(&mut T),
+        label: Option<&'static str>,
     ) -> Result<TaskHandle<T>, Box<T>> {
         if self.threads.is_empty() {
             return Err(data);
         }
 
         let thread_index = self.next_thread_index;
+        let handle = self.send_task(thread_index, data, func, label)?;
+        self.next_thread_index = (thread_index + 1) % self.thread_count();
+        Ok(handle)
+    }
+
+    /// Like [`ThreadPool::spawn_task`], but runs the task on the thread at
+    /// `thread_index` instead of the next thread in the rotation, e.g. for
+    /// reproducing the exact same distribution of tasks between threads when
+    /// profiling. Doesn't affect which thread the next
+    /// [`ThreadPool::spawn_task`] is sent off to.
+    ///
+    /// The returned handle still needs to be joined in the same order as the
+    /// other tasks sent to the same thread.
+    ///
+    /// ### Panics
+    ///
+    /// If `thread_index` is not less than [`ThreadPool::thread_count`].
+    #[track_caller]
+    pub fn spawn_task_on<T>(
+        &mut self,
+        thread_index: usize,
+        data: Box<T>,
+        func: fn(&mut T),
+    ) -> Result<TaskHandle<T>, Box<T>> {
+        self.spawn_labeled_task_on(thread_index, data, func, None)
+    }
+
+    /// Like [`ThreadPool::spawn_task_on`], but with a label describing the
+    /// task, like in [`ThreadPool::spawn_labeled_task`].
+    ///
+    /// ### Panics
+    ///
+    /// If `thread_index` is not less than [`ThreadPool::thread_count`].
+    #[track_caller]
+    pub fn spawn_labeled_task_on<T>(
+        &mut self,
+        thread_index: usize,
+        data: Box<T>,
+        func: fn(&mut T),
+        label: Option<&'static str>,
+    ) -> Result<TaskHandle<T>, Box<T>> {
+        assert!(
+            thread_index < self.thread_count(),
+            "thread index {thread_index} is out of bounds, the thread pool has {} threads",
+            self.thread_count(),
+        );
+        self.send_task(thread_index, data, func, label)
+    }
+
+    fn send_task<T>(
+        &mut self,
+        thread_index: usize,
+        data: Box<T>,
+        func: fn(&mut T),
+        label: Option<&'static str>,
+    ) -> Result<TaskHandle<T>, Box<T>> {
         let task_position = self.threads[thread_index].sent_count;
 
         let func = func as *const (); // type erase for TaskInFlight
@@ -267,6 +341,7 @@ impl ThreadPool {
             func,
             func_proxy: proxy::<T>,
             thread_panicked: false,
+            label,
         };
 
         (self.threads[thread_index].sender)
@@ -278,7 +353,6 @@ impl ThreadPool {
         self.threads[thread_index].sent_count = task_position
             .checked_add(1)
             .expect("thread pool sent_count should not overflow a u64");
-        self.next_thread_index = (thread_index + 1) % self.thread_count();
 
         Ok(TaskHandle {
             thread_index,
@@ -298,6 +372,11 @@ impl ThreadPool {
     /// could either call the function (if it's a one-channel state), or wait
     /// until another thread has finished calling it (if it's a two-channel
     /// state that actually has a corresponding parallel thread).
+    ///
+    /// ### Panics
+    ///
+    /// If the thread running the task panicked. The panic message includes the
+    /// label passed into [`ThreadPool::spawn_labeled_task`], if any.
     pub fn join_task<T>(&mut self, handle: TaskHandle<T>) -> Result<Box<T>, TaskHandle<T>> {
         let current_recv_count = self.threads[handle.thread_index].recv_count;
 
@@ -324,7 +403,7 @@ mod tests {
     use crate::channel::leak_channel;
     use alloc::boxed::Box;
 
-    use super::{TaskInFlight, ThreadPool, ThreadState};
+    use super::{TaskHandle, TaskInFlight, ThreadPool, ThreadState};
 
     #[derive(Debug)]
     struct ExampleData(u32);
@@ -354,4 +433,89 @@ mod tests {
         #[allow(clippy::drop_non_drop)]
         drop(data); // `data` lives at least until here, at which point the unsafe box has been dropped
     }
+
+    #[test]
+    fn pinned_tasks_are_joined_in_order_per_thread() {
+        // Two single-threaded states, so each thread has its own queue.
+        let thread_state = || {
+            let (tx, rx) = leak_channel::<TaskInFlight>(2);
+            ThreadState::new(tx, rx)
+        };
+        let threads = Box::leak(Box::new([thread_state(), thread_state()]));
+        let mut thread_pool = ThreadPool::new(crate::Box::from_mut(threads)).unwrap();
+        let data = |n| crate::Box::from_mut(Box::leak(Box::new(ExampleData(n))));
+
+        let first = thread_pool
+            .spawn_task_on(1, data(1), |n| n.0 *= 10)
+            .unwrap();
+        let second = thread_pool
+            .spawn_task_on(1, data(2), |n| n.0 *= 10)
+            .unwrap();
+        let third = thread_pool
+            .spawn_task_on(0, data(3), |n| n.0 *= 10)
+            .unwrap();
+        assert_eq!(
+            (1, 1, 0),
+            (first.thread_index, second.thread_index, third.thread_index)
+        );
+        // The thread 1 queue is full, and pinning doesn't affect the rotation.
+        assert!(thread_pool.spawn_task_on(1, data(4), |_| {}).is_err());
+        let fourth = thread_pool.spawn_task(data(5), |n| n.0 *= 10).unwrap();
+        assert_eq!(0, fourth.thread_index);
+
+        // The tasks on thread 1 can't be joined out of order, but the ones on
+        // thread 0 can be joined independently of them.
+        let second = thread_pool.join_task(second).unwrap_err();
+        assert_eq!(30, thread_pool.join_task(third).unwrap().0);
+        assert_eq!(10, thread_pool.join_task(first).unwrap().0);
+        assert_eq!(20, thread_pool.join_task(second).unwrap().0);
+        assert_eq!(50, thread_pool.join_task(fourth).unwrap().0);
+        assert!(!thread_pool.has_pending());
+    }
+
+    #[test]
+    #[should_panic(expected = "a thread in the thread pool panicked (task: example task)")]
+    fn panic_message_includes_the_task_label() {
+        join_panicking_task(|thread_pool, data| {
+            thread_pool
+                .spawn_labeled_task(data, |_| panic!("task panicked"), Some("example task"))
+                .unwrap()
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "a thread in the thread pool panicked (task: pinned task)")]
+    fn panic_message_includes_the_pinned_task_label() {
+        join_panicking_task(|thread_pool, data| {
+            thread_pool
+                .spawn_labeled_task_on(0, data, |_| panic!("task panicked"), Some("pinned task"))
+                .unwrap()
+        });
+    }
+
+    /// Spawns a task with `spawn` on a single-threaded pool, runs it, and
+    /// joins it, which should panic since the task should panic.
+    fn join_panicking_task(
+        spawn: impl FnOnce(&mut ThreadPool, crate::Box<ExampleData>) -> TaskHandle<ExampleData>,
+    ) {
+        extern crate std;
+
+        // Run the task "on another thread" by hand, like
+        // Platform::spawn_pool_thread implementations do.
+        let (task_tx, mut task_rx) = leak_channel::<TaskInFlight>(1);
+        let (mut result_tx, result_rx) = leak_channel::<TaskInFlight>(1);
+        let threads = Box::leak(Box::new([ThreadState::new(task_tx, result_rx)]));
+        let mut thread_pool = ThreadPool::new(crate::Box::from_mut(threads)).unwrap();
+
+        let data = crate::Box::from_mut(Box::leak(Box::new(ExampleData(0))));
+        let handle = spawn(&mut thread_pool, data);
+
+        let mut task = task_rx.recv();
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| task.run()));
+        assert!(result.is_err());
+        task.signal_panic();
+        result_tx.send(task).ok().unwrap();
+
+        let _ = thread_pool.join_task(handle);
+    }
 }
@@ -50,6 +50,22 @@ impl Instant {
             None
         }
     }
+
+    /// Returns the duration from `past_instant` to `self`, or zero if
+    /// `past_instant` is after `self`.
+    ///
+    /// Useful for timestamps which are expected to be in order, but might not
+    /// be, e.g. an event's timestamp from the platform compared to the
+    /// timestamp of the frame it's processed in.
+    pub fn saturating_duration_since(self, past_instant: Instant) -> Duration {
+        self.duration_since(past_instant).unwrap_or_default()
+    }
+
+    /// Returns the duration from `self` to `now`, or zero if `self` is after
+    /// `now`. Equivalent to `now.saturating_duration_since(self)`.
+    pub fn elapsed(self, now: Instant) -> Duration {
+        now.saturating_duration_since(self)
+    }
 }
 
 impl Sub<Duration> for Instant {
@@ -65,3 +81,30 @@ impl Add<Duration> for Instant {
         Instant(self.0 + rhs)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use super::Instant;
+
+    #[test]
+    fn out_of_order_instants_saturate_to_zero() {
+        let earlier = Instant::reference();
+        let later = earlier + Duration::from_millis(5);
+        assert_eq!(None, earlier.duration_since(later));
+        assert_eq!(Duration::ZERO, earlier.saturating_duration_since(later));
+        assert_eq!(Duration::ZERO, later.elapsed(earlier));
+    }
+
+    #[test]
+    fn in_order_instants_give_the_duration_between_them() {
+        let earlier = Instant::reference() - Duration::from_millis(3);
+        let later = earlier + Duration::from_millis(5);
+        assert_eq!(
+            Duration::from_millis(5),
+            later.saturating_duration_since(earlier)
+        );
+        assert_eq!(Duration::from_millis(5), earlier.elapsed(later));
+    }
+}